@@ -0,0 +1,47 @@
+#![allow(dead_code)]
+
+use super::{
+    event::TimedEvent,
+    gamepad::{Gamepads, MappingError},
+    keyboard::{Key, Scancode},
+    mouse::{Cursor, CursorMode},
+};
+
+/// The platform layer behind a `Window`, e.g. GLFW or a headless test backend.
+pub trait Backend {
+    /// Makes the window visible before the first frame
+    fn show(&mut self);
+
+    /// Returns the engine events received since the last call
+    fn poll_events(&mut self) -> Vec<TimedEvent>;
+
+    /// Reads the state of every controller slot
+    fn poll_gamepads(&mut self, gamepads: &mut Gamepads);
+
+    /// Presents the frame
+    fn swap_buffers(&mut self);
+
+    /// Returns the time in seconds since the backend was created
+    fn time(&self) -> f64;
+
+    fn should_close(&self) -> bool;
+    fn set_should_close(&mut self, value: bool);
+
+    /// Returns the size of the window in screen coordinates
+    fn size(&self) -> (u32, u32);
+
+    /// Returns true if an OpenGL context is current and the `gl` functions are loaded
+    fn has_gl(&self) -> bool;
+
+    /// Returns the scancode of the key at the position `key` has on a US layout
+    fn key_scancode(&self, key: Key) -> Option<Scancode>;
+
+    fn clipboard(&self) -> Option<String>;
+    fn set_clipboard(&mut self, text: &str);
+
+    fn set_cursor_mode(&mut self, mode: CursorMode);
+    fn set_cursor(&mut self, cursor: Option<Cursor>);
+
+    /// Adds SDL game controller mappings
+    fn update_gamepad_mappings(&mut self, mappings: &str) -> Result<(), MappingError>;
+}
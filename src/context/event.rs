@@ -0,0 +1,50 @@
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+
+use super::{
+    keyboard::{Key, Modifiers, Scancode},
+    mouse::MouseButton,
+};
+
+/// The state change reported by a key or button event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Press,
+    Release,
+    Repeat,
+}
+
+/// An input or window event reported during a frame.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    Key(Key, Scancode, Action, Modifiers),
+    Char(char),
+    MouseButton(MouseButton, Action),
+    MouseMove(f32, f32),
+    MouseEnter(bool),
+    Scroll(f32, f32),
+    Resize(u32, u32),
+    Focus(bool),
+    FileDrop(Vec<PathBuf>),
+    GamepadConnected(usize),
+    GamepadDisconnected(usize),
+}
+
+/// An `Event` along with the time in seconds at which it was received.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimedEvent {
+    pub time: f64,
+    pub event: Event,
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl Action {
+    /// Returns true if the key or button is held after this action
+    pub fn is_pressed(&self) -> bool {
+        *self != Action::Release
+    }
+}
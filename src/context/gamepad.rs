@@ -0,0 +1,380 @@
+#![allow(dead_code)]
+
+use std::convert::TryFrom;
+
+/// The number of controllers GLFW can report.
+pub const MAX_GAMEPADS: usize = 16;
+
+pub const BUTTON_COUNT: usize = 15;
+pub const AXIS_COUNT: usize = 6;
+
+/// A button in the SDL game controller layout, named after the Xbox controller.
+/// See `GamepadButton::CROSS`, `CIRCLE`, `SQUARE` and `TRIANGLE` for PlayStation names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum GamepadButton {
+    ButtonA,
+    ButtonB,
+    ButtonX,
+    ButtonY,
+    ButtonLeftBumper,
+    ButtonRightBumper,
+    ButtonBack,
+    ButtonStart,
+    ButtonGuide,
+    ButtonLeftThumb,
+    ButtonRightThumb,
+    ButtonDpadUp,
+    ButtonDpadRight,
+    ButtonDpadDown,
+    ButtonDpadLeft,
+}
+
+/// An axis in the SDL game controller layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum GamepadAxis {
+    AxisLeftX,
+    AxisLeftY,
+    AxisRightX,
+    AxisRightY,
+    AxisLeftTrigger,
+    AxisRightTrigger,
+}
+
+/// Axis values with a smaller magnitude are treated as zero unless changed per pad.
+pub const DEFAULT_DEAD_ZONE: f32 = 0.1;
+
+#[derive(Debug)]
+pub enum MappingError {
+    Io(std::io::Error),
+    InvalidMapping,
+}
+
+/// A snapshot of a controller, as read by the backend once per frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GamepadState {
+    pub name: String,
+    pub is_mapped: bool,
+    pub buttons: [bool; BUTTON_COUNT],
+    pub axes: [f32; AXIS_COUNT],
+}
+
+/// A single controller slot.
+/// Buttons and axes use the SDL game controller layout when the pad has a mapping.
+#[derive(Debug, Clone)]
+pub struct Gamepad {
+    id: usize,
+    state: Option<GamepadState>,
+    last_state: Option<GamepadState>,
+    dead_zone: f32,
+}
+
+/// All controller slots, polled by the `Window` every frame.
+#[derive(Debug, Clone)]
+pub struct Gamepads {
+    pads: Vec<Gamepad>,
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl GamepadButton {
+    pub const CROSS: GamepadButton = GamepadButton::ButtonA;
+    pub const CIRCLE: GamepadButton = GamepadButton::ButtonB;
+    pub const SQUARE: GamepadButton = GamepadButton::ButtonX;
+    pub const TRIANGLE: GamepadButton = GamepadButton::ButtonY;
+
+    /// Returns the button with an index into `GamepadState::buttons`
+    pub fn from_i32(index: i32) -> Option<GamepadButton> {
+        GAMEPAD_BUTTONS.get(usize::try_from(index).ok()?).copied()
+    }
+}
+
+impl GamepadAxis {
+    /// Returns the axis with an index into `GamepadState::axes`
+    pub fn from_i32(index: i32) -> Option<GamepadAxis> {
+        GAMEPAD_AXES.get(usize::try_from(index).ok()?).copied()
+    }
+}
+
+impl Gamepad {
+    pub fn new(id: usize) -> Gamepad {
+        Gamepad {
+            id,
+            state: None,
+            last_state: None,
+            dead_zone: DEFAULT_DEAD_ZONE,
+        }
+    }
+
+    /// Replaces the current state, `None` when the pad is disconnected
+    pub fn process_state(&mut self, state: Option<GamepadState>) {
+        self.state = state;
+    }
+
+    pub fn update(&mut self) {
+        self.last_state = self.state.clone();
+    }
+
+    /// Returns the current state, `None` when the pad is disconnected
+    pub fn state(&self) -> Option<&GamepadState> {
+        self.state.as_ref()
+    }
+
+    /// Returns true if the state changed since the last frame
+    pub fn changed(&self) -> bool {
+        self.state != self.last_state
+    }
+
+    /// Returns the slot index of the pad
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Returns the name of the pad, or `None` when it is disconnected
+    pub fn name(&self) -> Option<&str> {
+        self.state.as_ref().map(|state| state.name.as_str())
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.state.is_some()
+    }
+
+    /// Returns true if the pad has a game controller mapping, buttons and axes
+    /// of unmapped joysticks are not reported
+    pub fn is_mapped(&self) -> bool {
+        self.state.as_ref().is_some_and(|state| state.is_mapped)
+    }
+
+    /// Returns true on the frame the pad was connected
+    pub fn just_connected(&self) -> bool {
+        self.state.is_some() && self.last_state.is_none()
+    }
+
+    /// Returns true on the frame the pad was disconnected
+    pub fn just_disconnected(&self) -> bool {
+        self.state.is_none() && self.last_state.is_some()
+    }
+
+    pub fn dead_zone(&self) -> f32 {
+        self.dead_zone
+    }
+
+    /// Axis values with a smaller magnitude are reported as zero
+    pub fn set_dead_zone(&mut self, dead_zone: f32) {
+        self.dead_zone = dead_zone.clamp(0.0, 0.99);
+    }
+
+    pub fn get_button(&self, button: GamepadButton) -> bool {
+        button_state(&self.state, button)
+    }
+
+    pub fn get_button_down(&self, button: GamepadButton) -> bool {
+        let curr = button_state(&self.state, button);
+        let last = button_state(&self.last_state, button);
+        curr && curr != last
+    }
+
+    pub fn get_button_up(&self, button: GamepadButton) -> bool {
+        let curr = button_state(&self.state, button);
+        let last = button_state(&self.last_state, button);
+        last && curr != last
+    }
+
+    /// Returns an axis value with the dead zone applied.
+    /// Sticks range from -1 to 1, triggers from 0 to 1.
+    pub fn get_axis(&self, axis: GamepadAxis) -> f32 {
+        apply_dead_zone(self.get_axis_raw(axis), self.dead_zone)
+    }
+
+    /// Returns an axis value without the dead zone applied
+    pub fn get_axis_raw(&self, axis: GamepadAxis) -> f32 {
+        match &self.state {
+            Some(state) => normalize_axis(axis, state.axes[axis as usize]),
+            None => 0.0,
+        }
+    }
+}
+
+impl Gamepads {
+    pub fn new() -> Gamepads {
+        Gamepads {
+            pads: (0..MAX_GAMEPADS).map(Gamepad::new).collect(),
+        }
+    }
+
+    pub fn update(&mut self) {
+        for pad in self.pads.iter_mut() {
+            pad.update();
+        }
+    }
+
+    /// Returns the pad in a slot
+    pub fn get(&self, id: usize) -> Option<&Gamepad> {
+        self.pads.get(id)
+    }
+
+    pub fn get_mut(&mut self, id: usize) -> Option<&mut Gamepad> {
+        self.pads.get_mut(id)
+    }
+
+    /// Returns the first connected pad, useful for single player games
+    pub fn first(&self) -> Option<&Gamepad> {
+        self.connected().next()
+    }
+
+    /// Returns every connected pad
+    pub fn connected(&self) -> impl Iterator<Item = &Gamepad> {
+        self.pads.iter().filter(|pad| pad.is_connected())
+    }
+
+    /// Returns every slot, connected or not
+    pub fn iter(&self) -> std::slice::Iter<'_, Gamepad> {
+        self.pads.iter()
+    }
+
+    /// Sets the dead zone of every pad
+    pub fn set_dead_zone(&mut self, dead_zone: f32) {
+        for pad in self.pads.iter_mut() {
+            pad.set_dead_zone(dead_zone);
+        }
+    }
+}
+
+impl Default for Gamepads {
+    fn default() -> Self {
+        Gamepads::new()
+    }
+}
+
+/// Every `GamepadButton` in index order
+pub const GAMEPAD_BUTTONS: [GamepadButton; BUTTON_COUNT] = [
+    GamepadButton::ButtonA,
+    GamepadButton::ButtonB,
+    GamepadButton::ButtonX,
+    GamepadButton::ButtonY,
+    GamepadButton::ButtonLeftBumper,
+    GamepadButton::ButtonRightBumper,
+    GamepadButton::ButtonBack,
+    GamepadButton::ButtonStart,
+    GamepadButton::ButtonGuide,
+    GamepadButton::ButtonLeftThumb,
+    GamepadButton::ButtonRightThumb,
+    GamepadButton::ButtonDpadUp,
+    GamepadButton::ButtonDpadRight,
+    GamepadButton::ButtonDpadDown,
+    GamepadButton::ButtonDpadLeft,
+];
+
+/// Every `GamepadAxis` in index order
+pub const GAMEPAD_AXES: [GamepadAxis; AXIS_COUNT] = [
+    GamepadAxis::AxisLeftX,
+    GamepadAxis::AxisLeftY,
+    GamepadAxis::AxisRightX,
+    GamepadAxis::AxisRightY,
+    GamepadAxis::AxisLeftTrigger,
+    GamepadAxis::AxisRightTrigger,
+];
+
+fn button_state(state: &Option<GamepadState>, button: GamepadButton) -> bool {
+    state
+        .as_ref()
+        .is_some_and(|state| state.buttons[button as usize])
+}
+
+/// Triggers rest at -1, remap them so released is 0
+pub fn normalize_axis(axis: GamepadAxis, value: f32) -> f32 {
+    match axis {
+        GamepadAxis::AxisLeftTrigger | GamepadAxis::AxisRightTrigger => (value + 1.0) * 0.5,
+        _ => value,
+    }
+}
+
+/// Zeroes values inside the dead zone and rescales the rest to start from 0
+pub fn apply_dead_zone(value: f32, dead_zone: f32) -> f32 {
+    if value.abs() < dead_zone {
+        0.0
+    } else {
+        value.signum() * (value.abs() - dead_zone) / (1.0 - dead_zone)
+    }
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod gamepad_tests {
+
+    use super::*;
+
+    fn pad_state(button: Option<GamepadButton>, left_x: f32) -> GamepadState {
+        let mut state = GamepadState {
+            name: String::from("Test Pad"),
+            is_mapped: true,
+            buttons: [false; BUTTON_COUNT],
+            axes: [0.0, 0.0, 0.0, 0.0, -1.0, -1.0],
+        };
+
+        if let Some(button) = button {
+            state.buttons[button as usize] = true;
+        }
+        state.axes[GamepadAxis::AxisLeftX as usize] = left_x;
+        state
+    }
+
+    #[test]
+    fn connect_and_disconnect() {
+        let mut pad = Gamepad::new(0);
+        pad.process_state(Some(pad_state(None, 0.0)));
+        assert!(pad.just_connected());
+        assert_eq!(pad.name(), Some("Test Pad"));
+
+        pad.update();
+        assert!(!pad.just_connected());
+
+        pad.process_state(None);
+        assert!(pad.just_disconnected());
+        assert_eq!(pad.name(), None);
+    }
+
+    #[test]
+    fn button_edges() {
+        let mut pad = Gamepad::new(0);
+        pad.process_state(Some(pad_state(Some(GamepadButton::ButtonA), 0.0)));
+        assert!(pad.get_button_down(GamepadButton::ButtonA));
+
+        pad.update();
+        assert!(pad.get_button(GamepadButton::ButtonA));
+        assert!(!pad.get_button_down(GamepadButton::ButtonA));
+
+        pad.process_state(Some(pad_state(None, 0.0)));
+        assert!(pad.get_button_up(GamepadButton::ButtonA));
+    }
+
+    #[test]
+    fn axis_dead_zone() {
+        let mut pad = Gamepad::new(0);
+        pad.set_dead_zone(0.2);
+        pad.process_state(Some(pad_state(None, 0.15)));
+        assert_eq!(pad.get_axis(GamepadAxis::AxisLeftX), 0.0);
+        assert_eq!(pad.get_axis_raw(GamepadAxis::AxisLeftX), 0.15);
+        assert_eq!(pad.get_axis(GamepadAxis::AxisLeftTrigger), 0.0);
+
+        pad.process_state(Some(pad_state(None, -1.0)));
+        assert_eq!(pad.get_axis(GamepadAxis::AxisLeftX), -1.0);
+    }
+
+    #[test]
+    fn dead_zone_rescales() {
+        assert_eq!(apply_dead_zone(0.1, 0.2), 0.0);
+        assert_eq!(apply_dead_zone(-1.0, 0.2), -1.0);
+        assert!((apply_dead_zone(0.6, 0.2) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn triggers_rest_at_zero() {
+        assert_eq!(normalize_axis(GamepadAxis::AxisLeftTrigger, -1.0), 0.0);
+        assert_eq!(normalize_axis(GamepadAxis::AxisLeftX, -1.0), -1.0);
+    }
+}
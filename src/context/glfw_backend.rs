@@ -0,0 +1,518 @@
+#![allow(dead_code)]
+
+use glfw::Context;
+use std::sync::mpsc::Receiver;
+
+use super::{
+    backend::Backend,
+    event::{Action, Event, TimedEvent},
+    gamepad::{
+        GamepadAxis, GamepadButton, GamepadState, Gamepads, MappingError, AXIS_COUNT, BUTTON_COUNT,
+        GAMEPAD_AXES, GAMEPAD_BUTTONS, MAX_GAMEPADS,
+    },
+    keyboard::{Key, Modifiers, Scancode},
+    mouse::{Cursor, CursorMode, MouseButton, StandardCursor},
+    window::{WindowArgs, WindowError},
+};
+
+/// Desktop backend that opens a real window with an OpenGL context.
+pub struct GlfwBackend {
+    glfw: glfw::Glfw,
+    window: glfw::Window,
+    events: Receiver<(f64, glfw::WindowEvent)>,
+}
+
+impl GlfwBackend {
+    pub fn new(args: &WindowArgs) -> Result<GlfwBackend, WindowError> {
+        // Initializing GLFW
+        let mut glfw = match glfw::init(glfw::FAIL_ON_ERRORS) {
+            Err(_) => return Err(WindowError::FailedGLFWInit),
+            Ok(glfw) => glfw,
+        };
+
+        let mut width = args.width;
+        let mut height = args.height;
+
+        if args.borderless {
+            glfw.window_hint(glfw::WindowHint::Decorated(false));
+            glfw.with_primary_monitor(|_, monitor| {
+                monitor.and_then(|monitor| {
+                    monitor.get_video_mode().and_then(|video_mode| {
+                        width = video_mode.width;
+                        height = video_mode.height;
+                        Some(video_mode)
+                    })
+                })
+            });
+        }
+
+        glfw.window_hint(glfw::WindowHint::Resizable(false));
+        glfw.window_hint(glfw::WindowHint::Visible(false));
+
+        let (mut window, events) =
+            match glfw.create_window(width, height, args.title, glfw::WindowMode::Windowed) {
+                None => return Err(WindowError::FailedGLFWCreateWindow),
+                Some((window, events)) => (window, events),
+            };
+
+        window.make_current();
+        gl::load_with(|s| window.get_proc_address(s));
+
+        window.set_key_polling(true);
+        window.set_char_polling(true);
+        window.set_store_lock_key_mods(true);
+        window.set_mouse_button_polling(true);
+        window.set_cursor_pos_polling(true);
+        window.set_cursor_enter_polling(true);
+        window.set_scroll_polling(true);
+        window.set_size_polling(true);
+        window.set_focus_polling(true);
+        window.set_drag_and_drop_polling(true);
+
+        Ok(GlfwBackend {
+            glfw,
+            window,
+            events,
+        })
+    }
+}
+
+impl Backend for GlfwBackend {
+    fn show(&mut self) {
+        self.window.show();
+    }
+
+    fn poll_events(&mut self) -> Vec<TimedEvent> {
+        self.glfw.poll_events();
+
+        glfw::flush_messages(&self.events)
+            .filter_map(|(time, event)| {
+                convert_event(&event).map(|event| TimedEvent { time, event })
+            })
+            .collect()
+    }
+
+    fn poll_gamepads(&mut self, gamepads: &mut Gamepads) {
+        for id in 0..MAX_GAMEPADS {
+            let joystick = glfw::JoystickId::from_i32(id as i32).unwrap();
+            if let Some(pad) = gamepads.get_mut(id) {
+                pad.process_state(read_gamepad(&self.glfw.get_joystick(joystick)));
+            }
+        }
+    }
+
+    fn swap_buffers(&mut self) {
+        self.window.swap_buffers();
+    }
+
+    fn time(&self) -> f64 {
+        self.glfw.get_time()
+    }
+
+    fn should_close(&self) -> bool {
+        self.window.should_close()
+    }
+
+    fn set_should_close(&mut self, value: bool) {
+        self.window.set_should_close(value);
+    }
+
+    fn size(&self) -> (u32, u32) {
+        let (width, height) = self.window.get_size();
+        (width.max(0) as u32, height.max(0) as u32)
+    }
+
+    fn has_gl(&self) -> bool {
+        true
+    }
+
+    fn key_scancode(&self, key: Key) -> Option<Scancode> {
+        match key {
+            Key::Unknown => None,
+            key => key_to_glfw(key).get_scancode(),
+        }
+    }
+
+    fn clipboard(&self) -> Option<String> {
+        self.window.get_clipboard_string()
+    }
+
+    fn set_clipboard(&mut self, text: &str) {
+        self.window.set_clipboard_string(text);
+    }
+
+    fn set_cursor_mode(&mut self, mode: CursorMode) {
+        let raw_motion = mode == CursorMode::Captured && self.glfw.supports_raw_motion();
+        self.window.set_cursor_mode(match mode {
+            CursorMode::Normal => glfw::CursorMode::Normal,
+            CursorMode::Hidden => glfw::CursorMode::Hidden,
+            CursorMode::Captured => glfw::CursorMode::Disabled,
+        });
+        self.window.set_raw_mouse_motion(raw_motion);
+    }
+
+    fn set_cursor(&mut self, cursor: Option<Cursor>) {
+        self.window.set_cursor(cursor.as_ref().map(create_cursor));
+    }
+
+    fn update_gamepad_mappings(&mut self, mappings: &str) -> Result<(), MappingError> {
+        if self.glfw.update_gamepad_mappings(mappings) {
+            Ok(())
+        } else {
+            Err(MappingError::InvalidMapping)
+        }
+    }
+}
+
+/// Converts a GLFW window event, returns `None` for events the engine does not expose
+fn convert_event(event: &glfw::WindowEvent) -> Option<Event> {
+    use glfw::WindowEvent;
+
+    match event {
+        WindowEvent::Key(key, scancode, action, modifiers) => Some(Event::Key(
+            key_from_glfw(*key),
+            *scancode,
+            convert_action(*action),
+            Modifiers::from_bits_truncate(modifiers.bits() as u8),
+        )),
+        WindowEvent::Char(c) => Some(Event::Char(*c)),
+        WindowEvent::MouseButton(button, action, _) => Some(Event::MouseButton(
+            MouseButton::from_i32(*button as i32)?,
+            convert_action(*action),
+        )),
+        WindowEvent::CursorPos(x, y) => Some(Event::MouseMove(*x as f32, *y as f32)),
+        WindowEvent::CursorEnter(inside) => Some(Event::MouseEnter(*inside)),
+        WindowEvent::Scroll(x, y) => Some(Event::Scroll(*x as f32, *y as f32)),
+        WindowEvent::Size(width, height) => Some(Event::Resize(
+            (*width).max(0) as u32,
+            (*height).max(0) as u32,
+        )),
+        WindowEvent::Focus(focused) => Some(Event::Focus(*focused)),
+        WindowEvent::FileDrop(paths) => Some(Event::FileDrop(paths.clone())),
+        _ => None,
+    }
+}
+
+fn convert_action(action: glfw::Action) -> Action {
+    match action {
+        glfw::Action::Press => Action::Press,
+        glfw::Action::Release => Action::Release,
+        glfw::Action::Repeat => Action::Repeat,
+    }
+}
+
+/// Reads a controller slot, `None` when no controller is plugged in
+fn read_gamepad(joystick: &glfw::Joystick) -> Option<GamepadState> {
+    if !joystick.is_present() {
+        return None;
+    }
+
+    let mut state = GamepadState {
+        name: String::new(),
+        is_mapped: false,
+        buttons: [false; BUTTON_COUNT],
+        axes: [0.0; AXIS_COUNT],
+    };
+
+    match joystick.get_gamepad_state() {
+        Some(gamepad) => {
+            state.name = joystick.get_gamepad_name().unwrap_or_default();
+            state.is_mapped = true;
+
+            for (value, &button) in state.buttons.iter_mut().zip(GAMEPAD_BUTTONS.iter()) {
+                *value = gamepad.get_button_state(gamepad_button_to_glfw(button))
+                    != glfw::Action::Release;
+            }
+
+            for (value, &axis) in state.axes.iter_mut().zip(GAMEPAD_AXES.iter()) {
+                *value = gamepad.get_axis(gamepad_axis_to_glfw(axis));
+            }
+        }
+        None => {
+            state.name = joystick.get_name().unwrap_or_default();
+            // Unmapped triggers rest at -1 like mapped ones
+            state.axes[GamepadAxis::AxisLeftTrigger as usize] = -1.0;
+            state.axes[GamepadAxis::AxisRightTrigger as usize] = -1.0;
+        }
+    }
+
+    Some(state)
+}
+
+fn gamepad_button_to_glfw(button: GamepadButton) -> glfw::GamepadButton {
+    match button {
+        GamepadButton::ButtonA => glfw::GamepadButton::ButtonA,
+        GamepadButton::ButtonB => glfw::GamepadButton::ButtonB,
+        GamepadButton::ButtonX => glfw::GamepadButton::ButtonX,
+        GamepadButton::ButtonY => glfw::GamepadButton::ButtonY,
+        GamepadButton::ButtonLeftBumper => glfw::GamepadButton::ButtonLeftBumper,
+        GamepadButton::ButtonRightBumper => glfw::GamepadButton::ButtonRightBumper,
+        GamepadButton::ButtonBack => glfw::GamepadButton::ButtonBack,
+        GamepadButton::ButtonStart => glfw::GamepadButton::ButtonStart,
+        GamepadButton::ButtonGuide => glfw::GamepadButton::ButtonGuide,
+        GamepadButton::ButtonLeftThumb => glfw::GamepadButton::ButtonLeftThumb,
+        GamepadButton::ButtonRightThumb => glfw::GamepadButton::ButtonRightThumb,
+        GamepadButton::ButtonDpadUp => glfw::GamepadButton::ButtonDpadUp,
+        GamepadButton::ButtonDpadRight => glfw::GamepadButton::ButtonDpadRight,
+        GamepadButton::ButtonDpadDown => glfw::GamepadButton::ButtonDpadDown,
+        GamepadButton::ButtonDpadLeft => glfw::GamepadButton::ButtonDpadLeft,
+    }
+}
+
+fn gamepad_axis_to_glfw(axis: GamepadAxis) -> glfw::GamepadAxis {
+    match axis {
+        GamepadAxis::AxisLeftX => glfw::GamepadAxis::AxisLeftX,
+        GamepadAxis::AxisLeftY => glfw::GamepadAxis::AxisLeftY,
+        GamepadAxis::AxisRightX => glfw::GamepadAxis::AxisRightX,
+        GamepadAxis::AxisRightY => glfw::GamepadAxis::AxisRightY,
+        GamepadAxis::AxisLeftTrigger => glfw::GamepadAxis::AxisLeftTrigger,
+        GamepadAxis::AxisRightTrigger => glfw::GamepadAxis::AxisRightTrigger,
+    }
+}
+
+fn create_cursor(cursor: &Cursor) -> glfw::Cursor {
+    match cursor {
+        Cursor::Standard(shape) => glfw::Cursor::standard(match shape {
+            StandardCursor::Arrow => glfw::StandardCursor::Arrow,
+            StandardCursor::IBeam => glfw::StandardCursor::IBeam,
+            StandardCursor::Crosshair => glfw::StandardCursor::Crosshair,
+            StandardCursor::Hand => glfw::StandardCursor::Hand,
+            StandardCursor::HResize => glfw::StandardCursor::HResize,
+            StandardCursor::VResize => glfw::StandardCursor::VResize,
+        }),
+        Cursor::Image {
+            width,
+            height,
+            pixels,
+            hotspot,
+        } => {
+            let image = glfw::PixelImage {
+                width: *width,
+                height: *height,
+                pixels: pixels
+                    .iter()
+                    .map(|color| {
+                        u32::from_ne_bytes([color.red, color.green, color.blue, color.alpha])
+                    })
+                    .collect(),
+            };
+            glfw::Cursor::create_from_pixels(image, hotspot.0, hotspot.1)
+        }
+    }
+}
+
+/// Maps between engine and GLFW keys, the variants share their names
+macro_rules! convert_keys {
+    ($($key:ident),* $(,)?) => {
+        fn key_from_glfw(key: glfw::Key) -> Key {
+            match key {
+                $(glfw::Key::$key => Key::$key,)*
+                glfw::Key::Unknown => Key::Unknown,
+            }
+        }
+
+        fn key_to_glfw(key: Key) -> glfw::Key {
+            match key {
+                $(Key::$key => glfw::Key::$key,)*
+                Key::Unknown => glfw::Key::Unknown,
+            }
+        }
+    };
+}
+
+convert_keys!(
+    Space,
+    Apostrophe,
+    Comma,
+    Minus,
+    Period,
+    Slash,
+    Num0,
+    Num1,
+    Num2,
+    Num3,
+    Num4,
+    Num5,
+    Num6,
+    Num7,
+    Num8,
+    Num9,
+    Semicolon,
+    Equal,
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+    I,
+    J,
+    K,
+    L,
+    M,
+    N,
+    O,
+    P,
+    Q,
+    R,
+    S,
+    T,
+    U,
+    V,
+    W,
+    X,
+    Y,
+    Z,
+    LeftBracket,
+    Backslash,
+    RightBracket,
+    GraveAccent,
+    World1,
+    World2,
+    Escape,
+    Enter,
+    Tab,
+    Backspace,
+    Insert,
+    Delete,
+    Right,
+    Left,
+    Down,
+    Up,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    CapsLock,
+    ScrollLock,
+    NumLock,
+    PrintScreen,
+    Pause,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    F13,
+    F14,
+    F15,
+    F16,
+    F17,
+    F18,
+    F19,
+    F20,
+    F21,
+    F22,
+    F23,
+    F24,
+    F25,
+    Kp0,
+    Kp1,
+    Kp2,
+    Kp3,
+    Kp4,
+    Kp5,
+    Kp6,
+    Kp7,
+    Kp8,
+    Kp9,
+    KpDecimal,
+    KpDivide,
+    KpMultiply,
+    KpSubtract,
+    KpAdd,
+    KpEnter,
+    KpEqual,
+    LeftShift,
+    LeftControl,
+    LeftAlt,
+    LeftSuper,
+    RightShift,
+    RightControl,
+    RightAlt,
+    RightSuper,
+    Menu
+);
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod glfw_backend_tests {
+
+    use super::*;
+    use crate::context::keyboard::KEYS;
+
+    #[test]
+    fn converts_key_event() {
+        let event = glfw::WindowEvent::Key(
+            glfw::Key::A,
+            38,
+            glfw::Action::Repeat,
+            glfw::Modifiers::Shift | glfw::Modifiers::CapsLock,
+        );
+        assert_eq!(
+            convert_event(&event),
+            Some(Event::Key(
+                Key::A,
+                38,
+                Action::Repeat,
+                Modifiers::SHIFT | Modifiers::CAPS_LOCK
+            ))
+        );
+    }
+
+    #[test]
+    fn converts_mouse_button() {
+        let event = glfw::WindowEvent::MouseButton(
+            glfw::MouseButtonRight,
+            glfw::Action::Press,
+            glfw::Modifiers::empty(),
+        );
+        assert_eq!(
+            convert_event(&event),
+            Some(Event::MouseButton(MouseButton::RIGHT, Action::Press))
+        );
+    }
+
+    #[test]
+    fn keys_keep_glfw_codes() {
+        for key in KEYS.iter() {
+            assert_eq!(key_to_glfw(*key) as i32, *key as i32);
+            assert_eq!(key_from_glfw(key_to_glfw(*key)), *key);
+        }
+    }
+
+    #[test]
+    fn gamepad_layout_matches_glfw() {
+        for (index, &button) in GAMEPAD_BUTTONS.iter().enumerate() {
+            assert_eq!(gamepad_button_to_glfw(button) as usize, index);
+            assert_eq!(button as usize, index);
+        }
+        for (index, &axis) in GAMEPAD_AXES.iter().enumerate() {
+            assert_eq!(gamepad_axis_to_glfw(axis) as usize, index);
+            assert_eq!(axis as usize, index);
+        }
+    }
+
+    #[test]
+    fn ignores_unexposed_events() {
+        assert_eq!(convert_event(&glfw::WindowEvent::Refresh), None);
+    }
+
+    #[test]
+    fn negative_size_is_clamped() {
+        let event = glfw::WindowEvent::Size(-1, 20);
+        assert_eq!(convert_event(&event), Some(Event::Resize(0, 20)));
+    }
+}
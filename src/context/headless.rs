@@ -0,0 +1,213 @@
+#![allow(dead_code)]
+
+use std::collections::BTreeMap;
+
+use super::{
+    backend::Backend,
+    event::{Event, TimedEvent},
+    gamepad::{GamepadState, Gamepads, MappingError},
+    keyboard::{Key, Scancode},
+    mouse::{Cursor, CursorMode},
+};
+
+/// Backend without a display or OpenGL context for tests and CI.
+/// Runs for a fixed number of frames on a scripted clock and feeds scripted input.
+/// No renderer is loaded, game code should draw through a software renderer or skip drawing
+/// when `Window::has_gl` is false.
+pub struct HeadlessBackend {
+    frames: u64,
+    frame: u64,
+    /// Frame whose scripted input is being delivered, set when `poll_events` starts
+    current: u64,
+    delta_time: f64,
+    time: f64,
+    size: (u32, u32),
+    closed: bool,
+    events: BTreeMap<u64, Vec<Event>>,
+    gamepads: BTreeMap<u64, Vec<(usize, Option<GamepadState>)>>,
+    clipboard: Option<String>,
+    cursor_mode: CursorMode,
+    cursor: Option<Cursor>,
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl HeadlessBackend {
+    /// Creates a backend that closes after `frames` frames, each `delta_time` seconds long
+    pub fn new(frames: u64, delta_time: f64) -> HeadlessBackend {
+        HeadlessBackend {
+            frames,
+            frame: 0,
+            current: 0,
+            delta_time,
+            time: 0.0,
+            size: (1280, 720),
+            closed: false,
+            events: BTreeMap::new(),
+            gamepads: BTreeMap::new(),
+            clipboard: None,
+            cursor_mode: CursorMode::Normal,
+            cursor: None,
+        }
+    }
+
+    /// Sets the size reported for the window
+    pub fn with_size(mut self, width: u32, height: u32) -> HeadlessBackend {
+        self.size = (width, height);
+        self
+    }
+
+    /// Delivers an event at the start of a frame, frames count from 0
+    pub fn with_event(mut self, frame: u64, event: Event) -> HeadlessBackend {
+        self.events.entry(frame).or_default().push(event);
+        self
+    }
+
+    /// Sets the state of a gamepad slot at the start of a frame, `None` disconnects it
+    pub fn with_gamepad(
+        mut self,
+        frame: u64,
+        id: usize,
+        state: Option<GamepadState>,
+    ) -> HeadlessBackend {
+        self.gamepads.entry(frame).or_default().push((id, state));
+        self
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl HeadlessBackend {
+    /// Returns the number of frames started so far
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    pub fn cursor_mode(&self) -> CursorMode {
+        self.cursor_mode
+    }
+
+    /// Returns the cursor image last set, `None` for the default arrow
+    pub fn cursor(&self) -> Option<&Cursor> {
+        self.cursor.as_ref()
+    }
+}
+
+impl Backend for HeadlessBackend {
+    fn show(&mut self) {}
+
+    fn poll_events(&mut self) -> Vec<TimedEvent> {
+        self.time = self.frame as f64 * self.delta_time;
+        let time = self.time;
+        self.current = self.frame;
+        let events = self.events.remove(&self.frame).unwrap_or_default();
+        self.frame += 1;
+
+        events
+            .into_iter()
+            .map(|event| TimedEvent { time, event })
+            .collect()
+    }
+
+    fn poll_gamepads(&mut self, gamepads: &mut Gamepads) {
+        // Removing the states delivers them once even if gamepads are polled again
+        for (id, state) in self.gamepads.remove(&self.current).unwrap_or_default() {
+            if let Some(pad) = gamepads.get_mut(id) {
+                pad.process_state(state);
+            }
+        }
+    }
+
+    fn swap_buffers(&mut self) {}
+
+    fn time(&self) -> f64 {
+        self.time
+    }
+
+    fn should_close(&self) -> bool {
+        self.closed || self.frame >= self.frames
+    }
+
+    fn set_should_close(&mut self, value: bool) {
+        self.closed = value;
+    }
+
+    fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    fn has_gl(&self) -> bool {
+        false
+    }
+
+    /// Scripted key events carry their own scancodes, so no layout is known
+    fn key_scancode(&self, _: Key) -> Option<Scancode> {
+        None
+    }
+
+    fn clipboard(&self) -> Option<String> {
+        self.clipboard.clone()
+    }
+
+    fn set_clipboard(&mut self, text: &str) {
+        self.clipboard = Some(text.to_string());
+    }
+
+    fn set_cursor_mode(&mut self, mode: CursorMode) {
+        self.cursor_mode = mode;
+    }
+
+    fn set_cursor(&mut self, cursor: Option<Cursor>) {
+        self.cursor = cursor;
+    }
+
+    fn update_gamepad_mappings(&mut self, _: &str) -> Result<(), MappingError> {
+        Ok(())
+    }
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod headless_tests {
+
+    use super::*;
+    use crate::context::gamepad::{AXIS_COUNT, BUTTON_COUNT};
+
+    fn pad_state() -> GamepadState {
+        GamepadState {
+            name: String::from("Test Pad"),
+            is_mapped: true,
+            buttons: [false; BUTTON_COUNT],
+            axes: [0.0; AXIS_COUNT],
+        }
+    }
+
+    #[test]
+    fn gamepads_follow_the_current_frame() {
+        let mut backend = HeadlessBackend::new(3, 1.0)
+            .with_gamepad(0, 0, Some(pad_state()))
+            .with_gamepad(1, 0, None);
+        let mut gamepads = Gamepads::new();
+
+        // Polling before the events of the first frame still reads frame 0
+        backend.poll_gamepads(&mut gamepads);
+        backend.poll_events();
+        assert!(gamepads.get(0).unwrap().is_connected());
+
+        // Polling twice in a frame does not replay or skip ahead
+        backend.poll_gamepads(&mut gamepads);
+        backend.poll_gamepads(&mut gamepads);
+        assert!(gamepads.get(0).unwrap().is_connected());
+
+        backend.poll_events();
+        backend.poll_gamepads(&mut gamepads);
+        assert!(!gamepads.get(0).unwrap().is_connected());
+    }
+}
@@ -0,0 +1,462 @@
+#![allow(dead_code)]
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use super::{
+    gamepad::{apply_dead_zone, GamepadAxis, GamepadButton},
+    keyboard::{key_from_name, Key},
+    mouse::MouseButton,
+    window::Window,
+};
+
+/// Values at or beyond this magnitude count as pressed.
+pub const PRESS_THRESHOLD: f32 = 0.5;
+
+/// A physical input that can drive an action.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Input {
+    Key(Key),
+    MouseButton(MouseButton),
+    GamepadButton(GamepadButton),
+    GamepadAxis(GamepadAxis),
+}
+
+/// An `Input` bound to an action, its value is multiplied by `scale`.
+/// Binding `Key::A` with a scale of -1 and `Key::D` with 1 makes a digital axis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Binding {
+    pub input: Input,
+    pub scale: f32,
+}
+
+#[derive(Debug)]
+pub enum InputMapError {
+    Io(std::io::Error),
+    InvalidLine(usize),
+}
+
+#[derive(Debug, Clone, Default)]
+struct ActionState {
+    bindings: Vec<Binding>,
+    /// Replaces the dead zone of the pads, `None` uses each pad's own
+    dead_zone: Option<f32>,
+    value: f32,
+    last_value: f32,
+}
+
+/// Maps named actions such as "jump" or "move_x" to rebindable inputs.
+#[derive(Debug, Clone, Default)]
+pub struct InputMap {
+    actions: BTreeMap<String, ActionState>,
+    gamepad: Option<usize>,
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl InputMap {
+    pub fn new() -> InputMap {
+        InputMap::default()
+    }
+
+    /// Reads bindings from a config file written by `InputMap::save`
+    pub fn load(path: &str) -> Result<InputMap, InputMapError> {
+        let config = std::fs::read_to_string(path).map_err(InputMapError::Io)?;
+        InputMap::from_config(&config)
+    }
+
+    /// Parses bindings from config text, one binding per line:
+    ///
+    /// ```text
+    /// # action kind name [scale]
+    /// jump key Space
+    /// move_x key A -1
+    /// move_x gamepad_axis AxisLeftX
+    /// move_x dead_zone 0.2
+    /// ```
+    pub fn from_config(config: &str) -> Result<InputMap, InputMapError> {
+        let mut map = InputMap::new();
+
+        for (index, line) in config.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid = || InputMapError::InvalidLine(index + 1);
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let (action, kind, name) = match tokens.as_slice() {
+                [action, kind, name] | [action, kind, name, _] => (*action, *kind, *name),
+                _ => return Err(invalid()),
+            };
+
+            if kind == "dead_zone" {
+                let dead_zone = name.parse().map_err(|_| invalid())?;
+                map.set_dead_zone(action, dead_zone);
+                continue;
+            }
+
+            let scale = match tokens.get(3) {
+                Some(scale) => scale.parse().map_err(|_| invalid())?,
+                None => 1.0,
+            };
+
+            let input = Input::from_config(kind, name).ok_or_else(invalid)?;
+            map.bind_axis(action, input, scale);
+        }
+
+        Ok(map)
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl InputMap {
+    /// Binds an input to an action
+    pub fn bind(&mut self, action: &str, input: Input) {
+        self.bind_axis(action, input, 1.0);
+    }
+
+    /// Binds an input to an action, scaling its value
+    pub fn bind_axis(&mut self, action: &str, input: Input, scale: f32) {
+        self.action_mut(action)
+            .bindings
+            .push(Binding { input, scale });
+    }
+
+    /// Removes an input from an action
+    pub fn unbind(&mut self, action: &str, input: Input) {
+        if let Some(state) = self.actions.get_mut(action) {
+            state.bindings.retain(|binding| binding.input != input);
+        }
+    }
+
+    /// Removes every binding of an action, e.g. before rebinding it
+    pub fn clear(&mut self, action: &str) {
+        if let Some(state) = self.actions.get_mut(action) {
+            state.bindings.clear();
+        }
+    }
+
+    /// Returns the bindings of an action
+    pub fn bindings(&self, action: &str) -> &[Binding] {
+        self.actions
+            .get(action)
+            .map_or(&[], |state| state.bindings.as_slice())
+    }
+
+    /// Returns the names of all actions
+    pub fn actions(&self) -> impl Iterator<Item = &str> {
+        self.actions.keys().map(|name| name.as_str())
+    }
+
+    /// Analog values with a magnitude below the dead zone are treated as zero.
+    /// Without one the dead zone of each pad applies, see `Gamepad::set_dead_zone`.
+    pub fn set_dead_zone(&mut self, action: &str, dead_zone: f32) {
+        self.action_mut(action).dead_zone = Some(dead_zone.clamp(0.0, 0.99));
+    }
+
+    /// Reads gamepad inputs only from one pad slot, by default every connected pad is read
+    pub fn set_gamepad(&mut self, gamepad: Option<usize>) {
+        self.gamepad = gamepad;
+    }
+
+    /// Reads the current input state, should be called once per frame
+    pub fn update(&mut self, window: &Window) {
+        let gamepads: Vec<_> = window
+            .gamepads()
+            .connected()
+            .filter(|pad| self.gamepad.is_none_or(|id| pad.id() == id))
+            .collect();
+
+        for state in self.actions.values_mut() {
+            let mut value = 0.0;
+            for binding in state.bindings.iter() {
+                let raw = match binding.input {
+                    Input::Key(key) => digital(window.keyboard().get_key(key)),
+                    Input::MouseButton(button) => digital(window.mouse().get_button(button)),
+                    Input::GamepadButton(button) => {
+                        digital(gamepads.iter().any(|pad| pad.get_button(button)))
+                    }
+                    Input::GamepadAxis(axis) => gamepads
+                        .iter()
+                        .map(|pad| match state.dead_zone {
+                            Some(dead_zone) => apply_dead_zone(pad.get_axis_raw(axis), dead_zone),
+                            None => pad.get_axis(axis),
+                        })
+                        .fold(0.0, |strongest: f32, value| {
+                            if value.abs() > strongest.abs() {
+                                value
+                            } else {
+                                strongest
+                            }
+                        }),
+                };
+
+                value += raw * binding.scale;
+            }
+
+            state.last_value = state.value;
+            state.value = value.clamp(-1.0, 1.0);
+        }
+    }
+
+    /// Returns the value of an action in the range -1 to 1
+    pub fn value(&self, action: &str) -> f32 {
+        self.actions.get(action).map_or(0.0, |state| state.value)
+    }
+
+    /// Returns true while an action is held
+    pub fn pressed(&self, action: &str) -> bool {
+        self.value(action).abs() >= PRESS_THRESHOLD
+    }
+
+    /// Returns true on the frame an action becomes held
+    pub fn just_pressed(&self, action: &str) -> bool {
+        self.actions.get(action).is_some_and(|state| {
+            state.value.abs() >= PRESS_THRESHOLD && state.last_value.abs() < PRESS_THRESHOLD
+        })
+    }
+
+    /// Returns true on the frame an action stops being held
+    pub fn just_released(&self, action: &str) -> bool {
+        self.actions.get(action).is_some_and(|state| {
+            state.value.abs() < PRESS_THRESHOLD && state.last_value.abs() >= PRESS_THRESHOLD
+        })
+    }
+
+    /// Writes the bindings to a config file
+    pub fn save(&self, path: &str) -> Result<(), InputMapError> {
+        std::fs::write(path, self.to_config()).map_err(InputMapError::Io)
+    }
+
+    /// Returns the bindings in the format read by `InputMap::from_config`
+    pub fn to_config(&self) -> String {
+        let mut config = String::new();
+
+        for (action, state) in self.actions.iter() {
+            for binding in state.bindings.iter() {
+                let (kind, name) = binding.input.config_name();
+                if binding.scale == 1.0 {
+                    writeln!(config, "{} {} {}", action, kind, name).unwrap();
+                } else {
+                    writeln!(config, "{} {} {} {}", action, kind, name, binding.scale).unwrap();
+                }
+            }
+
+            if let Some(dead_zone) = state.dead_zone {
+                writeln!(config, "{} dead_zone {}", action, dead_zone).unwrap();
+            }
+        }
+
+        config
+    }
+
+    fn action_mut(&mut self, action: &str) -> &mut ActionState {
+        self.actions.entry(action.to_string()).or_default()
+    }
+}
+
+impl Input {
+    fn config_name(self) -> (&'static str, String) {
+        match self {
+            Input::Key(key) => ("key", format!("{:?}", key)),
+            Input::MouseButton(button) => ("mouse_button", format!("{:?}", button)),
+            Input::GamepadButton(button) => ("gamepad_button", format!("{:?}", button)),
+            Input::GamepadAxis(axis) => ("gamepad_axis", format!("{:?}", axis)),
+        }
+    }
+
+    fn from_config(kind: &str, name: &str) -> Option<Input> {
+        match kind {
+            "key" => key_from_name(name).map(Input::Key),
+            "mouse_button" => (0..8)
+                .filter_map(MouseButton::from_i32)
+                .find(|button| format!("{:?}", button) == name)
+                .map(Input::MouseButton),
+            "gamepad_button" => (0..15)
+                .filter_map(GamepadButton::from_i32)
+                .find(|button| format!("{:?}", button) == name)
+                .map(Input::GamepadButton),
+            "gamepad_axis" => (0..6)
+                .filter_map(GamepadAxis::from_i32)
+                .find(|axis| format!("{:?}", axis) == name)
+                .map(Input::GamepadAxis),
+            _ => None,
+        }
+    }
+}
+
+fn digital(pressed: bool) -> f32 {
+    if pressed {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod input_map_tests {
+
+    use super::*;
+    use crate::context::{
+        event::{Action, Event},
+        gamepad::{GamepadState, AXIS_COUNT, BUTTON_COUNT, DEFAULT_DEAD_ZONE},
+        headless::HeadlessBackend,
+        keyboard::Modifiers,
+        window::EventReciever,
+    };
+
+    /// Updates an input map every frame and keeps what its actions read
+    struct Player {
+        map: InputMap,
+        frames: Vec<(f32, f32, [bool; 3])>,
+    }
+
+    impl EventReciever for Player {
+        fn init(&mut self, _: &mut Window) {}
+
+        fn render_loop(&mut self, window: &mut Window, _: f32) {
+            self.map.update(window);
+            let jump = [
+                self.map.pressed("jump"),
+                self.map.just_pressed("jump"),
+                self.map.just_released("jump"),
+            ];
+            self.frames
+                .push((self.map.value("move_x"), self.map.value("raw_x"), jump));
+        }
+
+        fn closing(&mut self, _: &mut Window) {}
+    }
+
+    fn key(key: Key, action: Action) -> Event {
+        Event::Key(key, 0, action, Modifiers::empty())
+    }
+
+    fn pad(left_x: f32) -> Option<GamepadState> {
+        let mut axes = [0.0; AXIS_COUNT];
+        axes[GamepadAxis::AxisLeftX as usize] = left_x;
+        axes[GamepadAxis::AxisLeftTrigger as usize] = -1.0;
+        axes[GamepadAxis::AxisRightTrigger as usize] = -1.0;
+        Some(GamepadState {
+            name: String::from("Test Pad"),
+            is_mapped: true,
+            buttons: [false; BUTTON_COUNT],
+            axes,
+        })
+    }
+
+    fn play(backend: HeadlessBackend, map: InputMap) -> Vec<(f32, f32, [bool; 3])> {
+        let mut player = Player {
+            map,
+            frames: Vec::new(),
+        };
+        Window::from_backend(Box::new(backend)).show(&mut player);
+        player.frames
+    }
+
+    #[test]
+    fn actions_follow_input() {
+        let mut map = InputMap::new();
+        map.bind("jump", Input::Key(Key::Space));
+        map.bind_axis("move_x", Input::Key(Key::A), -1.0);
+        map.bind_axis("move_x", Input::Key(Key::D), 1.0);
+        map.bind("move_x", Input::GamepadAxis(GamepadAxis::AxisLeftX));
+        map.bind("raw_x", Input::GamepadAxis(GamepadAxis::AxisLeftX));
+        map.set_dead_zone("raw_x", 0.0);
+
+        // The pad drifts inside its default dead zone until frame 2
+        let backend = HeadlessBackend::new(5, 0.1)
+            .with_gamepad(0, 0, pad(0.05))
+            .with_event(0, key(Key::Space, Action::Press))
+            .with_event(2, key(Key::Space, Action::Release))
+            .with_gamepad(2, 0, pad(-1.0))
+            .with_event(3, key(Key::D, Action::Press))
+            .with_gamepad(3, 0, pad(0.0))
+            .with_event(4, key(Key::A, Action::Press));
+        let frames = play(backend, map);
+
+        let move_x: Vec<f32> = frames.iter().map(|frame| frame.0).collect();
+        assert_eq!(move_x, [0.0, 0.0, -1.0, 1.0, 0.0]);
+        assert_eq!(frames[0].1, 0.05);
+        assert_eq!(frames[2].1, -1.0);
+
+        let jump: Vec<[bool; 3]> = frames.iter().map(|frame| frame.2).collect();
+        assert_eq!(
+            jump,
+            [
+                [true, true, false],
+                [true, false, false],
+                [false, false, true],
+                [false, false, false],
+                [false, false, false],
+            ]
+        );
+    }
+
+    #[test]
+    fn dead_zones() {
+        // The action dead zone replaces the pad's and rescales what is left
+        let mut map = InputMap::new();
+        map.bind("move_x", Input::GamepadAxis(GamepadAxis::AxisLeftX));
+        map.bind("raw_x", Input::GamepadAxis(GamepadAxis::AxisLeftX));
+        map.set_dead_zone("move_x", 0.5);
+
+        let backend = HeadlessBackend::new(2, 0.1)
+            .with_gamepad(0, 0, pad(0.4))
+            .with_gamepad(1, 0, pad(0.75));
+        let frames = play(backend, map);
+        assert_eq!((frames[0].0, frames[1].0), (0.0, 0.5));
+
+        // Without one the pad's dead zone applies
+        let raw: Vec<f32> = frames.iter().map(|frame| frame.1).collect();
+        let pad_zone = |value: f32| apply_dead_zone(value, DEFAULT_DEAD_ZONE);
+        assert_eq!(raw, [pad_zone(0.4), pad_zone(0.75)]);
+    }
+
+    #[test]
+    fn config_round_trip() {
+        let mut map = InputMap::new();
+        map.bind("jump", Input::Key(Key::Space));
+        map.bind("jump", Input::GamepadButton(GamepadButton::ButtonA));
+        map.bind_axis("move_x", Input::Key(Key::A), -1.0);
+        map.bind_axis("move_x", Input::Key(Key::D), 1.0);
+        map.bind("move_x", Input::GamepadAxis(GamepadAxis::AxisLeftX));
+        map.bind("fire", Input::MouseButton(MouseButton::Button1));
+        map.set_dead_zone("move_x", 0.25);
+
+        let loaded = InputMap::from_config(&map.to_config()).unwrap();
+        assert_eq!(loaded.bindings("jump"), map.bindings("jump"));
+        assert_eq!(loaded.bindings("move_x"), map.bindings("move_x"));
+        assert_eq!(loaded.bindings("fire"), map.bindings("fire"));
+        assert_eq!(loaded.to_config(), map.to_config());
+    }
+
+    #[test]
+    fn invalid_line_is_reported() {
+        let config = "# comment\njump key Space\njump key NotAKey\n";
+        match InputMap::from_config(config) {
+            Err(InputMapError::InvalidLine(line)) => assert_eq!(line, 3),
+            _ => panic!("expected an invalid line error"),
+        }
+    }
+
+    #[test]
+    fn unbind_removes_input() {
+        let mut map = InputMap::new();
+        map.bind("jump", Input::Key(Key::Space));
+        map.bind("jump", Input::Key(Key::W));
+        map.unbind("jump", Input::Key(Key::Space));
+        assert_eq!(map.bindings("jump").len(), 1);
+        assert!(map.bindings("missing").is_empty());
+    }
+}
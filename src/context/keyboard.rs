@@ -1,38 +1,219 @@
 #![allow(dead_code)]
 
 use std::collections::HashMap;
+use std::fmt;
+use std::ops::{BitAnd, BitOr, BitOrAssign, Sub};
 
-pub type Key = glfw::Key;
+use super::event::{Action, Event};
+
+/// Platform specific code of a physical key, only stable on the same machine.
+pub type Scancode = i32;
+
+/// A key, letters and symbols are named after a US keyboard layout.
+/// The codes match GLFW's so recordings made before stay readable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[repr(i32)]
+pub enum Key {
+    Space = 32,
+    Apostrophe = 39,
+    Comma = 44,
+    Minus = 45,
+    Period = 46,
+    Slash = 47,
+    Num0 = 48,
+    Num1 = 49,
+    Num2 = 50,
+    Num3 = 51,
+    Num4 = 52,
+    Num5 = 53,
+    Num6 = 54,
+    Num7 = 55,
+    Num8 = 56,
+    Num9 = 57,
+    Semicolon = 59,
+    Equal = 61,
+    A = 65,
+    B = 66,
+    C = 67,
+    D = 68,
+    E = 69,
+    F = 70,
+    G = 71,
+    H = 72,
+    I = 73,
+    J = 74,
+    K = 75,
+    L = 76,
+    M = 77,
+    N = 78,
+    O = 79,
+    P = 80,
+    Q = 81,
+    R = 82,
+    S = 83,
+    T = 84,
+    U = 85,
+    V = 86,
+    W = 87,
+    X = 88,
+    Y = 89,
+    Z = 90,
+    LeftBracket = 91,
+    Backslash = 92,
+    RightBracket = 93,
+    GraveAccent = 96,
+    World1 = 161,
+    World2 = 162,
+    Escape = 256,
+    Enter = 257,
+    Tab = 258,
+    Backspace = 259,
+    Insert = 260,
+    Delete = 261,
+    Right = 262,
+    Left = 263,
+    Down = 264,
+    Up = 265,
+    PageUp = 266,
+    PageDown = 267,
+    Home = 268,
+    End = 269,
+    CapsLock = 280,
+    ScrollLock = 281,
+    NumLock = 282,
+    PrintScreen = 283,
+    Pause = 284,
+    F1 = 290,
+    F2 = 291,
+    F3 = 292,
+    F4 = 293,
+    F5 = 294,
+    F6 = 295,
+    F7 = 296,
+    F8 = 297,
+    F9 = 298,
+    F10 = 299,
+    F11 = 300,
+    F12 = 301,
+    F13 = 302,
+    F14 = 303,
+    F15 = 304,
+    F16 = 305,
+    F17 = 306,
+    F18 = 307,
+    F19 = 308,
+    F20 = 309,
+    F21 = 310,
+    F22 = 311,
+    F23 = 312,
+    F24 = 313,
+    F25 = 314,
+    Kp0 = 320,
+    Kp1 = 321,
+    Kp2 = 322,
+    Kp3 = 323,
+    Kp4 = 324,
+    Kp5 = 325,
+    Kp6 = 326,
+    Kp7 = 327,
+    Kp8 = 328,
+    Kp9 = 329,
+    KpDecimal = 330,
+    KpDivide = 331,
+    KpMultiply = 332,
+    KpSubtract = 333,
+    KpAdd = 334,
+    KpEnter = 335,
+    KpEqual = 336,
+    LeftShift = 340,
+    LeftControl = 341,
+    LeftAlt = 342,
+    LeftSuper = 343,
+    RightShift = 344,
+    RightControl = 345,
+    RightAlt = 346,
+    RightSuper = 347,
+    Menu = 348,
+    Unknown = -1,
+}
+
+/// The modifier keys held during a key event, along with the Caps and Num lock state.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Modifiers {
+    bits: u8,
+}
+
+/// A character or text editing key typed this frame, including OS key repeats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextEdit {
+    Char(char),
+    Backspace,
+    Delete,
+    Enter,
+    Tab,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+}
 
 pub struct Keyboard {
     state: HashMap<Key, bool>,
     last_state: HashMap<Key, bool>,
+    scancode_state: HashMap<Scancode, bool>,
+    last_scancode_state: HashMap<Scancode, bool>,
+    modifiers: Modifiers,
+    last_modifiers: Modifiers,
+    typed: Vec<TextEdit>,
+    /// Scancode of each key on a US layout, as reported by the backend
+    scancodes: HashMap<Key, Scancode>,
 }
 
 impl Keyboard {
-    pub fn new(window: &mut glfw::Window) -> Keyboard {
-        window.set_key_polling(true);
+    pub fn new() -> Keyboard {
+        Keyboard::default()
+    }
 
+    /// Creates a `Keyboard` that finds physical keys by the given scancodes
+    pub fn with_scancodes(scancodes: HashMap<Key, Scancode>) -> Keyboard {
         Keyboard {
-            state: HashMap::new(),
-            last_state: HashMap::new(),
+            scancodes,
+            ..Keyboard::default()
         }
     }
 
-    pub fn process_event(&mut self, event: &glfw::WindowEvent) {
+    pub fn process_event(&mut self, event: &Event) {
         match event {
-            glfw::WindowEvent::Key(key, _, action, _) => {
-                use glfw::Action;
-                let is_pressed = *action == Action::Press || *action == Action::Repeat;
+            Event::Key(key, scancode, action, modifiers) => {
+                let is_pressed = action.is_pressed();
 
-                if self.state.contains_key(&key) {
-                    let value = self.state.get_mut(&key).unwrap();
+                if let Some(value) = self.state.get_mut(key) {
                     *value = is_pressed;
                 } else {
                     self.state.insert(*key, is_pressed);
                     self.last_state.insert(*key, false);
                 }
+
+                if let Some(value) = self.scancode_state.get_mut(scancode) {
+                    *value = is_pressed;
+                } else {
+                    self.scancode_state.insert(*scancode, is_pressed);
+                    self.last_scancode_state.insert(*scancode, false);
+                }
+
+                // Held modifiers come from the key state so they are current even for the
+                // modifier key's own event, the lock state is only known from GLFW
+                self.modifiers = self.held_modifiers() | (*modifiers & LOCK_MODIFIERS);
+
+                if *action != Action::Release {
+                    if let Some(edit) = TextEdit::from_key(*key) {
+                        self.typed.push(edit);
+                    }
+                }
             }
+            Event::Char(c) => self.typed.push(TextEdit::Char(*c)),
             _ => {}
         }
     }
@@ -41,6 +222,13 @@ impl Keyboard {
         for (k, v) in self.state.iter() {
             *(self.last_state.get_mut(k).unwrap()) = *v;
         }
+
+        for (k, v) in self.scancode_state.iter() {
+            *(self.last_scancode_state.get_mut(k).unwrap()) = *v;
+        }
+
+        self.last_modifiers = self.modifiers;
+        self.typed.clear();
     }
 
     pub fn get_key(&self, key: Key) -> bool {
@@ -58,4 +246,451 @@ impl Keyboard {
         let last = *self.last_state.get(&key).unwrap_or(&false);
         return last && curr != last;
     }
+
+    /// Returns the modifier keys currently held, along with the Caps and Num lock state
+    pub fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    /// Returns true if Caps Lock is on
+    pub fn caps_lock(&self) -> bool {
+        self.modifiers.contains(Modifiers::CAPS_LOCK)
+    }
+
+    /// Returns true if Num Lock is on
+    pub fn num_lock(&self) -> bool {
+        self.modifiers.contains(Modifiers::NUM_LOCK)
+    }
+
+    /// Returns true while key is held with exactly the given modifiers, e.g. `Control | Shift`.
+    /// Caps and Num lock are only checked when they are part of `modifiers`.
+    pub fn get_chord(&self, modifiers: Modifiers, key: Key) -> bool {
+        self.get_key(key) && modifiers_match(self.modifiers, modifiers)
+    }
+
+    /// Returns true on the frame a chord becomes held
+    pub fn get_chord_down(&self, modifiers: Modifiers, key: Key) -> bool {
+        let curr = self.get_chord(modifiers, key);
+        let last = *self.last_state.get(&key).unwrap_or(&false)
+            && modifiers_match(self.last_modifiers, modifiers);
+        curr && !last
+    }
+
+    /// Returns true on the frame a chord stops being held
+    pub fn get_chord_up(&self, modifiers: Modifiers, key: Key) -> bool {
+        let curr = self.get_chord(modifiers, key);
+        let last = *self.last_state.get(&key).unwrap_or(&false)
+            && modifiers_match(self.last_modifiers, modifiers);
+        last && !curr
+    }
+
+    /// Returns true while the key with the platform scancode is held
+    pub fn get_scancode(&self, scancode: Scancode) -> bool {
+        *self.scancode_state.get(&scancode).unwrap_or(&false)
+    }
+
+    pub fn get_scancode_down(&self, scancode: Scancode) -> bool {
+        let curr = *self.scancode_state.get(&scancode).unwrap_or(&false);
+        let last = *self.last_scancode_state.get(&scancode).unwrap_or(&false);
+        curr && curr != last
+    }
+
+    pub fn get_scancode_up(&self, scancode: Scancode) -> bool {
+        let curr = *self.scancode_state.get(&scancode).unwrap_or(&false);
+        let last = *self.last_scancode_state.get(&scancode).unwrap_or(&false);
+        last && curr != last
+    }
+
+    /// Returns true while the key at the physical location of `key` on a US layout is held,
+    /// so `Key::W` stays the top of WASD on AZERTY or Dvorak keyboards
+    pub fn get_physical_key(&self, key: Key) -> bool {
+        self.scancodes
+            .get(&key)
+            .is_some_and(|scancode| self.get_scancode(*scancode))
+    }
+
+    pub fn get_physical_key_down(&self, key: Key) -> bool {
+        self.scancodes
+            .get(&key)
+            .is_some_and(|scancode| self.get_scancode_down(*scancode))
+    }
+
+    pub fn get_physical_key_up(&self, key: Key) -> bool {
+        self.scancodes
+            .get(&key)
+            .is_some_and(|scancode| self.get_scancode_up(*scancode))
+    }
+
+    /// Returns the characters and editing keys typed this frame in order.
+    /// Characters composed by the OS input method arrive as single `TextEdit::Char`s.
+    pub fn typed(&self) -> &[TextEdit] {
+        &self.typed
+    }
+
+    /// Returns the text typed this frame, ignoring editing keys
+    pub fn typed_text(&self) -> String {
+        self.typed
+            .iter()
+            .filter_map(|edit| match edit {
+                TextEdit::Char(c) => Some(*c),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns how many times an editing key was typed this frame, counting repeats
+    pub fn typed_count(&self, edit: TextEdit) -> usize {
+        self.typed.iter().filter(|e| **e == edit).count()
+    }
+
+    /// Returns true if backspace was pressed or repeated this frame
+    pub fn backspace_typed(&self) -> bool {
+        self.typed_count(TextEdit::Backspace) > 0
+    }
+
+    /// Returns true if enter was pressed or repeated this frame
+    pub fn enter_typed(&self) -> bool {
+        self.typed_count(TextEdit::Enter) > 0
+    }
+
+    fn held_modifiers(&self) -> Modifiers {
+        let mut modifiers = Modifiers::empty();
+        let held = |left, right| self.get_key(left) || self.get_key(right);
+
+        if held(Key::LeftShift, Key::RightShift) {
+            modifiers |= Modifiers::SHIFT;
+        }
+        if held(Key::LeftControl, Key::RightControl) {
+            modifiers |= Modifiers::CONTROL;
+        }
+        if held(Key::LeftAlt, Key::RightAlt) {
+            modifiers |= Modifiers::ALT;
+        }
+        if held(Key::LeftSuper, Key::RightSuper) {
+            modifiers |= Modifiers::SUPER;
+        }
+
+        modifiers
+    }
+}
+
+impl Default for Keyboard {
+    /// Creates a `Keyboard` with nothing pressed that is only fed by `process_event`
+    fn default() -> Self {
+        Keyboard {
+            state: HashMap::new(),
+            last_state: HashMap::new(),
+            scancode_state: HashMap::new(),
+            last_scancode_state: HashMap::new(),
+            modifiers: Modifiers::empty(),
+            last_modifiers: Modifiers::empty(),
+            typed: Vec::new(),
+            scancodes: HashMap::new(),
+        }
+    }
+}
+
+const LOCK_MODIFIERS: Modifiers =
+    Modifiers::from_bits_truncate(Modifiers::CAPS_LOCK.bits() | Modifiers::NUM_LOCK.bits());
+
+/// Compares held modifiers exactly, lock modifiers only need to be on if wanted
+fn modifiers_match(held: Modifiers, wanted: Modifiers) -> bool {
+    held - LOCK_MODIFIERS == wanted - LOCK_MODIFIERS && held.contains(wanted & LOCK_MODIFIERS)
+}
+
+impl Modifiers {
+    pub const SHIFT: Modifiers = Modifiers { bits: 0x01 };
+    pub const CONTROL: Modifiers = Modifiers { bits: 0x02 };
+    pub const ALT: Modifiers = Modifiers { bits: 0x04 };
+    pub const SUPER: Modifiers = Modifiers { bits: 0x08 };
+    pub const CAPS_LOCK: Modifiers = Modifiers { bits: 0x10 };
+    pub const NUM_LOCK: Modifiers = Modifiers { bits: 0x20 };
+
+    const NAMES: [(Modifiers, &'static str); 6] = [
+        (Modifiers::SHIFT, "SHIFT"),
+        (Modifiers::CONTROL, "CONTROL"),
+        (Modifiers::ALT, "ALT"),
+        (Modifiers::SUPER, "SUPER"),
+        (Modifiers::CAPS_LOCK, "CAPS_LOCK"),
+        (Modifiers::NUM_LOCK, "NUM_LOCK"),
+    ];
+
+    pub const fn empty() -> Modifiers {
+        Modifiers { bits: 0 }
+    }
+
+    pub const fn bits(&self) -> u8 {
+        self.bits
+    }
+
+    /// Creates a set from raw bits, dropping bits that name no modifier
+    pub const fn from_bits_truncate(bits: u8) -> Modifiers {
+        Modifiers { bits: bits & 0x3f }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits == 0
+    }
+
+    /// Returns true if every modifier of `other` is in the set
+    pub fn contains(&self, other: Modifiers) -> bool {
+        self.bits & other.bits == other.bits
+    }
+}
+
+impl TextEdit {
+    /// Returns the editing key that a `Key` produces, if any
+    pub fn from_key(key: Key) -> Option<TextEdit> {
+        match key {
+            Key::Backspace => Some(TextEdit::Backspace),
+            Key::Delete => Some(TextEdit::Delete),
+            Key::Enter | Key::KpEnter => Some(TextEdit::Enter),
+            Key::Tab => Some(TextEdit::Tab),
+            Key::Left => Some(TextEdit::Left),
+            Key::Right => Some(TextEdit::Right),
+            Key::Up => Some(TextEdit::Up),
+            Key::Down => Some(TextEdit::Down),
+            Key::Home => Some(TextEdit::Home),
+            Key::End => Some(TextEdit::End),
+            _ => None,
+        }
+    }
+}
+
+/// Every known `Key`, excluding `Key::Unknown`
+pub const KEYS: [Key; 120] = [
+    Key::Space,
+    Key::Apostrophe,
+    Key::Comma,
+    Key::Minus,
+    Key::Period,
+    Key::Slash,
+    Key::Num0,
+    Key::Num1,
+    Key::Num2,
+    Key::Num3,
+    Key::Num4,
+    Key::Num5,
+    Key::Num6,
+    Key::Num7,
+    Key::Num8,
+    Key::Num9,
+    Key::Semicolon,
+    Key::Equal,
+    Key::A,
+    Key::B,
+    Key::C,
+    Key::D,
+    Key::E,
+    Key::F,
+    Key::G,
+    Key::H,
+    Key::I,
+    Key::J,
+    Key::K,
+    Key::L,
+    Key::M,
+    Key::N,
+    Key::O,
+    Key::P,
+    Key::Q,
+    Key::R,
+    Key::S,
+    Key::T,
+    Key::U,
+    Key::V,
+    Key::W,
+    Key::X,
+    Key::Y,
+    Key::Z,
+    Key::LeftBracket,
+    Key::Backslash,
+    Key::RightBracket,
+    Key::GraveAccent,
+    Key::World1,
+    Key::World2,
+    Key::Escape,
+    Key::Enter,
+    Key::Tab,
+    Key::Backspace,
+    Key::Insert,
+    Key::Delete,
+    Key::Right,
+    Key::Left,
+    Key::Down,
+    Key::Up,
+    Key::PageUp,
+    Key::PageDown,
+    Key::Home,
+    Key::End,
+    Key::CapsLock,
+    Key::ScrollLock,
+    Key::NumLock,
+    Key::PrintScreen,
+    Key::Pause,
+    Key::F1,
+    Key::F2,
+    Key::F3,
+    Key::F4,
+    Key::F5,
+    Key::F6,
+    Key::F7,
+    Key::F8,
+    Key::F9,
+    Key::F10,
+    Key::F11,
+    Key::F12,
+    Key::F13,
+    Key::F14,
+    Key::F15,
+    Key::F16,
+    Key::F17,
+    Key::F18,
+    Key::F19,
+    Key::F20,
+    Key::F21,
+    Key::F22,
+    Key::F23,
+    Key::F24,
+    Key::F25,
+    Key::Kp0,
+    Key::Kp1,
+    Key::Kp2,
+    Key::Kp3,
+    Key::Kp4,
+    Key::Kp5,
+    Key::Kp6,
+    Key::Kp7,
+    Key::Kp8,
+    Key::Kp9,
+    Key::KpDecimal,
+    Key::KpDivide,
+    Key::KpMultiply,
+    Key::KpSubtract,
+    Key::KpAdd,
+    Key::KpEnter,
+    Key::KpEqual,
+    Key::LeftShift,
+    Key::LeftControl,
+    Key::LeftAlt,
+    Key::LeftSuper,
+    Key::RightShift,
+    Key::RightControl,
+    Key::RightAlt,
+    Key::RightSuper,
+    Key::Menu,
+];
+
+/// Returns the `Key` with the given name, e.g. "Space" or "LeftShift"
+pub fn key_from_name(name: &str) -> Option<Key> {
+    KEYS.iter()
+        .copied()
+        .find(|key| format!("{:?}", key) == name)
+}
+
+// ====================================
+// Other Implementations
+// ====================================
+
+impl BitOr for Modifiers {
+    type Output = Modifiers;
+
+    fn bitor(self, other: Modifiers) -> Modifiers {
+        Modifiers {
+            bits: self.bits | other.bits,
+        }
+    }
+}
+
+impl BitOrAssign for Modifiers {
+    fn bitor_assign(&mut self, other: Modifiers) {
+        self.bits |= other.bits;
+    }
+}
+
+impl BitAnd for Modifiers {
+    type Output = Modifiers;
+
+    fn bitand(self, other: Modifiers) -> Modifiers {
+        Modifiers {
+            bits: self.bits & other.bits,
+        }
+    }
+}
+
+/// Removes the modifiers of `other` from the set
+impl Sub for Modifiers {
+    type Output = Modifiers;
+
+    fn sub(self, other: Modifiers) -> Modifiers {
+        Modifiers {
+            bits: self.bits & !other.bits,
+        }
+    }
+}
+
+impl fmt::Debug for Modifiers {
+    /// Lists the set like `SHIFT | CONTROL`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "(empty)");
+        }
+        let names: Vec<&str> = Modifiers::NAMES
+            .iter()
+            .filter(|(modifier, _)| self.contains(*modifier))
+            .map(|(_, name)| *name)
+            .collect();
+        write!(f, "{}", names.join(" | "))
+    }
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod keyboard_tests {
+
+    use super::*;
+
+    #[test]
+    fn chord_requires_exact_modifiers() {
+        let held = Modifiers::CONTROL | Modifiers::SHIFT;
+        assert!(modifiers_match(held, Modifiers::CONTROL | Modifiers::SHIFT));
+        assert!(!modifiers_match(held, Modifiers::CONTROL));
+    }
+
+    #[test]
+    fn lock_modifiers_are_ignored_unless_wanted() {
+        let held = Modifiers::CONTROL | Modifiers::CAPS_LOCK;
+        assert!(modifiers_match(held, Modifiers::CONTROL));
+        assert!(modifiers_match(
+            held,
+            Modifiers::CONTROL | Modifiers::CAPS_LOCK
+        ));
+        assert!(!modifiers_match(
+            Modifiers::CONTROL,
+            Modifiers::CONTROL | Modifiers::NUM_LOCK
+        ));
+    }
+
+    #[test]
+    fn modifier_set_operations() {
+        let held = Modifiers::SHIFT | Modifiers::CAPS_LOCK;
+        assert!(held.contains(Modifiers::SHIFT));
+        assert!(!held.contains(Modifiers::SHIFT | Modifiers::ALT));
+        assert_eq!(held - LOCK_MODIFIERS, Modifiers::SHIFT);
+        assert_eq!(Modifiers::from_bits_truncate(0xff).bits(), 0x3f);
+        assert_eq!(format!("{:?}", held), "SHIFT | CAPS_LOCK");
+    }
+
+    #[test]
+    fn physical_keys_use_backend_scancodes() {
+        let scancodes = vec![(Key::W, 25)].into_iter().collect();
+        let mut keyboard = Keyboard::with_scancodes(scancodes);
+        keyboard.process_event(&Event::Key(Key::Z, 25, Action::Press, Modifiers::empty()));
+        assert!(keyboard.get_physical_key(Key::W));
+        assert!(keyboard.get_physical_key_down(Key::W));
+        assert!(!keyboard.get_physical_key(Key::Z));
+    }
 }
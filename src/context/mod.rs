@@ -1,3 +1,11 @@
+pub mod backend;
+pub mod event;
+pub mod gamepad;
+pub mod glfw_backend;
+pub mod headless;
+pub mod input_map;
 pub mod keyboard;
 pub mod mouse;
+pub mod recording;
+pub mod text_input;
 pub mod window;
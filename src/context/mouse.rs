@@ -1,43 +1,100 @@
 #![allow(dead_code)]
 
 use std::collections::HashMap;
+use std::convert::TryFrom;
 
-pub type MouseButton = glfw::MouseButton;
+use super::event::Event;
+use crate::graphics::{color::Color, texture::Texture};
 
+/// A mouse button, see `MouseButton::LEFT`, `RIGHT` and `MIDDLE` for the common ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum MouseButton {
+    Button1,
+    Button2,
+    Button3,
+    Button4,
+    Button5,
+    Button6,
+    Button7,
+    Button8,
+}
+
+/// How the cursor behaves while over the window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CursorMode {
+    Normal,
+    Hidden,
+    /// Hides the cursor and locks it to the window for mouse-look style controls,
+    /// using raw unaccelerated motion where the platform supports it
+    Captured,
+}
+
+/// One of the cursor shapes provided by the system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StandardCursor {
+    Arrow,
+    IBeam,
+    Crosshair,
+    Hand,
+    HResize,
+    VResize,
+}
+
+/// A cursor image that can be shown with `Window::set_cursor`, the backend creates it when set.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cursor {
+    Standard(StandardCursor),
+    /// Pixels in rows from the top, the hotspot is the clicking point
+    Image {
+        width: u32,
+        height: u32,
+        pixels: Vec<Color>,
+        hotspot: (u32, u32),
+    },
+}
+
+#[derive(Default)]
 pub struct Mouse {
     state: HashMap<MouseButton, bool>,
     last_state: HashMap<MouseButton, bool>,
-    position: (f32, f32),
+    position: Option<(f32, f32)>,
+    delta: (f32, f32),
+    scroll: (f32, f32),
+    inside: bool,
+    last_inside: bool,
 }
 
 impl Mouse {
-    pub fn new(window: &mut glfw::Window) -> Mouse {
-        window.set_mouse_button_polling(true);
-        window.set_cursor_pos_polling(true);
-
-        Mouse {
-            state: HashMap::new(),
-            last_state: HashMap::new(),
-            position: (0.0, 0.0),
-        }
+    pub fn new() -> Mouse {
+        Mouse::default()
     }
 
-    pub fn process_event(&mut self, event: &glfw::WindowEvent) {
+    pub fn process_event(&mut self, event: &Event) {
         match event {
-            glfw::WindowEvent::MouseButton(button, action, _) => {
-                use glfw::Action;
-                let is_pressed = *action == Action::Press || *action == Action::Repeat;
+            Event::MouseButton(button, action) => {
+                let is_pressed = action.is_pressed();
 
-                if self.state.contains_key(&button) {
-                    let value = self.state.get_mut(&button).unwrap();
+                if let Some(value) = self.state.get_mut(button) {
                     *value = is_pressed;
                 } else {
                     self.state.insert(*button, is_pressed);
                     self.last_state.insert(*button, is_pressed);
                 }
             }
-            glfw::WindowEvent::CursorPos(x, y) => {
-                self.position = ((*x as f32), (*y as f32));
+            Event::MouseMove(x, y) => {
+                if let Some((last_x, last_y)) = self.position {
+                    self.delta.0 += *x - last_x;
+                    self.delta.1 += *y - last_y;
+                }
+
+                self.position = Some((*x, *y));
+            }
+            Event::MouseEnter(inside) => {
+                self.inside = *inside;
+            }
+            Event::Scroll(x, y) => {
+                self.scroll.0 += *x;
+                self.scroll.1 += *y;
             }
             _ => {}
         }
@@ -47,6 +104,10 @@ impl Mouse {
         for (k, v) in self.state.iter() {
             *(self.last_state.get_mut(k).unwrap()) = *v;
         }
+
+        self.delta = (0.0, 0.0);
+        self.scroll = (0.0, 0.0);
+        self.last_inside = self.inside;
     }
 
     pub fn get_button(&self, button: MouseButton) -> bool {
@@ -66,6 +127,153 @@ impl Mouse {
     }
 
     pub fn position(&self) -> (f32, f32) {
-        self.position
+        self.position.unwrap_or((0.0, 0.0))
+    }
+
+    /// Returns how far the cursor moved this frame
+    pub fn delta(&self) -> (f32, f32) {
+        self.delta
+    }
+
+    /// Returns how far the wheel scrolled this frame, y is positive when scrolling up
+    pub fn scroll(&self) -> (f32, f32) {
+        self.scroll
+    }
+
+    /// Returns true while the cursor is over the window
+    pub fn is_inside(&self) -> bool {
+        self.inside
+    }
+
+    /// Returns true on the frame the cursor entered the window
+    pub fn entered(&self) -> bool {
+        self.inside && !self.last_inside
+    }
+
+    /// Returns true on the frame the cursor left the window
+    pub fn left(&self) -> bool {
+        !self.inside && self.last_inside
+    }
+}
+
+impl MouseButton {
+    pub const LEFT: MouseButton = MouseButton::Button1;
+    pub const RIGHT: MouseButton = MouseButton::Button2;
+    pub const MIDDLE: MouseButton = MouseButton::Button3;
+
+    /// Returns the button with a zero based index, 0 being the left button
+    pub fn from_i32(index: i32) -> Option<MouseButton> {
+        MOUSE_BUTTONS.get(usize::try_from(index).ok()?).copied()
+    }
+}
+
+impl Cursor {
+    /// Creates a cursor from the pixels of a `Texture`, the hotspot is the clicking point
+    pub fn from_texture(texture: &Texture, hotspot_x: u32, hotspot_y: u32) -> Cursor {
+        let mut pixels = Vec::with_capacity(texture.width() * texture.height());
+        for y in 0..texture.height() {
+            for x in 0..texture.width() {
+                pixels.push(texture.get_pixel(x, y));
+            }
+        }
+
+        Cursor::Image {
+            width: texture.width() as u32,
+            height: texture.height() as u32,
+            pixels,
+            hotspot: (hotspot_x, hotspot_y),
+        }
+    }
+
+    /// Creates one of the cursor shapes provided by the system
+    pub fn standard(shape: StandardCursor) -> Cursor {
+        Cursor::Standard(shape)
+    }
+}
+
+/// Every `MouseButton` in index order
+pub const MOUSE_BUTTONS: [MouseButton; 8] = [
+    MouseButton::Button1,
+    MouseButton::Button2,
+    MouseButton::Button3,
+    MouseButton::Button4,
+    MouseButton::Button5,
+    MouseButton::Button6,
+    MouseButton::Button7,
+    MouseButton::Button8,
+];
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod mouse_tests {
+
+    use super::*;
+
+    #[test]
+    fn delta_resets_each_frame() {
+        let mut mouse = Mouse::default();
+        mouse.process_event(&Event::MouseMove(10.0, 10.0));
+        assert_eq!(mouse.delta(), (0.0, 0.0));
+
+        mouse.process_event(&Event::MouseMove(12.0, 7.0));
+        mouse.process_event(&Event::MouseMove(15.0, 8.0));
+        assert_eq!(mouse.delta(), (5.0, -2.0));
+        assert_eq!(mouse.position(), (15.0, 8.0));
+
+        mouse.update();
+        assert_eq!(mouse.delta(), (0.0, 0.0));
+        mouse.process_event(&Event::MouseMove(16.0, 8.0));
+        assert_eq!(mouse.delta(), (1.0, 0.0));
+    }
+
+    #[test]
+    fn scroll_is_summed_per_frame() {
+        let mut mouse = Mouse::default();
+        mouse.process_event(&Event::Scroll(0.0, 1.0));
+        mouse.process_event(&Event::Scroll(0.5, 2.0));
+        assert_eq!(mouse.scroll(), (0.5, 3.0));
+
+        mouse.update();
+        assert_eq!(mouse.scroll(), (0.0, 0.0));
+    }
+
+    #[test]
+    fn enter_and_leave() {
+        let mut mouse = Mouse::default();
+        assert!(!mouse.is_inside());
+
+        mouse.process_event(&Event::MouseEnter(true));
+        assert!(mouse.is_inside());
+        assert!(mouse.entered());
+        mouse.update();
+        assert!(mouse.is_inside());
+        assert!(!mouse.entered());
+
+        mouse.process_event(&Event::MouseEnter(false));
+        assert!(!mouse.is_inside());
+        assert!(mouse.left());
+        mouse.update();
+        assert!(!mouse.left());
+    }
+
+    #[test]
+    fn cursor_from_texture_keeps_pixels() {
+        let mut texture = Texture::new(2, 1);
+        texture.set_pixel(1, 0, Color::from_rgba(1, 2, 3, 4));
+        match Cursor::from_texture(&texture, 1, 0) {
+            Cursor::Image {
+                width,
+                height,
+                pixels,
+                hotspot,
+            } => {
+                assert_eq!((width, height, hotspot), (2, 1, (1, 0)));
+                assert_eq!(pixels[1], Color::from_rgba(1, 2, 3, 4));
+            }
+            cursor => panic!("unexpected cursor {:?}", cursor),
+        }
     }
 }
@@ -0,0 +1,537 @@
+#![allow(dead_code)]
+
+use std::convert::TryInto;
+use std::path::PathBuf;
+
+use super::{
+    event::{Action, Event},
+    gamepad::{GamepadState, Gamepads, AXIS_COUNT, BUTTON_COUNT},
+    keyboard::{Key, Keyboard, Modifiers, KEYS},
+    mouse::{Mouse, MouseButton},
+};
+
+const MAGIC: &[u8; 4] = b"WREC";
+const VERSION: u8 = 1;
+
+#[derive(Debug)]
+pub enum RecordingError {
+    Io(std::io::Error),
+    InvalidHeader,
+    UnexpectedEnd,
+    InvalidData,
+}
+
+/// The input of a single frame of a `Recording`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedFrame {
+    pub delta_time: f32,
+    pub events: Vec<Event>,
+    /// Gamepad slots whose state changed this frame
+    pub gamepads: Vec<(usize, Option<GamepadState>)>,
+}
+
+/// Every input event and frame delta of a session, used to replay bugs exactly.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Recording {
+    frames: Vec<RecordedFrame>,
+}
+
+/// Feeds a `Recording` back frame by frame.
+#[derive(Debug, Clone)]
+pub struct Playback {
+    recording: Recording,
+    frame: usize,
+    time: f64,
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl Recording {
+    pub fn new() -> Recording {
+        Recording::default()
+    }
+
+    /// Reads a recording saved with `Recording::save`
+    pub fn load(path: &str) -> Result<Recording, RecordingError> {
+        let bytes = std::fs::read(path).map_err(RecordingError::Io)?;
+        Recording::from_bytes(&bytes)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), RecordingError> {
+        std::fs::write(path, self.to_bytes()).map_err(RecordingError::Io)
+    }
+
+    /// Records a frame, gamepad connection events are left out as they follow from the states
+    pub fn record_frame<'a>(
+        &mut self,
+        delta_time: f32,
+        events: impl Iterator<Item = &'a Event>,
+        gamepads: &Gamepads,
+    ) {
+        let events = events.filter(|event| is_input(event)).cloned().collect();
+        let gamepads = gamepads
+            .iter()
+            .filter(|pad| pad.changed())
+            .map(|pad| (pad.id(), pad.state().cloned()))
+            .collect();
+
+        self.frames.push(RecordedFrame {
+            delta_time,
+            events,
+            gamepads,
+        });
+    }
+
+    pub fn frames(&self) -> &[RecordedFrame] {
+        &self.frames
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Encodes the recording in a compact binary format
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        write_u32(&mut out, self.frames.len() as u32);
+
+        for frame in self.frames.iter() {
+            write_f32(&mut out, frame.delta_time);
+            write_u32(&mut out, frame.events.len() as u32);
+            for event in frame.events.iter() {
+                write_event(&mut out, event);
+            }
+
+            out.push(frame.gamepads.len() as u8);
+            for (id, state) in frame.gamepads.iter() {
+                out.push(*id as u8);
+                write_gamepad(&mut out, state);
+            }
+        }
+
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Recording, RecordingError> {
+        let mut reader = Reader { bytes, position: 0 };
+        if reader.take(4)? != MAGIC || reader.u8()? != VERSION {
+            return Err(RecordingError::InvalidHeader);
+        }
+
+        let frame_count = reader.u32()?;
+        let mut frames = Vec::new();
+        for _ in 0..frame_count {
+            let delta_time = reader.f32()?;
+
+            let event_count = reader.u32()?;
+            let mut events = Vec::new();
+            for _ in 0..event_count {
+                events.push(read_event(&mut reader)?);
+            }
+
+            let gamepad_count = reader.u8()?;
+            let mut gamepads = Vec::new();
+            for _ in 0..gamepad_count {
+                let id = reader.u8()? as usize;
+                gamepads.push((id, read_gamepad(&mut reader)?));
+            }
+
+            frames.push(RecordedFrame {
+                delta_time,
+                events,
+                gamepads,
+            });
+        }
+
+        Ok(Recording { frames })
+    }
+}
+
+impl RecordedFrame {
+    /// Feeds the frame's input into the input state, as the `Window` does with GLFW events
+    pub fn apply(&self, keyboard: &mut Keyboard, mouse: &mut Mouse, gamepads: &mut Gamepads) {
+        for event in self.events.iter() {
+            keyboard.process_event(event);
+            mouse.process_event(event);
+        }
+
+        for (id, state) in self.gamepads.iter() {
+            if let Some(pad) = gamepads.get_mut(*id) {
+                pad.process_state(state.clone());
+            }
+        }
+    }
+}
+
+impl Playback {
+    pub fn new(recording: Recording) -> Playback {
+        Playback {
+            recording,
+            frame: 0,
+            time: 0.0,
+        }
+    }
+
+    /// Returns the next frame and the playback time at its end, `None` once finished
+    pub fn next_frame(&mut self) -> Option<(&RecordedFrame, f64)> {
+        let frame = self.recording.frames.get(self.frame)?;
+        self.frame += 1;
+        self.time += frame.delta_time as f64;
+        Some((frame, self.time))
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.frame >= self.recording.len()
+    }
+
+    /// Returns the index of the next frame to be played
+    pub fn position(&self) -> usize {
+        self.frame
+    }
+}
+
+fn is_input(event: &Event) -> bool {
+    !matches!(
+        event,
+        Event::GamepadConnected(_) | Event::GamepadDisconnected(_)
+    )
+}
+
+// ====================================
+// Encoding
+// ====================================
+
+fn write_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn write_i32(out: &mut Vec<u8>, value: i32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn write_f32(out: &mut Vec<u8>, value: f32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn write_str(out: &mut Vec<u8>, value: &str) {
+    write_u32(out, value.len() as u32);
+    out.extend_from_slice(value.as_bytes());
+}
+
+fn write_action(out: &mut Vec<u8>, action: Action) {
+    out.push(match action {
+        Action::Press => 0,
+        Action::Release => 1,
+        Action::Repeat => 2,
+    });
+}
+
+fn write_event(out: &mut Vec<u8>, event: &Event) {
+    match event {
+        Event::Key(key, scancode, action, modifiers) => {
+            out.push(0);
+            write_i32(out, *key as i32);
+            write_i32(out, *scancode);
+            write_action(out, *action);
+            out.push(modifiers.bits());
+        }
+        Event::Char(c) => {
+            out.push(1);
+            write_u32(out, *c as u32);
+        }
+        Event::MouseButton(button, action) => {
+            out.push(2);
+            out.push(*button as u8);
+            write_action(out, *action);
+        }
+        Event::MouseMove(x, y) => {
+            out.push(3);
+            write_f32(out, *x);
+            write_f32(out, *y);
+        }
+        Event::MouseEnter(inside) => {
+            out.push(4);
+            out.push(*inside as u8);
+        }
+        Event::Scroll(x, y) => {
+            out.push(5);
+            write_f32(out, *x);
+            write_f32(out, *y);
+        }
+        Event::Resize(width, height) => {
+            out.push(6);
+            write_u32(out, *width);
+            write_u32(out, *height);
+        }
+        Event::Focus(focused) => {
+            out.push(7);
+            out.push(*focused as u8);
+        }
+        Event::FileDrop(paths) => {
+            out.push(8);
+            write_u32(out, paths.len() as u32);
+            for path in paths.iter() {
+                write_str(out, &path.to_string_lossy());
+            }
+        }
+        Event::GamepadConnected(id) => {
+            out.push(9);
+            out.push(*id as u8);
+        }
+        Event::GamepadDisconnected(id) => {
+            out.push(10);
+            out.push(*id as u8);
+        }
+    }
+}
+
+fn write_gamepad(out: &mut Vec<u8>, state: &Option<GamepadState>) {
+    let state = match state {
+        Some(state) => state,
+        None => {
+            out.push(0);
+            return;
+        }
+    };
+
+    out.push(1);
+    write_str(out, &state.name);
+    out.push(state.is_mapped as u8);
+
+    let buttons = state
+        .buttons
+        .iter()
+        .enumerate()
+        .fold(0u32, |bits, (index, pressed)| {
+            bits | ((*pressed as u32) << index)
+        });
+    write_u32(out, buttons);
+
+    for axis in state.axes.iter() {
+        write_f32(out, *axis);
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], RecordingError> {
+        let end = self.position + count;
+        if end > self.bytes.len() {
+            return Err(RecordingError::UnexpectedEnd);
+        }
+
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, RecordingError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, RecordingError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn i32(&mut self) -> Result<i32, RecordingError> {
+        Ok(i32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn f32(&mut self) -> Result<f32, RecordingError> {
+        Ok(f32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn bool(&mut self) -> Result<bool, RecordingError> {
+        Ok(self.u8()? != 0)
+    }
+
+    fn string(&mut self) -> Result<String, RecordingError> {
+        let length = self.u32()? as usize;
+        let bytes = self.take(length)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| RecordingError::InvalidData)
+    }
+
+    fn action(&mut self) -> Result<Action, RecordingError> {
+        match self.u8()? {
+            0 => Ok(Action::Press),
+            1 => Ok(Action::Release),
+            2 => Ok(Action::Repeat),
+            _ => Err(RecordingError::InvalidData),
+        }
+    }
+}
+
+fn read_event(reader: &mut Reader) -> Result<Event, RecordingError> {
+    let event = match reader.u8()? {
+        0 => {
+            let code = reader.i32()?;
+            let key = KEYS
+                .iter()
+                .copied()
+                .find(|key| *key as i32 == code)
+                .unwrap_or(Key::Unknown);
+            let scancode = reader.i32()?;
+            let action = reader.action()?;
+            let modifiers = Modifiers::from_bits_truncate(reader.u8()?);
+            Event::Key(key, scancode, action, modifiers)
+        }
+        1 => Event::Char(std::char::from_u32(reader.u32()?).ok_or(RecordingError::InvalidData)?),
+        2 => {
+            let button =
+                MouseButton::from_i32(reader.u8()? as i32).ok_or(RecordingError::InvalidData)?;
+            Event::MouseButton(button, reader.action()?)
+        }
+        3 => Event::MouseMove(reader.f32()?, reader.f32()?),
+        4 => Event::MouseEnter(reader.bool()?),
+        5 => Event::Scroll(reader.f32()?, reader.f32()?),
+        6 => Event::Resize(reader.u32()?, reader.u32()?),
+        7 => Event::Focus(reader.bool()?),
+        8 => {
+            let count = reader.u32()?;
+            let mut paths = Vec::new();
+            for _ in 0..count {
+                paths.push(PathBuf::from(reader.string()?));
+            }
+            Event::FileDrop(paths)
+        }
+        9 => Event::GamepadConnected(reader.u8()? as usize),
+        10 => Event::GamepadDisconnected(reader.u8()? as usize),
+        _ => return Err(RecordingError::InvalidData),
+    };
+
+    Ok(event)
+}
+
+fn read_gamepad(reader: &mut Reader) -> Result<Option<GamepadState>, RecordingError> {
+    if !reader.bool()? {
+        return Ok(None);
+    }
+
+    let name = reader.string()?;
+    let is_mapped = reader.bool()?;
+
+    let bits = reader.u32()?;
+    let mut buttons = [false; BUTTON_COUNT];
+    for (index, pressed) in buttons.iter_mut().enumerate() {
+        *pressed = bits & (1 << index) != 0;
+    }
+
+    let mut axes = [0.0; AXIS_COUNT];
+    for axis in axes.iter_mut() {
+        *axis = reader.f32()?;
+    }
+
+    Ok(Some(GamepadState {
+        name,
+        is_mapped,
+        buttons,
+        axes,
+    }))
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod recording_tests {
+
+    use super::*;
+
+    fn sample_recording() -> Recording {
+        let mut recording = Recording::new();
+        let mut gamepads = Gamepads::new();
+
+        let events = [
+            Event::Key(Key::W, 25, Action::Press, Modifiers::SHIFT),
+            Event::Char('é'),
+            Event::MouseButton(MouseButton::Button1, Action::Press),
+            Event::MouseMove(10.5, 20.0),
+            Event::Scroll(0.0, -1.0),
+            Event::FileDrop(vec![PathBuf::from("level.tmx")]),
+        ];
+        recording.record_frame(0.016, events.iter(), &gamepads);
+
+        gamepads
+            .get_mut(0)
+            .unwrap()
+            .process_state(Some(GamepadState {
+                name: String::from("Pad"),
+                is_mapped: true,
+                buttons: [true; BUTTON_COUNT],
+                axes: [0.5; AXIS_COUNT],
+            }));
+        let events = [
+            Event::Key(Key::W, 25, Action::Release, Modifiers::empty()),
+            Event::GamepadConnected(0),
+        ];
+        recording.record_frame(0.017, events.iter(), &gamepads);
+
+        recording
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let recording = sample_recording();
+        let decoded = Recording::from_bytes(&recording.to_bytes()).unwrap();
+        assert_eq!(decoded, recording);
+    }
+
+    #[test]
+    fn connection_events_are_not_recorded() {
+        let recording = sample_recording();
+        assert_eq!(recording.frames()[1].events.len(), 1);
+        assert_eq!(recording.frames()[1].gamepads.len(), 1);
+    }
+
+    #[test]
+    fn truncated_data_is_rejected() {
+        let bytes = sample_recording().to_bytes();
+        assert!(matches!(
+            Recording::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(RecordingError::UnexpectedEnd)
+        ));
+        assert!(matches!(
+            Recording::from_bytes(b"NOPE"),
+            Err(RecordingError::InvalidHeader)
+        ));
+    }
+
+    #[test]
+    fn playback_replays_input() {
+        let mut keyboard = Keyboard::default();
+        let mut mouse = Mouse::default();
+        let mut gamepads = Gamepads::new();
+        let mut playback = Playback::new(sample_recording());
+
+        let (frame, time) = playback.next_frame().unwrap();
+        assert_eq!(time, 0.016f32 as f64);
+        frame.apply(&mut keyboard, &mut mouse, &mut gamepads);
+        assert!(keyboard.get_key_down(Key::W));
+        assert!(mouse.get_button(MouseButton::Button1));
+        assert_eq!(mouse.position(), (10.5, 20.0));
+        keyboard.update();
+        mouse.update();
+        gamepads.update();
+
+        let (frame, _) = playback.next_frame().unwrap();
+        frame.apply(&mut keyboard, &mut mouse, &mut gamepads);
+        assert!(keyboard.get_key_up(Key::W));
+        assert!(gamepads.get(0).unwrap().just_connected());
+
+        assert!(playback.next_frame().is_none());
+        assert!(playback.is_finished());
+    }
+}
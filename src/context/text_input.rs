@@ -0,0 +1,360 @@
+#![allow(dead_code)]
+
+use super::{
+    keyboard::{Key, Modifiers, TextEdit},
+    window::Window,
+};
+
+/// Editable single line of text with a cursor and selection, e.g. a name or chat field.
+/// Positions are measured in characters, not bytes.
+#[derive(Debug, Clone, Default)]
+pub struct TextInput {
+    chars: Vec<char>,
+    cursor: usize,
+    anchor: Option<usize>,
+    max_length: Option<usize>,
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl TextInput {
+    /// Creates an empty `TextInput`
+    pub fn new() -> TextInput {
+        TextInput::default()
+    }
+
+    /// Creates a `TextInput` containing text with the cursor at the end
+    pub fn from_text(text: &str) -> TextInput {
+        let chars: Vec<char> = text.chars().collect();
+        let cursor = chars.len();
+
+        TextInput {
+            chars,
+            cursor,
+            ..TextInput::default()
+        }
+    }
+
+    /// Limits the number of characters the input accepts
+    pub fn with_max_length(mut self, max_length: usize) -> TextInput {
+        self.max_length = Some(max_length);
+        self.chars.truncate(max_length);
+        self.cursor = self.cursor.min(max_length);
+        self
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl TextInput {
+    /// Applies this frame's typed text, editing keys and clipboard shortcuts.
+    /// Returns true if enter was typed.
+    pub fn update(&mut self, window: &mut Window) -> bool {
+        let keyboard = window.keyboard();
+        let shift = keyboard.modifiers().contains(Modifiers::SHIFT);
+        let control = keyboard.modifiers().contains(Modifiers::CONTROL);
+        let select_all = keyboard.get_chord_down(Modifiers::CONTROL, Key::A);
+        let copy = keyboard.get_chord_down(Modifiers::CONTROL, Key::C);
+        let cut = keyboard.get_chord_down(Modifiers::CONTROL, Key::X);
+        let paste = keyboard.get_chord_down(Modifiers::CONTROL, Key::V);
+        let typed = keyboard.typed().to_vec();
+
+        let mut submitted = false;
+        for edit in typed {
+            match edit {
+                TextEdit::Char(c) => self.insert_char(c),
+                TextEdit::Backspace => self.backspace(),
+                TextEdit::Delete => self.delete(),
+                TextEdit::Left if control => self.move_word_left(shift),
+                TextEdit::Right if control => self.move_word_right(shift),
+                TextEdit::Left => self.move_left(shift),
+                TextEdit::Right => self.move_right(shift),
+                TextEdit::Home | TextEdit::Up => self.move_home(shift),
+                TextEdit::End | TextEdit::Down => self.move_end(shift),
+                TextEdit::Enter => submitted = true,
+                TextEdit::Tab => {}
+            }
+        }
+
+        if select_all {
+            self.select_all();
+        }
+
+        if copy {
+            if let Some(text) = self.copy() {
+                window.set_clipboard(&text);
+            }
+        } else if cut {
+            if let Some(text) = self.cut() {
+                window.set_clipboard(&text);
+            }
+        } else if paste {
+            if let Some(text) = window.clipboard() {
+                self.paste(&text);
+            }
+        }
+
+        submitted
+    }
+
+    /// Returns the current text
+    pub fn text(&self) -> String {
+        self.chars.iter().collect()
+    }
+
+    /// Returns the number of characters
+    pub fn len(&self) -> usize {
+        self.chars.len()
+    }
+
+    /// Returns true if there is no text
+    pub fn is_empty(&self) -> bool {
+        self.chars.is_empty()
+    }
+
+    /// Returns the cursor position
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Returns the selected range as (start, end), if anything is selected
+    pub fn selection(&self) -> Option<(usize, usize)> {
+        match self.anchor {
+            Some(anchor) if anchor != self.cursor => {
+                Some((anchor.min(self.cursor), anchor.max(self.cursor)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the selected text, if anything is selected
+    pub fn selected_text(&self) -> Option<String> {
+        self.selection()
+            .map(|(start, end)| self.chars[start..end].iter().collect())
+    }
+
+    /// Replaces the text and moves the cursor to the end
+    pub fn set_text(&mut self, text: &str) {
+        self.chars.clear();
+        self.cursor = 0;
+        self.anchor = None;
+        self.insert_str(text);
+    }
+
+    /// Removes all text
+    pub fn clear(&mut self) {
+        self.set_text("");
+    }
+
+    /// Inserts a character at the cursor, replacing the selection
+    pub fn insert_char(&mut self, c: char) {
+        if c.is_control() {
+            return;
+        }
+
+        self.delete_selection();
+        if self.max_length.is_none_or(|max| self.chars.len() < max) {
+            self.chars.insert(self.cursor, c);
+            self.cursor += 1;
+        }
+    }
+
+    /// Inserts text at the cursor, replacing the selection
+    pub fn insert_str(&mut self, text: &str) {
+        self.delete_selection();
+        for c in text.chars() {
+            self.insert_char(c);
+        }
+    }
+
+    /// Deletes the selection or the character before the cursor
+    pub fn backspace(&mut self) {
+        if !self.delete_selection() && self.cursor > 0 {
+            self.cursor -= 1;
+            self.chars.remove(self.cursor);
+        }
+    }
+
+    /// Deletes the selection or the character after the cursor
+    pub fn delete(&mut self) {
+        if !self.delete_selection() && self.cursor < self.chars.len() {
+            self.chars.remove(self.cursor);
+        }
+    }
+
+    /// Moves the cursor one character left, extending the selection if `select` is set
+    pub fn move_left(&mut self, select: bool) {
+        match self.selection() {
+            Some((start, _)) if !select => self.move_to(start, false),
+            _ => self.move_to(self.cursor.saturating_sub(1), select),
+        }
+    }
+
+    /// Moves the cursor one character right, extending the selection if `select` is set
+    pub fn move_right(&mut self, select: bool) {
+        match self.selection() {
+            Some((_, end)) if !select => self.move_to(end, false),
+            _ => self.move_to(self.cursor + 1, select),
+        }
+    }
+
+    /// Moves the cursor to the start of the previous word
+    pub fn move_word_left(&mut self, select: bool) {
+        let mut position = self.cursor;
+        while position > 0 && self.chars[position - 1].is_whitespace() {
+            position -= 1;
+        }
+        while position > 0 && !self.chars[position - 1].is_whitespace() {
+            position -= 1;
+        }
+        self.move_to(position, select);
+    }
+
+    /// Moves the cursor to the end of the next word
+    pub fn move_word_right(&mut self, select: bool) {
+        let mut position = self.cursor;
+        while position < self.chars.len() && self.chars[position].is_whitespace() {
+            position += 1;
+        }
+        while position < self.chars.len() && !self.chars[position].is_whitespace() {
+            position += 1;
+        }
+        self.move_to(position, select);
+    }
+
+    /// Moves the cursor to the start of the text
+    pub fn move_home(&mut self, select: bool) {
+        self.move_to(0, select);
+    }
+
+    /// Moves the cursor to the end of the text
+    pub fn move_end(&mut self, select: bool) {
+        self.move_to(self.chars.len(), select);
+    }
+
+    /// Selects all of the text
+    pub fn select_all(&mut self) {
+        self.anchor = Some(0);
+        self.cursor = self.chars.len();
+    }
+
+    /// Returns the selected text for the clipboard
+    pub fn copy(&self) -> Option<String> {
+        self.selected_text()
+    }
+
+    /// Removes and returns the selected text for the clipboard
+    pub fn cut(&mut self) -> Option<String> {
+        let text = self.selected_text();
+        self.delete_selection();
+        text
+    }
+
+    /// Inserts clipboard text at the cursor, line breaks are dropped
+    pub fn paste(&mut self, text: &str) {
+        let line: String = text.chars().filter(|c| *c != '\n' && *c != '\r').collect();
+        self.insert_str(&line);
+    }
+
+    fn move_to(&mut self, position: usize, select: bool) {
+        if select {
+            self.anchor.get_or_insert(self.cursor);
+        } else {
+            self.anchor = None;
+        }
+
+        self.cursor = position.min(self.chars.len());
+    }
+
+    fn delete_selection(&mut self) -> bool {
+        match self.selection() {
+            Some((start, end)) => {
+                self.chars.drain(start..end);
+                self.cursor = start;
+                self.anchor = None;
+                true
+            }
+            None => {
+                self.anchor = None;
+                false
+            }
+        }
+    }
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod text_input_tests {
+
+    use super::*;
+
+    #[test]
+    fn insert_and_backspace() {
+        let mut input = TextInput::new();
+        input.insert_str("héllo");
+        input.backspace();
+        assert_eq!(input.text(), "héll");
+        assert_eq!(input.cursor(), 4);
+    }
+
+    #[test]
+    fn insert_in_middle() {
+        let mut input = TextInput::from_text("ac");
+        input.move_left(false);
+        input.insert_char('b');
+        assert_eq!(input.text(), "abc");
+        assert_eq!(input.cursor(), 2);
+    }
+
+    #[test]
+    fn selection_is_replaced() {
+        let mut input = TextInput::from_text("hello world");
+        input.move_word_left(true);
+        assert_eq!(input.selected_text(), Some(String::from("world")));
+        input.insert_str("there");
+        assert_eq!(input.text(), "hello there");
+        assert_eq!(input.selection(), None);
+    }
+
+    #[test]
+    fn cut_and_paste() {
+        let mut input = TextInput::from_text("abc");
+        input.select_all();
+        let text = input.cut();
+        assert_eq!(text, Some(String::from("abc")));
+        assert!(input.is_empty());
+        input.paste("x\ny");
+        assert_eq!(input.text(), "xy");
+    }
+
+    #[test]
+    fn max_length_is_respected() {
+        let mut input = TextInput::new().with_max_length(3);
+        input.insert_str("abcdef");
+        assert_eq!(input.text(), "abc");
+    }
+
+    #[test]
+    fn moving_without_select_collapses_selection() {
+        let mut input = TextInput::from_text("abcd");
+        input.move_left(true);
+        input.move_left(true);
+        input.move_right(false);
+        assert_eq!(input.selection(), None);
+        assert_eq!(input.cursor(), 4);
+    }
+
+    #[test]
+    fn control_characters_are_ignored() {
+        let mut input = TextInput::new();
+        input.insert_char('\u{8}');
+        assert!(input.is_empty());
+    }
+}
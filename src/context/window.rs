@@ -1,16 +1,25 @@
 #![allow(dead_code)]
 
-use glfw::Context;
-use std::sync::mpsc::Receiver;
-
-use super::{keyboard::Keyboard, mouse::Mouse};
+use super::{
+    backend::Backend,
+    event::{Event, TimedEvent},
+    gamepad::{Gamepads, MappingError},
+    glfw_backend::GlfwBackend,
+    keyboard::{Keyboard, KEYS},
+    mouse::{Cursor, CursorMode, Mouse},
+    recording::{Playback, Recording},
+};
 
 pub struct Window {
-    glfw: glfw::Glfw,
-    window: glfw::Window,
-    events: Receiver<(f64, glfw::WindowEvent)>,
+    backend: Box<dyn Backend>,
+    event_queue: Vec<TimedEvent>,
+    injected: Vec<Event>,
     keyboard: Keyboard,
     mouse: Mouse,
+    gamepads: Gamepads,
+    cursor_mode: CursorMode,
+    recording: Option<Recording>,
+    playback: Option<Playback>,
 }
 
 pub struct WindowArgs {
@@ -33,81 +42,142 @@ pub trait EventReciever {
 
 impl Window {
     pub fn new(args: &WindowArgs) -> Result<Window, WindowError> {
-        // Initializing GLFW
-        let mut glfw = match glfw::init(glfw::FAIL_ON_ERRORS) {
-            Err(_) => return Err(WindowError::FailedGLFWInit),
-            Ok(glfw) => glfw,
-        };
-
-        let mut width = args.width;
-        let mut height = args.height;
-
-        if args.borderless {
-            glfw.window_hint(glfw::WindowHint::Decorated(false));
-            glfw.with_primary_monitor(|_, monitor| {
-                monitor.and_then(|monitor| {
-                    monitor.get_video_mode().and_then(|video_mode| {
-                        width = video_mode.width;
-                        height = video_mode.height;
-                        Some(video_mode)
-                    })
-                })
-            });
-        }
-
-        glfw.window_hint(glfw::WindowHint::Resizable(false));
-        glfw.window_hint(glfw::WindowHint::Visible(false));
-
-        let (mut window, events) =
-            match glfw.create_window(width, height, args.title, glfw::WindowMode::Windowed) {
-                None => return Err(WindowError::FailedGLFWCreateWindow),
-                Some((window, events)) => (window, events),
-            };
-
-        window.make_current();
-        gl::load_with(|s| window.get_proc_address(s));
+        let backend = GlfwBackend::new(args)?;
+        Ok(Window::from_backend(Box::new(backend)))
+    }
 
-        let keyboard = Keyboard::new(&mut window);
-        let mouse = Mouse::new(&mut window);
+    /// Creates a window on top of any backend, e.g. `HeadlessBackend` for tests
+    pub fn from_backend(backend: Box<dyn Backend>) -> Window {
+        let scancodes = KEYS
+            .iter()
+            .filter_map(|&key| backend.key_scancode(key).map(|scancode| (key, scancode)))
+            .collect();
 
-        return Ok(Window {
-            glfw,
-            window,
-            events,
-            keyboard,
-            mouse,
-        });
+        Window {
+            backend,
+            event_queue: Vec::new(),
+            injected: Vec::new(),
+            keyboard: Keyboard::with_scancodes(scancodes),
+            mouse: Mouse::new(),
+            gamepads: Gamepads::new(),
+            cursor_mode: CursorMode::Normal,
+            recording: None,
+            playback: None,
+        }
     }
 
     pub fn show(&mut self, reciever: &mut dyn EventReciever) {
-        self.window.show();
+        self.backend.show();
         reciever.init(self);
 
         let mut frame_time = 0.0;
 
-        while !self.window.should_close() {
-            self.glfw.poll_events();
+        while !self.backend.should_close() {
+            let mut events = self.backend.poll_events();
 
-            for (_, event) in glfw::flush_messages(&self.events) {
-                self.keyboard.process_event(&event);
-                self.mouse.process_event(&event);
+            let time = self.backend.time();
+            let mut delta_time = (time - frame_time) as f32;
+            frame_time = time;
+
+            // Injected events are delivered after the platform events of the frame
+            for event in self.injected.drain(..) {
+                events.push(TimedEvent { time, event });
             }
 
-            let time = self.glfw.get_time();
-            let delta_time = (time - frame_time) as f32;
-            frame_time = time;
+            self.event_queue.clear();
+
+            match self.playback.as_mut().and_then(|p| p.next_frame()) {
+                Some((frame, time)) => {
+                    // Recorded input replaces platform input while playing back
+                    delta_time = frame.delta_time;
+                    frame.apply(&mut self.keyboard, &mut self.mouse, &mut self.gamepads);
+                    for event in frame.events.iter() {
+                        let event = event.clone();
+                        self.event_queue.push(TimedEvent { time, event });
+                    }
+                }
+                None => {
+                    self.playback = None;
+                    for timed in events {
+                        self.keyboard.process_event(&timed.event);
+                        self.mouse.process_event(&timed.event);
+                        self.event_queue.push(timed);
+                    }
+                    self.backend.poll_gamepads(&mut self.gamepads);
+                }
+            }
+
+            for pad in self.gamepads.iter() {
+                if pad.just_connected() {
+                    let event = Event::GamepadConnected(pad.id());
+                    self.event_queue.push(TimedEvent { time, event });
+                } else if pad.just_disconnected() {
+                    let event = Event::GamepadDisconnected(pad.id());
+                    self.event_queue.push(TimedEvent { time, event });
+                }
+            }
+
+            if let Some(recording) = self.recording.as_mut() {
+                let events = self.event_queue.iter().map(|timed| &timed.event);
+                recording.record_frame(delta_time, events, &self.gamepads);
+            }
 
             reciever.render_loop(self, delta_time);
 
             self.keyboard.update();
             self.mouse.update();
+            self.gamepads.update();
 
-            self.window.swap_buffers();
+            self.backend.swap_buffers();
         }
 
         reciever.closing(self);
     }
 
+    /// Queues an event as if the platform had sent it, it is processed next frame
+    pub fn inject_event(&mut self, event: Event) {
+        self.injected.push(event);
+    }
+
+    /// Ends the loop in `show` after the current frame
+    pub fn close(&mut self) {
+        self.backend.set_should_close(true);
+    }
+
+    /// Returns the size of the window in screen coordinates
+    pub fn size(&self) -> (u32, u32) {
+        self.backend.size()
+    }
+
+    /// Returns false when running headless, in which case no OpenGL calls may be made
+    pub fn has_gl(&self) -> bool {
+        self.backend.has_gl()
+    }
+
+    /// Starts recording input and frame deltas, replacing any recording in progress
+    pub fn start_recording(&mut self) {
+        self.recording = Some(Recording::new());
+    }
+
+    /// Stops recording and returns what was recorded
+    pub fn stop_recording(&mut self) -> Option<Recording> {
+        self.recording.take()
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Replays a recording in place of platform input, starting next frame.
+    /// The recorded frame deltas are passed to `render_loop` so the session plays back exactly.
+    pub fn play(&mut self, recording: Recording) {
+        self.playback = Some(Playback::new(recording));
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playback.is_some()
+    }
+
     pub fn keyboard(&self) -> &Keyboard {
         &self.keyboard
     }
@@ -115,4 +185,164 @@ impl Window {
     pub fn mouse(&self) -> &Mouse {
         &self.mouse
     }
+
+    pub fn cursor_mode(&self) -> CursorMode {
+        self.cursor_mode
+    }
+
+    pub fn set_cursor_mode(&mut self, mode: CursorMode) {
+        self.backend.set_cursor_mode(mode);
+        self.cursor_mode = mode;
+    }
+
+    /// Changes the cursor image, `None` restores the default arrow
+    pub fn set_cursor(&mut self, cursor: Option<Cursor>) {
+        self.backend.set_cursor(cursor);
+    }
+
+    pub fn gamepads(&self) -> &Gamepads {
+        &self.gamepads
+    }
+
+    pub fn gamepads_mut(&mut self) -> &mut Gamepads {
+        &mut self.gamepads
+    }
+
+    /// Adds SDL game controller mappings from a file such as `gamecontrollerdb.txt`
+    pub fn load_gamepad_mappings(&mut self, path: &str) -> Result<(), MappingError> {
+        let mappings = std::fs::read_to_string(path).map_err(MappingError::Io)?;
+        self.backend.update_gamepad_mappings(&mappings)
+    }
+
+    /// Returns the contents of the system clipboard
+    pub fn clipboard(&self) -> Option<String> {
+        self.backend.clipboard()
+    }
+
+    /// Replaces the contents of the system clipboard
+    pub fn set_clipboard(&mut self, text: &str) {
+        self.backend.set_clipboard(text);
+    }
+
+    /// Returns the events received this frame in the order they occurred
+    pub fn events(&self) -> std::slice::Iter<'_, TimedEvent> {
+        self.event_queue.iter()
+    }
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod window_tests {
+
+    use super::*;
+    use crate::context::{
+        event::Action,
+        headless::HeadlessBackend,
+        keyboard::{Key, Modifiers},
+        mouse::StandardCursor,
+    };
+
+    struct Frame {
+        dt: f32,
+        held: bool,
+        pressed: bool,
+        typed: String,
+        position: (f32, f32),
+    }
+
+    #[derive(Default)]
+    struct Script {
+        frames: Vec<Frame>,
+        closed: bool,
+    }
+
+    impl EventReciever for Script {
+        fn init(&mut self, _: &mut Window) {}
+
+        fn render_loop(&mut self, window: &mut Window, dt: f32) {
+            let keyboard = window.keyboard();
+            self.frames.push(Frame {
+                dt,
+                held: keyboard.get_key(Key::A),
+                pressed: keyboard.get_key_down(Key::A),
+                typed: keyboard.typed_text(),
+                position: window.mouse().position(),
+            });
+
+            match self.frames.len() {
+                2 => window.inject_event(Event::Char('x')),
+                4 => window.close(),
+                _ => (),
+            }
+        }
+
+        fn closing(&mut self, _: &mut Window) {
+            self.closed = true;
+        }
+    }
+
+    fn key_a(action: Action) -> Event {
+        Event::Key(Key::A, 0, action, Modifiers::empty())
+    }
+
+    #[test]
+    fn headless_scripted_input() {
+        let backend = HeadlessBackend::new(10, 0.25)
+            .with_event(0, key_a(Action::Press))
+            .with_event(0, Event::MouseMove(3.0, 4.0))
+            .with_event(2, key_a(Action::Release));
+        let mut window = Window::from_backend(Box::new(backend));
+        assert!(!window.has_gl());
+
+        let mut script = Script::default();
+        window.show(&mut script);
+
+        assert!(script.closed);
+        assert_eq!(script.frames.len(), 4);
+
+        let dts: Vec<f32> = script.frames.iter().map(|frame| frame.dt).collect();
+        assert_eq!(dts, [0.0, 0.25, 0.25, 0.25]);
+
+        let held: Vec<(bool, bool)> = script.frames.iter().map(|f| (f.held, f.pressed)).collect();
+        assert_eq!(
+            held,
+            [(true, true), (true, false), (false, false), (false, false)]
+        );
+
+        assert_eq!(script.frames[1].typed, "");
+        assert_eq!(script.frames[2].typed, "x");
+        assert_eq!(script.frames[3].position, (3.0, 4.0));
+    }
+
+    #[test]
+    fn headless_runs_fixed_frame_count() {
+        struct Counter(usize);
+        impl EventReciever for Counter {
+            fn init(&mut self, _: &mut Window) {}
+            fn render_loop(&mut self, _: &mut Window, _: f32) {
+                self.0 += 1;
+            }
+            fn closing(&mut self, _: &mut Window) {}
+        }
+
+        let mut window = Window::from_backend(Box::new(HeadlessBackend::new(5, 1.0 / 60.0)));
+        window.set_clipboard("copied");
+        assert_eq!(window.clipboard().as_deref(), Some("copied"));
+
+        let mut counter = Counter(0);
+        window.show(&mut counter);
+        assert_eq!(counter.0, 5);
+    }
+
+    #[test]
+    fn headless_cursor() {
+        let mut window = Window::from_backend(Box::new(HeadlessBackend::new(1, 1.0)));
+        window.set_cursor(Some(Cursor::standard(StandardCursor::Hand)));
+        window.set_cursor_mode(CursorMode::Captured);
+        assert_eq!(window.cursor_mode(), CursorMode::Captured);
+        window.set_cursor(None);
+    }
 }
@@ -0,0 +1,107 @@
+#![allow(dead_code)]
+
+use std::cell::RefCell;
+
+use super::{
+    entity::{Entities, Entity},
+    world::{Bundle, World},
+};
+
+type Command = Box<dyn FnOnce(&mut World)>;
+
+/// Commands waiting for `World::flush`.
+#[derive(Default)]
+pub(crate) struct CommandQueue {
+    commands: RefCell<Vec<Command>>,
+}
+
+/// Queues spawns, despawns and component changes while the world is borrowed,
+/// for example from inside a query. They run in order on `World::flush`,
+/// which `Schedule` calls after every system.
+pub struct Commands<'w> {
+    entities: &'w Entities,
+    queue: &'w CommandQueue,
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl<'w> Commands<'w> {
+    pub(crate) fn new(entities: &'w Entities, queue: &'w CommandQueue) -> Commands<'w> {
+        Commands { entities, queue }
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl CommandQueue {
+    fn push(&self, command: Command) {
+        self.commands.borrow_mut().push(command);
+    }
+
+    /// Returns the queued commands, `None` if there are none
+    pub fn take(&self) -> Option<Vec<Command>> {
+        let commands = std::mem::take(&mut *self.commands.borrow_mut());
+        if commands.is_empty() {
+            None
+        } else {
+            Some(commands)
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.borrow().len()
+    }
+}
+
+impl<'w> Commands<'w> {
+    /// Returns the entity right away, it is alive once the commands are applied
+    pub fn spawn<B: Bundle>(&self, bundle: B) -> Entity {
+        let entity = self.entities.reserve();
+        self.add(move |world| {
+            world.insert_bundle(entity, bundle);
+        });
+        entity
+    }
+
+    pub fn despawn(&self, entity: Entity) {
+        self.add(move |world| {
+            world.despawn(entity);
+        });
+    }
+
+    pub fn insert<T: 'static>(&self, entity: Entity, component: T) {
+        self.add(move |world| {
+            world.insert(entity, component);
+        });
+    }
+
+    pub fn remove<T: 'static>(&self, entity: Entity) {
+        self.add(move |world| {
+            world.remove::<T>(entity);
+        });
+    }
+
+    pub fn insert_resource<R: 'static>(&self, resource: R) {
+        self.add(move |world| {
+            world.insert_resource(resource);
+        });
+    }
+
+    /// Queues any change to the world
+    pub fn add<F: FnOnce(&mut World) + 'static>(&self, command: F) {
+        self.queue.push(Box::new(command));
+    }
+
+    /// Returns the number of commands waiting
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.len() == 0
+    }
+}
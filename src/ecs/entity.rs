@@ -0,0 +1,151 @@
+#![allow(dead_code)]
+
+use std::cell::Cell;
+
+/// Handle to an entity in a `World`.
+/// The generation tells apart entities that reuse the index of a despawned one.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+/// Allocates entities and recycles the indices of despawned ones.
+#[derive(Debug, Default)]
+pub(crate) struct Entities {
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+    free: Vec<u32>,
+    /// Fresh indices handed out by `reserve` that are not alive yet
+    reserved: Cell<u32>,
+    count: usize,
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl Entity {
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+impl Entities {
+    pub fn spawn(&mut self) -> Entity {
+        self.flush_reserved();
+        self.count += 1;
+
+        match self.free.pop() {
+            Some(index) => {
+                self.alive[index as usize] = true;
+                Entity {
+                    index,
+                    generation: self.generations[index as usize],
+                }
+            }
+            None => {
+                self.generations.push(0);
+                self.alive.push(true);
+                Entity {
+                    index: self.generations.len() as u32 - 1,
+                    generation: 0,
+                }
+            }
+        }
+    }
+
+    /// Hands out an entity without a mutable borrow, it becomes alive with `flush_reserved`
+    pub fn reserve(&self) -> Entity {
+        let reserved = self.reserved.get();
+        self.reserved.set(reserved + 1);
+        Entity {
+            index: self.generations.len() as u32 + reserved,
+            generation: 0,
+        }
+    }
+
+    pub fn flush_reserved(&mut self) {
+        let reserved = self.reserved.replace(0) as usize;
+        self.generations
+            .resize(self.generations.len() + reserved, 0);
+        self.alive.resize(self.alive.len() + reserved, true);
+        self.count += reserved;
+    }
+
+    /// Returns false if the entity was not alive
+    pub fn despawn(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+
+        let index = entity.index as usize;
+        self.alive[index] = false;
+        self.generations[index] = self.generations[index].wrapping_add(1);
+        self.free.push(entity.index);
+        self.count -= 1;
+        true
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        let index = entity.index as usize;
+        index < self.alive.len()
+            && self.alive[index]
+            && self.generations[index] == entity.generation
+    }
+
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.generations
+            .iter()
+            .zip(self.alive.iter())
+            .enumerate()
+            .filter(|(_, (_, &alive))| alive)
+            .map(|(index, (&generation, _))| Entity {
+                index: index as u32,
+                generation,
+            })
+    }
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod entity_tests {
+
+    use super::*;
+
+    #[test]
+    fn recycle_with_generations() {
+        let mut entities = Entities::default();
+        let a = entities.spawn();
+        let b = entities.spawn();
+        assert!(entities.despawn(a));
+        assert!(!entities.despawn(a));
+
+        let c = entities.spawn();
+        assert_eq!(c.index(), a.index());
+        assert_eq!(c.generation(), a.generation() + 1);
+        assert!(!entities.is_alive(a));
+        assert!(entities.is_alive(c));
+
+        let reserved = entities.reserve();
+        assert_eq!(reserved.index(), 2);
+        assert!(!entities.is_alive(reserved));
+        let d = entities.spawn();
+        assert!(entities.is_alive(reserved));
+        assert_eq!(d.index(), 3);
+
+        assert_eq!(entities.len(), 4);
+        assert_eq!(entities.iter().collect::<Vec<_>>(), [c, b, reserved, d]);
+    }
+}
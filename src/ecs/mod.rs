@@ -0,0 +1,6 @@
+pub mod commands;
+pub mod entity;
+pub mod query;
+pub mod schedule;
+pub mod storage;
+pub mod world;
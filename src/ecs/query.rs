@@ -0,0 +1,382 @@
+#![allow(dead_code)]
+
+use std::{
+    any::TypeId,
+    cell::{Ref, RefMut},
+    marker::PhantomData,
+};
+
+use super::{entity::Entity, storage::SparseSet, world::World};
+
+/// What a query fetches for each entity: `&T`, `&mut T`, `Option<&T>`, `Option<&mut T>`
+/// or a tuple of those. Entities are matched when they have all the non optional components.
+pub trait QueryData {
+    /// Borrowed storages, held while the query runs
+    type State<'w>;
+    type Item<'s>;
+
+    /// Adds the components an entity must have
+    fn required(types: &mut Vec<TypeId>);
+
+    /// Returns `None` when a required component has no storage yet
+    fn borrow(world: &World) -> Option<Self::State<'_>>;
+
+    /// Fetches the components of an entity known to match, mutable ones are marked changed
+    fn fetch<'s>(state: &'s mut Self::State<'_>, entity: Entity, tick: u32) -> Self::Item<'s>;
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Filter {
+    With(TypeId),
+    Without(TypeId),
+    Added(TypeId),
+    Changed(TypeId),
+}
+
+/// Entities with some components, narrowed down by filters:
+///
+/// ```ignore
+/// world
+///     .query::<(&mut Position, &Velocity)>()
+///     .without::<Frozen>()
+///     .for_each(|entity, (position, velocity)| position.x += velocity.x);
+/// ```
+///
+/// `added` and `changed` pass components that changed since the system last ran,
+/// or since `World::clear_trackers` outside of a `Schedule`.
+pub struct Query<'w, Q: QueryData> {
+    world: &'w World,
+    filters: Vec<Filter>,
+    marker: PhantomData<Q>,
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl<'w, Q: QueryData> Query<'w, Q> {
+    pub(crate) fn new(world: &'w World) -> Query<'w, Q> {
+        Query {
+            world,
+            filters: Vec::new(),
+            marker: PhantomData,
+        }
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl<'w, Q: QueryData> Query<'w, Q> {
+    /// Only matches entities with a component
+    pub fn with<T: 'static>(mut self) -> Self {
+        self.filters.push(Filter::With(TypeId::of::<T>()));
+        self
+    }
+
+    /// Only matches entities without a component
+    pub fn without<T: 'static>(mut self) -> Self {
+        self.filters.push(Filter::Without(TypeId::of::<T>()));
+        self
+    }
+
+    /// Only matches entities whose component was added recently
+    pub fn added<T: 'static>(mut self) -> Self {
+        self.filters.push(Filter::Added(TypeId::of::<T>()));
+        self
+    }
+
+    /// Only matches entities whose component was added or borrowed mutably recently
+    pub fn changed<T: 'static>(mut self) -> Self {
+        self.filters.push(Filter::Changed(TypeId::of::<T>()));
+        self
+    }
+
+    /// Returns the matching entities
+    pub fn entities(&self) -> Vec<Entity> {
+        let mut required = Vec::new();
+        Q::required(&mut required);
+        for filter in self.filters.iter() {
+            if let Filter::With(id) | Filter::Added(id) | Filter::Changed(id) = *filter {
+                required.push(id);
+            }
+        }
+
+        // The smallest storage gives the fewest candidates
+        let mut storages = Vec::new();
+        for id in required {
+            match self.world.erased_storage(id) {
+                Some(storage) => storages.push(storage),
+                None => return Vec::new(),
+            }
+        }
+        let mut candidates: Vec<Entity> = match storages.iter().min_by_key(|s| s.entities().len()) {
+            Some(smallest) => smallest.entities().to_vec(),
+            None => self.world.entities().collect(),
+        };
+        candidates.retain(|&entity| storages.iter().all(|storage| storage.contains(entity)));
+
+        let last_run = self.world.last_run();
+        for filter in self.filters.iter() {
+            let (id, keep): (TypeId, fn(bool) -> bool) = match *filter {
+                Filter::With(_) => continue,
+                Filter::Without(id) => (id, |found| !found),
+                Filter::Added(id) | Filter::Changed(id) => (id, |found| found),
+            };
+            let storage = match self.world.erased_storage(id) {
+                Some(storage) => storage,
+                None if keep(false) => continue,
+                None => return Vec::new(),
+            };
+            candidates.retain(|&entity| {
+                keep(match *filter {
+                    Filter::Added(_) => storage.added_since(entity, last_run),
+                    Filter::Changed(_) => storage.changed_since(entity, last_run),
+                    _ => storage.contains(entity),
+                })
+            });
+        }
+
+        candidates
+    }
+
+    pub fn count(&self) -> usize {
+        self.entities().len()
+    }
+
+    /// Calls `f` with every matching entity and its components
+    pub fn for_each<F>(&self, mut f: F)
+    where
+        F: for<'s> FnMut(Entity, Q::Item<'s>),
+    {
+        let entities = self.entities();
+        if entities.is_empty() {
+            return;
+        }
+
+        let tick = self.world.tick();
+        if let Some(mut state) = Q::borrow(self.world) {
+            for entity in entities {
+                f(entity, Q::fetch(&mut state, entity, tick));
+            }
+        }
+    }
+
+    /// Calls `f` with the components of one entity if it matches
+    pub fn get<F, R>(&self, entity: Entity, f: F) -> Option<R>
+    where
+        F: for<'s> FnOnce(Q::Item<'s>) -> R,
+    {
+        if !self.entities().contains(&entity) {
+            return None;
+        }
+
+        let mut state = Q::borrow(self.world)?;
+        Some(f(Q::fetch(&mut state, entity, self.world.tick())))
+    }
+}
+
+// ====================================
+// Other Implementations
+// ====================================
+
+impl<T: 'static> QueryData for &T {
+    type State<'w> = Ref<'w, SparseSet<T>>;
+    type Item<'s> = &'s T;
+
+    fn required(types: &mut Vec<TypeId>) {
+        types.push(TypeId::of::<T>());
+    }
+
+    fn borrow(world: &World) -> Option<Self::State<'_>> {
+        world.storage::<T>()
+    }
+
+    fn fetch<'s>(state: &'s mut Self::State<'_>, entity: Entity, _: u32) -> Self::Item<'s> {
+        state.get(entity).unwrap()
+    }
+}
+
+impl<T: 'static> QueryData for &mut T {
+    type State<'w> = RefMut<'w, SparseSet<T>>;
+    type Item<'s> = &'s mut T;
+
+    fn required(types: &mut Vec<TypeId>) {
+        types.push(TypeId::of::<T>());
+    }
+
+    fn borrow(world: &World) -> Option<Self::State<'_>> {
+        world.storage_mut::<T>()
+    }
+
+    fn fetch<'s>(state: &'s mut Self::State<'_>, entity: Entity, tick: u32) -> Self::Item<'s> {
+        state.get_mut(entity, tick).unwrap()
+    }
+}
+
+impl<T: 'static> QueryData for Option<&T> {
+    type State<'w> = Option<Ref<'w, SparseSet<T>>>;
+    type Item<'s> = Option<&'s T>;
+
+    fn required(_: &mut Vec<TypeId>) {}
+
+    fn borrow(world: &World) -> Option<Self::State<'_>> {
+        Some(world.storage::<T>())
+    }
+
+    fn fetch<'s>(state: &'s mut Self::State<'_>, entity: Entity, _: u32) -> Self::Item<'s> {
+        state.as_ref()?.get(entity)
+    }
+}
+
+impl<T: 'static> QueryData for Option<&mut T> {
+    type State<'w> = Option<RefMut<'w, SparseSet<T>>>;
+    type Item<'s> = Option<&'s mut T>;
+
+    fn required(_: &mut Vec<TypeId>) {}
+
+    fn borrow(world: &World) -> Option<Self::State<'_>> {
+        Some(world.storage_mut::<T>())
+    }
+
+    fn fetch<'s>(state: &'s mut Self::State<'_>, entity: Entity, tick: u32) -> Self::Item<'s> {
+        state.as_mut()?.get_mut(entity, tick)
+    }
+}
+
+macro_rules! impl_query_data {
+    ($($name:ident),*) => {
+        impl<$($name: QueryData),*> QueryData for ($($name,)*) {
+            type State<'w> = ($($name::State<'w>,)*);
+            type Item<'s> = ($($name::Item<'s>,)*);
+
+            fn required(types: &mut Vec<TypeId>) {
+                $($name::required(types);)*
+            }
+
+            fn borrow(world: &World) -> Option<Self::State<'_>> {
+                Some(($($name::borrow(world)?,)*))
+            }
+
+            #[allow(non_snake_case)]
+            fn fetch<'s>(state: &'s mut Self::State<'_>, entity: Entity, tick: u32) -> Self::Item<'s> {
+                let ($($name,)*) = state;
+                ($($name::fetch($name, entity, tick),)*)
+            }
+        }
+    };
+}
+
+impl_query_data!(A);
+impl_query_data!(A, B);
+impl_query_data!(A, B, C);
+impl_query_data!(A, B, C, D);
+impl_query_data!(A, B, C, D, E);
+impl_query_data!(A, B, C, D, E, F);
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod query_tests {
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Clone, Copy)]
+    struct Position(f32);
+
+    #[derive(Debug, PartialEq, Clone, Copy)]
+    struct Velocity(f32);
+
+    struct Frozen;
+
+    #[test]
+    fn fetch_and_filter() {
+        let mut world = World::new();
+        let a = world.spawn((Position(0.0), Velocity(1.0)));
+        let b = world.spawn((Position(5.0), Velocity(2.0), Frozen));
+        let c = world.spawn((Position(9.0),));
+
+        world
+            .query::<(&mut Position, &Velocity)>()
+            .without::<Frozen>()
+            .for_each(|_, (position, velocity)| position.0 += velocity.0);
+        assert_eq!(*world.get::<Position>(a).unwrap(), Position(1.0));
+        assert_eq!(*world.get::<Position>(b).unwrap(), Position(5.0));
+
+        assert_eq!(world.query::<&Position>().count(), 3);
+        assert_eq!(world.query::<&Position>().with::<Frozen>().entities(), [b]);
+        assert_eq!(
+            world.query::<&Velocity>().without::<Frozen>().entities(),
+            [a]
+        );
+        assert_eq!(world.query::<&Frozen>().without::<Frozen>().count(), 0);
+        assert_eq!(world.query::<&String>().count(), 0);
+
+        let mut optional = Vec::new();
+        world
+            .query::<(&Position, Option<&Velocity>)>()
+            .for_each(|entity, (_, velocity)| optional.push((entity, velocity.copied())));
+        assert_eq!(
+            optional,
+            [
+                (a, Some(Velocity(1.0))),
+                (b, Some(Velocity(2.0))),
+                (c, None)
+            ]
+        );
+
+        let speed = world.query::<&Velocity>().get(b, |velocity| velocity.0);
+        assert_eq!(speed, Some(2.0));
+        assert_eq!(
+            world.query::<&Velocity>().get(c, |velocity| velocity.0),
+            None
+        );
+    }
+
+    #[test]
+    fn change_detection() {
+        let mut world = World::new();
+        let a = world.spawn((Position(0.0),));
+        let b = world.spawn((Position(0.0),));
+        assert_eq!(world.query::<&Position>().changed::<Position>().count(), 2);
+
+        world.clear_trackers();
+        assert_eq!(world.query::<&Position>().changed::<Position>().count(), 0);
+
+        world.get_mut::<Position>(b).unwrap().0 = 1.0;
+        let c = world.spawn((Position(0.0),));
+        assert_eq!(
+            world.query::<&Position>().changed::<Position>().entities(),
+            [b, c]
+        );
+        assert_eq!(
+            world.query::<&Position>().added::<Position>().entities(),
+            [c]
+        );
+
+        // A mutable query marks what it fetches as changed
+        world.clear_trackers();
+        world
+            .query::<&mut Position>()
+            .with::<Position>()
+            .for_each(|entity, position| {
+                if entity == a {
+                    position.0 = 2.0
+                }
+            });
+        assert_eq!(world.query::<&Position>().changed::<Position>().count(), 3);
+    }
+
+    #[test]
+    #[should_panic]
+    fn conflicting_borrows() {
+        let mut world = World::new();
+        world.spawn((Position(0.0),));
+        world
+            .query::<(&mut Position, &Position)>()
+            .for_each(|_, _| {});
+    }
+}
@@ -0,0 +1,173 @@
+#![allow(dead_code)]
+
+use super::world::World;
+
+/// Stages run in this order every frame.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Stage {
+    /// Reads devices and turns them into game state
+    Input,
+    Update,
+    /// Draws the world, should only read it
+    Render,
+}
+
+pub const STAGES: [Stage; 3] = [Stage::Input, Stage::Update, Stage::Render];
+
+struct System {
+    name: String,
+    stage: Stage,
+    /// Tick of the previous run, changes after it are new to the system
+    last_run: u32,
+    run: Box<dyn FnMut(&mut World)>,
+}
+
+/// Systems grouped by stage, they run in the order they were added.
+/// Commands queued by a system are applied before the next one runs.
+#[derive(Default)]
+pub struct Schedule {
+    systems: Vec<System>,
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl Schedule {
+    pub fn new() -> Schedule {
+        Schedule::default()
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl Schedule {
+    pub fn add_system<F: FnMut(&mut World) + 'static>(
+        &mut self,
+        stage: Stage,
+        name: &str,
+        system: F,
+    ) -> &mut Schedule {
+        self.systems.push(System {
+            name: name.to_string(),
+            stage,
+            last_run: 0,
+            run: Box::new(system),
+        });
+        self
+    }
+
+    /// Returns false if there is no system with this name
+    pub fn remove_system(&mut self, name: &str) -> bool {
+        let count = self.systems.len();
+        self.systems.retain(|system| system.name != name);
+        self.systems.len() != count
+    }
+
+    /// Returns the names of the systems of a stage in running order
+    pub fn systems(&self, stage: Stage) -> impl Iterator<Item = &str> {
+        self.systems
+            .iter()
+            .filter(move |system| system.stage == stage)
+            .map(|system| system.name.as_str())
+    }
+
+    /// Runs every stage in order
+    pub fn run(&mut self, world: &mut World) {
+        for stage in STAGES {
+            self.run_stage(stage, world);
+        }
+    }
+
+    pub fn run_stage(&mut self, stage: Stage, world: &mut World) {
+        for system in self
+            .systems
+            .iter_mut()
+            .filter(|system| system.stage == stage)
+        {
+            let run = &mut system.run;
+            system.last_run = world.run_system(system.last_run, |world| run(world));
+        }
+    }
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod schedule_tests {
+
+    use super::*;
+    use std::{cell::RefCell, rc::Rc};
+
+    #[derive(Debug, PartialEq)]
+    struct Counter(u32);
+
+    #[test]
+    fn stages_run_in_order() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut schedule = Schedule::new();
+        for (stage, name) in [
+            (Stage::Render, "draw"),
+            (Stage::Update, "move"),
+            (Stage::Input, "read"),
+            (Stage::Update, "collide"),
+        ] {
+            let log = log.clone();
+            schedule.add_system(stage, name, move |_| log.borrow_mut().push(name));
+        }
+
+        let mut world = World::new();
+        schedule.run(&mut world);
+        assert_eq!(*log.borrow(), ["read", "move", "collide", "draw"]);
+        assert_eq!(
+            schedule.systems(Stage::Update).collect::<Vec<_>>(),
+            ["move", "collide"]
+        );
+
+        assert!(schedule.remove_system("move"));
+        assert!(!schedule.remove_system("move"));
+        log.borrow_mut().clear();
+        schedule.run_stage(Stage::Update, &mut world);
+        assert_eq!(*log.borrow(), ["collide"]);
+    }
+
+    #[test]
+    fn systems_see_their_own_changes() {
+        let mut world = World::new();
+        world.insert_resource(Vec::<u32>::new());
+
+        let mut schedule = Schedule::new();
+        schedule
+            .add_system(Stage::Input, "spawn", |world| {
+                if world.is_empty() {
+                    world.commands().spawn((Counter(0),));
+                }
+            })
+            .add_system(Stage::Update, "count", |world| {
+                world
+                    .query::<&mut Counter>()
+                    .for_each(|_, counter| counter.0 += 1);
+            })
+            .add_system(Stage::Render, "report", |world| {
+                let mut seen = world.resource_mut::<Vec<u32>>().unwrap();
+                world
+                    .query::<&Counter>()
+                    .changed::<Counter>()
+                    .for_each(|_, counter| seen.push(counter.0));
+            });
+
+        // The entity spawned by the first system is seen by the next ones
+        schedule.run(&mut world);
+        schedule.run(&mut world);
+        assert_eq!(*world.resource::<Vec<u32>>().unwrap(), [1, 2]);
+
+        schedule.remove_system("count");
+        schedule.run(&mut world);
+        assert_eq!(*world.resource::<Vec<u32>>().unwrap(), [1, 2]);
+        assert_eq!(world.len(), 1);
+    }
+}
@@ -0,0 +1,203 @@
+#![allow(dead_code)]
+
+use std::any::Any;
+
+use super::entity::Entity;
+
+/// Marks an empty slot of the sparse array
+const EMPTY: u32 = u32::MAX;
+
+/// Components of one type packed densely, with a sparse array from entity index to slot.
+/// Every slot remembers the ticks its component was added and last changed at.
+#[derive(Debug)]
+pub struct SparseSet<T> {
+    sparse: Vec<u32>,
+    entities: Vec<Entity>,
+    components: Vec<T>,
+    added: Vec<u32>,
+    changed: Vec<u32>,
+}
+
+/// Storage with the component type erased, so a `World` can keep all of them together.
+pub(crate) trait AnyStorage: Any {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn contains(&self, entity: Entity) -> bool;
+    fn entities(&self) -> &[Entity];
+    fn remove_entity(&mut self, entity: Entity);
+    fn added_since(&self, entity: Entity, tick: u32) -> bool;
+    fn changed_since(&self, entity: Entity, tick: u32) -> bool;
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl<T> SparseSet<T> {
+    pub fn new() -> SparseSet<T> {
+        SparseSet {
+            sparse: Vec::new(),
+            entities: Vec::new(),
+            components: Vec::new(),
+            added: Vec::new(),
+            changed: Vec::new(),
+        }
+    }
+}
+
+impl<T> Default for SparseSet<T> {
+    fn default() -> SparseSet<T> {
+        SparseSet::new()
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl<T> SparseSet<T> {
+    fn slot(&self, entity: Entity) -> Option<usize> {
+        let slot = *self.sparse.get(entity.index() as usize)?;
+        if slot != EMPTY && self.entities[slot as usize] == entity {
+            Some(slot as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Adds or replaces the component of an entity and returns the old one
+    pub fn insert(&mut self, entity: Entity, component: T, tick: u32) -> Option<T> {
+        if let Some(slot) = self.slot(entity) {
+            self.changed[slot] = tick;
+            return Some(std::mem::replace(&mut self.components[slot], component));
+        }
+
+        let index = entity.index() as usize;
+        if index >= self.sparse.len() {
+            self.sparse.resize(index + 1, EMPTY);
+        }
+        self.sparse[index] = self.entities.len() as u32;
+        self.entities.push(entity);
+        self.components.push(component);
+        self.added.push(tick);
+        self.changed.push(tick);
+        None
+    }
+
+    pub fn remove(&mut self, entity: Entity) -> Option<T> {
+        let slot = self.slot(entity)?;
+        self.sparse[entity.index() as usize] = EMPTY;
+
+        // The last component moves into the freed slot
+        let last = *self.entities.last().unwrap();
+        if last != entity {
+            self.sparse[last.index() as usize] = slot as u32;
+        }
+        self.entities.swap_remove(slot);
+        self.added.swap_remove(slot);
+        self.changed.swap_remove(slot);
+        Some(self.components.swap_remove(slot))
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        self.slot(entity).map(|slot| &self.components[slot])
+    }
+
+    /// Returns the component and marks it changed at `tick`
+    pub fn get_mut(&mut self, entity: Entity, tick: u32) -> Option<&mut T> {
+        let slot = self.slot(entity)?;
+        self.changed[slot] = tick;
+        Some(&mut self.components[slot])
+    }
+
+    pub fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.entities.iter().copied().zip(self.components.iter())
+    }
+}
+
+impl<T: 'static> AnyStorage for SparseSet<T> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn contains(&self, entity: Entity) -> bool {
+        self.slot(entity).is_some()
+    }
+
+    fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    fn remove_entity(&mut self, entity: Entity) {
+        self.remove(entity);
+    }
+
+    fn added_since(&self, entity: Entity, tick: u32) -> bool {
+        self.slot(entity)
+            .is_some_and(|slot| self.added[slot] > tick)
+    }
+
+    fn changed_since(&self, entity: Entity, tick: u32) -> bool {
+        self.slot(entity)
+            .is_some_and(|slot| self.changed[slot] > tick)
+    }
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod storage_tests {
+
+    use super::super::entity::Entities;
+    use super::*;
+
+    #[test]
+    fn insert_remove_and_ticks() {
+        let mut entities = Entities::default();
+        let (a, b, c) = (entities.spawn(), entities.spawn(), entities.spawn());
+        let mut set = SparseSet::new();
+
+        set.insert(a, 'a', 1);
+        set.insert(c, 'c', 1);
+        assert_eq!(set.insert(b, 'b', 2), None);
+        assert_eq!(set.insert(a, 'A', 3), Some('a'));
+        assert_eq!(set.len(), 3);
+
+        assert_eq!(set.remove(a), Some('A'));
+        assert_eq!(set.remove(a), None);
+        assert_eq!(set.get(b), Some(&'b'));
+        assert_eq!(set.get(c), Some(&'c'));
+        assert_eq!(set.entities(), [b, c]);
+
+        // A stale handle to a reused index does not see the new component
+        entities.despawn(a);
+        let d = entities.spawn();
+        set.insert(d, 'd', 4);
+        assert_eq!(set.get(a), None);
+        assert!(!set.contains(a));
+
+        assert!(set.added_since(b, 1));
+        assert!(!set.added_since(c, 1));
+        *set.get_mut(c, 5).unwrap() = 'C';
+        assert!(set.changed_since(c, 4));
+        assert!(!set.added_since(c, 4));
+        assert_eq!(
+            set.iter().collect::<Vec<_>>(),
+            [(b, &'b'), (c, &'C'), (d, &'d')]
+        );
+    }
+}
@@ -0,0 +1,365 @@
+#![allow(dead_code)]
+
+use std::{
+    any::{Any, TypeId},
+    cell::{Ref, RefCell, RefMut},
+    collections::HashMap,
+};
+
+use super::{
+    commands::{CommandQueue, Commands},
+    entity::{Entities, Entity},
+    query::{Query, QueryData},
+    storage::{AnyStorage, SparseSet},
+};
+
+/// Entities with their components, and resources shared by all systems.
+///
+/// Storages sit behind `RefCell`s so queries only need a shared borrow,
+/// which lets a system read resources and queue commands while it iterates.
+/// Borrowing the same component mutably twice at once panics.
+pub struct World {
+    entities: Entities,
+    storages: HashMap<TypeId, RefCell<Box<dyn AnyStorage>>>,
+    resources: HashMap<TypeId, RefCell<Box<dyn Any>>>,
+    commands: CommandQueue,
+    /// Changes are stamped with the current tick
+    tick: u32,
+    /// Changes after this tick pass the `changed` and `added` filters
+    last_run: u32,
+}
+
+/// Group of components added to an entity together, implemented for tuples.
+pub trait Bundle: 'static {
+    fn insert_into(self, world: &mut World, entity: Entity);
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl World {
+    pub fn new() -> World {
+        World {
+            entities: Entities::default(),
+            storages: HashMap::new(),
+            resources: HashMap::new(),
+            commands: CommandQueue::default(),
+            tick: 1,
+            last_run: 0,
+        }
+    }
+}
+
+impl Default for World {
+    fn default() -> World {
+        World::new()
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl World {
+    pub fn spawn<B: Bundle>(&mut self, bundle: B) -> Entity {
+        let entity = self.entities.spawn();
+        bundle.insert_into(self, entity);
+        entity
+    }
+
+    /// Removes an entity with all its components, returns false if it was not alive
+    pub fn despawn(&mut self, entity: Entity) -> bool {
+        if !self.entities.despawn(entity) {
+            return false;
+        }
+
+        for storage in self.storages.values_mut() {
+            storage.get_mut().remove_entity(entity);
+        }
+        true
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        self.entities.is_alive(entity)
+    }
+
+    /// Returns the number of alive entities
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.len() == 0
+    }
+
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.entities.iter()
+    }
+
+    /// Adds or replaces a component, returns false if the entity is not alive
+    pub fn insert<T: 'static>(&mut self, entity: Entity, component: T) -> bool {
+        if !self.entities.is_alive(entity) {
+            return false;
+        }
+
+        let tick = self.tick;
+        self.storage_entry::<T>().insert(entity, component, tick);
+        true
+    }
+
+    /// Adds the components of a bundle, returns false if the entity is not alive
+    pub fn insert_bundle<B: Bundle>(&mut self, entity: Entity, bundle: B) -> bool {
+        if !self.entities.is_alive(entity) {
+            return false;
+        }
+
+        bundle.insert_into(self, entity);
+        true
+    }
+
+    pub fn remove<T: 'static>(&mut self, entity: Entity) -> Option<T> {
+        self.storages
+            .get_mut(&TypeId::of::<T>())?
+            .get_mut()
+            .as_any_mut()
+            .downcast_mut::<SparseSet<T>>()?
+            .remove(entity)
+    }
+
+    pub fn has<T: 'static>(&self, entity: Entity) -> bool {
+        self.storages
+            .get(&TypeId::of::<T>())
+            .is_some_and(|storage| storage.borrow().contains(entity))
+    }
+
+    pub fn get<T: 'static>(&self, entity: Entity) -> Option<Ref<'_, T>> {
+        Ref::filter_map(self.storage::<T>()?, |storage| storage.get(entity)).ok()
+    }
+
+    /// Returns a component and marks it changed
+    pub fn get_mut<T: 'static>(&mut self, entity: Entity) -> Option<&mut T> {
+        let tick = self.tick;
+        self.storages
+            .get_mut(&TypeId::of::<T>())?
+            .get_mut()
+            .as_any_mut()
+            .downcast_mut::<SparseSet<T>>()?
+            .get_mut(entity, tick)
+    }
+
+    /// Starts a query, see `Query` for the filters
+    pub fn query<Q: QueryData>(&self) -> Query<'_, Q> {
+        Query::new(self)
+    }
+
+    /// Replaces a resource and returns the old one
+    pub fn insert_resource<R: 'static>(&mut self, resource: R) -> Option<R> {
+        self.resources
+            .insert(TypeId::of::<R>(), RefCell::new(Box::new(resource)))
+            .and_then(|old| old.into_inner().downcast().ok())
+            .map(|old| *old)
+    }
+
+    pub fn remove_resource<R: 'static>(&mut self) -> Option<R> {
+        self.resources
+            .remove(&TypeId::of::<R>())
+            .and_then(|old| old.into_inner().downcast().ok())
+            .map(|old| *old)
+    }
+
+    pub fn has_resource<R: 'static>(&self) -> bool {
+        self.resources.contains_key(&TypeId::of::<R>())
+    }
+
+    pub fn resource<R: 'static>(&self) -> Option<Ref<'_, R>> {
+        let cell = self.resources.get(&TypeId::of::<R>())?;
+        Ref::filter_map(cell.borrow(), |resource| resource.downcast_ref()).ok()
+    }
+
+    pub fn resource_mut<R: 'static>(&self) -> Option<RefMut<'_, R>> {
+        let cell = self.resources.get(&TypeId::of::<R>())?;
+        RefMut::filter_map(cell.borrow_mut(), |resource| resource.downcast_mut()).ok()
+    }
+
+    /// Returns a buffer of changes applied by `flush`, usable while queries are running
+    pub fn commands(&self) -> Commands<'_> {
+        Commands::new(&self.entities, &self.commands)
+    }
+
+    /// Applies the queued commands, including the ones they queue themselves
+    pub fn flush(&mut self) {
+        self.entities.flush_reserved();
+        while let Some(commands) = self.commands.take() {
+            for command in commands {
+                command(self);
+            }
+            self.entities.flush_reserved();
+        }
+    }
+
+    pub fn tick(&self) -> u32 {
+        self.tick
+    }
+
+    pub fn last_run(&self) -> u32 {
+        self.last_run
+    }
+
+    /// Changes made before this call no longer pass the `changed` and `added` filters
+    pub fn clear_trackers(&mut self) {
+        self.last_run = self.tick;
+        self.tick = self.tick.wrapping_add(1);
+    }
+
+    /// Makes changes after `last_run` visible, then moves to the next tick.
+    /// Used by `Schedule` to give each system the changes since it last ran.
+    pub(crate) fn run_system<F: FnOnce(&mut World)>(&mut self, last_run: u32, system: F) -> u32 {
+        self.last_run = last_run;
+        system(self);
+        self.flush();
+
+        let ran = self.tick;
+        self.tick = self.tick.wrapping_add(1);
+        ran
+    }
+
+    pub(crate) fn storage<T: 'static>(&self) -> Option<Ref<'_, SparseSet<T>>> {
+        let cell = self.storages.get(&TypeId::of::<T>())?;
+        Ref::filter_map(cell.borrow(), |storage| {
+            storage.as_any().downcast_ref::<SparseSet<T>>()
+        })
+        .ok()
+    }
+
+    pub(crate) fn storage_mut<T: 'static>(&self) -> Option<RefMut<'_, SparseSet<T>>> {
+        let cell = self.storages.get(&TypeId::of::<T>())?;
+        RefMut::filter_map(cell.borrow_mut(), |storage| {
+            storage.as_any_mut().downcast_mut::<SparseSet<T>>()
+        })
+        .ok()
+    }
+
+    pub(crate) fn erased_storage(&self, id: TypeId) -> Option<Ref<'_, Box<dyn AnyStorage>>> {
+        self.storages.get(&id).map(RefCell::borrow)
+    }
+
+    fn storage_entry<T: 'static>(&mut self) -> &mut SparseSet<T> {
+        self.storages
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| RefCell::new(Box::new(SparseSet::<T>::new())))
+            .get_mut()
+            .as_any_mut()
+            .downcast_mut()
+            .unwrap()
+    }
+}
+
+// ====================================
+// Other Implementations
+// ====================================
+
+macro_rules! impl_bundle {
+    ($($name:ident),*) => {
+        impl<$($name: 'static),*> Bundle for ($($name,)*) {
+            #[allow(non_snake_case, unused_variables)]
+            fn insert_into(self, world: &mut World, entity: Entity) {
+                let ($($name,)*) = self;
+                $(world.insert(entity, $name);)*
+            }
+        }
+    };
+}
+
+impl_bundle!();
+impl_bundle!(A);
+impl_bundle!(A, B);
+impl_bundle!(A, B, C);
+impl_bundle!(A, B, C, D);
+impl_bundle!(A, B, C, D, E);
+impl_bundle!(A, B, C, D, E, F);
+impl_bundle!(A, B, C, D, E, F, G);
+impl_bundle!(A, B, C, D, E, F, G, H);
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod world_tests {
+
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Health(i32);
+
+    #[derive(Debug, PartialEq)]
+    struct Name(&'static str);
+
+    #[test]
+    fn components() {
+        let mut world = World::new();
+        let a = world.spawn((Health(3), Name("a")));
+        let b = world.spawn((Health(5),));
+        assert_eq!(world.len(), 2);
+
+        assert_eq!(*world.get::<Health>(a).unwrap(), Health(3));
+        assert!(world.get::<Name>(b).is_none());
+        world.get_mut::<Health>(b).unwrap().0 -= 1;
+        assert_eq!(*world.get::<Health>(b).unwrap(), Health(4));
+
+        assert!(world.insert(b, Name("b")));
+        assert!(world.has::<Name>(b));
+        assert_eq!(world.remove::<Name>(a), Some(Name("a")));
+        assert!(!world.has::<Name>(a));
+
+        assert!(world.despawn(a));
+        assert!(!world.is_alive(a));
+        assert!(!world.insert(a, Health(1)));
+        let c = world.spawn(());
+        assert_eq!(c.index(), a.index());
+        assert!(world.get::<Health>(c).is_none());
+        assert_eq!(world.entities().collect::<Vec<_>>(), [c, b]);
+    }
+
+    #[test]
+    fn resources() {
+        let mut world = World::new();
+        assert!(world.resource::<f32>().is_none());
+        assert_eq!(world.insert_resource(1.5f32), None);
+        assert_eq!(world.insert_resource(2.0f32), Some(1.5));
+
+        *world.resource_mut::<f32>().unwrap() += 1.0;
+        assert_eq!(*world.resource::<f32>().unwrap(), 3.0);
+        assert!(world.has_resource::<f32>());
+        assert_eq!(world.remove_resource::<f32>(), Some(3.0));
+        assert!(!world.has_resource::<f32>());
+    }
+
+    #[test]
+    fn deferred_commands() {
+        let mut world = World::new();
+        let a = world.spawn((Health(1),));
+        let b = world.spawn((Health(0),));
+
+        let spawned = {
+            let commands = world.commands();
+            world.query::<&Health>().for_each(|entity, health| {
+                if health.0 <= 0 {
+                    commands.despawn(entity);
+                }
+            });
+            commands.insert(a, Name("a"));
+            commands.spawn((Health(9),))
+        };
+        // Nothing happens until the commands are applied
+        assert!(world.is_alive(b));
+        assert!(!world.is_alive(spawned));
+
+        world.flush();
+        assert!(!world.is_alive(b));
+        assert!(world.has::<Name>(a));
+        assert_eq!(*world.get::<Health>(spawned).unwrap(), Health(9));
+        assert_eq!(world.len(), 2);
+    }
+}
@@ -0,0 +1,678 @@
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+
+use super::renderer::Rect;
+
+/// Regions of a sprite sheet texture, by index and optionally by name.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct SpriteSheet {
+    regions: Vec<Rect>,
+    names: HashMap<String, usize>,
+}
+
+/// Single image of an animation.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Frame {
+    /// Region of the sprite sheet, used as `DrawParams::source`
+    pub source: Rect,
+    /// Time the frame is shown in seconds
+    pub duration: f32,
+}
+
+/// What happens when an animation reaches its last frame.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum PlayMode {
+    /// Starts over at the first frame
+    #[default]
+    Loop,
+    /// Plays backwards to the first frame, then forwards again
+    PingPong,
+    /// Stops on the last frame
+    Once,
+}
+
+/// Sequence of frames with named events on some of them.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Animation {
+    frames: Vec<Frame>,
+    mode: PlayMode,
+    /// Frame index and event name
+    events: Vec<(usize, String)>,
+}
+
+/// Playback position of an `Animation`, advanced with the frame delta time.
+#[derive(Clone, PartialEq, Debug)]
+pub struct AnimationPlayer {
+    frame: usize,
+    /// Time spent on the current frame
+    elapsed: f32,
+    forward: bool,
+    finished: bool,
+    /// Whether the events of the first frame were reported
+    started: bool,
+    speed: f32,
+}
+
+/// When an `Animator` moves from one clip to another.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Condition {
+    /// The current clip finished playing, only happens for `PlayMode::Once`
+    Finished,
+    /// `Animator::trigger` was called with this name
+    Trigger(String),
+}
+
+/// Event reached during `Animator::update`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct AnimationEvent {
+    pub clip: String,
+    pub name: String,
+}
+
+/// State machine that plays one of several named clips and switches between them.
+pub struct Animator {
+    clips: HashMap<String, Animation>,
+    transitions: Vec<Transition>,
+    current: Option<String>,
+    player: AnimationPlayer,
+    triggers: Vec<String>,
+}
+
+struct Transition {
+    /// `None` allows the transition from every clip
+    from: Option<String>,
+    to: String,
+    condition: Condition,
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl SpriteSheet {
+    pub fn new() -> SpriteSheet {
+        SpriteSheet::default()
+    }
+
+    /// Slices a texture of width and height into equal frames, left to right and top to bottom
+    pub fn from_grid(
+        width: usize,
+        height: usize,
+        frame_width: usize,
+        frame_height: usize,
+    ) -> SpriteSheet {
+        let mut sheet = SpriteSheet::new();
+        if frame_width == 0 || frame_height == 0 {
+            return sheet;
+        }
+
+        for row in 0..height / frame_height {
+            for column in 0..width / frame_width {
+                sheet.regions.push(Rect::new(
+                    (column * frame_width) as i32,
+                    (row * frame_height) as i32,
+                    frame_width as u32,
+                    frame_height as u32,
+                ));
+            }
+        }
+
+        sheet
+    }
+}
+
+impl Animation {
+    pub fn new(frames: Vec<Frame>, mode: PlayMode) -> Animation {
+        Animation {
+            frames,
+            mode,
+            events: Vec::new(),
+        }
+    }
+
+    /// Creates an animation from sheet regions by index, each shown for `duration` seconds
+    pub fn from_sheet(
+        sheet: &SpriteSheet,
+        indices: &[usize],
+        duration: f32,
+        mode: PlayMode,
+    ) -> Animation {
+        let frames = indices
+            .iter()
+            .filter_map(|&index| sheet.region(index))
+            .map(|source| Frame { source, duration })
+            .collect();
+        Animation::new(frames, mode)
+    }
+
+    /// Creates an animation from named sheet regions, unknown names are skipped
+    pub fn from_names(
+        sheet: &SpriteSheet,
+        names: &[&str],
+        duration: f32,
+        mode: PlayMode,
+    ) -> Animation {
+        let frames = names
+            .iter()
+            .filter_map(|name| sheet.named(name))
+            .map(|source| Frame { source, duration })
+            .collect();
+        Animation::new(frames, mode)
+    }
+}
+
+impl AnimationPlayer {
+    pub fn new() -> AnimationPlayer {
+        AnimationPlayer {
+            frame: 0,
+            elapsed: 0.0,
+            forward: true,
+            finished: false,
+            started: false,
+            speed: 1.0,
+        }
+    }
+}
+
+impl Animator {
+    pub fn new() -> Animator {
+        Animator {
+            clips: HashMap::new(),
+            transitions: Vec::new(),
+            current: None,
+            player: AnimationPlayer::new(),
+            triggers: Vec::new(),
+        }
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl SpriteSheet {
+    /// Adds a named region and returns its index
+    pub fn add_region(&mut self, name: &str, region: Rect) -> usize {
+        self.regions.push(region);
+        self.names.insert(name.to_string(), self.regions.len() - 1);
+        self.regions.len() - 1
+    }
+
+    pub fn region(&self, index: usize) -> Option<Rect> {
+        self.regions.get(index).copied()
+    }
+
+    pub fn named(&self, name: &str) -> Option<Rect> {
+        self.names.get(name).and_then(|&index| self.region(index))
+    }
+
+    pub fn len(&self) -> usize {
+        self.regions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+}
+
+impl Animation {
+    /// Adds an event that is reported whenever the frame is reached
+    pub fn with_event(mut self, frame: usize, name: &str) -> Animation {
+        self.events.push((frame, name.to_string()));
+        self
+    }
+
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    pub fn mode(&self) -> PlayMode {
+        self.mode
+    }
+
+    /// Returns the time of one pass through all frames in seconds
+    pub fn duration(&self) -> f32 {
+        self.frames.iter().map(|frame| frame.duration).sum()
+    }
+
+    /// Returns the time after which a repeating animation is back at the same frame going the
+    /// same way, `None` for animations that play once
+    fn cycle(&self) -> Option<f32> {
+        match (self.mode, self.frames.first(), self.frames.last()) {
+            (PlayMode::Once, _, _) => None,
+            (PlayMode::PingPong, Some(first), Some(last)) if self.frames.len() > 1 => {
+                // The first and last frames are shown once per cycle, the others twice
+                Some(2.0 * self.duration() - first.duration - last.duration)
+            }
+            _ => Some(self.duration()),
+        }
+    }
+
+    fn events_at(&self, frame: usize) -> impl Iterator<Item = &str> {
+        self.events
+            .iter()
+            .filter(move |(at, _)| *at == frame)
+            .map(|(_, name)| name.as_str())
+    }
+}
+
+impl AnimationPlayer {
+    /// Advances by `delta_time` seconds and returns the events of every frame reached.
+    /// The events of the first frame are reported by the first update.
+    pub fn update(&mut self, animation: &Animation, delta_time: f32) -> Vec<String> {
+        let mut events = Vec::new();
+        if animation.frames.is_empty() {
+            return events;
+        }
+
+        if !self.started {
+            self.started = true;
+            events.extend(animation.events_at(self.frame).map(String::from));
+        }
+
+        // Without any duration the animation would never leave the loop below
+        if self.finished || animation.duration() <= 0.0 {
+            return events;
+        }
+
+        // Infinite or NaN times would never be used up by the loop below
+        let elapsed = self.elapsed + delta_time * self.speed;
+        if !elapsed.is_finite() {
+            return events;
+        }
+        self.elapsed = elapsed;
+
+        // Huge steps skip whole cycles, the frames of one cycle are still all reported
+        if let Some(cycle) = animation.cycle().filter(|&cycle| cycle > 0.0) {
+            if self.elapsed >= 2.0 * cycle {
+                self.elapsed = cycle + self.elapsed % cycle;
+            }
+        }
+
+        loop {
+            let duration = animation.frames[self.frame].duration;
+            if self.elapsed < duration {
+                break;
+            }
+
+            match self.next_frame(animation) {
+                Some(next) => {
+                    self.elapsed -= duration;
+                    self.frame = next;
+                    events.extend(animation.events_at(next).map(String::from));
+                }
+                None => {
+                    self.elapsed = duration;
+                    self.finished = true;
+                    break;
+                }
+            }
+        }
+
+        events
+    }
+
+    /// Starts over at the first frame, keeping the speed
+    pub fn reset(&mut self) {
+        *self = AnimationPlayer {
+            speed: self.speed,
+            ..AnimationPlayer::new()
+        };
+    }
+
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    /// Returns the sheet region of the current frame
+    pub fn source(&self, animation: &Animation) -> Option<Rect> {
+        animation.frames.get(self.frame).map(|frame| frame.source)
+    }
+
+    /// Returns true once a `PlayMode::Once` animation showed its last frame to the end
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    /// Sets the playback speed, 1 is normal speed.
+    /// Negative values and NaN are treated as 0 and infinity as the largest finite speed.
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = if speed.is_nan() {
+            0.0
+        } else {
+            speed.clamp(0.0, f32::MAX)
+        };
+    }
+
+    fn next_frame(&mut self, animation: &Animation) -> Option<usize> {
+        let last = animation.frames.len() - 1;
+        match animation.mode {
+            PlayMode::Loop => Some(if self.frame < last { self.frame + 1 } else { 0 }),
+            PlayMode::Once if self.frame < last => Some(self.frame + 1),
+            PlayMode::Once => None,
+            PlayMode::PingPong if last == 0 => Some(0),
+            PlayMode::PingPong => {
+                if self.forward && self.frame == last {
+                    self.forward = false;
+                } else if !self.forward && self.frame == 0 {
+                    self.forward = true;
+                }
+
+                Some(if self.forward {
+                    self.frame + 1
+                } else {
+                    self.frame - 1
+                })
+            }
+        }
+    }
+}
+
+impl Animator {
+    pub fn add_clip(&mut self, name: &str, animation: Animation) {
+        self.clips.insert(name.to_string(), animation);
+    }
+
+    /// Adds a transition between two clips, earlier transitions are checked first
+    pub fn add_transition(&mut self, from: &str, to: &str, condition: Condition) {
+        self.transitions.push(Transition {
+            from: Some(from.to_string()),
+            to: to.to_string(),
+            condition,
+        });
+    }
+
+    /// Adds a transition that can happen from every clip
+    pub fn add_any_transition(&mut self, to: &str, condition: Condition) {
+        self.transitions.push(Transition {
+            from: None,
+            to: to.to_string(),
+            condition,
+        });
+    }
+
+    /// Switches to a clip and starts it from the beginning, unless it is already playing.
+    /// Returns false if there is no such clip.
+    pub fn play(&mut self, name: &str) -> bool {
+        if !self.clips.contains_key(name) {
+            return false;
+        }
+
+        if self.current.as_deref() != Some(name) {
+            self.current = Some(name.to_string());
+            self.player.reset();
+        }
+        true
+    }
+
+    /// Fires a trigger, checked against the transitions on the next update
+    pub fn trigger(&mut self, name: &str) {
+        self.triggers.push(name.to_string());
+    }
+
+    /// Applies triggered transitions, advances the current clip and applies finished transitions
+    pub fn update(&mut self, delta_time: f32) -> Vec<AnimationEvent> {
+        let mut events = Vec::new();
+
+        let triggers = std::mem::take(&mut self.triggers);
+        for trigger in triggers.iter() {
+            let condition = Condition::Trigger(trigger.clone());
+            if let Some(to) = self.find_transition(&condition) {
+                self.play(&to);
+            }
+        }
+
+        self.advance(delta_time, &mut events);
+
+        if self.player.is_finished() {
+            if let Some(to) = self.find_transition(&Condition::Finished) {
+                self.play(&to);
+                // Reports the first frame of the new clip right away
+                self.advance(0.0, &mut events);
+            }
+        }
+
+        events
+    }
+
+    /// Returns the name of the playing clip
+    pub fn current(&self) -> Option<&str> {
+        self.current.as_deref()
+    }
+
+    pub fn player(&self) -> &AnimationPlayer {
+        &self.player
+    }
+
+    pub fn player_mut(&mut self) -> &mut AnimationPlayer {
+        &mut self.player
+    }
+
+    /// Returns the sheet region to draw, `None` before a clip is played
+    pub fn source(&self) -> Option<Rect> {
+        let clip = self.clips.get(self.current.as_deref()?)?;
+        self.player.source(clip)
+    }
+
+    fn advance(&mut self, delta_time: f32, events: &mut Vec<AnimationEvent>) {
+        let name = match &self.current {
+            Some(name) => name,
+            None => return,
+        };
+
+        if let Some(clip) = self.clips.get(name) {
+            for event in self.player.update(clip, delta_time) {
+                events.push(AnimationEvent {
+                    clip: name.clone(),
+                    name: event,
+                });
+            }
+        }
+    }
+
+    /// Returns the clip of the first transition from the current clip with a condition
+    fn find_transition(&self, condition: &Condition) -> Option<String> {
+        let current = self.current.as_deref();
+        self.transitions
+            .iter()
+            .find(|transition| {
+                transition.condition == *condition
+                    && transition
+                        .from
+                        .as_deref()
+                        .is_none_or(|from| Some(from) == current)
+                    && Some(transition.to.as_str()) != current
+            })
+            .map(|transition| transition.to.clone())
+    }
+}
+
+// ====================================
+// Other Implementations
+// ====================================
+
+impl Default for AnimationPlayer {
+    fn default() -> Self {
+        AnimationPlayer::new()
+    }
+}
+
+impl Default for Animator {
+    fn default() -> Self {
+        Animator::new()
+    }
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod animation_tests {
+
+    use super::*;
+
+    fn numbered(count: usize, mode: PlayMode) -> Animation {
+        let sheet = SpriteSheet::from_grid(count * 8, 8, 8, 8);
+        let indices: Vec<usize> = (0..count).collect();
+        Animation::from_sheet(&sheet, &indices, 0.1, mode)
+    }
+
+    /// Frame after each of several updates
+    fn sequence(animation: &Animation, player: &mut AnimationPlayer, steps: usize) -> Vec<usize> {
+        (0..steps)
+            .map(|_| {
+                player.update(animation, 0.1);
+                player.frame()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sheet_regions() {
+        let sheet = SpriteSheet::from_grid(32, 20, 8, 8);
+        assert_eq!(sheet.len(), 8);
+        assert_eq!(sheet.region(5), Some(Rect::new(8, 8, 8, 8)));
+        assert_eq!(sheet.region(8), None);
+
+        let mut sheet = SpriteSheet::new();
+        sheet.add_region("walk_0", Rect::new(0, 0, 16, 16));
+        sheet.add_region("walk_1", Rect::new(16, 0, 16, 24));
+        let walk = Animation::from_names(
+            &sheet,
+            &["walk_1", "missing", "walk_0"],
+            0.2,
+            PlayMode::Loop,
+        );
+        assert_eq!(walk.frames().len(), 2);
+        assert_eq!(walk.frames()[0].source, Rect::new(16, 0, 16, 24));
+        assert!((walk.duration() - 0.4).abs() < 1e-6);
+    }
+
+    #[test]
+    fn play_modes() {
+        let mut player = AnimationPlayer::new();
+        let looping = numbered(3, PlayMode::Loop);
+        assert_eq!(sequence(&looping, &mut player, 5), [1, 2, 0, 1, 2]);
+
+        let mut player = AnimationPlayer::new();
+        let ping_pong = numbered(3, PlayMode::PingPong);
+        assert_eq!(sequence(&ping_pong, &mut player, 6), [1, 2, 1, 0, 1, 2]);
+
+        let mut player = AnimationPlayer::new();
+        let once = numbered(3, PlayMode::Once);
+        assert_eq!(sequence(&once, &mut player, 2), [1, 2]);
+        assert!(!player.is_finished());
+        assert_eq!(sequence(&once, &mut player, 2), [2, 2]);
+        assert!(player.is_finished());
+    }
+
+    #[test]
+    fn durations_speed_and_events() {
+        let frames = vec![
+            Frame {
+                source: Rect::new(0, 0, 8, 8),
+                duration: 0.5,
+            },
+            Frame {
+                source: Rect::new(8, 0, 8, 8),
+                duration: 0.25,
+            },
+        ];
+        let animation = Animation::new(frames, PlayMode::Loop)
+            .with_event(0, "step")
+            .with_event(1, "land");
+
+        let mut player = AnimationPlayer::new();
+        assert_eq!(player.update(&animation, 0.25), ["step"]);
+        assert_eq!(player.update(&animation, 0.25), ["land"]);
+        assert_eq!(player.source(&animation), Some(Rect::new(8, 0, 8, 8)));
+
+        // A long frame delta passes several frames and reports all of them
+        assert_eq!(player.update(&animation, 0.75), ["step", "land"]);
+
+        player.reset();
+        player.set_speed(2.0);
+        player.update(&animation, 0.0);
+        assert_eq!(player.update(&animation, 0.25), ["land"]);
+        assert_eq!(player.speed(), 2.0);
+    }
+
+    #[test]
+    fn extreme_times_and_speeds() {
+        for &mode in [PlayMode::Loop, PlayMode::PingPong, PlayMode::Once].iter() {
+            let animation = numbered(3, mode);
+            let mut player = AnimationPlayer::new();
+            player.update(&animation, 0.05);
+            let frame = player.frame();
+
+            // Non-finite steps are ignored
+            player.update(&animation, f32::INFINITY);
+            player.update(&animation, f32::NAN);
+            assert_eq!(player.frame(), frame);
+
+            // Huge finite steps end in a valid frame instead of counting down forever
+            player.update(&animation, 1e30);
+            assert!(player.frame() < 3);
+
+            player.set_speed(f32::INFINITY);
+            assert_eq!(player.speed(), f32::MAX);
+            player.update(&animation, 1.0);
+            player.set_speed(f32::NAN);
+            assert_eq!(player.speed(), 0.0);
+        }
+
+        // Every frame of one cycle is still reported
+        let animation = numbered(3, PlayMode::Loop).with_event(1, "step");
+        let mut player = AnimationPlayer::new();
+        player.update(&animation, 0.0);
+        assert_eq!(player.update(&animation, 1e6), ["step"]);
+    }
+
+    #[test]
+    fn animator_transitions() {
+        let mut animator = Animator::new();
+        animator.add_clip(
+            "idle",
+            numbered(2, PlayMode::Loop).with_event(0, "idle_start"),
+        );
+        animator.add_clip(
+            "jump",
+            numbered(2, PlayMode::Once).with_event(1, "jump_peak"),
+        );
+        animator.add_transition("idle", "jump", Condition::Trigger("jump".to_string()));
+        animator.add_any_transition("idle", Condition::Finished);
+
+        assert!(!animator.play("run"));
+        assert!(animator.play("idle"));
+        assert_eq!(animator.update(0.0)[0].name, "idle_start");
+        animator.update(0.1);
+        assert_eq!(animator.player().frame(), 1);
+
+        animator.trigger("jump");
+        let events = animator.update(0.1);
+        assert_eq!(animator.current(), Some("jump"));
+        assert_eq!(
+            events,
+            [AnimationEvent {
+                clip: "jump".to_string(),
+                name: "jump_peak".to_string()
+            }]
+        );
+
+        // Triggers without a transition from the current clip are ignored
+        animator.trigger("jump");
+        animator.update(0.0);
+        assert_eq!(animator.current(), Some("jump"));
+
+        let events = animator.update(0.1);
+        assert_eq!(animator.current(), Some("idle"));
+        assert_eq!(events[0].clip, "idle");
+        assert_eq!(animator.source(), Some(Rect::new(0, 0, 8, 8)));
+    }
+}
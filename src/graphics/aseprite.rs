@@ -0,0 +1,981 @@
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::convert::TryInto;
+
+use super::{
+    animation::{Animation, Frame, PlayMode, SpriteSheet},
+    blend::BlendMode,
+    color::Color,
+    color_f::ColorF,
+    palette::{Palette, MAX_COLORS},
+    renderer::Rect,
+    texture::Texture,
+};
+
+const HEADER_MAGIC: u16 = 0xA5E0;
+const FRAME_MAGIC: u16 = 0xF1FA;
+
+const OLD_PALETTE_CHUNK: u16 = 0x0004;
+const LAYER_CHUNK: u16 = 0x2004;
+const CEL_CHUNK: u16 = 0x2005;
+const TAGS_CHUNK: u16 = 0x2018;
+const PALETTE_CHUNK: u16 = 0x2019;
+const SLICE_CHUNK: u16 = 0x2022;
+
+/// Decoded `.aseprite` / `.ase` file.
+/// Cels are decoded into textures, linked cels share the image of the cel they link to.
+#[derive(Clone)]
+pub struct Aseprite {
+    pub width: usize,
+    pub height: usize,
+    pub frames: Vec<AseFrame>,
+    /// Bottom layer first
+    pub layers: Vec<Layer>,
+    pub tags: Vec<Tag>,
+    pub slices: Vec<Slice>,
+    pub palette: Palette,
+}
+
+#[derive(Clone)]
+pub struct AseFrame {
+    /// Time the frame is shown in seconds
+    pub duration: f32,
+    pub cels: Vec<Cel>,
+}
+
+/// Image of one layer in one frame.
+#[derive(Clone)]
+pub struct Cel {
+    pub layer: usize,
+    /// Position of the image in the sprite
+    pub x: i32,
+    pub y: i32,
+    pub opacity: u8,
+    /// Moves the cel above or below other layers
+    pub z_index: i16,
+    pub image: Texture,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LayerKind {
+    Normal,
+    Group,
+    /// Tilemap cels are skipped
+    Tilemap,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Layer {
+    pub name: String,
+    pub kind: LayerKind,
+    pub visible: bool,
+    /// Opaque bottom layer, its transparent palette index is drawn as a color
+    pub background: bool,
+    /// Nesting depth, a layer belongs to the closest group above it with a lower level
+    pub child_level: u16,
+    /// Aseprite modes without an engine equivalent, like darken or hue, fall back to alpha
+    pub blend: BlendMode,
+    pub opacity: u8,
+}
+
+/// Direction a tag is played in.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LoopDirection {
+    Forward,
+    Reverse,
+    PingPong,
+    PingPongReverse,
+}
+
+/// Named range of frames, turned into an animation clip.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Tag {
+    pub name: String,
+    pub from: usize,
+    /// Last frame of the tag, inclusive
+    pub to: usize,
+    pub direction: LoopDirection,
+    /// Times the tag is played, 0 is forever
+    pub repeat: u16,
+}
+
+/// Named region of the sprite, like a hitbox or a nine patch.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Slice {
+    pub name: String,
+    pub keys: Vec<SliceKey>,
+}
+
+/// Slice bounds from a frame on, until the next key.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SliceKey {
+    pub frame: usize,
+    pub bounds: Rect,
+    /// Center of a nine patch, relative to the bounds
+    pub center: Option<Rect>,
+    /// Pivot point, relative to the bounds
+    pub pivot: Option<(i32, i32)>,
+}
+
+#[derive(Debug)]
+pub enum AsepriteError {
+    Io(std::io::Error),
+    /// The data is not an Aseprite file or ends early
+    InvalidFormat,
+    /// A compressed cel could not be inflated
+    Decompress,
+}
+
+/// Little endian reader over the file data
+struct Reader<'a> {
+    data: &'a [u8],
+    at: usize,
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl Aseprite {
+    pub fn load(path: &str) -> Result<Aseprite, AsepriteError> {
+        let data = std::fs::read(path).map_err(AsepriteError::Io)?;
+        Aseprite::parse(&data)
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Aseprite, AsepriteError> {
+        let mut header = Reader::new(data);
+        header.skip(4)?;
+        if header.u16()? != HEADER_MAGIC {
+            return Err(AsepriteError::InvalidFormat);
+        }
+
+        let frame_count = header.u16()? as usize;
+        let width = header.u16()? as usize;
+        let height = header.u16()? as usize;
+        let depth = header.u16()?;
+        header.skip(14)?;
+        let transparent = header.u8()?;
+
+        let mut file = Aseprite {
+            width,
+            height,
+            frames: Vec::with_capacity(frame_count),
+            layers: Vec::new(),
+            tags: Vec::new(),
+            slices: Vec::new(),
+            palette: Palette::new(),
+        };
+
+        let format = PixelFormat { depth, transparent };
+
+        let mut at = 128;
+        for _ in 0..frame_count {
+            let mut frame = Reader::new(data.get(at..).ok_or(AsepriteError::InvalidFormat)?);
+            let frame_size = frame.u32()? as usize;
+            if frame.u16()? != FRAME_MAGIC || frame_size < 16 {
+                return Err(AsepriteError::InvalidFormat);
+            }
+
+            let old_chunks = frame.u16()? as usize;
+            let duration = frame.u16()? as f32 / 1000.0;
+            frame.skip(2)?;
+            let chunks = match frame.u32()? as usize {
+                0 => old_chunks,
+                new_chunks => new_chunks,
+            };
+
+            let mut cels = Vec::new();
+            for _ in 0..chunks {
+                let chunk_size = frame.u32()? as usize;
+                let kind = frame.u16()?;
+                let body = frame.bytes(chunk_size.saturating_sub(6))?;
+                file.read_chunk(kind, body, &format, &mut cels)?;
+            }
+
+            file.frames.push(AseFrame { duration, cels });
+            at += frame_size;
+        }
+
+        Ok(file)
+    }
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Reader<'a> {
+        Reader { data, at: 0 }
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl Aseprite {
+    /// Composites the visible layers of a frame into one texture the size of the sprite
+    pub fn frame_texture(&self, frame: usize) -> Texture {
+        let mut texture = Texture::new(self.width, self.height);
+        let cels = match self.frames.get(frame) {
+            Some(frame) => &frame.cels,
+            None => return texture,
+        };
+
+        // The z index moves a cel between layers, ties go to the higher z index
+        let mut order: Vec<&Cel> = cels
+            .iter()
+            .filter(|cel| self.is_visible(cel.layer))
+            .collect();
+        order.sort_by_key(|cel| (cel.layer as i32 + cel.z_index as i32, cel.z_index));
+
+        for cel in order {
+            let layer = &self.layers[cel.layer];
+            let opacity = cel.opacity as f32 / 255.0 * layer.opacity as f32 / 255.0;
+            draw_cel(&mut texture, cel, layer.blend, opacity);
+        }
+
+        texture
+    }
+
+    /// Returns the image of one layer in a frame, placed in a texture the size of the sprite
+    pub fn layer_texture(&self, frame: usize, layer: usize) -> Texture {
+        let mut texture = Texture::new(self.width, self.height);
+        let cel = self
+            .frames
+            .get(frame)
+            .and_then(|frame| frame.cels.iter().find(|cel| cel.layer == layer));
+        if let Some(cel) = cel {
+            draw_cel(
+                &mut texture,
+                cel,
+                BlendMode::Alpha,
+                cel.opacity as f32 / 255.0,
+            );
+        }
+        texture
+    }
+
+    /// Places all composited frames next to each other in one texture
+    pub fn sprite_sheet(&self) -> (Texture, SpriteSheet) {
+        let columns = self.frames.len().max(1);
+        let mut texture = Texture::new(self.width * columns, self.height);
+        for frame in 0..self.frames.len() {
+            let image = self.frame_texture(frame);
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    texture.set_pixel(frame * self.width + x, y, image.get_pixel(x, y));
+                }
+            }
+        }
+
+        let sheet = SpriteSheet::from_grid(texture.width(), self.height, self.width, self.height);
+        (texture, sheet)
+    }
+
+    /// Creates a clip per tag with the frame durations, for a sheet from `sprite_sheet`.
+    /// Tags repeated once play once, all others loop.
+    pub fn animations(&self, sheet: &SpriteSheet) -> HashMap<String, Animation> {
+        let mut animations = HashMap::new();
+        for tag in self.tags.iter() {
+            let mut indices: Vec<usize> = (tag.from..=tag.to).collect();
+            if let LoopDirection::Reverse | LoopDirection::PingPongReverse = tag.direction {
+                indices.reverse();
+            }
+
+            let frames = indices
+                .iter()
+                .filter_map(|&index| {
+                    Some(Frame {
+                        source: sheet.region(index)?,
+                        duration: self.frames.get(index)?.duration,
+                    })
+                })
+                .collect();
+
+            let mode = match (tag.direction, tag.repeat) {
+                (_, 1) => PlayMode::Once,
+                (LoopDirection::PingPong, _) | (LoopDirection::PingPongReverse, _) => {
+                    PlayMode::PingPong
+                }
+                _ => PlayMode::Loop,
+            };
+            animations.insert(tag.name.clone(), Animation::new(frames, mode));
+        }
+        animations
+    }
+
+    /// Returns the bounds of a slice in a frame, from the last key at or before the frame
+    pub fn slice_bounds(&self, name: &str, frame: usize) -> Option<Rect> {
+        let slice = self.slices.iter().find(|slice| slice.name == name)?;
+        slice
+            .keys
+            .iter()
+            .filter(|key| key.frame <= frame)
+            .max_by_key(|key| key.frame)
+            .map(|key| key.bounds)
+    }
+
+    /// A layer is visible when it and every group it is in are visible
+    fn is_visible(&self, layer: usize) -> bool {
+        let mut level = match self.layers.get(layer) {
+            Some(found) if found.visible && found.kind != LayerKind::Group => found.child_level,
+            _ => return false,
+        };
+
+        for parent in self.layers[..layer].iter().rev() {
+            if level == 0 {
+                break;
+            }
+            if parent.child_level < level {
+                if !parent.visible {
+                    return false;
+                }
+                level = parent.child_level;
+            }
+        }
+        true
+    }
+
+    fn read_chunk(
+        &mut self,
+        kind: u16,
+        body: &[u8],
+        format: &PixelFormat,
+        cels: &mut Vec<Cel>,
+    ) -> Result<(), AsepriteError> {
+        let mut reader = Reader::new(body);
+        match kind {
+            LAYER_CHUNK => {
+                let flags = reader.u16()?;
+                let kind = match reader.u16()? {
+                    1 => LayerKind::Group,
+                    2 => LayerKind::Tilemap,
+                    _ => LayerKind::Normal,
+                };
+                let child_level = reader.u16()?;
+                reader.skip(4)?;
+                let blend = match reader.u16()? {
+                    1 => BlendMode::Multiply,
+                    2 => BlendMode::Screen,
+                    3 => BlendMode::Overlay,
+                    16 => BlendMode::Additive,
+                    _ => BlendMode::Alpha,
+                };
+                let opacity = reader.u8()?;
+                reader.skip(3)?;
+                let name = reader.string()?;
+
+                self.layers.push(Layer {
+                    name,
+                    kind,
+                    visible: flags & 0x0001 != 0,
+                    background: flags & 0x0008 != 0,
+                    child_level,
+                    blend,
+                    opacity,
+                });
+            }
+            CEL_CHUNK => {
+                let layer = reader.u16()? as usize;
+                let x = reader.i16()? as i32;
+                let y = reader.i16()? as i32;
+                let opacity = reader.u8()?;
+                let cel_type = reader.u16()?;
+                let z_index = reader.i16()?;
+                reader.skip(5)?;
+
+                let image = match cel_type {
+                    0 | 2 => {
+                        let width = reader.u16()? as usize;
+                        let height = reader.u16()? as usize;
+                        let rest = &body[reader.at..];
+                        let pixels = if cel_type == 2 {
+                            // The size is known, so a small stream can't inflate to gigabytes
+                            let size = width
+                                .checked_mul(height)
+                                .and_then(|count| count.checked_mul(format.bytes_per_pixel()?))
+                                .ok_or(AsepriteError::InvalidFormat)?;
+                            miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(rest, size)
+                                .map_err(|_| AsepriteError::Decompress)?
+                        } else {
+                            rest.to_vec()
+                        };
+                        let background =
+                            self.layers.get(layer).is_some_and(|layer| layer.background);
+                        format.decode(&pixels, width, height, &self.palette, background)?
+                    }
+                    1 => {
+                        let linked = reader.u16()? as usize;
+                        let source = self
+                            .frames
+                            .get(linked)
+                            .and_then(|frame| frame.cels.iter().find(|cel| cel.layer == layer));
+                        match source {
+                            Some(source) => source.image.clone(),
+                            None => return Ok(()),
+                        }
+                    }
+                    _ => return Ok(()),
+                };
+
+                cels.push(Cel {
+                    layer,
+                    x,
+                    y,
+                    opacity,
+                    z_index,
+                    image,
+                });
+            }
+            TAGS_CHUNK => {
+                let count = reader.u16()?;
+                reader.skip(8)?;
+                for _ in 0..count {
+                    let from = reader.u16()? as usize;
+                    let to = reader.u16()? as usize;
+                    let direction = match reader.u8()? {
+                        1 => LoopDirection::Reverse,
+                        2 => LoopDirection::PingPong,
+                        3 => LoopDirection::PingPongReverse,
+                        _ => LoopDirection::Forward,
+                    };
+                    let repeat = reader.u16()?;
+                    reader.skip(10)?;
+                    let name = reader.string()?;
+
+                    self.tags.push(Tag {
+                        name,
+                        from,
+                        to: to.max(from),
+                        direction,
+                        repeat,
+                    });
+                }
+            }
+            PALETTE_CHUNK => {
+                let size = reader.u32()? as usize;
+                let first = reader.u32()? as usize;
+                let last = reader.u32()? as usize;
+                reader.skip(8)?;
+
+                // Entries take at least 6 bytes, so counts the chunk cannot hold are corrupt
+                if first > last || last >= size || last - first >= reader.remaining() / 6 {
+                    return Err(AsepriteError::InvalidFormat);
+                }
+
+                // Colors past MAX_COLORS are dropped by the palette anyway
+                let mut colors = self.palette.colors().to_vec();
+                colors.resize(size.min(MAX_COLORS).max(colors.len()), Color::CLEAR);
+                for index in first..=last {
+                    let flags = reader.u16()?;
+                    let color =
+                        Color::from_rgba(reader.u8()?, reader.u8()?, reader.u8()?, reader.u8()?);
+                    if flags & 0x0001 != 0 {
+                        reader.string()?;
+                    }
+                    if let Some(entry) = colors.get_mut(index) {
+                        *entry = color;
+                    }
+                }
+                self.palette = Palette::from_colors(&colors);
+            }
+            OLD_PALETTE_CHUNK => {
+                // Only used when the file has no new palette chunk
+                if !self.palette.is_empty() {
+                    return Ok(());
+                }
+
+                let mut colors = Vec::new();
+                for _ in 0..reader.u16()? {
+                    let skip = reader.u8()? as usize;
+                    colors.resize(colors.len() + skip, Color::CLEAR);
+                    let count = match reader.u8()? {
+                        0 => 256,
+                        count => count as usize,
+                    };
+                    for _ in 0..count {
+                        colors.push(Color::from_rgb(reader.u8()?, reader.u8()?, reader.u8()?));
+                    }
+                }
+                self.palette = Palette::from_colors(&colors);
+            }
+            SLICE_CHUNK => {
+                let count = reader.u32()?;
+                let flags = reader.u32()?;
+                reader.skip(4)?;
+                let name = reader.string()?;
+
+                let mut keys = Vec::new();
+                for _ in 0..count {
+                    let frame = reader.u32()? as usize;
+                    let bounds = reader.rect()?;
+                    let center = if flags & 0x0001 != 0 {
+                        Some(reader.rect()?)
+                    } else {
+                        None
+                    };
+                    let pivot = if flags & 0x0002 != 0 {
+                        Some((reader.i32()?, reader.i32()?))
+                    } else {
+                        None
+                    };
+                    keys.push(SliceKey {
+                        frame,
+                        bounds,
+                        center,
+                        pivot,
+                    });
+                }
+                self.slices.push(Slice { name, keys });
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+}
+
+/// Color depth of the file and the palette index that is transparent in indexed files
+struct PixelFormat {
+    depth: u16,
+    transparent: u8,
+}
+
+impl PixelFormat {
+    fn bytes_per_pixel(&self) -> Option<usize> {
+        match self.depth {
+            32 => Some(4),
+            16 => Some(2),
+            8 => Some(1),
+            _ => None,
+        }
+    }
+
+    fn decode(
+        &self,
+        pixels: &[u8],
+        width: usize,
+        height: usize,
+        palette: &Palette,
+        background: bool,
+    ) -> Result<Texture, AsepriteError> {
+        let bytes = self.bytes_per_pixel().ok_or(AsepriteError::InvalidFormat)?;
+        if pixels.len() < width * height * bytes {
+            return Err(AsepriteError::InvalidFormat);
+        }
+
+        let mut texture = Texture::new(width, height);
+        for (i, pixel) in pixels.chunks_exact(bytes).take(width * height).enumerate() {
+            let color = match pixel {
+                [r, g, b, a] => Color::from_rgba(*r, *g, *b, *a),
+                [value, alpha] => Color::from_rgba(*value, *value, *value, *alpha),
+                // The background layer is opaque, so its transparent index is a real color
+                [index] if *index == self.transparent && !background => Color::CLEAR,
+                [index] => palette.get(*index),
+                _ => Color::CLEAR,
+            };
+            texture.set_pixel(i % width, i / width, color);
+        }
+
+        Ok(texture)
+    }
+}
+
+/// Blends a cel onto a texture the size of the sprite
+fn draw_cel(texture: &mut Texture, cel: &Cel, blend: BlendMode, opacity: f32) {
+    for y in 0..cel.image.height() {
+        for x in 0..cel.image.width() {
+            let (tx, ty) = (cel.x + x as i32, cel.y + y as i32);
+            if tx < 0 || ty < 0 {
+                continue;
+            }
+
+            let mut color = ColorF::from(cel.image.get_pixel(x, y));
+            color.alpha *= opacity;
+            texture.blend_pixel(tx as usize, ty as usize, color, blend);
+        }
+    }
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], AsepriteError> {
+        let bytes = self
+            .data
+            .get(self.at..self.at + count)
+            .ok_or(AsepriteError::InvalidFormat)?;
+        self.at += count;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, count: usize) -> Result<(), AsepriteError> {
+        self.bytes(count).map(|_| ())
+    }
+
+    fn remaining(&self) -> usize {
+        self.data.len().saturating_sub(self.at)
+    }
+
+    fn u8(&mut self) -> Result<u8, AsepriteError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, AsepriteError> {
+        Ok(u16::from_le_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    fn i16(&mut self) -> Result<i16, AsepriteError> {
+        self.u16().map(|v| v as i16)
+    }
+
+    fn u32(&mut self) -> Result<u32, AsepriteError> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn i32(&mut self) -> Result<i32, AsepriteError> {
+        self.u32().map(|v| v as i32)
+    }
+
+    fn string(&mut self) -> Result<String, AsepriteError> {
+        let length = self.u16()? as usize;
+        Ok(String::from_utf8_lossy(self.bytes(length)?).to_string())
+    }
+
+    /// Reads a signed position followed by an unsigned size
+    fn rect(&mut self) -> Result<Rect, AsepriteError> {
+        Ok(Rect::new(
+            self.i32()?,
+            self.i32()?,
+            self.u32()?,
+            self.u32()?,
+        ))
+    }
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod aseprite_tests {
+
+    use super::*;
+
+    /// Writes chunks in the order the fields are given
+    #[derive(Default)]
+    struct Writer(Vec<u8>);
+
+    impl Writer {
+        fn u8(mut self, value: u8) -> Writer {
+            self.0.push(value);
+            self
+        }
+
+        fn u16(mut self, value: u16) -> Writer {
+            self.0.extend_from_slice(&value.to_le_bytes());
+            self
+        }
+
+        fn u32(mut self, value: u32) -> Writer {
+            self.0.extend_from_slice(&value.to_le_bytes());
+            self
+        }
+
+        fn zeros(mut self, count: usize) -> Writer {
+            self.0.resize(self.0.len() + count, 0);
+            self
+        }
+
+        fn string(self, value: &str) -> Writer {
+            let mut writer = self.u16(value.len() as u16);
+            writer.0.extend_from_slice(value.as_bytes());
+            writer
+        }
+
+        fn bytes(mut self, bytes: &[u8]) -> Writer {
+            self.0.extend_from_slice(bytes);
+            self
+        }
+    }
+
+    fn chunk(kind: u16, body: Writer) -> Vec<u8> {
+        Writer::default()
+            .u32(body.0.len() as u32 + 6)
+            .u16(kind)
+            .bytes(&body.0)
+            .0
+    }
+
+    fn layer(name: &str, flags: u16, kind: u16, level: u16, blend: u16, opacity: u8) -> Vec<u8> {
+        let body = Writer::default()
+            .u16(flags)
+            .u16(kind)
+            .u16(level)
+            .zeros(4)
+            .u16(blend)
+            .u8(opacity)
+            .zeros(3)
+            .string(name);
+        chunk(LAYER_CHUNK, body)
+    }
+
+    fn cel_header(layer: u16, x: u16, y: u16, opacity: u8, cel_type: u16) -> Writer {
+        Writer::default()
+            .u16(layer)
+            .u16(x)
+            .u16(y)
+            .u8(opacity)
+            .u16(cel_type)
+            .zeros(7)
+    }
+
+    fn raw_cel(
+        layer: u16,
+        x: u16,
+        y: u16,
+        opacity: u8,
+        size: (u16, u16),
+        pixels: &[u8],
+    ) -> Vec<u8> {
+        let body = cel_header(layer, x, y, opacity, 0)
+            .u16(size.0)
+            .u16(size.1)
+            .bytes(pixels);
+        chunk(CEL_CHUNK, body)
+    }
+
+    fn file(width: u16, height: u16, depth: u16, frames: &[(u16, Vec<Vec<u8>>)]) -> Vec<u8> {
+        let mut data = Writer::default()
+            .u32(0)
+            .u16(HEADER_MAGIC)
+            .u16(frames.len() as u16)
+            .u16(width)
+            .u16(height)
+            .u16(depth)
+            .zeros(14)
+            .u8(0)
+            .0;
+        data.resize(128, 0);
+
+        for (duration, chunks) in frames.iter() {
+            let body: Vec<u8> = chunks.concat();
+            let frame = Writer::default()
+                .u32(body.len() as u32 + 16)
+                .u16(FRAME_MAGIC)
+                .u16(chunks.len() as u16)
+                .u16(*duration)
+                .zeros(2)
+                .u32(chunks.len() as u32)
+                .bytes(&body);
+            data.extend(frame.0);
+        }
+        data
+    }
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const BLUE: [u8; 4] = [0, 0, 255, 255];
+
+    /// Two 2x2 frames with a red background, a half transparent blue cel on top,
+    /// a hidden layer and a hidden group with a layer inside
+    fn two_frames() -> Vec<u8> {
+        let red = [RED; 4].concat();
+        let blue = miniz_oxide::deflate::compress_to_vec_zlib(&BLUE, 6);
+        let compressed = chunk(
+            CEL_CHUNK,
+            cel_header(1, 1, 0, 128, 2).u16(1).u16(1).bytes(&blue),
+        );
+        let linked = chunk(CEL_CHUNK, cel_header(0, 0, 0, 255, 1).u16(0));
+
+        let tags = Writer::default()
+            .u16(2)
+            .zeros(8)
+            .u16(0)
+            .u16(1)
+            .u8(2)
+            .u16(0)
+            .zeros(10)
+            .string("idle")
+            .u16(1)
+            .u16(1)
+            .u8(0)
+            .u16(1)
+            .zeros(10)
+            .string("hit");
+
+        // Two keys with pivots: frame, x, y, width, height, pivot x and pivot y
+        let slice = Writer::default()
+            .u32(2)
+            .u32(2)
+            .zeros(4)
+            .string("hitbox")
+            .u32(0)
+            .zeros(8)
+            .u32(1)
+            .u32(2)
+            .zeros(8)
+            .u32(1)
+            .zeros(8)
+            .u32(2)
+            .u32(2)
+            .u32(1)
+            .u32(1);
+
+        file(
+            2,
+            2,
+            32,
+            &[
+                (
+                    100,
+                    vec![
+                        layer("Background", 0x0009, 0, 0, 0, 255),
+                        layer("Top", 0x0001, 0, 0, 0, 255),
+                        layer("Hidden", 0, 0, 0, 0, 255),
+                        layer("Group", 0, 1, 0, 0, 255),
+                        layer("Inside", 0x0001, 0, 1, 0, 255),
+                        raw_cel(0, 0, 0, 255, (2, 2), &red),
+                        compressed,
+                        raw_cel(2, 0, 0, 255, (1, 1), &BLUE),
+                        raw_cel(4, 0, 1, 255, (1, 1), &BLUE),
+                        chunk(TAGS_CHUNK, tags),
+                        chunk(SLICE_CHUNK, slice),
+                    ],
+                ),
+                (250, vec![linked]),
+            ],
+        )
+    }
+
+    #[test]
+    fn parse_file() {
+        let file = Aseprite::parse(&two_frames()).unwrap();
+        assert_eq!((file.width, file.height), (2, 2));
+        assert_eq!(file.layers.len(), 5);
+        assert_eq!(file.layers[3].kind, LayerKind::Group);
+        assert_eq!(file.frames[0].duration, 0.1);
+        assert_eq!(file.frames[1].duration, 0.25);
+
+        let compressed = &file.frames[0].cels[1];
+        assert_eq!(
+            (compressed.x, compressed.y, compressed.opacity),
+            (1, 0, 128)
+        );
+        assert_eq!(compressed.image.get_pixel(0, 0), Color::BLUE);
+
+        // The second frame links to the background of the first
+        assert_eq!(file.frames[1].cels.len(), 1);
+        assert_eq!(file.frames[1].cels[0].image.get_pixel(1, 1), Color::RED);
+
+        assert_eq!(file.slice_bounds("hitbox", 0), Some(Rect::new(0, 0, 1, 2)));
+        assert_eq!(file.slice_bounds("hitbox", 5), Some(Rect::new(0, 0, 2, 2)));
+        assert_eq!(file.slices[0].keys[1].pivot, Some((1, 1)));
+    }
+
+    #[test]
+    fn composite_visible_layers() {
+        let file = Aseprite::parse(&two_frames()).unwrap();
+        let frame = file.frame_texture(0);
+
+        assert_eq!(frame.get_pixel(0, 0), Color::RED);
+        let mixed = frame.get_pixel(1, 0);
+        assert_eq!((mixed.red, mixed.blue, mixed.alpha), (127, 128, 255));
+        // Hidden layers and layers in hidden groups are not drawn
+        assert_eq!(frame.get_pixel(0, 1), Color::RED);
+
+        assert_eq!(file.frame_texture(1).get_pixel(1, 0), Color::RED);
+        assert_eq!(file.layer_texture(0, 2).get_pixel(0, 0), Color::BLUE);
+    }
+
+    #[test]
+    fn animations_from_tags() {
+        let file = Aseprite::parse(&two_frames()).unwrap();
+        let (texture, sheet) = file.sprite_sheet();
+        assert_eq!((texture.width(), texture.height()), (4, 2));
+        assert_eq!(sheet.len(), 2);
+
+        let animations = file.animations(&sheet);
+        let idle = &animations["idle"];
+        assert_eq!(idle.mode(), PlayMode::PingPong);
+        assert_eq!(idle.frames().len(), 2);
+        assert_eq!(idle.frames()[1].source, Rect::new(2, 0, 2, 2));
+        assert!((idle.duration() - 0.35).abs() < 1e-6);
+
+        let hit = &animations["hit"];
+        assert_eq!(hit.mode(), PlayMode::Once);
+        assert_eq!(hit.frames().len(), 1);
+    }
+
+    #[test]
+    fn indexed_colors() {
+        let palette = Writer::default()
+            .u32(2)
+            .u32(0)
+            .u32(1)
+            .zeros(8)
+            .u16(0)
+            .bytes(&[0, 0, 0, 255])
+            .u16(1)
+            .bytes(&[0, 255, 0, 255])
+            .string("green");
+
+        let data = file(
+            2,
+            1,
+            8,
+            &[(
+                100,
+                vec![
+                    chunk(PALETTE_CHUNK, palette),
+                    layer("Layer", 0x0001, 0, 0, 0, 255),
+                    raw_cel(0, 0, 0, 255, (2, 1), &[0, 1]),
+                ],
+            )],
+        );
+
+        let file = Aseprite::parse(&data).unwrap();
+        assert_eq!(file.palette.len(), 2);
+        let frame = file.frame_texture(0);
+        // Index 0 is the transparent index
+        assert_eq!(frame.get_pixel(0, 0), Color::CLEAR);
+        assert_eq!(frame.get_pixel(1, 0), Color::GREEN);
+    }
+
+    #[test]
+    fn invalid_data() {
+        assert!(matches!(
+            Aseprite::parse(b"not an aseprite file"),
+            Err(AsepriteError::InvalidFormat)
+        ));
+
+        let mut truncated = two_frames();
+        truncated.truncate(200);
+        assert!(matches!(
+            Aseprite::parse(&truncated),
+            Err(AsepriteError::InvalidFormat)
+        ));
+
+        // A palette claiming billions of colors with a single entry
+        let palette = Writer::default()
+            .u32(u32::MAX)
+            .u32(0)
+            .u32(u32::MAX - 1)
+            .zeros(8)
+            .u16(0)
+            .bytes(&[0, 0, 0, 255]);
+        let data = file(1, 1, 8, &[(100, vec![chunk(PALETTE_CHUNK, palette)])]);
+        assert!(matches!(
+            Aseprite::parse(&data),
+            Err(AsepriteError::InvalidFormat)
+        ));
+
+        // A 1x1 cel whose pixels inflate to a megabyte
+        let bomb = miniz_oxide::deflate::compress_to_vec_zlib(&[0; 1 << 20], 6);
+        let cel = chunk(
+            CEL_CHUNK,
+            cel_header(0, 0, 0, 255, 2).u16(1).u16(1).bytes(&bomb),
+        );
+        let data = file(1, 1, 32, &[(100, vec![cel])]);
+        assert!(matches!(
+            Aseprite::parse(&data),
+            Err(AsepriteError::Decompress)
+        ));
+    }
+}
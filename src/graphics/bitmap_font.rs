@@ -0,0 +1,534 @@
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::path::Path;
+
+use super::{
+    renderer::Rect,
+    sprite::Sprite,
+    text::{Font, Glyph},
+    texture::Texture,
+    xml::Element,
+};
+use image::ImageError;
+
+/// Highest page id a font file may use, BMFont itself stops far below
+const MAX_PAGES: usize = 256;
+
+/// Font made of pre-drawn glyph images on one or more atlas pages.
+pub struct BitmapFont {
+    line_height: f32,
+    base: f32,
+    glyphs: HashMap<char, Glyph>,
+    kerning: HashMap<(char, char), f32>,
+    page_files: Vec<String>,
+    pages: Vec<Option<Sprite>>,
+}
+
+#[derive(Debug)]
+pub enum FontError {
+    Io(std::io::Error),
+    Image(ImageError),
+    /// The data is not a BMFont file, or a value could not be read
+    InvalidFormat,
+    /// The font stores its glyphs in a way that is not supported, like CFF outlines
+    UnsupportedOutlines,
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl BitmapFont {
+    /// Loads a BMFont `.fnt` file in text, XML or binary format, and the page images next to it
+    pub fn load(path: &str) -> Result<BitmapFont, FontError> {
+        let data = std::fs::read(path).map_err(FontError::Io)?;
+        let mut font = BitmapFont::parse(&data)?;
+
+        let directory = Path::new(path).parent().unwrap_or_else(|| Path::new(""));
+        for index in 0..font.page_files.len() {
+            let file = directory.join(&font.page_files[index]);
+            let texture = Texture::from_image(&file.to_string_lossy()).map_err(FontError::Image)?;
+            font.set_page(index, texture);
+        }
+
+        Ok(font)
+    }
+
+    /// Reads the glyphs of a BMFont file, the pages must be added with `set_page`
+    pub fn parse(data: &[u8]) -> Result<BitmapFont, FontError> {
+        if data.starts_with(b"BMF") {
+            return parse_binary(data);
+        }
+
+        let text = std::str::from_utf8(data).map_err(|_| FontError::InvalidFormat)?;
+        let text = text.trim_start_matches('\u{feff}').trim_start();
+        let records = if text.starts_with('<') {
+            let root = Element::parse(text).map_err(|_| FontError::InvalidFormat)?;
+            let mut records = Vec::new();
+            xml_records(&root, &mut records);
+            records
+        } else {
+            text.lines().filter_map(text_record).collect()
+        };
+
+        from_records(&records)
+    }
+
+    /// Creates a font from a texture divided into equal cells.
+    /// The cells hold `chars` in order, left to right and top to bottom.
+    pub fn from_grid(
+        texture: Texture,
+        cell_width: usize,
+        cell_height: usize,
+        chars: &str,
+    ) -> BitmapFont {
+        let columns = (texture.width() / cell_width.max(1)).max(1);
+        let mut glyphs = HashMap::new();
+
+        for (index, c) in chars.chars().enumerate() {
+            let source = Rect::new(
+                ((index % columns) * cell_width) as i32,
+                ((index / columns) * cell_height) as i32,
+                cell_width as u32,
+                cell_height as u32,
+            );
+            glyphs.insert(
+                c,
+                Glyph {
+                    page: 0,
+                    source,
+                    offset: (0.0, 0.0),
+                    advance: cell_width as f32,
+                },
+            );
+        }
+
+        BitmapFont {
+            line_height: cell_height as f32,
+            base: cell_height as f32,
+            glyphs,
+            kerning: HashMap::new(),
+            page_files: Vec::new(),
+            pages: vec![Some(Sprite::from_texture(texture))],
+        }
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl BitmapFont {
+    /// Sets the atlas texture of a page
+    pub fn set_page(&mut self, index: usize, texture: Texture) {
+        if self.pages.len() <= index {
+            self.pages.resize_with(index + 1, || None);
+        }
+        self.pages[index] = Some(Sprite::from_texture(texture));
+    }
+
+    /// Returns the image file names of the pages, relative to the font file
+    pub fn page_files(&self) -> &[String] {
+        &self.page_files
+    }
+
+    /// Returns the distance from the top of a line to the baseline
+    pub fn base(&self) -> f32 {
+        self.base
+    }
+
+    /// Returns true if the font has a glyph for a character
+    pub fn has_glyph(&self, c: char) -> bool {
+        self.glyphs.contains_key(&c)
+    }
+}
+
+impl Font for BitmapFont {
+    fn line_height(&self) -> f32 {
+        self.line_height
+    }
+
+    fn glyph(&self, c: char) -> Option<Glyph> {
+        self.glyphs.get(&c).copied()
+    }
+
+    fn kerning(&self, left: char, right: char) -> f32 {
+        self.kerning.get(&(left, right)).copied().unwrap_or(0.0)
+    }
+
+    fn page(&self, page: usize) -> Option<&Sprite> {
+        self.pages.get(page).and_then(|page| page.as_ref())
+    }
+}
+
+/// Tag and attributes of one line of the text format or one element of the XML format
+struct Record {
+    tag: String,
+    attributes: HashMap<String, String>,
+}
+
+impl Record {
+    fn get<T: std::str::FromStr>(&self, key: &str) -> Result<T, FontError> {
+        self.attributes
+            .get(key)
+            .and_then(|value| value.parse().ok())
+            .ok_or(FontError::InvalidFormat)
+    }
+
+    fn get_or<T: std::str::FromStr>(&self, key: &str, default: T) -> T {
+        self.get(key).unwrap_or(default)
+    }
+}
+
+fn from_records(records: &[Record]) -> Result<BitmapFont, FontError> {
+    let mut font = BitmapFont {
+        line_height: 0.0,
+        base: 0.0,
+        glyphs: HashMap::new(),
+        kerning: HashMap::new(),
+        page_files: Vec::new(),
+        pages: Vec::new(),
+    };
+    let mut has_common = false;
+
+    for record in records.iter() {
+        match record.tag.as_str() {
+            "common" => {
+                font.line_height = record.get("lineHeight")?;
+                font.base = record.get_or("base", font.line_height);
+                has_common = true;
+            }
+            "page" => {
+                let id: usize = record.get("id")?;
+                let file: String = record.get("file")?;
+                if id >= MAX_PAGES {
+                    return Err(FontError::InvalidFormat);
+                }
+                if font.page_files.len() <= id {
+                    font.page_files.resize(id + 1, String::new());
+                }
+                font.page_files[id] = file;
+            }
+            "char" => {
+                let id: u32 = record.get("id")?;
+                let c = std::char::from_u32(id).ok_or(FontError::InvalidFormat)?;
+                let glyph = Glyph {
+                    page: record.get_or("page", 0),
+                    source: Rect::new(
+                        record.get("x")?,
+                        record.get("y")?,
+                        record.get("width")?,
+                        record.get("height")?,
+                    ),
+                    offset: (record.get_or("xoffset", 0.0), record.get_or("yoffset", 0.0)),
+                    advance: record.get("xadvance")?,
+                };
+                font.glyphs.insert(c, glyph);
+            }
+            "kerning" => {
+                let first = std::char::from_u32(record.get("first")?);
+                let second = std::char::from_u32(record.get("second")?);
+                if let (Some(first), Some(second)) = (first, second) {
+                    font.kerning.insert((first, second), record.get("amount")?);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    if !has_common {
+        return Err(FontError::InvalidFormat);
+    }
+
+    Ok(font)
+}
+
+/// Parses a line such as `char id=65 x=0 y=0` into a record
+fn text_record(line: &str) -> Option<Record> {
+    let line = line.trim();
+    let split = line.find(char::is_whitespace).unwrap_or(line.len());
+    let (tag, rest) = line.split_at(split);
+    if tag.is_empty() {
+        return None;
+    }
+
+    Some(Record {
+        tag: tag.to_string(),
+        attributes: parse_attributes(rest),
+    })
+}
+
+/// Adds a record for an element of a BMFont XML file and each element inside it,
+/// nesting is ignored
+fn xml_records(element: &Element, records: &mut Vec<Record>) {
+    records.push(Record {
+        tag: element.name.clone(),
+        attributes: element.attributes.iter().cloned().collect(),
+    });
+    for child in element.children.iter() {
+        xml_records(child, records);
+    }
+}
+
+/// Parses `key=value` pairs where values may be quoted
+fn parse_attributes(text: &str) -> HashMap<String, String> {
+    let mut attributes = HashMap::new();
+    let mut chars = text.chars().peekable();
+
+    loop {
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+
+        let key: String = chars
+            .by_ref()
+            .take_while(|&c| c != '=')
+            .collect::<String>()
+            .trim()
+            .to_string();
+        if key.is_empty() {
+            break;
+        }
+
+        let value: String = match chars.peek() {
+            Some(&quote) if quote == '"' || quote == '\'' => {
+                chars.next();
+                chars.by_ref().take_while(|&c| c != quote).collect()
+            }
+            _ => chars.by_ref().take_while(|c| !c.is_whitespace()).collect(),
+        };
+
+        attributes.insert(key, value);
+    }
+
+    attributes
+}
+
+/// Parses the binary format, version 3
+fn parse_binary(data: &[u8]) -> Result<BitmapFont, FontError> {
+    if data.len() < 4 || data[3] != 3 {
+        return Err(FontError::InvalidFormat);
+    }
+
+    let u16_at = |block: &[u8], at: usize| -> Result<u16, FontError> {
+        let bytes = block.get(at..at + 2).ok_or(FontError::InvalidFormat)?;
+        Ok(u16::from_le_bytes(bytes.try_into().unwrap()))
+    };
+    let u32_at = |block: &[u8], at: usize| -> Result<u32, FontError> {
+        let bytes = block.get(at..at + 4).ok_or(FontError::InvalidFormat)?;
+        Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+    };
+
+    let mut records = Vec::new();
+    let mut record = |tag: &str, values: &[(&str, String)]| {
+        records.push(Record {
+            tag: tag.to_string(),
+            attributes: values
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.clone()))
+                .collect(),
+        });
+    };
+
+    let mut at = 4;
+    while at < data.len() {
+        let kind = data[at];
+        let size = u32_at(data, at + 1)? as usize;
+        let block = data
+            .get(at + 5..at + 5 + size)
+            .ok_or(FontError::InvalidFormat)?;
+        at += 5 + size;
+
+        match kind {
+            2 => record(
+                "common",
+                &[
+                    ("lineHeight", u16_at(block, 0)?.to_string()),
+                    ("base", u16_at(block, 2)?.to_string()),
+                ],
+            ),
+            3 => {
+                let names = block.split(|&b| b == 0).filter(|name| !name.is_empty());
+                for (id, name) in names.enumerate() {
+                    let file = String::from_utf8_lossy(name).to_string();
+                    record("page", &[("id", id.to_string()), ("file", file)]);
+                }
+            }
+            4 => {
+                for entry in block.chunks_exact(20) {
+                    let signed = |at: usize| u16_at(entry, at).map(|v| v as i16);
+                    record(
+                        "char",
+                        &[
+                            ("id", u32_at(entry, 0)?.to_string()),
+                            ("x", u16_at(entry, 4)?.to_string()),
+                            ("y", u16_at(entry, 6)?.to_string()),
+                            ("width", u16_at(entry, 8)?.to_string()),
+                            ("height", u16_at(entry, 10)?.to_string()),
+                            ("xoffset", signed(12)?.to_string()),
+                            ("yoffset", signed(14)?.to_string()),
+                            ("xadvance", signed(16)?.to_string()),
+                            ("page", entry[18].to_string()),
+                        ],
+                    );
+                }
+            }
+            5 => {
+                for entry in block.chunks_exact(10) {
+                    record(
+                        "kerning",
+                        &[
+                            ("first", u32_at(entry, 0)?.to_string()),
+                            ("second", u32_at(entry, 4)?.to_string()),
+                            ("amount", (u16_at(entry, 8)? as i16).to_string()),
+                        ],
+                    );
+                }
+            }
+            _ => (),
+        }
+    }
+
+    from_records(&records)
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod bitmap_font_tests {
+
+    use super::*;
+
+    const TEXT_FONT: &str = "info face=\"Test Font\" size=8
+common lineHeight=10 base=8 scaleW=64 scaleH=64 pages=1
+page id=0 file=\"test font.png\"
+chars count=2
+char id=65 x=0 y=0 width=6 height=8 xoffset=0 yoffset=2 xadvance=7 page=0 chnl=15
+char id=86 x=6 y=0 width=6 height=8 xoffset=-1 yoffset=2 xadvance=6 page=0 chnl=15
+kernings count=1
+kerning first=65 second=86 amount=-2
+";
+
+    const XML_FONT: &str = "<?xml version=\"1.0\"?>
+<font>
+  <info face=\"Test Font\" size=\"8\"/>
+  <common lineHeight=\"10\" base=\"8\" scaleW=\"64\" scaleH=\"64\" pages=\"1\"/>
+  <pages>
+    <page id=\"0\" file=\"test &amp; font.png\" />
+  </pages>
+  <!-- <char id=\"66\"/> -->
+  <chars count=\"2\">
+    <char id=\"65\" x=\"0\" y=\"0\" width=\"6\" height=\"8\" xoffset=\"0\" yoffset=\"2\" xadvance=\"7\" page=\"0\"/>
+    <char id=\"86\" x=\"6\" y=\"0\" width=\"6\" height=\"8\" xoffset=\"-1\" yoffset=\"2\" xadvance=\"6\" page=\"0\"/>
+  </chars>
+  <kernings count=\"1\">
+    <kerning first=\"65\" second=\"86\" amount=\"-2\"/>
+  </kernings>
+</font>
+";
+
+    fn binary_font() -> Vec<u8> {
+        let mut data = b"BMF\x03".to_vec();
+        let mut block = |kind: u8, bytes: Vec<u8>| {
+            data.push(kind);
+            data.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+            data.extend_from_slice(&bytes);
+        };
+
+        let mut common = Vec::new();
+        for value in [10u16, 8, 64, 64, 1].iter() {
+            common.extend_from_slice(&value.to_le_bytes());
+        }
+        common.extend_from_slice(&[0, 0, 0, 0, 0]);
+        block(2, common);
+        block(3, b"test font.png\0".to_vec());
+
+        let mut chars = Vec::new();
+        for (id, x, xoffset, xadvance) in [(65u32, 0u16, 0i16, 7i16), (86, 6, -1, 6)].iter() {
+            chars.extend_from_slice(&id.to_le_bytes());
+            for value in [*x, 0, 6, 8].iter() {
+                chars.extend_from_slice(&value.to_le_bytes());
+            }
+            for value in [*xoffset, 2, *xadvance].iter() {
+                chars.extend_from_slice(&value.to_le_bytes());
+            }
+            chars.extend_from_slice(&[0, 15]);
+        }
+        block(4, chars);
+
+        let mut kerning = Vec::new();
+        kerning.extend_from_slice(&65u32.to_le_bytes());
+        kerning.extend_from_slice(&86u32.to_le_bytes());
+        kerning.extend_from_slice(&(-2i16).to_le_bytes());
+        block(5, kerning);
+
+        data
+    }
+
+    fn check_font(font: &BitmapFont) {
+        assert_eq!(font.line_height(), 10.0);
+        assert_eq!(font.base(), 8.0);
+        assert_eq!(
+            font.glyph('V'),
+            Some(Glyph {
+                page: 0,
+                source: Rect::new(6, 0, 6, 8),
+                offset: (-1.0, 2.0),
+                advance: 6.0,
+            })
+        );
+        assert_eq!(font.glyph('A').map(|g| g.advance), Some(7.0));
+        assert_eq!(font.glyph('B'), None);
+        assert_eq!(font.kerning('A', 'V'), -2.0);
+        assert_eq!(font.kerning('V', 'A'), 0.0);
+    }
+
+    #[test]
+    fn parse_text_format() {
+        let font = BitmapFont::parse(TEXT_FONT.as_bytes()).unwrap();
+        check_font(&font);
+        assert_eq!(font.page_files(), ["test font.png"]);
+
+        // Page ids size the list of page files
+        for id in ["256", "4000000000", &usize::MAX.to_string()].iter() {
+            let text = TEXT_FONT.replace("page id=0", &format!("page id={}", id));
+            assert!(matches!(
+                BitmapFont::parse(text.as_bytes()),
+                Err(FontError::InvalidFormat)
+            ));
+        }
+    }
+
+    #[test]
+    fn parse_xml_format() {
+        let font = BitmapFont::parse(XML_FONT.as_bytes()).unwrap();
+        check_font(&font);
+        assert_eq!(font.page_files(), ["test & font.png"]);
+    }
+
+    #[test]
+    fn parse_binary_format() {
+        let font = BitmapFont::parse(&binary_font()).unwrap();
+        check_font(&font);
+        assert_eq!(font.page_files(), ["test font.png"]);
+
+        let truncated = &binary_font()[..30];
+        assert!(matches!(
+            BitmapFont::parse(truncated),
+            Err(FontError::InvalidFormat)
+        ));
+    }
+
+    #[test]
+    fn grid_font() {
+        let font = BitmapFont::from_grid(Texture::new(16, 16), 4, 8, "abcdef");
+        assert_eq!(font.line_height(), 8.0);
+        assert_eq!(font.glyph('f').unwrap().source, Rect::new(4, 8, 4, 8));
+        assert!(font.page(0).is_some());
+        assert!(!font.has_glyph('g'));
+    }
+}
@@ -0,0 +1,181 @@
+#![allow(dead_code)]
+
+use super::color_f::ColorF;
+
+/// How a drawn color is combined with the color already on the target.
+/// Colors are straight alpha unless the mode says otherwise.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum BlendMode {
+    /// Porter-Duff source over destination
+    #[default]
+    Alpha,
+    /// Source over destination where both colors are premultiplied by alpha
+    Premultiplied,
+    /// Adds the source to the destination, for lights and particles
+    Additive,
+    /// Darkens the destination by the source
+    Multiply,
+    /// Lightens the destination by the inverted source
+    Screen,
+    /// Multiply on dark destination colors and screen on light ones
+    Overlay,
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl BlendMode {
+    /// Combines a source color with a destination color
+    pub fn blend(self, src: ColorF, dst: ColorF) -> ColorF {
+        match self {
+            BlendMode::Alpha => over(src, dst),
+            BlendMode::Premultiplied => premultiplied_over(src, dst),
+            BlendMode::Additive => additive(src, dst),
+            BlendMode::Multiply => separable(src, dst, |s, d| s * d),
+            BlendMode::Screen => separable(src, dst, |s, d| s + d - s * d),
+            BlendMode::Overlay => separable(src, dst, |s, d| {
+                if d <= 0.5 {
+                    2.0 * s * d
+                } else {
+                    1.0 - 2.0 * (1.0 - s) * (1.0 - d)
+                }
+            }),
+        }
+    }
+
+    /// Returns the OpenGL source and destination blend factors for this mode.
+    /// The fragment shader must output premultiplied colors, `Overlay` can not be expressed
+    /// with blend factors and returns `None`.
+    pub fn gl_factors(self) -> Option<(u32, u32)> {
+        match self {
+            BlendMode::Alpha | BlendMode::Premultiplied => Some((gl::ONE, gl::ONE_MINUS_SRC_ALPHA)),
+            BlendMode::Additive => Some((gl::ONE, gl::ONE)),
+            BlendMode::Multiply => Some((gl::DST_COLOR, gl::ONE_MINUS_SRC_ALPHA)),
+            BlendMode::Screen => Some((gl::ONE, gl::ONE_MINUS_SRC_COLOR)),
+            BlendMode::Overlay => None,
+        }
+    }
+}
+
+/// Porter-Duff source over destination with straight alpha
+pub fn over(src: ColorF, dst: ColorF) -> ColorF {
+    premultiplied_over(src.premultiply(), dst.premultiply()).unpremultiply()
+}
+
+/// Porter-Duff source over destination with premultiplied alpha
+pub fn premultiplied_over(src: ColorF, dst: ColorF) -> ColorF {
+    src + dst * (1.0 - src.alpha)
+}
+
+/// Adds the source weighted by its alpha to the destination
+pub fn additive(src: ColorF, dst: ColorF) -> ColorF {
+    (src.premultiply() + dst.premultiply())
+        .clamp()
+        .unpremultiply()
+}
+
+/// Applies a separable blend function to each color channel and composites the result
+/// over the destination, following the W3C compositing specification
+fn separable(src: ColorF, dst: ColorF, function: impl Fn(f32, f32) -> f32) -> ColorF {
+    let (sa, da) = (src.alpha, dst.alpha);
+    let alpha = sa + da * (1.0 - sa);
+    if alpha <= 0.0 {
+        return ColorF::CLEAR;
+    }
+
+    let channel = |s: f32, d: f32| {
+        let premultiplied = sa * (1.0 - da) * s + sa * da * function(s, d) + (1.0 - sa) * da * d;
+        premultiplied / alpha
+    };
+
+    ColorF::from_rgba(
+        channel(src.red, dst.red),
+        channel(src.green, dst.green),
+        channel(src.blue, dst.blue),
+        alpha,
+    )
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod blend_tests {
+
+    use super::*;
+    use crate::graphics::color::Color;
+
+    fn blend(mode: BlendMode, src: Color, dst: Color) -> Color {
+        mode.blend(src.into(), dst.into()).into()
+    }
+
+    #[test]
+    fn alpha_over() {
+        let half_red = Color::from_rgba(255, 0, 0, 128);
+        assert_eq!(
+            blend(BlendMode::Alpha, half_red, Color::BLUE),
+            Color::from_rgb(128, 0, 127)
+        );
+        assert_eq!(blend(BlendMode::Alpha, half_red, Color::CLEAR), half_red);
+        assert_eq!(
+            blend(BlendMode::Alpha, Color::CLEAR, Color::GREEN),
+            Color::GREEN
+        );
+
+        // Two half transparent layers cover three quarters
+        let over_clear = blend(BlendMode::Alpha, half_red, Color::from_rgba(0, 0, 255, 128));
+        assert_eq!(over_clear.alpha, 192);
+    }
+
+    #[test]
+    fn premultiplied_matches_straight() {
+        let src = ColorF::from_rgba(0.2, 0.6, 1.0, 0.4);
+        let dst = ColorF::from_rgba(0.9, 0.1, 0.3, 0.7);
+
+        let straight = BlendMode::Alpha.blend(src, dst);
+        let premultiplied = BlendMode::Premultiplied
+            .blend(src.premultiply(), dst.premultiply())
+            .unpremultiply();
+        assert_eq!(Color::from(straight), Color::from(premultiplied));
+    }
+
+    #[test]
+    fn separable_modes() {
+        let gray = Color::from_rgb(128, 128, 128);
+        let dark = Color::from_rgb(64, 64, 64);
+
+        assert_eq!(
+            blend(BlendMode::Additive, gray, gray),
+            Color::from_rgb(255, 255, 255)
+        );
+        assert_eq!(blend(BlendMode::Additive, gray.with_alpha(0), dark), dark);
+        assert_eq!(
+            blend(BlendMode::Multiply, gray, dark),
+            Color::from_rgb(32, 32, 32)
+        );
+        assert_eq!(
+            blend(BlendMode::Screen, gray, dark),
+            Color::from_rgb(160, 160, 160)
+        );
+        assert_eq!(
+            blend(BlendMode::Overlay, gray, dark),
+            Color::from_rgb(64, 64, 64)
+        );
+        assert_eq!(
+            blend(BlendMode::Overlay, dark, Color::from_rgb(192, 192, 192)),
+            Color::from_rgb(161, 161, 161)
+        );
+
+        // Half transparent multiply only goes half way
+        assert_eq!(
+            blend(
+                BlendMode::Multiply,
+                Color::BLACK.with_alpha(128),
+                Color::WHITE
+            ),
+            Color::from_rgb(127, 127, 127)
+        );
+    }
+}
@@ -0,0 +1,95 @@
+#![allow(dead_code)]
+
+use super::renderer::Rect;
+
+/// View into a world measured in pixels.
+/// The world point at `position` is shown at the center of the viewport.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Camera {
+    pub position: (f32, f32),
+    /// Screen pixels per world pixel
+    pub zoom: f32,
+    /// Size of the area the camera draws to in screen pixels
+    pub viewport: (f32, f32),
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl Camera {
+    /// Creates a camera with a viewport of width and height showing the world from its origin
+    pub fn new(width: f32, height: f32) -> Camera {
+        Camera {
+            position: (width / 2.0, height / 2.0),
+            zoom: 1.0,
+            viewport: (width, height),
+        }
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl Camera {
+    pub fn world_to_screen(&self, point: (f32, f32)) -> (f32, f32) {
+        (
+            (point.0 - self.position.0) * self.zoom + self.viewport.0 / 2.0,
+            (point.1 - self.position.1) * self.zoom + self.viewport.1 / 2.0,
+        )
+    }
+
+    pub fn screen_to_world(&self, point: (f32, f32)) -> (f32, f32) {
+        (
+            (point.0 - self.viewport.0 / 2.0) / self.zoom + self.position.0,
+            (point.1 - self.viewport.1 / 2.0) / self.zoom + self.position.1,
+        )
+    }
+
+    /// Returns the part of the world that is in view, rounded out to whole pixels
+    pub fn visible_area(&self) -> Rect {
+        let (left, top) = self.screen_to_world((0.0, 0.0));
+        let (right, bottom) = self.screen_to_world(self.viewport);
+        let (left, top) = (left.floor(), top.floor());
+
+        Rect::new(
+            left as i32,
+            top as i32,
+            (right.ceil() - left).max(0.0) as u32,
+            (bottom.ceil() - top).max(0.0) as u32,
+        )
+    }
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod camera_tests {
+
+    use super::*;
+
+    #[test]
+    fn convert_points() {
+        let mut camera = Camera::new(100.0, 50.0);
+        assert_eq!(camera.world_to_screen((10.0, 20.0)), (10.0, 20.0));
+
+        camera.position = (200.0, 100.0);
+        camera.zoom = 2.0;
+        assert_eq!(camera.world_to_screen((200.0, 100.0)), (50.0, 25.0));
+        assert_eq!(camera.world_to_screen((210.0, 90.0)), (70.0, 5.0));
+        assert_eq!(camera.screen_to_world((70.0, 5.0)), (210.0, 90.0));
+    }
+
+    #[test]
+    fn visible_area() {
+        let mut camera = Camera::new(100.0, 50.0);
+        assert_eq!(camera.visible_area(), Rect::new(0, 0, 100, 50));
+
+        camera.position = (0.0, 0.0);
+        camera.zoom = 4.0;
+        assert_eq!(camera.visible_area(), Rect::new(-13, -7, 26, 14));
+    }
+}
@@ -2,7 +2,7 @@
 
 /// Structure that represents an RGBA color.
 #[repr(C)]
-#[derive(Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
 pub struct Color {
     pub red: u8,
     pub green: u8,
@@ -10,6 +10,12 @@ pub struct Color {
     pub alpha: u8,
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ParseColorError {
+    InvalidHex,
+    UnknownName,
+}
+
 // ====================================
 // Implementation
 // ====================================
@@ -29,6 +35,226 @@ impl Color {
     pub const fn from_rgb(red: u8, green: u8, blue: u8) -> Color {
         Color::from_rgba(red, green, blue, 255)
     }
+
+    /// Parses `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa`, the `#` is optional
+    pub fn from_hex(hex: &str) -> Result<Color, ParseColorError> {
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        // from_str_radix would also accept a leading sign in each pair
+        if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(ParseColorError::InvalidHex);
+        }
+
+        let nibble = |i: usize| {
+            u8::from_str_radix(&digits[i..i + 1], 16).map_err(|_| ParseColorError::InvalidHex)
+        };
+        let byte = |i: usize| {
+            u8::from_str_radix(&digits[i..i + 2], 16).map_err(|_| ParseColorError::InvalidHex)
+        };
+
+        match digits.len() {
+            3 | 4 => {
+                let alpha = if digits.len() == 4 {
+                    nibble(3)? * 17
+                } else {
+                    255
+                };
+                Ok(Color::from_rgba(
+                    nibble(0)? * 17,
+                    nibble(1)? * 17,
+                    nibble(2)? * 17,
+                    alpha,
+                ))
+            }
+            6 | 8 => {
+                let alpha = if digits.len() == 8 { byte(6)? } else { 255 };
+                Ok(Color::from_rgba(byte(0)?, byte(2)?, byte(4)?, alpha))
+            }
+            _ => Err(ParseColorError::InvalidHex),
+        }
+    }
+
+    /// Returns `#rrggbb`, or `#rrggbbaa` if the color is not opaque
+    pub fn to_hex(self) -> String {
+        if self.alpha == 255 {
+            format!("#{:02x}{:02x}{:02x}", self.red, self.green, self.blue)
+        } else {
+            format!(
+                "#{:02x}{:02x}{:02x}{:02x}",
+                self.red, self.green, self.blue, self.alpha
+            )
+        }
+    }
+
+    /// Looks up a CSS named color such as `"cornflowerblue"`, ignoring case
+    pub fn from_name(name: &str) -> Option<Color> {
+        let name = name.to_ascii_lowercase();
+        if name == "transparent" {
+            return Some(Color::CLEAR);
+        }
+
+        NAMED_COLORS
+            .iter()
+            .find(|(named, _)| *named == name)
+            .map(|(_, rgb)| Color::from_rgb((rgb >> 16) as u8, (rgb >> 8) as u8, *rgb as u8))
+    }
+
+    /// Creates a Color from hue in degrees and saturation and value from 0 to 1
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Color {
+        let saturation = saturation.clamp(0.0, 1.0);
+        let value = value.clamp(0.0, 1.0);
+        let chroma = value * saturation;
+        Color::from_hue_chroma(hue, chroma, value - chroma)
+    }
+
+    /// Returns hue in degrees and saturation and value from 0 to 1
+    pub fn to_hsv(self) -> (f32, f32, f32) {
+        let (hue, min, max) = self.hue_min_max();
+        let saturation = if max > 0.0 { (max - min) / max } else { 0.0 };
+        (hue, saturation, max)
+    }
+
+    /// Creates a Color from hue in degrees and saturation and lightness from 0 to 1
+    pub fn from_hsl(hue: f32, saturation: f32, lightness: f32) -> Color {
+        let saturation = saturation.clamp(0.0, 1.0);
+        let lightness = lightness.clamp(0.0, 1.0);
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        Color::from_hue_chroma(hue, chroma, lightness - chroma / 2.0)
+    }
+
+    /// Returns hue in degrees and saturation and lightness from 0 to 1
+    pub fn to_hsl(self) -> (f32, f32, f32) {
+        let (hue, min, max) = self.hue_min_max();
+        let lightness = (max + min) / 2.0;
+        let saturation = if max == min {
+            0.0
+        } else {
+            (max - min) / (1.0 - (2.0 * lightness - 1.0).abs())
+        };
+        (hue, saturation, lightness)
+    }
+
+    /// Creates a Color from linear light components from 0 to 1, alpha is not converted
+    pub fn from_linear(red: f32, green: f32, blue: f32, alpha: f32) -> Color {
+        Color::from_rgba(
+            to_byte(linear_to_srgb(red)),
+            to_byte(linear_to_srgb(green)),
+            to_byte(linear_to_srgb(blue)),
+            to_byte(alpha),
+        )
+    }
+
+    /// Returns the components in linear light from 0 to 1, alpha is not converted
+    pub fn to_linear(self) -> (f32, f32, f32, f32) {
+        (
+            srgb_to_linear(self.red as f32 / 255.0),
+            srgb_to_linear(self.green as f32 / 255.0),
+            srgb_to_linear(self.blue as f32 / 255.0),
+            self.alpha as f32 / 255.0,
+        )
+    }
+
+    /// Interpolates between two colors in sRGB space, t of 0 returns self
+    pub fn lerp(self, other: Color, t: f32) -> Color {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+        Color::from_rgba(
+            mix(self.red, other.red),
+            mix(self.green, other.green),
+            mix(self.blue, other.blue),
+            mix(self.alpha, other.alpha),
+        )
+    }
+
+    /// Interpolates between two colors in linear space, which avoids dark midpoints
+    pub fn lerp_linear(self, other: Color, t: f32) -> Color {
+        let t = t.clamp(0.0, 1.0);
+        let a = self.to_linear();
+        let b = other.to_linear();
+        let mix = |a: f32, b: f32| a + (b - a) * t;
+        Color::from_linear(mix(a.0, b.0), mix(a.1, b.1), mix(a.2, b.2), mix(a.3, b.3))
+    }
+
+    /// Returns the same color with a different alpha
+    pub const fn with_alpha(self, alpha: u8) -> Color {
+        Color::from_rgba(self.red, self.green, self.blue, alpha)
+    }
+
+    /// Returns the relative luminance from 0 to 1 as defined by WCAG
+    pub fn luminance(self) -> f32 {
+        let (red, green, blue, _) = self.to_linear();
+        0.2126 * red + 0.7152 * green + 0.0722 * blue
+    }
+
+    /// Returns a gray of the same luminance, alpha is kept
+    pub fn grayscale(self) -> Color {
+        let gray = to_byte(linear_to_srgb(self.luminance()));
+        Color::from_rgba(gray, gray, gray, self.alpha)
+    }
+
+    fn from_hue_chroma(hue: f32, chroma: f32, min: f32) -> Color {
+        let hue = hue.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+
+        let (red, green, blue) = match hue as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+
+        Color::from_rgb(
+            to_byte(red + min),
+            to_byte(green + min),
+            to_byte(blue + min),
+        )
+    }
+
+    /// Returns the hue in degrees and the smallest and largest component from 0 to 1
+    fn hue_min_max(self) -> (f32, f32, f32) {
+        let red = self.red as f32 / 255.0;
+        let green = self.green as f32 / 255.0;
+        let blue = self.blue as f32 / 255.0;
+
+        let max = red.max(green).max(blue);
+        let min = red.min(green).min(blue);
+        let delta = max - min;
+
+        let hue = if delta == 0.0 {
+            0.0
+        } else if max == red {
+            60.0 * ((green - blue) / delta).rem_euclid(6.0)
+        } else if max == green {
+            60.0 * ((blue - red) / delta + 2.0)
+        } else {
+            60.0 * ((red - green) / delta + 4.0)
+        };
+
+        (hue, min, max)
+    }
+}
+
+/// Converts an sRGB encoded component from 0 to 1 to linear light
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Converts a linear light component from 0 to 1 to sRGB encoding
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn to_byte(value: f32) -> u8 {
+    (value * 255.0).round().clamp(0.0, 255.0) as u8
 }
 
 // ====================================
@@ -49,10 +275,189 @@ impl Color {
     pub const CYAN: Color = Color::from_rgb(0, 255, 255);
 }
 
+/// CSS named colors as 0xRRGGBB
+const NAMED_COLORS: [(&str, u32); 148] = [
+    ("aliceblue", 0xF0F8FF),
+    ("antiquewhite", 0xFAEBD7),
+    ("aqua", 0x00FFFF),
+    ("aquamarine", 0x7FFFD4),
+    ("azure", 0xF0FFFF),
+    ("beige", 0xF5F5DC),
+    ("bisque", 0xFFE4C4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xFFEBCD),
+    ("blue", 0x0000FF),
+    ("blueviolet", 0x8A2BE2),
+    ("brown", 0xA52A2A),
+    ("burlywood", 0xDEB887),
+    ("cadetblue", 0x5F9EA0),
+    ("chartreuse", 0x7FFF00),
+    ("chocolate", 0xD2691E),
+    ("coral", 0xFF7F50),
+    ("cornflowerblue", 0x6495ED),
+    ("cornsilk", 0xFFF8DC),
+    ("crimson", 0xDC143C),
+    ("cyan", 0x00FFFF),
+    ("darkblue", 0x00008B),
+    ("darkcyan", 0x008B8B),
+    ("darkgoldenrod", 0xB8860B),
+    ("darkgray", 0xA9A9A9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xA9A9A9),
+    ("darkkhaki", 0xBDB76B),
+    ("darkmagenta", 0x8B008B),
+    ("darkolivegreen", 0x556B2F),
+    ("darkorange", 0xFF8C00),
+    ("darkorchid", 0x9932CC),
+    ("darkred", 0x8B0000),
+    ("darksalmon", 0xE9967A),
+    ("darkseagreen", 0x8FBC8F),
+    ("darkslateblue", 0x483D8B),
+    ("darkslategray", 0x2F4F4F),
+    ("darkslategrey", 0x2F4F4F),
+    ("darkturquoise", 0x00CED1),
+    ("darkviolet", 0x9400D3),
+    ("deeppink", 0xFF1493),
+    ("deepskyblue", 0x00BFFF),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1E90FF),
+    ("firebrick", 0xB22222),
+    ("floralwhite", 0xFFFAF0),
+    ("forestgreen", 0x228B22),
+    ("fuchsia", 0xFF00FF),
+    ("gainsboro", 0xDCDCDC),
+    ("ghostwhite", 0xF8F8FF),
+    ("gold", 0xFFD700),
+    ("goldenrod", 0xDAA520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xADFF2F),
+    ("grey", 0x808080),
+    ("honeydew", 0xF0FFF0),
+    ("hotpink", 0xFF69B4),
+    ("indianred", 0xCD5C5C),
+    ("indigo", 0x4B0082),
+    ("ivory", 0xFFFFF0),
+    ("khaki", 0xF0E68C),
+    ("lavender", 0xE6E6FA),
+    ("lavenderblush", 0xFFF0F5),
+    ("lawngreen", 0x7CFC00),
+    ("lemonchiffon", 0xFFFACD),
+    ("lightblue", 0xADD8E6),
+    ("lightcoral", 0xF08080),
+    ("lightcyan", 0xE0FFFF),
+    ("lightgoldenrodyellow", 0xFAFAD2),
+    ("lightgray", 0xD3D3D3),
+    ("lightgreen", 0x90EE90),
+    ("lightgrey", 0xD3D3D3),
+    ("lightpink", 0xFFB6C1),
+    ("lightsalmon", 0xFFA07A),
+    ("lightseagreen", 0x20B2AA),
+    ("lightskyblue", 0x87CEFA),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xB0C4DE),
+    ("lightyellow", 0xFFFFE0),
+    ("lime", 0x00FF00),
+    ("limegreen", 0x32CD32),
+    ("linen", 0xFAF0E6),
+    ("magenta", 0xFF00FF),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66CDAA),
+    ("mediumblue", 0x0000CD),
+    ("mediumorchid", 0xBA55D3),
+    ("mediumpurple", 0x9370DB),
+    ("mediumseagreen", 0x3CB371),
+    ("mediumslateblue", 0x7B68EE),
+    ("mediumspringgreen", 0x00FA9A),
+    ("mediumturquoise", 0x48D1CC),
+    ("mediumvioletred", 0xC71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xF5FFFA),
+    ("mistyrose", 0xFFE4E1),
+    ("moccasin", 0xFFE4B5),
+    ("navajowhite", 0xFFDEAD),
+    ("navy", 0x000080),
+    ("oldlace", 0xFDF5E6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6B8E23),
+    ("orange", 0xFFA500),
+    ("orangered", 0xFF4500),
+    ("orchid", 0xDA70D6),
+    ("palegoldenrod", 0xEEE8AA),
+    ("palegreen", 0x98FB98),
+    ("paleturquoise", 0xAFEEEE),
+    ("palevioletred", 0xDB7093),
+    ("papayawhip", 0xFFEFD5),
+    ("peachpuff", 0xFFDAB9),
+    ("peru", 0xCD853F),
+    ("pink", 0xFFC0CB),
+    ("plum", 0xDDA0DD),
+    ("powderblue", 0xB0E0E6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xFF0000),
+    ("rosybrown", 0xBC8F8F),
+    ("royalblue", 0x4169E1),
+    ("saddlebrown", 0x8B4513),
+    ("salmon", 0xFA8072),
+    ("sandybrown", 0xF4A460),
+    ("seagreen", 0x2E8B57),
+    ("seashell", 0xFFF5EE),
+    ("sienna", 0xA0522D),
+    ("silver", 0xC0C0C0),
+    ("skyblue", 0x87CEEB),
+    ("slateblue", 0x6A5ACD),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xFFFAFA),
+    ("springgreen", 0x00FF7F),
+    ("steelblue", 0x4682B4),
+    ("tan", 0xD2B48C),
+    ("teal", 0x008080),
+    ("thistle", 0xD8BFD8),
+    ("tomato", 0xFF6347),
+    ("turquoise", 0x40E0D0),
+    ("violet", 0xEE82EE),
+    ("wheat", 0xF5DEB3),
+    ("white", 0xFFFFFF),
+    ("whitesmoke", 0xF5F5F5),
+    ("yellow", 0xFFFF00),
+    ("yellowgreen", 0x9ACD32),
+];
+
 // ====================================
 // Other Implementations
 // ====================================
 
+impl std::str::FromStr for Color {
+    type Err = ParseColorError;
+
+    /// Parses a hex color or a CSS color name
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.starts_with('#') {
+            return Color::from_hex(s);
+        }
+
+        match Color::from_name(s) {
+            Some(color) => Ok(color),
+            // Hex without the `#` is still accepted
+            None => Color::from_hex(s).map_err(|_| ParseColorError::UnknownName),
+        }
+    }
+}
+
+impl std::fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseColorError::InvalidHex => f.write_str("invalid hex color"),
+            ParseColorError::UnknownName => f.write_str("unknown color name"),
+        }
+    }
+}
+
 impl std::fmt::Display for Color {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_fmt(format_args!(
@@ -122,3 +527,100 @@ impl std::ops::MulAssign<f32> for Color {
         *self = *self * rhs;
     }
 }
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod color_tests {
+
+    use super::*;
+
+    #[test]
+    fn hex_round_trip() {
+        assert_eq!(
+            Color::from_hex("#ff8800cc"),
+            Ok(Color::from_rgba(255, 136, 0, 204))
+        );
+        assert_eq!(Color::from_hex("ff8800"), Ok(Color::from_rgb(255, 136, 0)));
+        assert_eq!(Color::from_hex("#f80"), Ok(Color::from_rgb(255, 136, 0)));
+        assert_eq!(
+            Color::from_hex("#f80c"),
+            Ok(Color::from_rgba(255, 136, 0, 204))
+        );
+        assert_eq!(Color::from_hex("#ff888"), Err(ParseColorError::InvalidHex));
+        assert_eq!(Color::from_hex("#gg8800"), Err(ParseColorError::InvalidHex));
+        assert_eq!(Color::from_hex("#ff880é"), Err(ParseColorError::InvalidHex));
+        assert_eq!(Color::from_hex("#+f+f+f"), Err(ParseColorError::InvalidHex));
+
+        assert_eq!(Color::from_rgba(255, 136, 0, 204).to_hex(), "#ff8800cc");
+        assert_eq!(Color::from_rgb(1, 2, 3).to_hex(), "#010203");
+    }
+
+    #[test]
+    fn parse_names() {
+        assert_eq!("CornflowerBlue".parse(), Ok(Color::from_rgb(100, 149, 237)));
+        assert_eq!(" #00ff00 ".parse(), Ok(Color::GREEN));
+        assert_eq!("transparent".parse(), Ok(Color::CLEAR));
+        assert_eq!("abc".parse(), Ok(Color::from_rgb(170, 187, 204)));
+        assert_eq!(
+            "notacolor".parse::<Color>(),
+            Err(ParseColorError::UnknownName)
+        );
+    }
+
+    #[test]
+    fn hsv_and_hsl() {
+        assert_eq!(Color::from_hsv(0.0, 1.0, 1.0), Color::RED);
+        assert_eq!(Color::from_hsv(120.0, 1.0, 1.0), Color::GREEN);
+        assert_eq!(Color::from_hsv(-120.0, 1.0, 1.0), Color::BLUE);
+        assert_eq!(Color::from_hsl(60.0, 1.0, 0.5), Color::YELLOW);
+        assert_eq!(Color::from_hsl(0.0, 0.0, 1.0), Color::WHITE);
+
+        assert_eq!(Color::CYAN.to_hsv(), (180.0, 1.0, 1.0));
+        assert_eq!(Color::MAGENTA.to_hsl(), (300.0, 1.0, 0.5));
+
+        // Every hue survives a round trip
+        for hue in (0..360).step_by(15) {
+            let color = Color::from_hsv(hue as f32, 0.8, 0.6);
+            let (h, s, v) = color.to_hsv();
+            assert_eq!(Color::from_hsv(h, s, v), color);
+            let (h, s, l) = color.to_hsl();
+            assert_eq!(Color::from_hsl(h, s, l), color);
+        }
+    }
+
+    #[test]
+    fn linear_conversion() {
+        for value in 0..=255 {
+            let color = Color::from_rgba(value, value, value, value);
+            let (r, g, b, a) = color.to_linear();
+            assert_eq!(Color::from_linear(r, g, b, a), color);
+        }
+
+        let (mid, _, _, _) = Color::from_rgb(188, 0, 0).to_linear();
+        assert!((mid - 0.5).abs() < 0.01);
+
+        // Linear interpolation keeps the midpoint of black and white brighter
+        assert_eq!(
+            Color::BLACK.lerp(Color::WHITE, 0.5),
+            Color::from_rgb(128, 128, 128)
+        );
+        assert_eq!(
+            Color::BLACK.lerp_linear(Color::WHITE, 0.5),
+            Color::from_rgb(188, 188, 188)
+        );
+    }
+
+    #[test]
+    fn luminance_and_grayscale() {
+        assert_eq!(Color::WHITE.luminance(), 1.0);
+        assert_eq!(Color::BLACK.luminance(), 0.0);
+        assert!(Color::GREEN.luminance() > Color::RED.luminance());
+
+        let gray = Color::from_rgba(255, 0, 0, 10).grayscale();
+        assert_eq!(gray, Color::from_rgba(127, 127, 127, 10));
+        assert_eq!(Color::RED.with_alpha(7), Color::from_rgba(255, 0, 0, 7));
+    }
+}
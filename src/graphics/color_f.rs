@@ -0,0 +1,252 @@
+#![allow(dead_code)]
+
+use super::color::Color;
+
+/// Structure that represents an RGBA color with floating point components from 0 to 1.
+/// Laid out as four consecutive `f32`s so it can be uploaded to OpenGL directly.
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct ColorF {
+    pub red: f32,
+    pub green: f32,
+    pub blue: f32,
+    pub alpha: f32,
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl ColorF {
+    /// Creates a ColorF from all four components
+    pub const fn from_rgba(red: f32, green: f32, blue: f32, alpha: f32) -> ColorF {
+        ColorF {
+            red,
+            green,
+            blue,
+            alpha,
+        }
+    }
+
+    /// Creates a ColorF from only three components, alpha is 1
+    pub const fn from_rgb(red: f32, green: f32, blue: f32) -> ColorF {
+        ColorF::from_rgba(red, green, blue, 1.0)
+    }
+
+    /// Returns the color with red, green and blue multiplied by alpha
+    pub fn premultiply(self) -> ColorF {
+        ColorF::from_rgba(
+            self.red * self.alpha,
+            self.green * self.alpha,
+            self.blue * self.alpha,
+            self.alpha,
+        )
+    }
+
+    /// Reverses `premultiply`, fully transparent colors become `ColorF::CLEAR`
+    pub fn unpremultiply(self) -> ColorF {
+        if self.alpha <= 0.0 {
+            return ColorF::CLEAR;
+        }
+
+        ColorF::from_rgba(
+            self.red / self.alpha,
+            self.green / self.alpha,
+            self.blue / self.alpha,
+            self.alpha,
+        )
+    }
+
+    /// Returns the color with every component limited to 0 to 1
+    pub fn clamp(self) -> ColorF {
+        ColorF::from_rgba(
+            self.red.clamp(0.0, 1.0),
+            self.green.clamp(0.0, 1.0),
+            self.blue.clamp(0.0, 1.0),
+            self.alpha.clamp(0.0, 1.0),
+        )
+    }
+
+    /// Interpolates between two colors, t of 0 returns self
+    pub fn lerp(self, other: ColorF, t: f32) -> ColorF {
+        self + (other - self) * t
+    }
+
+    /// Returns the components as an array for uniforms
+    pub fn to_array(self) -> [f32; 4] {
+        [self.red, self.green, self.blue, self.alpha]
+    }
+}
+
+// ====================================
+// Constants
+// ====================================
+
+impl ColorF {
+    pub const BLACK: ColorF = ColorF::from_rgb(0.0, 0.0, 0.0);
+    pub const WHITE: ColorF = ColorF::from_rgb(1.0, 1.0, 1.0);
+    pub const CLEAR: ColorF = ColorF::from_rgba(0.0, 0.0, 0.0, 0.0);
+}
+
+// ====================================
+// Other Implementations
+// ====================================
+
+impl From<Color> for ColorF {
+    fn from(color: Color) -> Self {
+        ColorF::from_rgba(
+            color.red as f32 / 255.0,
+            color.green as f32 / 255.0,
+            color.blue as f32 / 255.0,
+            color.alpha as f32 / 255.0,
+        )
+    }
+}
+
+impl From<ColorF> for Color {
+    /// Rounds to the nearest byte, components outside 0 to 1 are clamped
+    fn from(color: ColorF) -> Self {
+        let byte = |value: f32| (value * 255.0).round().clamp(0.0, 255.0) as u8;
+        Color::from_rgba(
+            byte(color.red),
+            byte(color.green),
+            byte(color.blue),
+            byte(color.alpha),
+        )
+    }
+}
+
+impl std::fmt::Display for ColorF {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "({}, {}, {}, {})",
+            self.red, self.green, self.blue, self.alpha
+        ))
+    }
+}
+
+// ====================================
+// Overloading Operators
+// ====================================
+
+impl std::ops::Add for ColorF {
+    type Output = ColorF;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        ColorF::from_rgba(
+            self.red + rhs.red,
+            self.green + rhs.green,
+            self.blue + rhs.blue,
+            self.alpha + rhs.alpha,
+        )
+    }
+}
+
+impl std::ops::AddAssign for ColorF {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl std::ops::Sub for ColorF {
+    type Output = ColorF;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        ColorF::from_rgba(
+            self.red - rhs.red,
+            self.green - rhs.green,
+            self.blue - rhs.blue,
+            self.alpha - rhs.alpha,
+        )
+    }
+}
+
+impl std::ops::SubAssign for ColorF {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl std::ops::Mul for ColorF {
+    type Output = ColorF;
+
+    /// Multiplies component-wise, as used for tinting
+    fn mul(self, rhs: Self) -> Self::Output {
+        ColorF::from_rgba(
+            self.red * rhs.red,
+            self.green * rhs.green,
+            self.blue * rhs.blue,
+            self.alpha * rhs.alpha,
+        )
+    }
+}
+
+impl std::ops::MulAssign for ColorF {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
+impl std::ops::Mul<f32> for ColorF {
+    type Output = ColorF;
+
+    fn mul(self, rhs: f32) -> Self::Output {
+        ColorF::from_rgba(
+            self.red * rhs,
+            self.green * rhs,
+            self.blue * rhs,
+            self.alpha * rhs,
+        )
+    }
+}
+
+impl std::ops::MulAssign<f32> for ColorF {
+    fn mul_assign(&mut self, rhs: f32) {
+        *self = *self * rhs;
+    }
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod color_f_tests {
+
+    use super::*;
+
+    #[test]
+    fn byte_round_trip() {
+        for value in 0..=255 {
+            let color = Color::from_rgba(value, 255 - value, value / 2, value);
+            assert_eq!(Color::from(ColorF::from(color)), color);
+        }
+
+        let out_of_range = ColorF::from_rgba(1.5, -0.2, 0.5, 1.0);
+        assert_eq!(Color::from(out_of_range), Color::from_rgb(255, 0, 128));
+    }
+
+    #[test]
+    fn premultiplied_alpha() {
+        let color = ColorF::from_rgba(1.0, 0.5, 0.25, 0.5);
+        assert_eq!(
+            color.premultiply(),
+            ColorF::from_rgba(0.5, 0.25, 0.125, 0.5)
+        );
+        assert_eq!(color.premultiply().unpremultiply(), color);
+        assert_eq!(
+            ColorF::from_rgba(1.0, 1.0, 1.0, 0.0).unpremultiply(),
+            ColorF::CLEAR
+        );
+    }
+
+    #[test]
+    fn repeated_tint_keeps_precision() {
+        let tint = ColorF::from_rgb(0.999, 0.999, 0.999);
+        let mut color = ColorF::WHITE;
+        for _ in 0..100 {
+            color *= tint;
+        }
+        assert_eq!(Color::from(color), Color::from_rgb(231, 231, 231));
+    }
+}
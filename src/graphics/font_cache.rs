@@ -0,0 +1,502 @@
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+
+use super::{
+    color::Color,
+    renderer::Rect,
+    sprite::Sprite,
+    text::{Font, Glyph},
+    texture::Texture,
+    truetype::TrueTypeFont,
+};
+
+/// Width and starting height of the glyph atlas
+const INITIAL_ATLAS_SIZE: usize = 256;
+
+/// Largest width and height the atlas grows to, glyphs that do not fit are skipped
+const MAX_ATLAS_SIZE: usize = 4096;
+
+/// Empty pixels between glyphs in the atlas, so bilinear filtering does not bleed
+const ATLAS_GAP: usize = 1;
+
+/// Handle to a font added to a `FontCache`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct FontId(usize);
+
+/// Rasterizes TrueType glyphs on demand into one growing atlas texture.
+/// Glyphs are cached per font, size and glyph, and must be prepared with `prepare` before
+/// the text is laid out or drawn through the `Font` returned by `sized`.
+pub struct FontCache {
+    fonts: Vec<CachedFont>,
+    atlas: Sprite,
+    shelves: Vec<Shelf>,
+    glyphs: HashMap<GlyphKey, Glyph>,
+    /// Spread in pixels when glyphs are stored as signed distance fields
+    spread: Option<usize>,
+}
+
+/// Font of a `FontCache` at one size, used for layout and drawing.
+pub struct SizedFont<'a> {
+    cache: &'a FontCache,
+    font: FontId,
+    size: f32,
+}
+
+struct CachedFont {
+    font: TrueTypeFont,
+    /// Searched in order for characters the font does not have
+    fallbacks: Vec<FontId>,
+}
+
+/// Row of the atlas that glyphs are placed in left to right
+struct Shelf {
+    y: usize,
+    height: usize,
+    x: usize,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+struct GlyphKey {
+    font: FontId,
+    /// Size in 1/64 pixels
+    size: u32,
+    glyph: u16,
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl FontCache {
+    /// Creates a cache that stores glyph coverage, for text drawn at the size it was prepared at
+    pub fn new() -> FontCache {
+        FontCache {
+            fonts: Vec::new(),
+            atlas: Sprite::from_texture(Texture::new(INITIAL_ATLAS_SIZE, INITIAL_ATLAS_SIZE)),
+            shelves: Vec::new(),
+            glyphs: HashMap::new(),
+            spread: None,
+        }
+    }
+
+    /// Creates a cache that stores glyphs as signed distance fields.
+    /// Such text stays sharp when scaled and can be drawn with an outline up to `spread` pixels.
+    pub fn with_distance_field(spread: usize) -> FontCache {
+        FontCache {
+            spread: Some(spread.max(1)),
+            ..FontCache::new()
+        }
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl FontCache {
+    pub fn add_font(&mut self, font: TrueTypeFont) -> FontId {
+        self.fonts.push(CachedFont {
+            font,
+            fallbacks: Vec::new(),
+        });
+        FontId(self.fonts.len() - 1)
+    }
+
+    /// Sets the fonts searched in order when a font is missing a character
+    pub fn set_fallbacks(&mut self, font: FontId, fallbacks: &[FontId]) {
+        if let Some(cached) = self.fonts.get_mut(font.0) {
+            cached.fallbacks = fallbacks.to_vec();
+        }
+    }
+
+    pub fn font(&self, font: FontId) -> Option<&TrueTypeFont> {
+        self.fonts.get(font.0).map(|cached| &cached.font)
+    }
+
+    /// Rasterizes the glyphs of a text that are not cached yet and uploads the atlas
+    pub fn prepare(&mut self, font: FontId, size: f32, text: &str) {
+        let mut changed = false;
+        for c in text.chars() {
+            let (resolved, glyph) = match self.resolve(font, c) {
+                Some(found) => found,
+                None => continue,
+            };
+
+            let key = GlyphKey {
+                font: resolved,
+                size: size_key(size),
+                glyph,
+            };
+            if !self.glyphs.contains_key(&key) {
+                let rasterized = self.rasterize(resolved, size, glyph);
+                self.glyphs.insert(key, rasterized);
+                changed = true;
+            }
+        }
+
+        if changed {
+            self.atlas.refresh();
+        }
+    }
+
+    /// Returns a font for layout and drawing, only prepared glyphs are available
+    pub fn sized(&self, font: FontId, size: f32) -> SizedFont<'_> {
+        SizedFont {
+            cache: self,
+            font,
+            size,
+        }
+    }
+
+    /// Returns the number of glyphs in the atlas
+    pub fn cached_glyphs(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    /// Returns the atlas all glyphs are stored in
+    pub fn atlas(&self) -> &Sprite {
+        &self.atlas
+    }
+
+    /// Returns the font and glyph used for a character, trying the fallbacks in order
+    fn resolve(&self, font: FontId, c: char) -> Option<(FontId, u16)> {
+        let cached = self.fonts.get(font.0)?;
+        std::iter::once(font)
+            .chain(cached.fallbacks.iter().copied())
+            .find_map(|id| {
+                let glyph = self.fonts.get(id.0)?.font.glyph_index(c)?;
+                Some((id, glyph))
+            })
+    }
+
+    /// Rasterizes a glyph into the atlas, its offset is from the pen on the baseline
+    fn rasterize(&mut self, font: FontId, size: f32, glyph: u16) -> Glyph {
+        let truetype = &self.fonts[font.0].font;
+        let advance = truetype.advance(glyph) as f32 * truetype.scale(size);
+        let padding = self.spread.unwrap_or(0);
+
+        // Glyphs without an outline or too large to store only advance the pen
+        let empty = Glyph {
+            page: 0,
+            source: Rect::default(),
+            offset: (0.0, 0.0),
+            advance,
+        };
+        let rasterized = match truetype.rasterize(glyph, size, padding) {
+            Some(rasterized) => rasterized,
+            None => return empty,
+        };
+
+        let bitmap = match self.spread {
+            Some(spread) => rasterized.bitmap.to_distance_field(spread),
+            None => rasterized.bitmap,
+        };
+
+        let (x, y) = match self.allocate(bitmap.width, bitmap.height) {
+            Some(position) => position,
+            None => return empty,
+        };
+        let texture = self.atlas.texture_mut();
+        for by in 0..bitmap.height {
+            for bx in 0..bitmap.width {
+                let alpha = (bitmap.get(bx, by) * 255.0).round() as u8;
+                texture.set_pixel(x + bx, y + by, Color::from_rgba(255, 255, 255, alpha));
+            }
+        }
+
+        Glyph {
+            page: 0,
+            source: Rect::new(
+                x as i32,
+                y as i32,
+                bitmap.width as u32,
+                bitmap.height as u32,
+            ),
+            offset: rasterized.offset,
+            advance,
+        }
+    }
+
+    /// Finds room for an image in the atlas, growing it when full.
+    /// Returns `None` when the atlas would grow past `MAX_ATLAS_SIZE`.
+    fn allocate(&mut self, width: usize, height: usize) -> Option<(usize, usize)> {
+        let (width, height) = (width + ATLAS_GAP, height + ATLAS_GAP);
+        loop {
+            let atlas_width = self.atlas.texture().width();
+            let atlas_height = self.atlas.texture().height();
+
+            // The shortest shelf with room wastes the least space
+            let shelf = self
+                .shelves
+                .iter_mut()
+                .filter(|shelf| shelf.height >= height && shelf.x + width <= atlas_width)
+                .min_by_key(|shelf| shelf.height);
+            if let Some(shelf) = shelf {
+                shelf.x += width;
+                return Some((shelf.x - width, shelf.y));
+            }
+
+            let y = self.shelves.last().map_or(0, |last| last.y + last.height);
+            if y + height <= atlas_height && width <= atlas_width {
+                self.shelves.push(Shelf {
+                    y,
+                    height,
+                    x: width,
+                });
+                return Some((0, y));
+            }
+
+            if !self.grow(width > atlas_width) {
+                return None;
+            }
+        }
+    }
+
+    /// Doubles the atlas height, and the width if requested, keeping the placed glyphs.
+    /// Returns false when that would pass `MAX_ATLAS_SIZE`.
+    fn grow(&mut self, wider: bool) -> bool {
+        let old = self.atlas.texture();
+        let width = if wider { old.width() * 2 } else { old.width() };
+        let height = old.height() * 2;
+        if width > MAX_ATLAS_SIZE || height > MAX_ATLAS_SIZE {
+            return false;
+        }
+
+        let mut texture = Texture::new(width, height);
+        for y in 0..old.height() {
+            for x in 0..old.width() {
+                texture.set_pixel(x, y, old.get_pixel(x, y));
+            }
+        }
+
+        self.atlas = Sprite::from_texture(texture);
+        true
+    }
+}
+
+impl<'a> SizedFont<'a> {
+    fn scale(&self) -> f32 {
+        self.cache
+            .font(self.font)
+            .map_or(0.0, |font| font.scale(self.size))
+    }
+
+    fn ascent(&self) -> f32 {
+        self.cache
+            .font(self.font)
+            .map_or(0.0, |font| font.ascent() as f32 * self.scale())
+    }
+}
+
+impl<'a> Font for SizedFont<'a> {
+    fn line_height(&self) -> f32 {
+        self.cache.font(self.font).map_or(0.0, |font| {
+            (font.ascent() as f32 - font.descent() as f32 + font.line_gap() as f32) * self.scale()
+        })
+    }
+
+    fn glyph(&self, c: char) -> Option<Glyph> {
+        let (font, glyph) = self.cache.resolve(self.font, c)?;
+        let key = GlyphKey {
+            font,
+            size: size_key(self.size),
+            glyph,
+        };
+
+        // Layout measures from the top of the line, the cache from the baseline
+        self.cache.glyphs.get(&key).map(|glyph| Glyph {
+            offset: (glyph.offset.0, glyph.offset.1 + self.ascent()),
+            ..*glyph
+        })
+    }
+
+    fn kerning(&self, left: char, right: char) -> f32 {
+        match (
+            self.cache.resolve(self.font, left),
+            self.cache.resolve(self.font, right),
+        ) {
+            (Some((left_font, left)), Some((right_font, right))) if left_font == right_font => {
+                let font = &self.cache.fonts[left_font.0].font;
+                font.kerning(left, right) as f32 * font.scale(self.size)
+            }
+            _ => 0.0,
+        }
+    }
+
+    fn page(&self, page: usize) -> Option<&Sprite> {
+        if page == 0 {
+            Some(&self.cache.atlas)
+        } else {
+            None
+        }
+    }
+
+    fn distance_field(&self) -> Option<f32> {
+        self.cache.spread.map(|spread| spread as f32)
+    }
+}
+
+fn size_key(size: f32) -> u32 {
+    (size.max(0.0) * 64.0).round() as u32
+}
+
+// ====================================
+// Other Implementations
+// ====================================
+
+impl Default for FontCache {
+    fn default() -> Self {
+        FontCache::new()
+    }
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod font_cache_tests {
+
+    use super::*;
+    use crate::graphics::{
+        renderer::Renderer,
+        software_renderer::SoftwareRenderer,
+        text::{measure_text, TextOutline, TextShadow, TextStyle},
+        truetype::truetype_tests::test_font,
+    };
+
+    fn cache_with_font() -> (FontCache, FontId) {
+        let mut cache = FontCache::new();
+        let font = cache.add_font(TrueTypeFont::parse(test_font('O')).unwrap());
+        (cache, font)
+    }
+
+    #[test]
+    fn prepare_and_layout() {
+        let (mut cache, font) = cache_with_font();
+        assert!(cache.sized(font, 10.0).glyph('I').is_none());
+
+        cache.prepare(font, 10.0, "IJ O");
+        let sized = cache.sized(font, 10.0);
+        assert_eq!(sized.line_height(), 10.0);
+
+        // The bar is 3x7 pixels and starts 1 pixel right of the pen, sitting on the baseline
+        let bar = sized.glyph('I').unwrap();
+        assert_eq!(bar.offset, (1.0, 1.0));
+        assert_eq!((bar.source.width, bar.source.height), (3, 7));
+        assert_eq!(bar.advance, 5.0);
+        let atlas = cache.atlas().texture();
+        let pixel = atlas.get_pixel(bar.source.x as usize + 1, bar.source.y as usize + 3);
+        assert_eq!(pixel, Color::WHITE);
+
+        assert_eq!(sized.glyph(' ').unwrap().advance, 2.5);
+        assert!(sized.glyph('K').is_none());
+        assert_eq!(sized.kerning('O', 'I'), -0.5);
+        assert_eq!(
+            measure_text(&sized, "OI", &TextStyle::default()),
+            (10.5, 10.0)
+        );
+    }
+
+    #[test]
+    fn cache_per_size() {
+        let (mut cache, font) = cache_with_font();
+        cache.prepare(font, 10.0, "IIII");
+        assert_eq!(cache.cached_glyphs(), 1);
+        cache.prepare(font, 10.0, "I");
+        assert_eq!(cache.cached_glyphs(), 1);
+        cache.prepare(font, 20.0, "I");
+        assert_eq!(cache.cached_glyphs(), 2);
+
+        let small = cache.sized(font, 10.0).glyph('I').unwrap();
+        let large = cache.sized(font, 20.0).glyph('I').unwrap();
+        assert_eq!((large.source.width, large.source.height), (6, 14));
+        assert_ne!(small.source, large.source);
+    }
+
+    #[test]
+    fn fallback_fonts() {
+        let (mut cache, font) = cache_with_font();
+        let fallback = cache.add_font(TrueTypeFont::parse(test_font('Q')).unwrap());
+        cache.set_fallbacks(font, &[fallback]);
+
+        cache.prepare(font, 10.0, "IQ");
+        let sized = cache.sized(font, 10.0);
+        assert_eq!(sized.glyph('Q').unwrap().advance, 6.0);
+        // Kerning only applies between glyphs of the same font
+        assert_eq!(sized.kerning('Q', 'I'), 0.0);
+        assert!(cache.sized(fallback, 10.0).glyph('O').is_none());
+    }
+
+    #[test]
+    fn atlas_grows() {
+        let (mut cache, font) = cache_with_font();
+        cache.prepare(font, 10.0, "I");
+        let first = cache.sized(font, 10.0).glyph('I').unwrap();
+
+        for size in 40..80 {
+            cache.prepare(font, size as f32, "O");
+        }
+        let atlas = cache.atlas().texture();
+        assert!(atlas.height() > INITIAL_ATLAS_SIZE);
+
+        // Glyphs placed before growing are still there
+        let pixel = atlas.get_pixel(first.source.x as usize + 1, first.source.y as usize + 3);
+        assert_eq!(pixel, Color::WHITE);
+    }
+
+    #[test]
+    fn oversized_glyphs_are_skipped() {
+        let (mut cache, font) = cache_with_font();
+        cache.prepare(font, 10000.0, "I");
+        let glyph = cache.sized(font, 10000.0).glyph('I').unwrap();
+        assert_eq!(glyph.source, Rect::default());
+        assert_eq!(glyph.advance, 5000.0);
+        assert_eq!(cache.atlas().texture().width(), INITIAL_ATLAS_SIZE);
+
+        // The atlas stops growing at its limit
+        assert!(cache.allocate(3000, 3000).is_some());
+        assert!(cache.allocate(3000, 3000).is_none());
+        let atlas = cache.atlas().texture();
+        assert_eq!(
+            (atlas.width(), atlas.height()),
+            (MAX_ATLAS_SIZE, MAX_ATLAS_SIZE)
+        );
+    }
+
+    #[test]
+    fn distance_field_outline_and_shadow() {
+        let mut cache = FontCache::with_distance_field(4);
+        let font = cache.add_font(TrueTypeFont::parse(test_font('O')).unwrap());
+        cache.prepare(font, 20.0, "I");
+
+        // The bar covers x 2 to 8 and y 2 to 16
+        let style = TextStyle {
+            outline: Some(TextOutline {
+                width: 2.0,
+                color: Color::RED,
+            }),
+            ..TextStyle::default()
+        };
+        let mut renderer = SoftwareRenderer::new(20, 20);
+        renderer.draw_text(&cache.sized(font, 20.0), "I", (0.0, 0.0), &style);
+        let target = renderer.target();
+        assert_eq!(target.get_pixel(5, 9), Color::WHITE);
+        assert_eq!(target.get_pixel(1, 9), Color::RED);
+        assert_eq!(target.get_pixel(11, 9).alpha, 0);
+
+        let style = TextStyle {
+            shadow: Some(TextShadow {
+                offset: (4.0, 0.0),
+                color: Color::BLACK,
+            }),
+            ..TextStyle::default()
+        };
+        let mut renderer = SoftwareRenderer::new(20, 20);
+        renderer.draw_text(&cache.sized(font, 20.0), "I", (0.0, 0.0), &style);
+        let target = renderer.target();
+        assert_eq!(target.get_pixel(5, 9), Color::WHITE);
+        assert_eq!(target.get_pixel(10, 9), Color::BLACK);
+    }
+}
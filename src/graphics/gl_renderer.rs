@@ -0,0 +1,495 @@
+#![allow(dead_code)]
+
+use super::{
+    blend::BlendMode,
+    camera::Camera,
+    color::Color,
+    color_f::ColorF,
+    program::Program,
+    renderer::{DrawParams, Filter, Rect, Renderer},
+    shader::{Shader, ShaderType},
+    sprite::{IndexedSprite, Sprite},
+    tilemap::{Tilemap, CHUNK_SIZE, VERTEX_SIZE},
+};
+
+const VERTEX_SHADER: &str = r#"
+#version 330 core
+layout (location = 0) in vec2 corner;
+
+uniform mat3 transform;
+uniform vec4 source;
+
+out vec2 uv;
+
+void main() {
+    uv = source.xy + corner * source.zw;
+    gl_Position = vec4((transform * vec3(corner, 1.0)).xy, 0.0, 1.0);
+}
+"#;
+
+const TILE_VERTEX_SHADER: &str = r#"
+#version 330 core
+layout (location = 0) in vec2 position;
+layout (location = 1) in vec2 tex_coord;
+
+uniform mat3 transform;
+
+out vec2 uv;
+
+void main() {
+    uv = tex_coord;
+    gl_Position = vec4((transform * vec3(position, 1.0)).xy, 0.0, 1.0);
+}
+"#;
+
+const FRAGMENT_SHADER: &str = r#"
+#version 330 core
+in vec2 uv;
+
+uniform sampler2D sprite;
+uniform vec4 tint;
+uniform bool premultiplied;
+uniform bool distance_field;
+uniform vec2 field;
+
+out vec4 color;
+
+// Outputs premultiplied colors so every blend mode can use the same blend factors
+void main() {
+    vec4 texel = texture(sprite, uv);
+    if (distance_field) {
+        texel.a = smoothstep(field.x - field.y, field.x + field.y, texel.a);
+    }
+    color = texel * tint;
+    if (!premultiplied) {
+        color.rgb *= color.a;
+    }
+}
+"#;
+
+const PALETTE_FRAGMENT_SHADER: &str = r#"
+#version 330 core
+in vec2 uv;
+
+uniform sampler2D indices;
+uniform sampler2D palette;
+uniform vec4 tint;
+uniform bool premultiplied;
+
+out vec4 color;
+
+void main() {
+    int index = int(texture(indices, uv).r * 255.0 + 0.5);
+    color = texelFetch(palette, ivec2(index, 0), 0) * tint;
+    if (!premultiplied) {
+        color.rgb *= color.a;
+    }
+}
+"#;
+
+/// Shader program for drawing a quad and the locations of its uniforms.
+struct QuadProgram {
+    program: Program,
+    transform: i32,
+    source: i32,
+    tint: i32,
+    premultiplied: i32,
+    /// Only used by the sprite and tile programs, -1 in the others
+    distance_field: i32,
+    field: i32,
+}
+
+/// Renderer that draws sprites as textured quads with OpenGL.
+/// Requires a window with a current OpenGL context.
+pub struct GlRenderer {
+    sprite_program: QuadProgram,
+    palette_program: QuadProgram,
+    /// Draws chunk meshes, the source uniform is unused
+    tile_program: QuadProgram,
+    vao: u32,
+    vbo: u32,
+    /// Vertex layout of chunk meshes, each mesh brings its own buffer
+    tile_vao: u32,
+    width: u32,
+    height: u32,
+    /// Kept so the scissor can be restored after clearing or resizing
+    clip: Option<Rect>,
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl GlRenderer {
+    /// Creates a renderer for a framebuffer of width and height
+    pub fn new(width: u32, height: u32) -> Result<GlRenderer, String> {
+        let sprite_program = QuadProgram::new(VERTEX_SHADER, FRAGMENT_SHADER)?;
+        let palette_program = QuadProgram::new(VERTEX_SHADER, PALETTE_FRAGMENT_SHADER)?;
+        let tile_program = QuadProgram::new(TILE_VERTEX_SHADER, FRAGMENT_SHADER)?;
+
+        // The palette is always bound to texture unit 1
+        unsafe {
+            palette_program.program.bind();
+            let palette =
+                gl::GetUniformLocation(palette_program.program.id(), b"palette\0".as_ptr() as _);
+            gl::Uniform1i(palette, 1);
+        }
+
+        // Unit quad as a triangle strip, scaled to the sprite by the transform
+        let corners: [f32; 8] = [0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0];
+
+        unsafe {
+            let mut vao = 0;
+            let mut vbo = 0;
+            gl::GenVertexArrays(1, &mut vao);
+            gl::GenBuffers(1, &mut vbo);
+
+            gl::BindVertexArray(vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                std::mem::size_of_val(&corners) as isize,
+                corners.as_ptr() as *const _,
+                gl::STATIC_DRAW,
+            );
+            gl::VertexAttribPointer(0, 2, gl::FLOAT, gl::FALSE, 0, std::ptr::null());
+            gl::EnableVertexAttribArray(0);
+
+            let mut tile_vao = 0;
+            gl::GenVertexArrays(1, &mut tile_vao);
+            gl::BindVertexArray(tile_vao);
+            gl::EnableVertexAttribArray(0);
+            gl::EnableVertexAttribArray(1);
+            gl::BindVertexArray(0);
+
+            Ok(GlRenderer {
+                sprite_program,
+                palette_program,
+                tile_program,
+                vao,
+                vbo,
+                tile_vao,
+                width,
+                height,
+                clip: None,
+            })
+        }
+    }
+}
+
+impl QuadProgram {
+    fn new(vertex_shader: &str, fragment_shader: &str) -> Result<QuadProgram, String> {
+        let program = Program::new([
+            Shader::from_source(vertex_shader, ShaderType::Vertex)?,
+            Shader::from_source(fragment_shader, ShaderType::Fragment)?,
+        ])?;
+
+        let location =
+            |name: &[u8]| unsafe { gl::GetUniformLocation(program.id(), name.as_ptr() as _) };
+
+        Ok(QuadProgram {
+            transform: location(b"transform\0"),
+            source: location(b"source\0"),
+            tint: location(b"tint\0"),
+            premultiplied: location(b"premultiplied\0"),
+            distance_field: location(b"distance_field\0"),
+            field: location(b"field\0"),
+            program,
+        })
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl GlRenderer {
+    /// Changes the framebuffer size, call when the window is resized
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+        unsafe {
+            gl::Viewport(0, 0, width as i32, height as i32);
+        }
+        // The scissor is measured from the bottom, so it moves with the height
+        self.apply_clip();
+    }
+
+    fn apply_clip(&self) {
+        unsafe {
+            match scissor_box(self.clip, self.height) {
+                Some([x, y, width, height]) => {
+                    gl::Enable(gl::SCISSOR_TEST);
+                    gl::Scissor(x, y, width, height);
+                }
+                None => gl::Disable(gl::SCISSOR_TEST),
+            }
+        }
+    }
+}
+
+impl Renderer for GlRenderer {
+    fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn clear(&mut self, color: Color) {
+        unsafe {
+            // Clearing ignores the clip rectangle like the software renderer
+            gl::Disable(gl::SCISSOR_TEST);
+            gl::ClearColor(
+                color.red as f32 / 255.0,
+                color.green as f32 / 255.0,
+                color.blue as f32 / 255.0,
+                color.alpha as f32 / 255.0,
+            );
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+        }
+        self.apply_clip();
+    }
+
+    fn set_clip(&mut self, clip: Option<Rect>) {
+        self.clip = clip;
+        self.apply_clip();
+    }
+
+    fn draw_sprite(&mut self, sprite: &Sprite, params: &DrawParams) {
+        let size = (sprite.texture().width(), sprite.texture().height());
+
+        let filter = match params.filter {
+            Filter::Nearest => gl::NEAREST,
+            Filter::Bilinear => gl::LINEAR,
+        } as i32;
+
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0);
+            sprite.bind();
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, filter);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, filter);
+        }
+
+        draw_quad(&self.sprite_program, self.vao, self.size(), size, params);
+    }
+
+    fn draw_indexed_sprite(&mut self, sprite: &IndexedSprite, params: &DrawParams) {
+        let size = (sprite.indexed().width(), sprite.indexed().height());
+        sprite.bind();
+        draw_quad(&self.palette_program, self.vao, self.size(), size, params);
+    }
+
+    /// Draws the chunk meshes built by `Tilemap::update`, one draw call per chunk and tileset
+    fn draw_tilemap(&mut self, tilemap: &Tilemap, camera: &Camera) {
+        let (sx, sy) = (2.0 / self.width as f32, -2.0 / self.height as f32);
+        let stride = (VERTEX_SIZE * std::mem::size_of::<f32>()) as i32;
+
+        unsafe {
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::ONE, gl::ONE_MINUS_SRC_ALPHA);
+            gl::ActiveTexture(gl::TEXTURE0);
+
+            self.tile_program.program.bind();
+            gl::Uniform1i(self.tile_program.premultiplied, 0);
+            gl::Uniform1i(self.tile_program.distance_field, 0);
+            gl::BindVertexArray(self.tile_vao);
+        }
+
+        for layer in tilemap.layers().iter().filter(|layer| layer.visible) {
+            // World pixels to screen pixels, then to clip space
+            let (left, top) = camera.world_to_screen(layer.offset);
+            let transform: [f32; 9] = [
+                camera.zoom * sx,
+                0.0,
+                0.0,
+                0.0,
+                camera.zoom * sy,
+                0.0,
+                left * sx - 1.0,
+                top * sy + 1.0,
+                1.0,
+            ];
+            let tint = [1.0, 1.0, 1.0, layer.opacity.clamp(0.0, 1.0)];
+
+            unsafe {
+                gl::UniformMatrix3fv(
+                    self.tile_program.transform,
+                    1,
+                    gl::FALSE,
+                    transform.as_ptr(),
+                );
+                gl::Uniform4fv(self.tile_program.tint, 1, tint.as_ptr());
+            }
+
+            let (columns, rows) = tilemap.visible_cells(layer, camera);
+            if columns.is_empty() || rows.is_empty() {
+                continue;
+            }
+
+            let chunk_columns = columns.start / CHUNK_SIZE..(columns.end - 1) / CHUNK_SIZE + 1;
+            for row in rows.start / CHUNK_SIZE..(rows.end - 1) / CHUNK_SIZE + 1 {
+                for column in chunk_columns.clone() {
+                    let chunk = match layer.chunk(column, row) {
+                        Some(chunk) => chunk,
+                        None => continue,
+                    };
+
+                    for mesh in chunk.meshes.iter().filter(|mesh| mesh.vbo != 0) {
+                        let tileset = match tilemap.tileset(mesh.tileset) {
+                            Some(tileset) => tileset,
+                            None => continue,
+                        };
+
+                        unsafe {
+                            tileset.sprite().bind();
+                            gl::TexParameteri(
+                                gl::TEXTURE_2D,
+                                gl::TEXTURE_MIN_FILTER,
+                                gl::NEAREST as i32,
+                            );
+                            gl::TexParameteri(
+                                gl::TEXTURE_2D,
+                                gl::TEXTURE_MAG_FILTER,
+                                gl::NEAREST as i32,
+                            );
+
+                            gl::BindBuffer(gl::ARRAY_BUFFER, mesh.vbo);
+                            gl::VertexAttribPointer(
+                                0,
+                                2,
+                                gl::FLOAT,
+                                gl::FALSE,
+                                stride,
+                                std::ptr::null(),
+                            );
+                            gl::VertexAttribPointer(
+                                1,
+                                2,
+                                gl::FLOAT,
+                                gl::FALSE,
+                                stride,
+                                (2 * std::mem::size_of::<f32>()) as *const _,
+                            );
+                            gl::DrawArrays(gl::TRIANGLES, 0, mesh.len() as i32);
+                        }
+                    }
+                }
+            }
+        }
+
+        unsafe {
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+            gl::BindVertexArray(0);
+        }
+    }
+}
+
+/// Returns the scissor box of a clip rectangle as x, y, width and height,
+/// OpenGL puts the scissor origin at the bottom left
+fn scissor_box(clip: Option<Rect>, height: u32) -> Option<[i32; 4]> {
+    clip.map(|clip| {
+        let y = height as i32 - (clip.y + clip.height as i32);
+        [clip.x, y, clip.width as i32, clip.height as i32]
+    })
+}
+
+/// Draws the unit quad with a program, the textures must already be bound
+fn draw_quad(
+    quad: &QuadProgram,
+    vao: u32,
+    target: (u32, u32),
+    texture: (usize, usize),
+    params: &DrawParams,
+) {
+    let source = params.source_rect(texture.0, texture.1);
+    if source.width == 0 || source.height == 0 {
+        return;
+    }
+
+    let uv = [
+        source.x as f32 / texture.0 as f32,
+        source.y as f32 / texture.1 as f32,
+        source.width as f32 / texture.0 as f32,
+        source.height as f32 / texture.1 as f32,
+    ];
+
+    // Unit quad to sprite pixels, then to target pixels, then to clip space
+    let t = params.transform();
+    let (w, h) = (source.width as f32, source.height as f32);
+    let (sx, sy) = (2.0 / target.0 as f32, -2.0 / target.1 as f32);
+    let transform: [f32; 9] = [
+        t.a * w * sx,
+        t.b * w * sy,
+        0.0,
+        t.c * h * sx,
+        t.d * h * sy,
+        0.0,
+        t.tx * sx - 1.0,
+        t.ty * sy + 1.0,
+        1.0,
+    ];
+
+    // Premultiplied textures need a premultiplied tint
+    let premultiplied = params.blend == BlendMode::Premultiplied;
+    let tint = if premultiplied {
+        ColorF::from(params.tint).premultiply()
+    } else {
+        ColorF::from(params.tint)
+    };
+
+    // Overlay has no blend factors and falls back to alpha blending
+    let (src_factor, dst_factor) = params
+        .blend
+        .gl_factors()
+        .unwrap_or((gl::ONE, gl::ONE_MINUS_SRC_ALPHA));
+
+    unsafe {
+        gl::Enable(gl::BLEND);
+        gl::BlendFunc(src_factor, dst_factor);
+
+        quad.program.bind();
+        gl::UniformMatrix3fv(quad.transform, 1, gl::FALSE, transform.as_ptr());
+        gl::Uniform4fv(quad.source, 1, uv.as_ptr());
+        gl::Uniform4fv(quad.tint, 1, tint.to_array().as_ptr());
+        gl::Uniform1i(quad.premultiplied, premultiplied as i32);
+        gl::Uniform1i(quad.distance_field, params.distance_field.is_some() as i32);
+        if let Some(field) = params.distance_field {
+            gl::Uniform2f(quad.field, field.edge, field.smoothing);
+        }
+
+        gl::BindVertexArray(vao);
+        gl::DrawArrays(gl::TRIANGLE_STRIP, 0, 4);
+        gl::BindVertexArray(0);
+    }
+}
+
+// ====================================
+// Other Implementations
+// ====================================
+
+impl Drop for GlRenderer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteBuffers(1, &self.vbo);
+            gl::DeleteVertexArrays(1, &self.vao);
+            gl::DeleteVertexArrays(1, &self.tile_vao);
+        }
+    }
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod gl_renderer_tests {
+
+    use super::*;
+
+    #[test]
+    fn scissor_flips_the_clip() {
+        assert_eq!(scissor_box(None, 100), None);
+        assert_eq!(
+            scissor_box(Some(Rect::new(10, 20, 30, 40)), 100),
+            Some([10, 40, 30, 40])
+        );
+    }
+}
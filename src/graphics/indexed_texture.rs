@@ -0,0 +1,322 @@
+#![allow(dead_code)]
+
+use super::{
+    color::Color,
+    palette::{Palette, MAX_COLORS},
+    texture::Texture,
+};
+
+/// Error diffusion used when mapping colors to a palette.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Dither {
+    None,
+    FloydSteinberg,
+}
+
+/// Image that stores palette indices instead of colors.
+/// Recoloring is done by drawing it with a different `Palette`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct IndexedTexture {
+    width: usize,
+    height: usize,
+    indices: Vec<u8>,
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl IndexedTexture {
+    /// Creates an `IndexedTexture` of width and height filled with index 0
+    pub fn new(width: usize, height: usize) -> IndexedTexture {
+        IndexedTexture {
+            width,
+            height,
+            indices: vec![0; width * height],
+        }
+    }
+
+    /// Maps every pixel of a `Texture` to the closest color of a palette
+    pub fn from_texture(texture: &Texture, palette: &Palette, dither: Dither) -> IndexedTexture {
+        let mut indexed = IndexedTexture::new(texture.width(), texture.height());
+
+        match dither {
+            Dither::None => {
+                for y in 0..texture.height() {
+                    for x in 0..texture.width() {
+                        let index = palette.nearest(texture.get_pixel(x, y));
+                        indexed.set_index(x, y, index);
+                    }
+                }
+            }
+            Dither::FloydSteinberg => indexed.dither_from(texture, palette),
+        }
+
+        indexed
+    }
+
+    /// Reduces a `Texture` to at most `max_colors` colors using median cut
+    pub fn quantize(
+        texture: &Texture,
+        max_colors: usize,
+        dither: Dither,
+    ) -> (IndexedTexture, Palette) {
+        let palette = median_cut(texture.pixels(), max_colors.clamp(1, MAX_COLORS));
+        let indexed = IndexedTexture::from_texture(texture, &palette, dither);
+        (indexed, palette)
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl IndexedTexture {
+    /// Returns the palette index at the specified x and y coordinates
+    pub fn get_index(&self, x: usize, y: usize) -> u8 {
+        if x < self.width && y < self.height {
+            self.indices[y * self.width + x]
+        } else {
+            0
+        }
+    }
+
+    /// Sets the palette index at the specified x and y coordinates
+    pub fn set_index(&mut self, x: usize, y: usize, index: u8) {
+        if x < self.width && y < self.height {
+            self.indices[y * self.width + x] = index;
+        }
+    }
+
+    /// Returns the indices row by row, starting at the top left
+    pub fn indices(&self) -> &[u8] {
+        &self.indices
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Looks every index up in a palette and creates an RGBA `Texture`
+    pub fn to_texture(&self, palette: &Palette) -> Texture {
+        let mut texture = Texture::new(self.width, self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                texture.set_pixel(x, y, palette.get(self.get_index(x, y)));
+            }
+        }
+        texture
+    }
+
+    fn dither_from(&mut self, texture: &Texture, palette: &Palette) {
+        // Accumulated error per pixel and channel
+        let mut error = vec![[0.0f32; 4]; self.width * self.height];
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let pixel = texture.get_pixel(x, y);
+                let carried = error[y * self.width + x];
+                let wanted = [
+                    pixel.red as f32 + carried[0],
+                    pixel.green as f32 + carried[1],
+                    pixel.blue as f32 + carried[2],
+                    pixel.alpha as f32 + carried[3],
+                ];
+
+                let byte = |value: f32| value.round().clamp(0.0, 255.0) as u8;
+                let index = palette.nearest(Color::from_rgba(
+                    byte(wanted[0]),
+                    byte(wanted[1]),
+                    byte(wanted[2]),
+                    byte(wanted[3]),
+                ));
+                self.set_index(x, y, index);
+
+                let chosen = palette.get(index);
+                let chosen = [
+                    chosen.red as f32,
+                    chosen.green as f32,
+                    chosen.blue as f32,
+                    chosen.alpha as f32,
+                ];
+
+                let neighbours = [(1, 0, 7.0), (-1, 1, 3.0), (0, 1, 5.0), (1, 1, 1.0)];
+                for (dx, dy, weight) in neighbours.iter() {
+                    let nx = x as isize + dx;
+                    let ny = y + dy;
+                    if nx < 0 || nx as usize >= self.width || ny >= self.height {
+                        continue;
+                    }
+
+                    let target = &mut error[ny * self.width + nx as usize];
+                    for channel in 0..4 {
+                        target[channel] += (wanted[channel] - chosen[channel]) * weight / 16.0;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Builds a palette by repeatedly splitting the box of colors with the widest channel range
+fn median_cut(pixels: &[Color], max_colors: usize) -> Palette {
+    let mut boxes: Vec<Vec<[u8; 4]>> = Vec::new();
+    let all: Vec<[u8; 4]> = pixels
+        .iter()
+        .map(|c| [c.red, c.green, c.blue, c.alpha])
+        .collect();
+    if !all.is_empty() {
+        boxes.push(all);
+    }
+
+    while boxes.len() < max_colors {
+        // The box with the widest range is split next, boxes of one color can not be split
+        let widest = boxes
+            .iter()
+            .enumerate()
+            .map(|(index, colors)| {
+                let (channel, range) = widest_channel(colors);
+                (index, channel, range)
+            })
+            .filter(|(_, _, range)| *range > 0)
+            .max_by_key(|(_, _, range)| *range);
+
+        let (index, channel, _) = match widest {
+            Some(widest) => widest,
+            None => break,
+        };
+
+        let mut colors = boxes.swap_remove(index);
+        colors.sort_unstable_by_key(|color| color[channel]);
+
+        // Split at the median but never between two equal values, so both halves differ
+        let median = colors[colors.len() / 2][channel];
+        let split = if colors[0][channel] == median {
+            colors.partition_point(|color| color[channel] <= median)
+        } else {
+            colors.partition_point(|color| color[channel] < median)
+        };
+
+        let upper = colors.split_off(split);
+        boxes.push(colors);
+        boxes.push(upper);
+    }
+
+    let mut averages: Vec<Color> = boxes.iter().map(|colors| average(colors)).collect();
+    averages.sort_by_key(|c| (c.alpha, c.red as u32 + c.green as u32 + c.blue as u32));
+    Palette::from_colors(&averages)
+}
+
+/// Returns the channel with the largest range and that range
+fn widest_channel(colors: &[[u8; 4]]) -> (usize, u8) {
+    let mut min = [u8::MAX; 4];
+    let mut max = [u8::MIN; 4];
+    for color in colors.iter() {
+        for channel in 0..4 {
+            min[channel] = min[channel].min(color[channel]);
+            max[channel] = max[channel].max(color[channel]);
+        }
+    }
+
+    (0..4)
+        .map(|channel| (channel, max[channel] - min[channel]))
+        .max_by_key(|(_, range)| *range)
+        .unwrap_or((0, 0))
+}
+
+fn average(colors: &[[u8; 4]]) -> Color {
+    let mut sum = [0u64; 4];
+    for color in colors.iter() {
+        for channel in 0..4 {
+            sum[channel] += color[channel] as u64;
+        }
+    }
+
+    let count = colors.len().max(1) as u64;
+    let mean = |channel: usize| ((sum[channel] + count / 2) / count) as u8;
+    Color::from_rgba(mean(0), mean(1), mean(2), mean(3))
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod indexed_texture_tests {
+
+    use super::*;
+
+    fn four_colors() -> Texture {
+        let mut texture = Texture::new(4, 4);
+        for y in 0..4 {
+            for x in 0..4 {
+                let color = match (x < 2, y < 2) {
+                    (true, true) => Color::RED,
+                    (false, true) => Color::GREEN,
+                    (true, false) => Color::BLUE,
+                    (false, false) => Color::CLEAR,
+                };
+                texture.set_pixel(x, y, color);
+            }
+        }
+        texture
+    }
+
+    #[test]
+    fn quantize_is_lossless_with_enough_colors() {
+        let texture = four_colors();
+        let (indexed, palette) = IndexedTexture::quantize(&texture, 16, Dither::None);
+
+        assert_eq!(palette.len(), 4);
+        assert!(palette.colors().contains(&Color::CLEAR));
+        assert_eq!(indexed.to_texture(&palette).pixels(), texture.pixels());
+    }
+
+    #[test]
+    fn quantize_reduces_colors() {
+        let mut texture = Texture::new(16, 1);
+        for x in 0..16 {
+            texture.set_pixel(x, 0, Color::from_rgb(x as u8 * 16, 0, 0));
+        }
+
+        let (indexed, palette) = IndexedTexture::quantize(&texture, 2, Dither::None);
+        assert_eq!(palette.len(), 2);
+        assert_eq!(palette.get(0), Color::from_rgb(56, 0, 0));
+        assert_eq!(palette.get(1), Color::from_rgb(184, 0, 0));
+        assert_eq!(indexed.get_index(7, 0), 0);
+        assert_eq!(indexed.get_index(8, 0), 1);
+    }
+
+    #[test]
+    fn dithering_keeps_average() {
+        let gray = Color::from_rgb(128, 128, 128);
+        let texture = Texture::from_color(8, 8, gray);
+        let palette = Palette::from_colors(&[Color::BLACK, Color::WHITE]);
+
+        let flat = IndexedTexture::from_texture(&texture, &palette, Dither::None);
+        assert!(flat.indices().iter().all(|&index| index == 1));
+
+        let dithered = IndexedTexture::from_texture(&texture, &palette, Dither::FloydSteinberg);
+        let white = dithered
+            .indices()
+            .iter()
+            .filter(|&&index| index == 1)
+            .count();
+        assert_eq!(white, 32);
+    }
+
+    #[test]
+    fn palette_swap() {
+        let texture = four_colors();
+        let (indexed, palette) = IndexedTexture::quantize(&texture, 4, Dither::None);
+
+        let swapped = palette.swapped(Color::RED, Color::YELLOW);
+        let recolored = indexed.to_texture(&swapped);
+        assert_eq!(recolored.get_pixel(0, 0), Color::YELLOW);
+        assert_eq!(recolored.get_pixel(3, 0), Color::GREEN);
+    }
+}
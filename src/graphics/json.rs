@@ -0,0 +1,325 @@
+#![allow(dead_code)]
+
+/// Parsed JSON value. Objects keep their keys in document order.
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+/// Cursor over the document text.
+struct Parser<'a> {
+    text: &'a str,
+    at: usize,
+}
+
+/// Deepest nesting of arrays and objects, guards the stack against hostile files
+const MAX_DEPTH: usize = 256;
+
+// ====================================
+// Constructors
+// ====================================
+
+impl Json {
+    pub fn parse(text: &str) -> Result<Json, String> {
+        let mut parser = Parser { text, at: 0 };
+        let value = parser.value(0)?;
+        parser.skip_whitespace();
+
+        if parser.at < text.len() {
+            return Err(parser.error("content after the value"));
+        }
+        Ok(value)
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl Json {
+    /// Returns the value of a key, `None` if this is not an object or the key is missing
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(entries) => entries
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        *self == Json::Null
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the number if it is a whole number
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_f64()
+            .filter(|value| value.fract() == 0.0)
+            .map(|value| value as i64)
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&[(String, Json)]> {
+        match self {
+            Json::Object(entries) => Some(entries),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.text[self.at..]
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("{} at byte {}", message, self.at)
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.at += rest.len() - rest.trim_start_matches([' ', '\t', '\n', '\r']).len();
+    }
+
+    /// Skips whitespace and consumes a character if it comes next
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(c) {
+            self.at += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Json, String> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("nested too deeply"));
+        }
+
+        self.skip_whitespace();
+        let rest = self.rest();
+        match rest.chars().next() {
+            Some('{') => {
+                self.at += 1;
+                let mut entries = Vec::new();
+                if self.eat('}') {
+                    return Ok(Json::Object(entries));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let key = self.string()?;
+                    if !self.eat(':') {
+                        return Err(self.error("expected ':'"));
+                    }
+                    entries.push((key, self.value(depth + 1)?));
+                    if self.eat('}') {
+                        return Ok(Json::Object(entries));
+                    }
+                    if !self.eat(',') {
+                        return Err(self.error("expected ',' or '}'"));
+                    }
+                }
+            }
+            Some('[') => {
+                self.at += 1;
+                let mut values = Vec::new();
+                if self.eat(']') {
+                    return Ok(Json::Array(values));
+                }
+                loop {
+                    values.push(self.value(depth + 1)?);
+                    if self.eat(']') {
+                        return Ok(Json::Array(values));
+                    }
+                    if !self.eat(',') {
+                        return Err(self.error("expected ',' or ']'"));
+                    }
+                }
+            }
+            Some('"') => Ok(Json::String(self.string()?)),
+            Some('-') | Some('0'..='9') => {
+                let end = rest
+                    .find(|c: char| !matches!(c, '0'..='9' | '-' | '+' | '.' | 'e' | 'E'))
+                    .unwrap_or(rest.len());
+                let number = rest[..end]
+                    .parse()
+                    .map_err(|_| self.error("invalid number"))?;
+                self.at += end;
+                Ok(Json::Number(number))
+            }
+            _ => {
+                for (word, value) in [
+                    ("true", Json::Bool(true)),
+                    ("false", Json::Bool(false)),
+                    ("null", Json::Null),
+                ] {
+                    if rest.starts_with(word) {
+                        self.at += word.len();
+                        return Ok(value);
+                    }
+                }
+                Err(self.error("expected a value"))
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        if !self.rest().starts_with('"') {
+            return Err(self.error("expected a string"));
+        }
+        self.at += 1;
+
+        let mut value = String::new();
+        loop {
+            let rest = self.rest();
+            let end = rest
+                .find(['"', '\\'])
+                .ok_or_else(|| self.error("unterminated string"))?;
+            value.push_str(&rest[..end]);
+            self.at += end + 1;
+
+            if rest[end..].starts_with('"') {
+                return Ok(value);
+            }
+
+            let escape = self
+                .rest()
+                .chars()
+                .next()
+                .ok_or_else(|| self.error("unterminated string"))?;
+            self.at += 1;
+            match escape {
+                '"' | '\\' | '/' => value.push(escape),
+                'b' => value.push('\u{8}'),
+                'f' => value.push('\u{c}'),
+                'n' => value.push('\n'),
+                'r' => value.push('\r'),
+                't' => value.push('\t'),
+                'u' => {
+                    let mut code = self.hex4()?;
+                    // Characters outside the basic plane come as a surrogate pair
+                    if (0xd800..0xdc00).contains(&code) && self.rest().starts_with("\\u") {
+                        self.at += 2;
+                        let low = self.hex4()?;
+                        code =
+                            0x10000 + ((code - 0xd800) << 10) + (low.wrapping_sub(0xdc00) & 0x3ff);
+                    }
+                    value.push(std::char::from_u32(code).unwrap_or('\u{fffd}'));
+                }
+                _ => return Err(self.error("invalid escape")),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits = self
+            .rest()
+            .get(..4)
+            .ok_or_else(|| self.error("invalid unicode escape"))?;
+        let code =
+            u32::from_str_radix(digits, 16).map_err(|_| self.error("invalid unicode escape"))?;
+        self.at += 4;
+        Ok(code)
+    }
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod json_tests {
+
+    use super::*;
+
+    #[test]
+    fn parse_values() {
+        let text = r#" {
+            "name": "level \"one\"\né😀",
+            "size": [16, -2.5e1, 0.125],
+            "visible": true, "parent": null,
+            "empty": {}, "list": []
+        } "#;
+        let json = Json::parse(text).unwrap();
+
+        assert_eq!(
+            json.get("name").and_then(Json::as_str),
+            Some("level \"one\"\n\u{e9}\u{1f600}")
+        );
+        let size: Vec<f64> = json
+            .get("size")
+            .and_then(Json::as_array)
+            .unwrap()
+            .iter()
+            .filter_map(Json::as_f64)
+            .collect();
+        assert_eq!(size, [16.0, -25.0, 0.125]);
+        assert_eq!(
+            json.get("size").unwrap().as_array().unwrap()[0].as_i64(),
+            Some(16)
+        );
+        assert_eq!(json.get("visible").and_then(Json::as_bool), Some(true));
+        assert!(json.get("parent").unwrap().is_null());
+        assert_eq!(json.get("empty"), Some(&Json::Object(Vec::new())));
+        assert_eq!(json.get("missing"), None);
+
+        let keys: Vec<&str> = json
+            .as_object()
+            .unwrap()
+            .iter()
+            .map(|(key, _)| key.as_str())
+            .collect();
+        assert_eq!(keys, ["name", "size", "visible", "parent", "empty", "list"]);
+    }
+
+    #[test]
+    fn reject_malformed() {
+        for text in [
+            "",
+            "{",
+            "[1,]",
+            "{\"a\" 1}",
+            "\"open",
+            "tru",
+            "[1] 2",
+            "01x",
+        ] {
+            assert!(Json::parse(text).is_err(), "{}", text);
+        }
+        assert!(Json::parse(&"[".repeat(1000)).is_err());
+    }
+}
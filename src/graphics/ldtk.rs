@@ -0,0 +1,1131 @@
+#![allow(dead_code)]
+
+use std::{collections::HashMap, path::Path};
+
+use super::{
+    color::Color,
+    json::Json,
+    renderer::Rect,
+    texture::Texture,
+    tilemap::{Tile, Tilemap, Tileset},
+};
+use image::ImageError;
+
+/// Project made in the LDtk editor, loaded from a `.ldtk` file.
+#[derive(Clone, PartialEq, Debug)]
+pub struct LdtkProject {
+    pub background: Option<Color>,
+    pub tilesets: Vec<LdtkTileset>,
+    pub layer_defs: Vec<LdtkLayerDef>,
+    /// Projects without multiple worlds have a single world holding all levels
+    pub worlds: Vec<LdtkWorld>,
+}
+
+/// Tileset definition, tiles are numbered row by row.
+#[derive(Clone, PartialEq, Debug)]
+pub struct LdtkTileset {
+    pub uid: i64,
+    pub identifier: String,
+    /// Image path, relative to the project until `LdtkProject::load` resolves it
+    pub path: Option<String>,
+    /// Image size in pixels
+    pub width: u32,
+    pub height: u32,
+    pub grid_size: u32,
+    pub spacing: u32,
+    /// Pixels around the tiles at the edge of the image
+    pub padding: u32,
+    /// Custom data by tile id
+    pub custom_data: HashMap<u32, String>,
+}
+
+/// Layer definition shared by the layer instances of every level.
+#[derive(Clone, PartialEq, Debug)]
+pub struct LdtkLayerDef {
+    pub uid: i64,
+    pub identifier: String,
+    pub kind: LayerType,
+    pub grid_size: u32,
+    /// Values an IntGrid layer can hold
+    pub int_grid_values: Vec<IntGridValue>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct IntGridValue {
+    pub value: i32,
+    pub identifier: String,
+    pub color: Color,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LayerType {
+    /// Grid of numbers, optionally painted by auto-layer rules
+    IntGrid,
+    Entities,
+    /// Tiles placed by hand
+    Tiles,
+    /// Tiles placed by rules that read the IntGrid of another layer
+    AutoLayer,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum WorldLayout {
+    /// Levels are placed anywhere
+    #[default]
+    Free,
+    /// Levels are placed on a grid
+    GridVania,
+    LinearHorizontal,
+    LinearVertical,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct LdtkWorld {
+    pub identifier: String,
+    pub iid: String,
+    pub layout: WorldLayout,
+    pub levels: Vec<LdtkLevel>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct LdtkLevel {
+    pub identifier: String,
+    pub iid: String,
+    pub uid: i64,
+    /// Top left corner in the world in pixels
+    pub world_position: (i32, i32),
+    pub world_depth: i32,
+    /// Size in pixels
+    pub width: u32,
+    pub height: u32,
+    pub background: Option<Color>,
+    pub fields: Fields,
+    /// Layers from bottom to top
+    pub layers: Vec<LdtkLayer>,
+    pub neighbors: Vec<Neighbor>,
+    /// File of a level saved separately, `LdtkProject::load` reads its layers
+    pub external: Option<String>,
+}
+
+/// Level touching or overlapping another level.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Neighbor {
+    pub level_iid: String,
+    pub direction: Direction,
+}
+
+/// Where a neighbor is, y points down so north is above.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Direction {
+    North,
+    South,
+    East,
+    West,
+    NorthEast,
+    NorthWest,
+    SouthEast,
+    SouthWest,
+    /// Overlapping level with a greater depth
+    Above,
+    /// Overlapping level with a smaller depth
+    Below,
+    /// Overlapping level at the same depth
+    Overlap,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct LdtkLayer {
+    pub identifier: String,
+    pub iid: String,
+    pub def_uid: i64,
+    pub kind: LayerType,
+    /// Size in cells
+    pub width: usize,
+    pub height: usize,
+    pub grid_size: u32,
+    pub opacity: f32,
+    pub visible: bool,
+    /// Offset in pixels, including the offset of the layer definition
+    pub offset: (f32, f32),
+    /// Uid of the tileset used by the tiles
+    pub tileset: Option<i64>,
+    /// Values of IntGrid layers row by row, 0 is empty
+    pub int_grid: Vec<i32>,
+    /// Tiles in drawing order, several tiles can share a cell
+    pub tiles: Vec<LdtkTile>,
+    pub entities: Vec<LdtkEntity>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LdtkTile {
+    /// Top left corner in the layer in pixels
+    pub position: (i32, i32),
+    /// Top left corner in the tileset image in pixels
+    pub source: (i32, i32),
+    pub id: u32,
+    pub flip_x: bool,
+    pub flip_y: bool,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct LdtkEntity {
+    pub identifier: String,
+    pub iid: String,
+    pub def_uid: i64,
+    /// Cell of the pivot
+    pub grid: (i32, i32),
+    /// Pivot position in the layer in pixels
+    pub position: (f32, f32),
+    /// Pivot position in the world in pixels
+    pub world_position: (f32, f32),
+    /// Fraction of the size the pivot is at, (0, 0) is the top left corner
+    pub pivot: (f32, f32),
+    pub width: u32,
+    pub height: u32,
+    pub tags: Vec<String>,
+    /// Tile the entity is shown with in the editor
+    pub tile: Option<TileRect>,
+    pub fields: Fields,
+}
+
+/// Region of a tileset.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TileRect {
+    pub tileset: i64,
+    pub source: Rect,
+}
+
+/// Reference to an entity, possibly in another level or world.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct EntityRef {
+    pub entity_iid: String,
+    pub layer_iid: String,
+    pub level_iid: String,
+    pub world_iid: String,
+}
+
+/// Value of a custom field of a level or entity.
+#[derive(Clone, PartialEq, Debug)]
+pub enum FieldValue {
+    /// Unset optional value, or a value of an unknown type
+    Null,
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    /// Single or multiple lines of text
+    String(String),
+    FilePath(String),
+    Color(Color),
+    /// Cell in the level
+    Point(i32, i32),
+    /// Enum value by name
+    Enum(String),
+    EntityRef(EntityRef),
+    Tile(TileRect),
+    Array(Vec<FieldValue>),
+}
+
+pub type Fields = HashMap<String, FieldValue>;
+
+#[derive(Debug)]
+pub enum LdtkError {
+    Io(std::io::Error),
+    Image(ImageError),
+    /// The JSON could not be parsed
+    Parse(String),
+    /// A value is missing or has the wrong type
+    InvalidFormat(String),
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl LdtkProject {
+    /// Loads a `.ldtk` project and the levels saved in separate files.
+    /// Tileset paths are made relative to the working directory.
+    pub fn load(path: &str) -> Result<LdtkProject, LdtkError> {
+        let text = std::fs::read_to_string(path).map_err(LdtkError::Io)?;
+        let mut project = LdtkProject::parse(&text)?;
+
+        let directory = Path::new(path).parent().unwrap_or_else(|| Path::new(""));
+        for tileset in project.tilesets.iter_mut() {
+            if let Some(path) = &mut tileset.path {
+                *path = directory.join(&path).to_string_lossy().into_owned();
+            }
+        }
+
+        for world in project.worlds.iter_mut() {
+            for level in world.levels.iter_mut() {
+                if let Some(file) = level.external.clone() {
+                    let text =
+                        std::fs::read_to_string(directory.join(&file)).map_err(LdtkError::Io)?;
+                    let json = Json::parse(&text).map_err(LdtkError::Parse)?;
+                    *level = parse_level(&json)?;
+                    level.external = Some(file);
+                }
+            }
+        }
+
+        Ok(project)
+    }
+
+    /// Parses a project, levels in separate files are left for `load` to read
+    pub fn parse(source: &str) -> Result<LdtkProject, LdtkError> {
+        let root = Json::parse(source).map_err(LdtkError::Parse)?;
+        let defs = root.get("defs").ok_or_else(|| invalid("defs"))?;
+
+        let mut project = LdtkProject {
+            background: string(&root, "bgColor").and_then(|hex| Color::from_hex(hex).ok()),
+            tilesets: array(defs, "tilesets")
+                .iter()
+                .map(parse_tileset)
+                .collect::<Result<_, _>>()?,
+            layer_defs: array(defs, "layers")
+                .iter()
+                .map(parse_layer_def)
+                .collect::<Result<_, _>>()?,
+            worlds: Vec::new(),
+        };
+
+        for world in array(&root, "worlds") {
+            project.worlds.push(LdtkWorld {
+                identifier: text(world, "identifier"),
+                iid: text(world, "iid"),
+                layout: world_layout(world),
+                levels: array(world, "levels")
+                    .iter()
+                    .map(parse_level)
+                    .collect::<Result<_, _>>()?,
+            });
+        }
+
+        // Without multiple worlds the levels are stored in the project
+        if project.worlds.is_empty() {
+            project.worlds.push(LdtkWorld {
+                identifier: "World".to_string(),
+                iid: text(&root, "iid"),
+                layout: world_layout(&root),
+                levels: array(&root, "levels")
+                    .iter()
+                    .map(parse_level)
+                    .collect::<Result<_, _>>()?,
+            });
+        }
+
+        Ok(project)
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl LdtkProject {
+    /// Returns all levels of all worlds
+    pub fn levels(&self) -> impl Iterator<Item = &LdtkLevel> {
+        self.worlds.iter().flat_map(|world| world.levels.iter())
+    }
+
+    pub fn level(&self, identifier: &str) -> Option<&LdtkLevel> {
+        self.levels().find(|level| level.identifier == identifier)
+    }
+
+    pub fn level_by_iid(&self, iid: &str) -> Option<&LdtkLevel> {
+        self.levels().find(|level| level.iid == iid)
+    }
+
+    pub fn tileset(&self, uid: i64) -> Option<&LdtkTileset> {
+        self.tilesets.iter().find(|tileset| tileset.uid == uid)
+    }
+
+    pub fn layer_def(&self, uid: i64) -> Option<&LdtkLayerDef> {
+        self.layer_defs.iter().find(|def| def.uid == uid)
+    }
+
+    /// Returns the definition of a value in an IntGrid layer
+    pub fn int_grid_value(&self, layer: &LdtkLayer, value: i32) -> Option<&IntGridValue> {
+        self.layer_def(layer.def_uid)?
+            .int_grid_values
+            .iter()
+            .find(|definition| definition.value == value)
+    }
+
+    /// Loads the image of every tileset, in the order of `tilesets`
+    pub fn load_textures(&self) -> Result<Vec<Texture>, LdtkError> {
+        self.tilesets
+            .iter()
+            .map(|tileset| match &tileset.path {
+                Some(path) => Texture::from_image(path).map_err(LdtkError::Image),
+                // Tilesets without an image, like the embedded icons, are never used by layers
+                None => Ok(Texture::new(
+                    tileset.width as usize,
+                    tileset.height as usize,
+                )),
+            })
+            .collect()
+    }
+
+    /// Builds a `Tilemap` from the tiles of a level with a texture for each tileset in order.
+    /// The cells are as large as the smallest grid of the tile layers. Tiles sharing a cell,
+    /// which auto-layer rules often produce, are spread over layers of the same name.
+    pub fn build_tilemap(&self, level: &LdtkLevel, textures: &[Texture]) -> Tilemap {
+        let tiled_layers: Vec<&LdtkLayer> = level
+            .layers
+            .iter()
+            .filter(|layer| !layer.tiles.is_empty() && layer.tileset.is_some())
+            .collect();
+        let cell = tiled_layers
+            .iter()
+            .map(|layer| layer.grid_size)
+            .min()
+            .unwrap_or(16)
+            .max(1);
+
+        let mut tilemap = Tilemap::new(
+            level.width.div_ceil(cell) as usize,
+            level.height.div_ceil(cell) as usize,
+            cell,
+            cell,
+        );
+
+        // Only the tilesets used by the level are added, tileset uid to tilemap index
+        let mut added: HashMap<i64, usize> = HashMap::new();
+        for layer in tiled_layers {
+            let uid = layer.tileset.unwrap();
+            let definition = match self.tilesets.iter().position(|tileset| tileset.uid == uid) {
+                Some(definition) => definition,
+                None => continue,
+            };
+            let tileset = &self.tilesets[definition];
+            let index = match added.get(&uid) {
+                Some(&index) => index,
+                None => {
+                    let texture = textures.get(definition).cloned().unwrap_or_else(|| {
+                        Texture::new(tileset.width as usize, tileset.height as usize)
+                    });
+                    let index = tilemap.add_tileset(Tileset::with_spacing(
+                        texture,
+                        tileset.grid_size,
+                        tileset.grid_size,
+                        tileset.padding,
+                        tileset.spacing,
+                    ));
+                    added.insert(uid, index);
+                    index
+                }
+            };
+
+            let mut stack: Vec<usize> = Vec::new();
+            for tile in layer.tiles.iter() {
+                // Tiles larger than the cells are aligned to the bottom left of a cell
+                let x = tile.position.0.div_euclid(cell as i32);
+                let bottom = tile.position.1.saturating_add(tileset.grid_size as i32);
+                let y = bottom.div_euclid(cell as i32) - 1;
+                if x < 0 || y < 0 {
+                    continue;
+                }
+                let (x, y) = (x as usize, y as usize);
+
+                let free = stack.iter().copied().find(|&target| {
+                    tilemap
+                        .layer(target)
+                        .is_some_and(|target| target.get(x, y).is_none())
+                });
+                let target = match free {
+                    Some(target) => target,
+                    None => {
+                        let target = tilemap.add_layer(&layer.identifier);
+                        let added = tilemap.layer_mut(target).unwrap();
+                        added.visible = layer.visible;
+                        added.opacity = layer.opacity;
+                        added.offset = layer.offset;
+                        stack.push(target);
+                        target
+                    }
+                };
+
+                tilemap.layer_mut(target).unwrap().set(
+                    x,
+                    y,
+                    Some(Tile {
+                        tileset: index,
+                        index: tile.id,
+                        flip_x: tile.flip_x,
+                        flip_y: tile.flip_y,
+                        flip_diagonal: false,
+                    }),
+                );
+            }
+        }
+
+        tilemap
+    }
+}
+
+impl LdtkLevel {
+    pub fn layer(&self, identifier: &str) -> Option<&LdtkLayer> {
+        self.layers
+            .iter()
+            .find(|layer| layer.identifier == identifier)
+    }
+
+    /// Calls `spawn` for every entity, layer by layer from bottom to top.
+    /// This is where a game creates its objects from the entities placed in the editor.
+    pub fn spawn_entities<F: FnMut(&LdtkEntity, &LdtkLayer)>(&self, mut spawn: F) {
+        for layer in self.layers.iter() {
+            for entity in layer.entities.iter() {
+                spawn(entity, layer);
+            }
+        }
+    }
+
+    /// Returns the neighbors in a direction
+    pub fn neighbors_to(&self, direction: Direction) -> impl Iterator<Item = &str> {
+        self.neighbors
+            .iter()
+            .filter(move |neighbor| neighbor.direction == direction)
+            .map(|neighbor| neighbor.level_iid.as_str())
+    }
+}
+
+impl LdtkLayer {
+    /// Returns the IntGrid value of a cell, 0 outside the layer
+    pub fn int_grid_at(&self, x: usize, y: usize) -> i32 {
+        if x < self.width && y < self.height {
+            self.int_grid.get(y * self.width + x).copied().unwrap_or(0)
+        } else {
+            0
+        }
+    }
+
+    /// Returns the entities with an identifier
+    pub fn entities_named<'a>(
+        &'a self,
+        identifier: &'a str,
+    ) -> impl Iterator<Item = &'a LdtkEntity> {
+        self.entities
+            .iter()
+            .filter(move |entity| entity.identifier == identifier)
+    }
+}
+
+impl LdtkEntity {
+    /// Returns the top left corner of the entity in the world in pixels
+    pub fn world_bounds_origin(&self) -> (f32, f32) {
+        (
+            self.world_position.0 - self.pivot.0 * self.width as f32,
+            self.world_position.1 - self.pivot.1 * self.height as f32,
+        )
+    }
+
+    pub fn field(&self, identifier: &str) -> Option<&FieldValue> {
+        self.fields.get(identifier)
+    }
+}
+
+fn invalid(key: &str) -> LdtkError {
+    LdtkError::InvalidFormat(format!("'{}'", key))
+}
+
+fn string<'a>(json: &'a Json, key: &str) -> Option<&'a str> {
+    json.get(key).and_then(Json::as_str)
+}
+
+fn text(json: &Json, key: &str) -> String {
+    string(json, key).unwrap_or("").to_string()
+}
+
+fn array<'a>(json: &'a Json, key: &str) -> &'a [Json] {
+    json.get(key).and_then(Json::as_array).unwrap_or(&[])
+}
+
+fn number(json: &Json, key: &str) -> Result<f64, LdtkError> {
+    json.get(key)
+        .and_then(Json::as_f64)
+        .ok_or_else(|| invalid(key))
+}
+
+/// Reads a pair of numbers stored as a two element array
+fn pair(json: &Json, key: &str) -> Result<(f64, f64), LdtkError> {
+    match json.get(key).and_then(Json::as_array) {
+        Some([x, y]) => Ok((
+            x.as_f64().ok_or_else(|| invalid(key))?,
+            y.as_f64().ok_or_else(|| invalid(key))?,
+        )),
+        _ => Err(invalid(key)),
+    }
+}
+
+fn world_layout(json: &Json) -> WorldLayout {
+    match string(json, "worldLayout") {
+        Some("GridVania") => WorldLayout::GridVania,
+        Some("LinearHorizontal") => WorldLayout::LinearHorizontal,
+        Some("LinearVertical") => WorldLayout::LinearVertical,
+        _ => WorldLayout::Free,
+    }
+}
+
+fn layer_type(json: &Json, key: &str) -> Result<LayerType, LdtkError> {
+    match string(json, key) {
+        Some("IntGrid") => Ok(LayerType::IntGrid),
+        Some("Entities") => Ok(LayerType::Entities),
+        Some("Tiles") => Ok(LayerType::Tiles),
+        Some("AutoLayer") => Ok(LayerType::AutoLayer),
+        _ => Err(invalid(key)),
+    }
+}
+
+fn parse_tileset(json: &Json) -> Result<LdtkTileset, LdtkError> {
+    let mut custom_data = HashMap::new();
+    for entry in array(json, "customData") {
+        custom_data.insert(number(entry, "tileId")? as u32, text(entry, "data"));
+    }
+
+    Ok(LdtkTileset {
+        uid: number(json, "uid")? as i64,
+        identifier: text(json, "identifier"),
+        path: string(json, "relPath").map(String::from),
+        width: number(json, "pxWid")? as u32,
+        height: number(json, "pxHei")? as u32,
+        grid_size: number(json, "tileGridSize")? as u32,
+        spacing: number(json, "spacing").unwrap_or(0.0) as u32,
+        padding: number(json, "padding").unwrap_or(0.0) as u32,
+        custom_data,
+    })
+}
+
+fn parse_layer_def(json: &Json) -> Result<LdtkLayerDef, LdtkError> {
+    let mut int_grid_values = Vec::new();
+    for value in array(json, "intGridValues") {
+        int_grid_values.push(IntGridValue {
+            value: number(value, "value")? as i32,
+            identifier: text(value, "identifier"),
+            color: string(value, "color")
+                .and_then(|hex| Color::from_hex(hex).ok())
+                .unwrap_or(Color::WHITE),
+        });
+    }
+
+    Ok(LdtkLayerDef {
+        uid: number(json, "uid")? as i64,
+        identifier: text(json, "identifier"),
+        kind: layer_type(json, "type")?,
+        grid_size: number(json, "gridSize")? as u32,
+        int_grid_values,
+    })
+}
+
+fn parse_level(json: &Json) -> Result<LdtkLevel, LdtkError> {
+    let mut neighbors = Vec::new();
+    for neighbor in array(json, "__neighbours") {
+        let direction = match string(neighbor, "dir") {
+            Some("n") => Direction::North,
+            Some("s") => Direction::South,
+            Some("e") => Direction::East,
+            Some("w") => Direction::West,
+            Some("ne") => Direction::NorthEast,
+            Some("nw") => Direction::NorthWest,
+            Some("se") => Direction::SouthEast,
+            Some("sw") => Direction::SouthWest,
+            Some(">") => Direction::Above,
+            Some("<") => Direction::Below,
+            Some("o") => Direction::Overlap,
+            _ => continue,
+        };
+        neighbors.push(Neighbor {
+            level_iid: text(neighbor, "levelIid"),
+            direction,
+        });
+    }
+
+    let world_position = (
+        number(json, "worldX")? as i32,
+        number(json, "worldY")? as i32,
+    );
+
+    // The file lists layers from top to bottom
+    let mut layers = Vec::new();
+    for layer in array(json, "layerInstances").iter().rev() {
+        layers.push(parse_layer(layer, world_position)?);
+    }
+
+    Ok(LdtkLevel {
+        identifier: text(json, "identifier"),
+        iid: text(json, "iid"),
+        uid: number(json, "uid")? as i64,
+        world_position,
+        world_depth: number(json, "worldDepth").unwrap_or(0.0) as i32,
+        width: number(json, "pxWid")? as u32,
+        height: number(json, "pxHei")? as u32,
+        background: string(json, "__bgColor").and_then(|hex| Color::from_hex(hex).ok()),
+        fields: parse_fields(json),
+        layers,
+        neighbors,
+        external: string(json, "externalRelPath").map(String::from),
+    })
+}
+
+fn parse_layer(json: &Json, level_position: (i32, i32)) -> Result<LdtkLayer, LdtkError> {
+    let offset = (
+        number(json, "__pxTotalOffsetX").unwrap_or(0.0) as f32,
+        number(json, "__pxTotalOffsetY").unwrap_or(0.0) as f32,
+    );
+
+    let mut tiles = Vec::new();
+    // Auto-layer tiles come first, IntGrid layers can have them too
+    for tile in array(json, "autoLayerTiles")
+        .iter()
+        .chain(array(json, "gridTiles").iter())
+    {
+        let position = pair(tile, "px")?;
+        let source = pair(tile, "src")?;
+        let flips = number(tile, "f").unwrap_or(0.0) as u32;
+        tiles.push(LdtkTile {
+            position: (position.0 as i32, position.1 as i32),
+            source: (source.0 as i32, source.1 as i32),
+            id: number(tile, "t")? as u32,
+            flip_x: flips & 1 != 0,
+            flip_y: flips & 2 != 0,
+        });
+    }
+
+    let mut entities = Vec::new();
+    for entity in array(json, "entityInstances") {
+        let grid = pair(entity, "__grid")?;
+        let position = pair(entity, "px")?;
+        let pivot = pair(entity, "__pivot").unwrap_or((0.0, 0.0));
+        let world_position = (
+            level_position.0 as f32 + offset.0 + position.0 as f32,
+            level_position.1 as f32 + offset.1 + position.1 as f32,
+        );
+
+        entities.push(LdtkEntity {
+            identifier: text(entity, "__identifier"),
+            iid: text(entity, "iid"),
+            def_uid: number(entity, "defUid").unwrap_or(0.0) as i64,
+            grid: (grid.0 as i32, grid.1 as i32),
+            position: (position.0 as f32, position.1 as f32),
+            world_position,
+            pivot: (pivot.0 as f32, pivot.1 as f32),
+            width: number(entity, "width")? as u32,
+            height: number(entity, "height")? as u32,
+            tags: array(entity, "__tags")
+                .iter()
+                .filter_map(Json::as_str)
+                .map(String::from)
+                .collect(),
+            tile: entity.get("__tile").and_then(tile_rect),
+            fields: parse_fields(entity),
+        });
+    }
+
+    Ok(LdtkLayer {
+        identifier: text(json, "__identifier"),
+        iid: text(json, "iid"),
+        def_uid: number(json, "layerDefUid").unwrap_or(0.0) as i64,
+        kind: layer_type(json, "__type")?,
+        width: number(json, "__cWid")? as usize,
+        height: number(json, "__cHei")? as usize,
+        grid_size: number(json, "__gridSize")? as u32,
+        opacity: number(json, "__opacity").unwrap_or(1.0) as f32,
+        visible: json.get("visible").and_then(Json::as_bool).unwrap_or(true),
+        offset,
+        tileset: json.get("__tilesetDefUid").and_then(Json::as_i64),
+        int_grid: array(json, "intGridCsv")
+            .iter()
+            .map(|value| value.as_i64().unwrap_or(0) as i32)
+            .collect(),
+        tiles,
+        entities,
+    })
+}
+
+fn tile_rect(json: &Json) -> Option<TileRect> {
+    let value = |key: &str| json.get(key).and_then(Json::as_i64);
+    Some(TileRect {
+        tileset: value("tilesetUid")?,
+        source: Rect::new(
+            value("x")? as i32,
+            value("y")? as i32,
+            value("w")? as u32,
+            value("h")? as u32,
+        ),
+    })
+}
+
+fn parse_fields(json: &Json) -> Fields {
+    array(json, "fieldInstances")
+        .iter()
+        .map(|field| {
+            let value = field.get("__value").unwrap_or(&Json::Null);
+            (
+                text(field, "__identifier"),
+                field_value(string(field, "__type").unwrap_or(""), value),
+            )
+        })
+        .collect()
+}
+
+/// Converts a field value by its LDtk type name, like `Int`, `LocalEnum.Item` or `Array<Point>`
+fn field_value(kind: &str, value: &Json) -> FieldValue {
+    if let Some(item) = kind
+        .strip_prefix("Array<")
+        .and_then(|kind| kind.strip_suffix('>'))
+    {
+        return FieldValue::Array(
+            value
+                .as_array()
+                .unwrap_or(&[])
+                .iter()
+                .map(|value| field_value(item, value))
+                .collect(),
+        );
+    }
+
+    let converted = match kind {
+        "Int" => value.as_i64().map(FieldValue::Int),
+        "Float" => value.as_f64().map(FieldValue::Float),
+        "Bool" => value.as_bool().map(FieldValue::Bool),
+        "String" | "Multilines" => value
+            .as_str()
+            .map(|text| FieldValue::String(text.to_string())),
+        "FilePath" => value
+            .as_str()
+            .map(|path| FieldValue::FilePath(path.to_string())),
+        "Color" => value
+            .as_str()
+            .and_then(|hex| Color::from_hex(hex).ok())
+            .map(FieldValue::Color),
+        "Point" => {
+            let cell = |key: &str| value.get(key).and_then(Json::as_i64);
+            cell("cx")
+                .zip(cell("cy"))
+                .map(|(x, y)| FieldValue::Point(x as i32, y as i32))
+        }
+        "EntityRef" => value.as_object().map(|_| {
+            FieldValue::EntityRef(EntityRef {
+                entity_iid: text(value, "entityIid"),
+                layer_iid: text(value, "layerIid"),
+                level_iid: text(value, "levelIid"),
+                world_iid: text(value, "worldIid"),
+            })
+        }),
+        "Tile" => tile_rect(value).map(FieldValue::Tile),
+        _ if kind.starts_with("LocalEnum.") || kind.starts_with("ExternEnum.") => value
+            .as_str()
+            .map(|name| FieldValue::Enum(name.to_string())),
+        _ => None,
+    };
+
+    converted.unwrap_or(FieldValue::Null)
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod ldtk_tests {
+
+    use super::*;
+
+    const PROJECT: &str = r##"{
+        "jsonVersion": "1.5.3",
+        "bgColor": "#40465B",
+        "iid": "project",
+        "defs": {
+            "layers": [
+                { "uid": 1, "identifier": "Entities", "type": "Entities", "gridSize": 8 },
+                { "uid": 2, "identifier": "Ground", "type": "Tiles", "gridSize": 8 },
+                { "uid": 3, "identifier": "Collisions", "type": "IntGrid", "gridSize": 8,
+                  "intGridValues": [
+                    { "value": 1, "identifier": "wall", "color": "#FFFFFF" },
+                    { "value": 2, "identifier": "water", "color": "#0000FF" }
+                  ] }
+            ],
+            "tilesets": [
+                { "uid": 7, "identifier": "Cave", "relPath": "cave.png", "pxWid": 34,
+                  "pxHei": 26, "tileGridSize": 8, "spacing": 2, "padding": 1,
+                  "customData": [ { "tileId": 3, "data": "solid" } ] },
+                { "uid": 8, "identifier": "Icons", "relPath": null, "pxWid": 16,
+                  "pxHei": 16, "tileGridSize": 16 }
+            ]
+        },
+        "worlds": [
+            { "identifier": "Overworld", "iid": "w1", "worldLayout": "GridVania", "levels": [
+                { "identifier": "Start", "iid": "l1", "uid": 0, "worldX": 256, "worldY": 0,
+                  "pxWid": 24, "pxHei": 16, "__bgColor": "#000000",
+                  "fieldInstances": [
+                    { "__identifier": "title", "__type": "String", "__value": "First steps" },
+                    { "__identifier": "music", "__type": "FilePath", "__value": null }
+                  ],
+                  "__neighbours": [ { "levelIid": "l2", "dir": "e" }, { "levelIid": "l3", "dir": ">" } ],
+                  "layerInstances": [
+                    { "__identifier": "Entities", "__type": "Entities", "__cWid": 3, "__cHei": 2,
+                      "__gridSize": 8, "__opacity": 1, "__pxTotalOffsetX": 0, "__pxTotalOffsetY": 0,
+                      "iid": "e-layer", "layerDefUid": 1, "visible": true,
+                      "entityInstances": [
+                        { "__identifier": "Player", "__grid": [1, 1], "__pivot": [0.5, 1],
+                          "__tags": ["actor"], "__tile": { "tilesetUid": 8, "x": 0, "y": 0, "w": 16, "h": 16 },
+                          "iid": "player", "width": 8, "height": 16, "defUid": 10, "px": [12, 16],
+                          "fieldInstances": [
+                            { "__identifier": "health", "__type": "Int", "__value": 3 },
+                            { "__identifier": "speed", "__type": "Float", "__value": 1.5 },
+                            { "__identifier": "flying", "__type": "Bool", "__value": false },
+                            { "__identifier": "tint", "__type": "Color", "__value": "#FF8000" },
+                            { "__identifier": "item", "__type": "LocalEnum.Item", "__value": "Sword" },
+                            { "__identifier": "path", "__type": "Array<Point>",
+                              "__value": [ { "cx": 1, "cy": 0 }, { "cx": 2, "cy": 1 } ] },
+                            { "__identifier": "target", "__type": "EntityRef",
+                              "__value": { "entityIid": "door", "layerIid": "e-layer",
+                                           "levelIid": "l2", "worldIid": "w1" } },
+                            { "__identifier": "note", "__type": "Multilines", "__value": null }
+                          ] },
+                        { "__identifier": "Coin", "__grid": [0, 0], "__pivot": [0, 0], "__tags": [],
+                          "__tile": null, "iid": "coin", "width": 8, "height": 8, "defUid": 11,
+                          "px": [0, 0], "fieldInstances": [] }
+                      ] },
+                    { "__identifier": "Ground", "__type": "Tiles", "__cWid": 3, "__cHei": 2,
+                      "__gridSize": 8, "__opacity": 0.5, "__pxTotalOffsetX": 2, "__pxTotalOffsetY": 0,
+                      "__tilesetDefUid": 7, "iid": "g-layer", "layerDefUid": 2, "visible": false,
+                      "gridTiles": [
+                        { "px": [0, 8], "src": [1, 1], "f": 1, "t": 0 },
+                        { "px": [8, 8], "src": [11, 1], "f": 3, "t": 1 },
+                        { "px": [8, 8], "src": [21, 1], "f": 0, "t": 2 }
+                      ] },
+                    { "__identifier": "Collisions", "__type": "IntGrid", "__cWid": 3, "__cHei": 2,
+                      "__gridSize": 8, "__opacity": 1, "__pxTotalOffsetX": 0, "__pxTotalOffsetY": 0,
+                      "__tilesetDefUid": 7, "iid": "c-layer", "layerDefUid": 3, "visible": true,
+                      "intGridCsv": [0, 0, 0, 1, 2, 1],
+                      "autoLayerTiles": [ { "px": [16, 0], "src": [31, 1], "f": 2, "t": 3 } ] }
+                  ] }
+            ] },
+            { "identifier": "Underworld", "iid": "w2", "worldLayout": null, "levels": [
+                { "identifier": "Depths", "iid": "l3", "uid": 1, "worldX": 0, "worldY": 0,
+                  "worldDepth": 1, "pxWid": 8, "pxHei": 8, "layerInstances": [] }
+            ] }
+        ]
+    }"##;
+
+    #[test]
+    fn parse_project() {
+        let project = LdtkProject::parse(PROJECT).unwrap();
+
+        assert_eq!(project.background, Some(Color::from_rgb(0x40, 0x46, 0x5b)));
+        assert_eq!(project.tilesets.len(), 2);
+        let cave = project.tileset(7).unwrap();
+        assert_eq!((cave.grid_size, cave.spacing, cave.padding), (8, 2, 1));
+        assert_eq!(cave.custom_data.get(&3).map(String::as_str), Some("solid"));
+        assert_eq!(project.tileset(8).unwrap().path, None);
+
+        let worlds: Vec<(&str, WorldLayout)> = project
+            .worlds
+            .iter()
+            .map(|world| (world.identifier.as_str(), world.layout))
+            .collect();
+        assert_eq!(
+            worlds,
+            [
+                ("Overworld", WorldLayout::GridVania),
+                ("Underworld", WorldLayout::Free)
+            ]
+        );
+        assert_eq!(project.levels().count(), 2);
+        assert_eq!(project.level_by_iid("l3").unwrap().world_depth, 1);
+
+        let level = project.level("Start").unwrap();
+        assert_eq!(level.world_position, (256, 0));
+        assert_eq!((level.width, level.height), (24, 16));
+        assert_eq!(level.background, Some(Color::BLACK));
+        assert_eq!(
+            level.fields.get("title"),
+            Some(&FieldValue::String("First steps".to_string()))
+        );
+        assert_eq!(level.fields.get("music"), Some(&FieldValue::Null));
+        assert_eq!(
+            level.neighbors_to(Direction::East).collect::<Vec<_>>(),
+            ["l2"]
+        );
+        assert_eq!(
+            level.neighbors_to(Direction::Above).collect::<Vec<_>>(),
+            ["l3"]
+        );
+
+        // Layers go from bottom to top
+        let names: Vec<&str> = level
+            .layers
+            .iter()
+            .map(|layer| layer.identifier.as_str())
+            .collect();
+        assert_eq!(names, ["Collisions", "Ground", "Entities"]);
+
+        let collisions = level.layer("Collisions").unwrap();
+        assert_eq!(collisions.kind, LayerType::IntGrid);
+        assert_eq!(collisions.int_grid_at(1, 1), 2);
+        assert_eq!(collisions.int_grid_at(3, 0), 0);
+        let water = project.int_grid_value(collisions, 2).unwrap();
+        assert_eq!(water.identifier, "water");
+        assert_eq!(water.color, Color::from_rgb(0, 0, 255));
+
+        let ground = level.layer("Ground").unwrap();
+        assert_eq!(
+            (ground.opacity, ground.visible, ground.offset),
+            (0.5, false, (2.0, 0.0))
+        );
+        assert_eq!(
+            ground.tiles[1],
+            LdtkTile {
+                position: (8, 8),
+                source: (11, 1),
+                id: 1,
+                flip_x: true,
+                flip_y: true,
+            }
+        );
+
+        let player = level
+            .layer("Entities")
+            .unwrap()
+            .entities_named("Player")
+            .next()
+            .unwrap();
+        assert_eq!(player.grid, (1, 1));
+        assert_eq!(player.world_position, (268.0, 16.0));
+        assert_eq!(player.world_bounds_origin(), (264.0, 0.0));
+        assert_eq!(player.tags, ["actor"]);
+        assert_eq!(player.tile.unwrap().source, Rect::new(0, 0, 16, 16));
+        assert_eq!(player.field("health"), Some(&FieldValue::Int(3)));
+        assert_eq!(player.field("speed"), Some(&FieldValue::Float(1.5)));
+        assert_eq!(player.field("flying"), Some(&FieldValue::Bool(false)));
+        assert_eq!(
+            player.field("tint"),
+            Some(&FieldValue::Color(Color::from_rgb(255, 128, 0)))
+        );
+        assert_eq!(
+            player.field("item"),
+            Some(&FieldValue::Enum("Sword".to_string()))
+        );
+        assert_eq!(
+            player.field("path"),
+            Some(&FieldValue::Array(vec![
+                FieldValue::Point(1, 0),
+                FieldValue::Point(2, 1)
+            ]))
+        );
+        assert!(matches!(
+            player.field("target"),
+            Some(FieldValue::EntityRef(target)) if target.entity_iid == "door" && target.level_iid == "l2"
+        ));
+        assert_eq!(player.field("note"), Some(&FieldValue::Null));
+    }
+
+    #[test]
+    fn build_tilemap_and_spawn() {
+        let project = LdtkProject::parse(PROJECT).unwrap();
+        let textures = vec![Texture::new(34, 26), Texture::new(16, 16)];
+        let level = project.level("Start").unwrap();
+        let tilemap = project.build_tilemap(level, &textures);
+
+        assert_eq!((tilemap.width(), tilemap.height()), (3, 2));
+        assert_eq!(tilemap.tilesets().len(), 1);
+        assert_eq!(tilemap.tileset(0).unwrap().count(), 6);
+
+        // The stacked tile of "Ground" spills into a second layer of the same name
+        let names: Vec<&str> = tilemap
+            .layers()
+            .iter()
+            .map(|layer| layer.name.as_str())
+            .collect();
+        assert_eq!(names, ["Collisions", "Ground", "Ground"]);
+
+        let collisions = tilemap.layer(0).unwrap();
+        let tile = collisions.get(2, 0).unwrap();
+        assert_eq!((tile.index, tile.flip_x, tile.flip_y), (3, false, true));
+
+        let ground = tilemap.layer(1).unwrap();
+        assert_eq!((ground.visible, ground.opacity), (false, 0.5));
+        assert_eq!(ground.get(0, 1).map(|tile| tile.index), Some(0));
+        assert_eq!(ground.get(1, 1).map(|tile| tile.index), Some(1));
+        let stacked = tilemap.layer(2).unwrap();
+        assert_eq!(stacked.get(1, 1).map(|tile| tile.index), Some(2));
+        assert_eq!(stacked.get(0, 1), None);
+
+        // Tiles far outside the level are left out
+        let mut far = level.clone();
+        for layer in far.layers.iter_mut() {
+            if let Some(&tile) = layer.tiles.first() {
+                for &position in [(i32::MAX, i32::MAX), (i32::MIN, i32::MIN)].iter() {
+                    layer.tiles.push(LdtkTile { position, ..tile });
+                }
+            }
+        }
+        let far = project.build_tilemap(&far, &textures);
+        assert_eq!(far.layers().len(), tilemap.layers().len());
+
+        let mut spawned = Vec::new();
+        level.spawn_entities(|entity, layer| {
+            spawned.push((
+                entity.identifier.clone(),
+                layer.identifier.clone(),
+                entity.world_position,
+            ))
+        });
+        assert_eq!(
+            spawned,
+            [
+                ("Player".to_string(), "Entities".to_string(), (268.0, 16.0)),
+                ("Coin".to_string(), "Entities".to_string(), (256.0, 0.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn load_external_levels() {
+        let directory = std::env::temp_dir().join(format!("walrust_ldtk_{}", std::process::id()));
+        std::fs::create_dir_all(directory.join("levels")).unwrap();
+
+        // Projects without worlds store their levels at the top
+        let project = r#"{
+            "worldLayout": "LinearHorizontal", "externalLevels": true,
+            "defs": { "layers": [], "tilesets": [
+                { "uid": 1, "identifier": "Tiles", "relPath": "art/tiles.png",
+                  "pxWid": 16, "pxHei": 16, "tileGridSize": 16 }
+            ] },
+            "worlds": [],
+            "levels": [ { "identifier": "Outside", "iid": "a", "uid": 0, "worldX": 0, "worldY": 0,
+                          "pxWid": 16, "pxHei": 16, "layerInstances": null,
+                          "externalRelPath": "levels/Outside.ldtkl" } ]
+        }"#;
+        let level = r#"{ "identifier": "Outside", "iid": "a", "uid": 0, "worldX": 0, "worldY": 0,
+            "pxWid": 16, "pxHei": 16, "layerInstances": [
+                { "__identifier": "Floor", "__type": "IntGrid", "__cWid": 1, "__cHei": 1,
+                  "__gridSize": 16, "intGridCsv": [4] }
+            ] }"#;
+        std::fs::write(directory.join("game.ldtk"), project).unwrap();
+        std::fs::write(directory.join("levels/Outside.ldtkl"), level).unwrap();
+
+        let path = directory.join("game.ldtk");
+        let loaded = LdtkProject::load(&path.to_string_lossy());
+        std::fs::remove_dir_all(&directory).unwrap();
+        let project = loaded.unwrap();
+
+        assert_eq!(project.worlds.len(), 1);
+        assert_eq!(project.worlds[0].layout, WorldLayout::LinearHorizontal);
+        let image = directory
+            .join("art/tiles.png")
+            .to_string_lossy()
+            .into_owned();
+        assert_eq!(project.tilesets[0].path, Some(image));
+
+        let level = project.level("Outside").unwrap();
+        assert_eq!(level.external.as_deref(), Some("levels/Outside.ldtkl"));
+        assert_eq!(level.layer("Floor").unwrap().int_grid_at(0, 0), 4);
+
+        assert!(matches!(
+            LdtkProject::parse(r#"{ "levels": [] }"#),
+            Err(LdtkError::InvalidFormat(_))
+        ));
+        assert!(matches!(LdtkProject::parse("{"), Err(LdtkError::Parse(_))));
+    }
+}
@@ -1,5 +1,27 @@
+pub mod animation;
+pub mod aseprite;
+pub mod bitmap_font;
+pub mod blend;
+pub mod camera;
 pub mod color;
+pub mod color_f;
+pub mod font_cache;
+pub mod gl_renderer;
+pub mod indexed_texture;
+pub mod json;
+pub mod ldtk;
+pub mod palette;
 pub mod program;
+pub mod rasterizer;
+pub mod renderer;
 pub mod shader;
+#[cfg(test)]
+pub mod snapshot;
+pub mod software_renderer;
 pub mod sprite;
+pub mod text;
 pub mod texture;
+pub mod tiled;
+pub mod tilemap;
+pub mod truetype;
+pub mod xml;
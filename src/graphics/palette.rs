@@ -0,0 +1,182 @@
+#![allow(dead_code)]
+
+use super::color::Color;
+
+/// Largest number of colors a palette can hold, indices are stored as `u8`
+pub const MAX_COLORS: usize = 256;
+
+/// List of colors referenced by index from an `IndexedTexture`.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Palette {
+    colors: Vec<Color>,
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl Palette {
+    /// Creates an empty `Palette`
+    pub fn new() -> Palette {
+        Palette { colors: Vec::new() }
+    }
+
+    /// Creates a `Palette` from a list of colors, anything past `MAX_COLORS` is dropped
+    pub fn from_colors(colors: &[Color]) -> Palette {
+        let len = colors.len().min(MAX_COLORS);
+        Palette {
+            colors: colors[..len].to_vec(),
+        }
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl Palette {
+    /// Returns the color at an index, indices past the end are `Color::CLEAR`
+    pub fn get(&self, index: u8) -> Color {
+        self.colors
+            .get(index as usize)
+            .copied()
+            .unwrap_or(Color::CLEAR)
+    }
+
+    /// Replaces the color at an index, does nothing if the index is past the end
+    pub fn set(&mut self, index: u8, color: Color) {
+        if let Some(entry) = self.colors.get_mut(index as usize) {
+            *entry = color;
+        }
+    }
+
+    /// Appends a color and returns its index, `None` if the palette is full
+    pub fn push(&mut self, color: Color) -> Option<u8> {
+        if self.colors.len() >= MAX_COLORS {
+            return None;
+        }
+
+        self.colors.push(color);
+        Some((self.colors.len() - 1) as u8)
+    }
+
+    pub fn len(&self) -> usize {
+        self.colors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.colors.is_empty()
+    }
+
+    pub fn colors(&self) -> &[Color] {
+        &self.colors
+    }
+
+    /// Returns the colors as laid out in the lookup texture, always `MAX_COLORS` entries
+    /// so every index has a color, `Color::CLEAR` past the end
+    pub fn lookup_colors(&self) -> Vec<Color> {
+        let mut colors = self.colors.clone();
+        colors.resize(MAX_COLORS, Color::CLEAR);
+        colors
+    }
+
+    /// Returns a copy with every occurrence of a color replaced, for team and enemy recolors
+    pub fn swapped(&self, from: Color, to: Color) -> Palette {
+        Palette {
+            colors: self
+                .colors
+                .iter()
+                .map(|&color| if color == from { to } else { color })
+                .collect(),
+        }
+    }
+
+    /// Returns the index of the closest color, 0 for an empty palette
+    pub fn nearest(&self, color: Color) -> u8 {
+        let mut best = 0;
+        let mut best_distance = u32::MAX;
+
+        for (index, candidate) in self.colors.iter().enumerate() {
+            let distance = distance(color, *candidate);
+            if distance < best_distance {
+                best = index;
+                best_distance = distance;
+                if distance == 0 {
+                    break;
+                }
+            }
+        }
+
+        best as u8
+    }
+}
+
+/// Squared distance between two colors including alpha
+fn distance(a: Color, b: Color) -> u32 {
+    let channel = |a: u8, b: u8| {
+        let d = a as i32 - b as i32;
+        (d * d) as u32
+    };
+
+    channel(a.red, b.red)
+        + channel(a.green, b.green)
+        + channel(a.blue, b.blue)
+        + channel(a.alpha, b.alpha)
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod palette_tests {
+
+    use super::*;
+
+    #[test]
+    fn out_of_range_is_clear() {
+        let mut palette = Palette::from_colors(&[Color::RED, Color::BLUE]);
+        assert_eq!(palette.get(1), Color::BLUE);
+        assert_eq!(palette.get(2), Color::CLEAR);
+        assert_eq!(palette.get(255), Color::CLEAR);
+
+        palette.set(5, Color::WHITE);
+        assert_eq!(palette.len(), 2);
+        assert!(Palette::new().is_empty());
+        assert_eq!(Palette::new().get(0), Color::CLEAR);
+    }
+
+    #[test]
+    fn holds_at_most_max_colors() {
+        let mut palette = Palette::from_colors(&[Color::WHITE; 300]);
+        assert_eq!(palette.len(), MAX_COLORS);
+        assert_eq!(palette.push(Color::RED), None);
+
+        let mut palette = Palette::new();
+        assert_eq!(palette.push(Color::RED), Some(0));
+        assert_eq!(palette.push(Color::BLUE), Some(1));
+    }
+
+    #[test]
+    fn lookup_texture_layout() {
+        let palette = Palette::from_colors(&[Color::RED, Color::BLUE]);
+        let colors = palette.lookup_colors();
+        assert_eq!(colors.len(), MAX_COLORS);
+        assert_eq!(&colors[..2], &[Color::RED, Color::BLUE]);
+        assert!(colors[2..].iter().all(|&color| color == Color::CLEAR));
+        assert_eq!(
+            Palette::new().lookup_colors(),
+            vec![Color::CLEAR; MAX_COLORS]
+        );
+    }
+
+    #[test]
+    fn nearest_and_swapped() {
+        let palette = Palette::from_colors(&[Color::BLACK, Color::WHITE, Color::RED]);
+        assert_eq!(palette.nearest(Color::from_rgb(250, 10, 10)), 2);
+        assert_eq!(Palette::new().nearest(Color::RED), 0);
+
+        let swapped = palette.swapped(Color::RED, Color::BLUE);
+        assert_eq!(swapped.colors(), &[Color::BLACK, Color::WHITE, Color::BLUE]);
+    }
+}
@@ -0,0 +1,278 @@
+#![allow(dead_code)]
+
+/// Grayscale coverage image produced by the `Rasterizer`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Bitmap {
+    pub width: usize,
+    pub height: usize,
+    /// Values from 0 to 1, row by row
+    pub data: Vec<f32>,
+}
+
+/// Scanline rasterizer for closed outlines made of lines and quadratic curves.
+/// Coverage is computed exactly from the signed area under each edge, so the result is
+/// antialiased without supersampling. Outlines are filled with the non-zero winding rule.
+pub struct Rasterizer {
+    width: usize,
+    height: usize,
+    accumulation: Vec<f32>,
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl Rasterizer {
+    pub fn new(width: usize, height: usize) -> Rasterizer {
+        Rasterizer {
+            width,
+            height,
+            // One extra cell per row end so edges at the right border have somewhere to go
+            accumulation: vec![0.0; width * height + 2],
+        }
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl Rasterizer {
+    /// Adds a straight edge, coordinates are in pixels with y pointing down
+    pub fn line(&mut self, p0: (f32, f32), p1: (f32, f32)) {
+        if (p0.1 - p1.1).abs() <= f32::EPSILON {
+            return;
+        }
+
+        let (direction, p0, p1) = if p0.1 < p1.1 {
+            (1.0, p0, p1)
+        } else {
+            (-1.0, p1, p0)
+        };
+
+        let max_x = self.width as f32;
+        let p0 = (p0.0.clamp(0.0, max_x), p0.1);
+        let p1 = (p1.0.clamp(0.0, max_x), p1.1);
+        let dxdy = (p1.0 - p0.0) / (p1.1 - p0.1);
+
+        let mut x = p0.0;
+        if p0.1 < 0.0 {
+            x -= p0.1 * dxdy;
+        }
+
+        let start = p0.1.max(0.0) as usize;
+        let end = self.height.min(p1.1.ceil().max(0.0) as usize);
+        for y in start..end {
+            let row = y * self.width;
+            let dy = ((y + 1) as f32).min(p1.1) - (y as f32).max(p0.1);
+            let next_x = x + dxdy * dy;
+            let d = dy * direction;
+
+            let (x0, x1) = if x < next_x { (x, next_x) } else { (next_x, x) };
+            let x0_floor = x0.floor();
+            let x0_index = x0_floor as usize;
+            let x1_ceil = x1.ceil();
+            let x1_index = x1_ceil as usize;
+
+            if x1_index <= x0_index + 1 {
+                // The edge stays within one pixel column on this row
+                let middle = 0.5 * (x + next_x) - x0_floor;
+                self.accumulation[row + x0_index] += d - d * middle;
+                self.accumulation[row + x0_index + 1] += d * middle;
+            } else {
+                let s = (x1 - x0).recip();
+                let x0_fraction = x0 - x0_floor;
+                let first = 0.5 * s * (1.0 - x0_fraction) * (1.0 - x0_fraction);
+                let x1_fraction = x1 - x1_ceil + 1.0;
+                let last = 0.5 * s * x1_fraction * x1_fraction;
+
+                self.accumulation[row + x0_index] += d * first;
+                if x1_index == x0_index + 2 {
+                    self.accumulation[row + x0_index + 1] += d * (1.0 - first - last);
+                } else {
+                    let second = s * (1.5 - x0_fraction);
+                    self.accumulation[row + x0_index + 1] += d * (second - first);
+                    for xi in x0_index + 2..x1_index - 1 {
+                        self.accumulation[row + xi] += d * s;
+                    }
+                    let covered = second + (x1_index - x0_index - 3) as f32 * s;
+                    self.accumulation[row + x1_index - 1] += d * (1.0 - covered - last);
+                }
+                self.accumulation[row + x1_index] += d * last;
+            }
+
+            x = next_x;
+        }
+    }
+
+    /// Adds a quadratic curve, flattened into lines
+    pub fn quad(&mut self, p0: (f32, f32), control: (f32, f32), p2: (f32, f32)) {
+        let dx = p0.0 - 2.0 * control.0 + p2.0;
+        let dy = p0.1 - 2.0 * control.1 + p2.1;
+        let deviation = dx * dx + dy * dy;
+        if deviation < 0.333 {
+            self.line(p0, p2);
+            return;
+        }
+
+        let segments = 1 + (3.0 * deviation).sqrt().sqrt().floor() as usize;
+        let mut previous = p0;
+        for i in 1..=segments {
+            let t = i as f32 / segments as f32;
+            let u = 1.0 - t;
+            let point = (
+                u * u * p0.0 + 2.0 * u * t * control.0 + t * t * p2.0,
+                u * u * p0.1 + 2.0 * u * t * control.1 + t * t * p2.1,
+            );
+            self.line(previous, point);
+            previous = point;
+        }
+    }
+
+    /// Returns the coverage of every pixel
+    pub fn finish(self) -> Bitmap {
+        let mut data = Vec::with_capacity(self.width * self.height);
+        let mut sum = 0.0;
+        for value in self.accumulation.iter().take(self.width * self.height) {
+            sum += value;
+            data.push(sum.abs().min(1.0));
+        }
+
+        Bitmap {
+            width: self.width,
+            height: self.height,
+            data,
+        }
+    }
+}
+
+impl Bitmap {
+    pub fn get(&self, x: usize, y: usize) -> f32 {
+        if x < self.width && y < self.height {
+            self.data[y * self.width + x]
+        } else {
+            0.0
+        }
+    }
+
+    /// Converts coverage into a signed distance field.
+    /// 0.5 lies on the outline and values fall off to 0 and 1 at `spread` pixels outside and
+    /// inside. The bitmap needs `spread` pixels of empty border for the field to fade out.
+    pub fn to_distance_field(&self, spread: usize) -> Bitmap {
+        let spread = spread.max(1);
+        let inside = |x: isize, y: isize| {
+            x >= 0
+                && y >= 0
+                && (x as usize) < self.width
+                && (y as usize) < self.height
+                && self.data[y as usize * self.width + x as usize] >= 0.5
+        };
+
+        let mut data = Vec::with_capacity(self.width * self.height);
+        for y in 0..self.height as isize {
+            for x in 0..self.width as isize {
+                let here = inside(x, y);
+
+                // Nearest pixel of the opposite kind within the spread
+                let mut nearest = (spread * spread) as f32;
+                let radius = spread as isize;
+                for oy in -radius..=radius {
+                    for ox in -radius..=radius {
+                        if inside(x + ox, y + oy) != here {
+                            nearest = nearest.min((ox * ox + oy * oy) as f32);
+                        }
+                    }
+                }
+
+                // Edges lie halfway between two pixel centers
+                let distance = (nearest.sqrt() - 0.5).max(0.0);
+                let signed = if here { distance } else { -distance };
+                data.push((0.5 + signed / (2.0 * spread as f32)).clamp(0.0, 1.0));
+            }
+        }
+
+        Bitmap {
+            width: self.width,
+            height: self.height,
+            data,
+        }
+    }
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod rasterizer_tests {
+
+    use super::*;
+
+    fn square(rasterizer: &mut Rasterizer, x0: f32, y0: f32, x1: f32, y1: f32) {
+        rasterizer.line((x0, y0), (x1, y0));
+        rasterizer.line((x1, y0), (x1, y1));
+        rasterizer.line((x1, y1), (x0, y1));
+        rasterizer.line((x0, y1), (x0, y0));
+    }
+
+    #[test]
+    fn pixel_aligned_square() {
+        let mut rasterizer = Rasterizer::new(4, 4);
+        square(&mut rasterizer, 1.0, 1.0, 3.0, 3.0);
+        let bitmap = rasterizer.finish();
+
+        let expected = [
+            0.0, 0.0, 0.0, 0.0, //
+            0.0, 1.0, 1.0, 0.0, //
+            0.0, 1.0, 1.0, 0.0, //
+            0.0, 0.0, 0.0, 0.0,
+        ];
+        assert_eq!(bitmap.data, expected);
+    }
+
+    #[test]
+    fn partial_coverage() {
+        let mut rasterizer = Rasterizer::new(3, 1);
+        square(&mut rasterizer, 0.5, 0.0, 2.25, 1.0);
+        let bitmap = rasterizer.finish();
+        assert_eq!(bitmap.data, [0.5, 1.0, 0.25]);
+
+        // A triangle covering half of a pixel diagonally
+        let mut rasterizer = Rasterizer::new(1, 1);
+        rasterizer.line((0.0, 0.0), (1.0, 1.0));
+        rasterizer.line((1.0, 1.0), (0.0, 1.0));
+        rasterizer.line((0.0, 1.0), (0.0, 0.0));
+        assert!((rasterizer.finish().data[0] - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn curve_area() {
+        // Four parabolic arcs with their control points on the corners of the bounding square
+        // enclose 10/3 r^2
+        let mut rasterizer = Rasterizer::new(20, 20);
+        let (c, r) = (10.0, 8.0);
+        let points = [(c + r, c), (c, c + r), (c - r, c), (c, c - r), (c + r, c)];
+        for pair in points.windows(2) {
+            let (p0, p2) = (pair[0], pair[1]);
+            let control = (p0.0 + p2.0 - c, p0.1 + p2.1 - c);
+            rasterizer.quad(p0, control, p2);
+        }
+
+        let area: f32 = rasterizer.finish().data.iter().sum();
+        // Flattening cuts slightly into the curves
+        let expected = 10.0 / 3.0 * r * r;
+        assert!(area < expected && area > expected * 0.98, "area {}", area);
+    }
+
+    #[test]
+    fn distance_field() {
+        let mut rasterizer = Rasterizer::new(16, 16);
+        square(&mut rasterizer, 4.0, 4.0, 12.0, 12.0);
+        let field = rasterizer.finish().to_distance_field(4);
+
+        // Outside, just outside, just inside and in the middle of the square
+        let row: Vec<f32> = [0, 3, 4, 7].iter().map(|&x| field.get(x, 8)).collect();
+        assert_eq!(row, [0.0625, 0.4375, 0.5625, 0.9375]);
+        assert_eq!(field.get(8, 0), 0.0625);
+    }
+}
@@ -0,0 +1,351 @@
+#![allow(dead_code)]
+
+use super::{
+    blend::BlendMode,
+    camera::Camera,
+    color::Color,
+    sprite::{IndexedSprite, Sprite},
+    text::{layout_text, Font, PositionedGlyph, TextStyle},
+    tilemap::Tilemap,
+};
+
+/// Sampling used when a sprite is scaled or rotated.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Filter {
+    Nearest,
+    Bilinear,
+}
+
+/// Rectangle in whole pixels, used for source regions and clipping.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// How a sprite is placed on the target.
+/// The sprite is scaled and rotated around `origin`, which is then placed at `position`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct DrawParams {
+    pub position: (f32, f32),
+    pub origin: (f32, f32),
+    pub scale: (f32, f32),
+    /// Clockwise rotation in radians, y points down
+    pub rotation: f32,
+    pub tint: Color,
+    /// Region of the texture to draw, `None` draws the whole texture
+    pub source: Option<Rect>,
+    pub filter: Filter,
+    pub blend: BlendMode,
+    /// Treats the texture alpha as a signed distance field, see `DistanceField`
+    pub distance_field: Option<DistanceField>,
+}
+
+/// Turns the alpha of a signed distance field texture into coverage.
+/// Alpha values above `edge` are inside the shape.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct DistanceField {
+    /// Lower values grow the shape, which is how outlines are drawn
+    pub edge: f32,
+    /// Half of the alpha range the edge is antialiased over
+    pub smoothing: f32,
+}
+
+/// Drawing API shared by the OpenGL and software renderers.
+/// Coordinates are in pixels with the origin at the top left of the target.
+pub trait Renderer {
+    /// Returns the size of the render target in pixels
+    fn size(&self) -> (u32, u32);
+
+    /// Fills the whole target with a color, ignoring the clip rectangle
+    fn clear(&mut self, color: Color);
+
+    /// Restricts drawing to a rectangle, `None` allows the whole target
+    fn set_clip(&mut self, clip: Option<Rect>);
+
+    fn draw_sprite(&mut self, sprite: &Sprite, params: &DrawParams);
+
+    /// Draws an indexed sprite through its palette, indices are always sampled nearest
+    fn draw_indexed_sprite(&mut self, sprite: &IndexedSprite, params: &DrawParams);
+
+    /// Draws text with the top left of the first line at `position`, see `TextStyle`
+    fn draw_text(&mut self, font: &dyn Font, text: &str, position: (f32, f32), style: &TextStyle) {
+        let glyphs = layout_text(font, text, position, style);
+
+        // One step of the field per target pixel, the edge is smoothed over about a pixel
+        let step = font
+            .distance_field()
+            .map(|spread| 1.0 / (2.0 * spread * style.scale));
+        let field = step.map(|step| DistanceField {
+            edge: 0.5,
+            smoothing: step / 2.0,
+        });
+
+        if let Some(shadow) = style.shadow {
+            draw_glyphs(
+                self,
+                font,
+                &glyphs,
+                style.scale,
+                shadow.offset,
+                shadow.color,
+                field,
+            );
+        }
+
+        if let (Some(outline), Some(step), Some(field)) = (style.outline, step, field) {
+            let grown = DistanceField {
+                edge: (0.5 - outline.width * step).max(0.0),
+                ..field
+            };
+            draw_glyphs(
+                self,
+                font,
+                &glyphs,
+                style.scale,
+                (0.0, 0.0),
+                outline.color,
+                Some(grown),
+            );
+        }
+
+        draw_glyphs(
+            self,
+            font,
+            &glyphs,
+            style.scale,
+            (0.0, 0.0),
+            style.color,
+            field,
+        );
+    }
+
+    /// Draws the visible layers of a tilemap as seen through a camera, tiles outside are skipped
+    fn draw_tilemap(&mut self, tilemap: &Tilemap, camera: &Camera) {
+        for layer in tilemap.layers().iter().filter(|layer| layer.visible) {
+            let alpha = (layer.opacity.clamp(0.0, 1.0) * 255.0).round() as u8;
+            let (columns, rows) = tilemap.visible_cells(layer, camera);
+
+            for y in rows {
+                for x in columns.clone() {
+                    let tile = match layer.get(x, y) {
+                        Some(tile) => tile,
+                        None => continue,
+                    };
+                    let tileset = match tilemap.tileset(tile.tileset) {
+                        Some(tileset) => tileset,
+                        None => continue,
+                    };
+                    let shown = tileset.frame_at(tile.index, tilemap.time());
+                    let source = match tileset.tile_rect(shown) {
+                        Some(source) => source,
+                        None => continue,
+                    };
+
+                    // Flips happen around the tile center
+                    let (left, top) = tilemap.tile_position(x, y, &tile);
+                    let origin = (source.width as f32 / 2.0, source.height as f32 / 2.0);
+                    let center = (
+                        left + layer.offset.0 + origin.0,
+                        top + layer.offset.1 + origin.1,
+                    );
+                    let (rotation, scale) = tile.rotation_and_scale();
+
+                    let params = DrawParams {
+                        position: camera.world_to_screen(center),
+                        origin,
+                        scale: (scale.0 * camera.zoom, scale.1 * camera.zoom),
+                        rotation,
+                        tint: Color::WHITE.with_alpha(alpha),
+                        source: Some(source),
+                        ..DrawParams::default()
+                    };
+                    self.draw_sprite(tileset.sprite(), &params);
+                }
+            }
+        }
+    }
+}
+
+/// Draws laid out glyphs moved by an offset in one color
+fn draw_glyphs<R: Renderer + ?Sized>(
+    renderer: &mut R,
+    font: &dyn Font,
+    glyphs: &[PositionedGlyph],
+    scale: f32,
+    offset: (f32, f32),
+    color: Color,
+    field: Option<DistanceField>,
+) {
+    for placed in glyphs.iter() {
+        if let Some(page) = font.page(placed.glyph.page) {
+            let params = DrawParams {
+                position: (placed.position.0 + offset.0, placed.position.1 + offset.1),
+                scale: (scale, scale),
+                tint: color,
+                source: Some(placed.glyph.source),
+                filter: if field.is_some() {
+                    Filter::Bilinear
+                } else {
+                    Filter::Nearest
+                },
+                distance_field: field,
+                ..DrawParams::default()
+            };
+            renderer.draw_sprite(page, &params);
+        }
+    }
+}
+
+/// Affine transform from sprite pixels to target pixels:
+/// `x' = a * x + c * y + tx` and `y' = b * x + d * y + ty`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub(crate) struct Affine {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    pub d: f32,
+    pub tx: f32,
+    pub ty: f32,
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl Rect {
+    pub const fn new(x: i32, y: i32, width: u32, height: u32) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Returns the overlapping part of two rectangles
+    pub fn intersect(&self, other: &Rect) -> Option<Rect> {
+        // Edges are found in i64 so rectangles reaching past i32::MAX don't overflow
+        let left = self.x.max(other.x) as i64;
+        let top = self.y.max(other.y) as i64;
+        let right = (self.x as i64 + self.width as i64).min(other.x as i64 + other.width as i64);
+        let bottom = (self.y as i64 + self.height as i64).min(other.y as i64 + other.height as i64);
+
+        if left < right && top < bottom {
+            Some(Rect::new(
+                left as i32,
+                top as i32,
+                (right - left) as u32,
+                (bottom - top) as u32,
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+impl DrawParams {
+    /// Draws the sprite unscaled with its top left corner at a position
+    pub fn at(x: f32, y: f32) -> DrawParams {
+        DrawParams {
+            position: (x, y),
+            ..DrawParams::default()
+        }
+    }
+
+    /// Returns the region of a texture of width and height that is drawn, clamped to the texture
+    pub fn source_rect(&self, width: usize, height: usize) -> Rect {
+        let full = Rect::new(0, 0, width as u32, height as u32);
+        match self.source {
+            Some(source) => source.intersect(&full).unwrap_or_default(),
+            None => full,
+        }
+    }
+
+    pub(crate) fn transform(&self) -> Affine {
+        let (sin, cos) = self.rotation.sin_cos();
+        let (sx, sy) = self.scale;
+        let (ox, oy) = self.origin;
+
+        let a = cos * sx;
+        let b = sin * sx;
+        let c = -sin * sy;
+        let d = cos * sy;
+
+        Affine {
+            a,
+            b,
+            c,
+            d,
+            tx: self.position.0 - a * ox - c * oy,
+            ty: self.position.1 - b * ox - d * oy,
+        }
+    }
+}
+
+impl DistanceField {
+    /// Returns the coverage for an alpha value sampled from the field
+    pub fn coverage(&self, alpha: f32) -> f32 {
+        let low = self.edge - self.smoothing;
+        let high = self.edge + self.smoothing;
+        if high <= low {
+            return if alpha >= self.edge { 1.0 } else { 0.0 };
+        }
+
+        let t = ((alpha - low) / (high - low)).clamp(0.0, 1.0);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+impl Affine {
+    pub fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+        (
+            self.a * x + self.c * y + self.tx,
+            self.b * x + self.d * y + self.ty,
+        )
+    }
+
+    /// Returns the inverse transform, `None` if the sprite is scaled to nothing
+    pub fn inverse(&self) -> Option<Affine> {
+        let det = self.a * self.d - self.b * self.c;
+        if det.abs() <= f32::EPSILON {
+            return None;
+        }
+
+        let a = self.d / det;
+        let b = -self.b / det;
+        let c = -self.c / det;
+        let d = self.a / det;
+
+        Some(Affine {
+            a,
+            b,
+            c,
+            d,
+            tx: -(a * self.tx + c * self.ty),
+            ty: -(b * self.tx + d * self.ty),
+        })
+    }
+}
+
+// ====================================
+// Other Implementations
+// ====================================
+
+impl Default for DrawParams {
+    fn default() -> Self {
+        DrawParams {
+            position: (0.0, 0.0),
+            origin: (0.0, 0.0),
+            scale: (1.0, 1.0),
+            rotation: 0.0,
+            tint: Color::WHITE,
+            source: None,
+            filter: Filter::Nearest,
+            blend: BlendMode::Alpha,
+            distance_field: None,
+        }
+    }
+}
@@ -0,0 +1,285 @@
+#![allow(dead_code)]
+
+//! Golden image testing for rendering code.
+//!
+//! A test renders into a `Texture`, usually with the `SoftwareRenderer`, and compares it with
+//! `tests/snapshots/<name>.png`. On a mismatch the rendered image and a diff image are written
+//! to `target/snapshots` and the test fails.
+//! Run the tests with `WALRUST_BLESS=1` to write new or changed snapshots instead.
+
+use super::{color::Color, texture::Texture};
+use image::ImageError;
+use std::path::{Path, PathBuf};
+
+/// Environment variable that makes snapshot assertions write the snapshot instead of comparing
+pub const BLESS_VAR: &str = "WALRUST_BLESS";
+
+/// How far a rendered image may stray from its snapshot.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Tolerance {
+    /// Largest difference allowed in any single color channel
+    pub channel: u8,
+    /// Number of pixels allowed to exceed `channel`
+    pub max_differing: usize,
+}
+
+/// Result of comparing two textures pixel by pixel.
+pub struct Comparison {
+    /// Number of pixels that differ by more than the channel tolerance
+    pub differing: usize,
+    /// Largest channel difference over all pixels
+    pub max_difference: u8,
+    /// Red where pixels differ, a faded grayscale of the expected image elsewhere
+    pub diff: Texture,
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Image(ImageError),
+    /// The snapshot does not exist and blessing is off
+    Missing(PathBuf),
+    SizeMismatch {
+        expected: (usize, usize),
+        actual: (usize, usize),
+    },
+    Mismatch {
+        differing: usize,
+        max_difference: u8,
+        diff_path: PathBuf,
+    },
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl Tolerance {
+    /// Requires every pixel to match exactly
+    pub const EXACT: Tolerance = Tolerance {
+        channel: 0,
+        max_differing: 0,
+    };
+}
+
+impl Comparison {
+    pub fn passes(&self, tolerance: Tolerance) -> bool {
+        self.differing <= tolerance.max_differing
+    }
+}
+
+/// Compares two textures of the same size
+pub fn compare(expected: &Texture, actual: &Texture, channel_tolerance: u8) -> Comparison {
+    let mut diff = Texture::new(expected.width(), expected.height());
+    let mut differing = 0;
+    let mut max_difference = 0;
+
+    for y in 0..expected.height() {
+        for x in 0..expected.width() {
+            let a = expected.get_pixel(x, y);
+            let b = actual.get_pixel(x, y);
+
+            let difference = [
+                a.red.abs_diff(b.red),
+                a.green.abs_diff(b.green),
+                a.blue.abs_diff(b.blue),
+                a.alpha.abs_diff(b.alpha),
+            ]
+            .iter()
+            .copied()
+            .max()
+            .unwrap_or(0);
+            max_difference = max_difference.max(difference);
+
+            if difference > channel_tolerance {
+                differing += 1;
+                diff.set_pixel(x, y, Color::RED);
+            } else {
+                let gray = (a.red as u32 + a.green as u32 + a.blue as u32) / 3;
+                let faded = (gray / 4 + 64) as u8;
+                diff.set_pixel(x, y, Color::from_rgb(faded, faded, faded));
+            }
+        }
+    }
+
+    Comparison {
+        differing,
+        max_difference,
+        diff,
+    }
+}
+
+/// Compares a texture with the snapshot at `path`, writing the snapshot when `bless` is set.
+/// Failure artifacts are written to `output_dir`.
+pub fn check_snapshot(
+    path: &Path,
+    output_dir: &Path,
+    actual: &Texture,
+    tolerance: Tolerance,
+    bless: bool,
+) -> Result<(), SnapshotError> {
+    if bless {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| SnapshotError::Image(ImageError::IoError(e)))?;
+        }
+        return save(actual, path);
+    }
+
+    if !path.exists() {
+        return Err(SnapshotError::Missing(path.to_path_buf()));
+    }
+
+    let expected = Texture::from_image(&path.to_string_lossy()).map_err(SnapshotError::Image)?;
+    if (expected.width(), expected.height()) != (actual.width(), actual.height()) {
+        return Err(SnapshotError::SizeMismatch {
+            expected: (expected.width(), expected.height()),
+            actual: (actual.width(), actual.height()),
+        });
+    }
+
+    let comparison = compare(&expected, actual, tolerance.channel);
+    if comparison.passes(tolerance) {
+        return Ok(());
+    }
+
+    let name = path.file_stem().unwrap_or_default().to_string_lossy();
+    std::fs::create_dir_all(output_dir)
+        .map_err(|e| SnapshotError::Image(ImageError::IoError(e)))?;
+    let diff_path = output_dir.join(format!("{}.diff.png", name));
+    save(&comparison.diff, &diff_path)?;
+    save(actual, &output_dir.join(format!("{}.actual.png", name)))?;
+
+    Err(SnapshotError::Mismatch {
+        differing: comparison.differing,
+        max_difference: comparison.max_difference,
+        diff_path,
+    })
+}
+
+/// Asserts that a texture matches `tests/snapshots/<name>.png` exactly
+pub fn assert_snapshot(name: &str, actual: &Texture) {
+    assert_snapshot_with(name, actual, Tolerance::EXACT);
+}
+
+/// Asserts that a texture matches `tests/snapshots/<name>.png` within a tolerance
+pub fn assert_snapshot_with(name: &str, actual: &Texture, tolerance: Tolerance) {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let path = root.join("tests/snapshots").join(format!("{}.png", name));
+    let output_dir = root.join("target/snapshots");
+    let bless = std::env::var_os(BLESS_VAR).is_some_and(|value| value != "0");
+
+    match check_snapshot(&path, &output_dir, actual, tolerance, bless) {
+        Ok(()) => (),
+        Err(SnapshotError::Missing(path)) => panic!(
+            "snapshot {} does not exist, run with {}=1 to create it",
+            path.display(),
+            BLESS_VAR
+        ),
+        Err(SnapshotError::SizeMismatch { expected, actual }) => panic!(
+            "snapshot {} is {}x{} but the image is {}x{}",
+            name, expected.0, expected.1, actual.0, actual.1
+        ),
+        Err(SnapshotError::Mismatch {
+            differing,
+            max_difference,
+            diff_path,
+        }) => panic!(
+            "snapshot {} differs in {} pixels (allowed {}), largest channel difference {}, see {}",
+            name,
+            differing,
+            tolerance.max_differing,
+            max_difference,
+            diff_path.display()
+        ),
+        Err(SnapshotError::Image(e)) => panic!("snapshot {} could not be read: {}", name, e),
+    }
+}
+
+fn save(texture: &Texture, path: &Path) -> Result<(), SnapshotError> {
+    texture
+        .save(&path.to_string_lossy())
+        .map_err(SnapshotError::Image)
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod snapshot_tests {
+
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("walrust_snapshot_{}", name));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn gradient() -> Texture {
+        let mut texture = Texture::new(4, 3);
+        for y in 0..3 {
+            for x in 0..4 {
+                texture.set_pixel(x, y, Color::from_rgba(x as u8 * 60, y as u8 * 100, 7, 255));
+            }
+        }
+        texture
+    }
+
+    #[test]
+    fn compare_with_tolerance() {
+        let expected = gradient();
+        let mut actual = gradient();
+        actual.set_pixel(1, 1, Color::from_rgba(62, 100, 7, 255));
+        actual.set_pixel(3, 2, Color::from_rgba(180, 200, 17, 255));
+
+        let comparison = compare(&expected, &actual, 0);
+        assert_eq!(comparison.differing, 2);
+        assert_eq!(comparison.max_difference, 10);
+        assert_eq!(comparison.diff.get_pixel(1, 1), Color::RED);
+        assert_ne!(comparison.diff.get_pixel(0, 0), Color::RED);
+
+        let comparison = compare(&expected, &actual, 2);
+        assert_eq!(comparison.differing, 1);
+        assert!(comparison.passes(Tolerance {
+            channel: 2,
+            max_differing: 1,
+        }));
+        assert!(!comparison.passes(Tolerance::EXACT));
+    }
+
+    #[test]
+    fn bless_then_check() {
+        let dir = temp_dir("bless");
+        let path = dir.join("snapshots/gradient.png");
+        let output = dir.join("output");
+
+        let missing = check_snapshot(&path, &output, &gradient(), Tolerance::EXACT, false);
+        assert!(matches!(missing, Err(SnapshotError::Missing(_))));
+
+        check_snapshot(&path, &output, &gradient(), Tolerance::EXACT, true).unwrap();
+        check_snapshot(&path, &output, &gradient(), Tolerance::EXACT, false).unwrap();
+
+        let mut changed = gradient();
+        changed.set_pixel(0, 0, Color::WHITE);
+        match check_snapshot(&path, &output, &changed, Tolerance::EXACT, false) {
+            Err(SnapshotError::Mismatch {
+                differing,
+                diff_path,
+                ..
+            }) => {
+                assert_eq!(differing, 1);
+                let diff = Texture::from_image(&diff_path.to_string_lossy()).unwrap();
+                assert_eq!(diff.get_pixel(0, 0), Color::RED);
+                assert!(output.join("gradient.actual.png").exists());
+            }
+            _ => panic!("expected a mismatch"),
+        }
+
+        let small = Texture::new(2, 2);
+        let size = check_snapshot(&path, &output, &small, Tolerance::EXACT, false);
+        assert!(matches!(size, Err(SnapshotError::SizeMismatch { .. })));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
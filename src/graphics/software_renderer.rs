@@ -0,0 +1,469 @@
+#![allow(dead_code)]
+
+use super::{
+    color::Color,
+    color_f::ColorF,
+    renderer::{DrawParams, Filter, Rect, Renderer},
+    sprite::{IndexedSprite, Sprite},
+    texture::Texture,
+};
+
+/// CPU renderer that draws into a `Texture`.
+/// Needs no OpenGL context, so it works headless and in unit tests.
+pub struct SoftwareRenderer {
+    target: Texture,
+    clip: Option<Rect>,
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl SoftwareRenderer {
+    /// Creates a renderer with a blank target of width and height
+    pub fn new(width: usize, height: usize) -> SoftwareRenderer {
+        SoftwareRenderer::from_texture(Texture::new(width, height))
+    }
+
+    /// Creates a renderer that draws on top of an existing `Texture`
+    pub fn from_texture(target: Texture) -> SoftwareRenderer {
+        SoftwareRenderer { target, clip: None }
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl SoftwareRenderer {
+    /// Returns the texture drawn into
+    pub fn target(&self) -> &Texture {
+        &self.target
+    }
+
+    /// Returns the texture drawn into, consuming the renderer
+    pub fn into_texture(self) -> Texture {
+        self.target
+    }
+
+    /// Returns the area that may be drawn to, the clip rectangle limited to the target
+    fn draw_area(&self) -> Option<Rect> {
+        let full = Rect::new(
+            0,
+            0,
+            self.target.width() as u32,
+            self.target.height() as u32,
+        );
+        match self.clip {
+            Some(clip) => clip.intersect(&full),
+            None => Some(full),
+        }
+    }
+
+    /// Transforms the source rectangle and fills the covered pixels with `sample`,
+    /// which is given coordinates relative to the source rectangle
+    fn draw_quad(&mut self, source: Rect, params: &DrawParams, sample: impl Fn(f32, f32) -> Color) {
+        if source.width == 0 || source.height == 0 {
+            return;
+        }
+
+        let area = match self.draw_area() {
+            Some(area) => area,
+            None => return,
+        };
+
+        let transform = params.transform();
+        let inverse = match transform.inverse() {
+            Some(inverse) => inverse,
+            None => return,
+        };
+
+        // Bounding box of the transformed quad
+        let (width, height) = (source.width as f32, source.height as f32);
+        let corners = [
+            transform.apply(0.0, 0.0),
+            transform.apply(width, 0.0),
+            transform.apply(0.0, height),
+            transform.apply(width, height),
+        ];
+        let min_x = corners.iter().map(|c| c.0).fold(f32::INFINITY, f32::min);
+        let max_x = corners
+            .iter()
+            .map(|c| c.0)
+            .fold(f32::NEG_INFINITY, f32::max);
+        let min_y = corners.iter().map(|c| c.1).fold(f32::INFINITY, f32::min);
+        let max_y = corners
+            .iter()
+            .map(|c| c.1)
+            .fold(f32::NEG_INFINITY, f32::max);
+
+        // Clamped to the area before the casts, far off positions would saturate
+        let left = min_x.floor().max(area.x as f32);
+        let top = min_y.floor().max(area.y as f32);
+        let right = max_x.ceil().min((area.x + area.width as i32) as f32);
+        let bottom = max_y.ceil().min((area.y + area.height as i32) as f32);
+        if !(left < right && top < bottom) {
+            return;
+        }
+        let bounds = Rect::new(
+            left as i32,
+            top as i32,
+            (right - left) as u32,
+            (bottom - top) as u32,
+        );
+
+        for y in bounds.y..bounds.y + bounds.height as i32 {
+            for x in bounds.x..bounds.x + bounds.width as i32 {
+                // Pixels are sampled at their centers
+                let (u, v) = inverse.apply(x as f32 + 0.5, y as f32 + 0.5);
+                if u < 0.0 || v < 0.0 || u >= width || v >= height {
+                    continue;
+                }
+
+                let mut color = ColorF::from(sample(u, v));
+                if let Some(field) = params.distance_field {
+                    color.alpha = field.coverage(color.alpha);
+                }
+
+                let color = color * ColorF::from(params.tint);
+                self.target
+                    .blend_pixel(x as usize, y as usize, color, params.blend);
+            }
+        }
+    }
+}
+
+impl Renderer for SoftwareRenderer {
+    fn size(&self) -> (u32, u32) {
+        (self.target.width() as u32, self.target.height() as u32)
+    }
+
+    fn clear(&mut self, color: Color) {
+        for y in 0..self.target.height() {
+            for x in 0..self.target.width() {
+                self.target.set_pixel(x, y, color);
+            }
+        }
+    }
+
+    fn set_clip(&mut self, clip: Option<Rect>) {
+        self.clip = clip;
+    }
+
+    fn draw_sprite(&mut self, sprite: &Sprite, params: &DrawParams) {
+        let texture = sprite.texture();
+        let source = params.source_rect(texture.width(), texture.height());
+
+        self.draw_quad(source, params, |u, v| match params.filter {
+            Filter::Nearest => sample_nearest(texture, &source, u, v),
+            Filter::Bilinear => sample_bilinear(texture, &source, u, v),
+        });
+    }
+
+    fn draw_indexed_sprite(&mut self, sprite: &IndexedSprite, params: &DrawParams) {
+        let indexed = sprite.indexed();
+        let palette = sprite.palette();
+        let source = params.source_rect(indexed.width(), indexed.height());
+
+        self.draw_quad(source, params, |u, v| {
+            let x = (u as u32).min(source.width - 1) as i32 + source.x;
+            let y = (v as u32).min(source.height - 1) as i32 + source.y;
+            palette.get(indexed.get_index(x as usize, y as usize))
+        });
+    }
+}
+
+fn sample_nearest(texture: &Texture, source: &Rect, u: f32, v: f32) -> Color {
+    let x = (u as u32).min(source.width - 1) as i32 + source.x;
+    let y = (v as u32).min(source.height - 1) as i32 + source.y;
+    texture.get_pixel(x as usize, y as usize)
+}
+
+fn sample_bilinear(texture: &Texture, source: &Rect, u: f32, v: f32) -> Color {
+    // Texel centers sit at half pixels, edges are clamped to the source rectangle
+    let u = (u - 0.5).clamp(0.0, source.width as f32 - 1.0);
+    let v = (v - 0.5).clamp(0.0, source.height as f32 - 1.0);
+
+    let x0 = u.floor() as u32;
+    let y0 = v.floor() as u32;
+    let x1 = (x0 + 1).min(source.width - 1);
+    let y1 = (y0 + 1).min(source.height - 1);
+    let fx = u - x0 as f32;
+    let fy = v - y0 as f32;
+
+    let texel = |x: u32, y: u32| {
+        let color = texture.get_pixel(
+            (source.x + x as i32) as usize,
+            (source.y + y as i32) as usize,
+        );
+        [
+            color.red as f32,
+            color.green as f32,
+            color.blue as f32,
+            color.alpha as f32,
+        ]
+    };
+
+    let (c00, c10, c01, c11) = (texel(x0, y0), texel(x1, y0), texel(x0, y1), texel(x1, y1));
+    let mut out = [0u8; 4];
+    for i in 0..4 {
+        let top = c00[i] + (c10[i] - c00[i]) * fx;
+        let bottom = c01[i] + (c11[i] - c01[i]) * fx;
+        out[i] = (top + (bottom - top) * fy).round().clamp(0.0, 255.0) as u8;
+    }
+
+    Color::from_rgba(out[0], out[1], out[2], out[3])
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod software_renderer_tests {
+
+    use super::*;
+    use crate::graphics::{
+        indexed_texture::IndexedTexture, palette::Palette, snapshot::assert_snapshot,
+    };
+
+    /// 2x2 sprite with a different color in each corner
+    fn quad_sprite() -> Sprite {
+        let mut texture = Texture::new(2, 2);
+        texture.set_pixel(0, 0, Color::RED);
+        texture.set_pixel(1, 0, Color::GREEN);
+        texture.set_pixel(0, 1, Color::BLUE);
+        texture.set_pixel(1, 1, Color::WHITE);
+        Sprite::from_texture(texture)
+    }
+
+    fn row(renderer: &SoftwareRenderer, y: usize) -> Vec<Color> {
+        let target = renderer.target();
+        (0..target.width())
+            .map(|x| target.get_pixel(x, y))
+            .collect()
+    }
+
+    #[test]
+    fn draw_at_position() {
+        let mut renderer = SoftwareRenderer::new(4, 4);
+        renderer.clear(Color::BLACK);
+        renderer.draw_sprite(&quad_sprite(), &DrawParams::at(1.0, 2.0));
+
+        assert_eq!(row(&renderer, 1), [Color::BLACK; 4]);
+        assert_eq!(
+            row(&renderer, 2),
+            [Color::BLACK, Color::RED, Color::GREEN, Color::BLACK]
+        );
+        assert_eq!(
+            row(&renderer, 3),
+            [Color::BLACK, Color::BLUE, Color::WHITE, Color::BLACK]
+        );
+    }
+
+    #[test]
+    fn scale_and_rotate() {
+        let mut renderer = SoftwareRenderer::new(4, 4);
+        let params = DrawParams {
+            scale: (2.0, 2.0),
+            ..DrawParams::default()
+        };
+        renderer.draw_sprite(&quad_sprite(), &params);
+        assert_eq!(
+            row(&renderer, 1),
+            [Color::RED, Color::RED, Color::GREEN, Color::GREEN]
+        );
+
+        // A quarter turn clockwise around the center moves the top left corner to the top right
+        let mut renderer = SoftwareRenderer::new(2, 2);
+        let params = DrawParams {
+            position: (1.0, 1.0),
+            origin: (1.0, 1.0),
+            rotation: std::f32::consts::FRAC_PI_2,
+            ..DrawParams::default()
+        };
+        renderer.draw_sprite(&quad_sprite(), &params);
+        assert_eq!(row(&renderer, 0), [Color::BLUE, Color::RED]);
+        assert_eq!(row(&renderer, 1), [Color::WHITE, Color::GREEN]);
+    }
+
+    #[test]
+    fn tint_and_alpha_blend() {
+        let mut renderer = SoftwareRenderer::new(2, 2);
+        renderer.clear(Color::BLUE);
+
+        let params = DrawParams {
+            tint: Color::from_rgba(255, 0, 0, 128),
+            ..DrawParams::default()
+        };
+        let sprite = Sprite::from_texture(Texture::from_color(1, 1, Color::WHITE));
+        renderer.draw_sprite(&sprite, &params);
+
+        assert_eq!(
+            renderer.target().get_pixel(0, 0),
+            Color::from_rgb(128, 0, 127)
+        );
+        assert_eq!(renderer.target().get_pixel(1, 0), Color::BLUE);
+    }
+
+    #[test]
+    fn clip_and_source() {
+        let mut renderer = SoftwareRenderer::new(4, 1);
+        renderer.set_clip(Some(Rect::new(1, 0, 2, 1)));
+
+        let params = DrawParams {
+            scale: (4.0, 1.0),
+            source: Some(Rect::new(1, 1, 1, 1)),
+            ..DrawParams::default()
+        };
+        renderer.draw_sprite(&quad_sprite(), &params);
+
+        assert_eq!(
+            row(&renderer, 0),
+            [
+                Color::default(),
+                Color::WHITE,
+                Color::WHITE,
+                Color::default()
+            ]
+        );
+    }
+
+    #[test]
+    fn clip_survives_clear() {
+        let mut renderer = SoftwareRenderer::new(4, 1);
+        renderer.set_clip(Some(Rect::new(1, 0, 2, 1)));
+        renderer.clear(Color::BLUE);
+
+        let params = DrawParams {
+            scale: (4.0, 1.0),
+            ..DrawParams::default()
+        };
+        renderer.draw_sprite(
+            &Sprite::from_texture(Texture::from_color(1, 1, Color::WHITE)),
+            &params,
+        );
+
+        assert_eq!(
+            row(&renderer, 0),
+            [Color::BLUE, Color::WHITE, Color::WHITE, Color::BLUE]
+        );
+    }
+
+    #[test]
+    fn far_off_positions() {
+        let mut renderer = SoftwareRenderer::new(4, 4);
+        for &position in [
+            (3e9, 3e9),
+            (-3e9, -3e9),
+            (f32::MIN, f32::MIN),
+            (f32::MAX, 0.0),
+        ]
+        .iter()
+        {
+            let params = DrawParams {
+                position,
+                ..DrawParams::default()
+            };
+            renderer.draw_sprite(&quad_sprite(), &params);
+        }
+        for y in 0..4 {
+            assert_eq!(row(&renderer, y), [Color::default(); 4]);
+        }
+
+        let far = Rect::new(i32::MAX - 1, 0, u32::MAX, 4);
+        assert_eq!(far.intersect(&Rect::new(0, 0, 4, 4)), None);
+        assert_eq!(
+            Rect::new(-5, 0, u32::MAX, 4).intersect(&Rect::new(0, 0, 4, 4)),
+            Some(Rect::new(0, 0, 4, 4))
+        );
+    }
+
+    #[test]
+    fn bilinear_filtering() {
+        let mut texture = Texture::new(2, 1);
+        texture.set_pixel(0, 0, Color::BLACK);
+        texture.set_pixel(1, 0, Color::WHITE);
+        let sprite = Sprite::from_texture(texture);
+
+        let mut renderer = SoftwareRenderer::new(4, 1);
+        let params = DrawParams {
+            scale: (2.0, 1.0),
+            filter: Filter::Bilinear,
+            ..DrawParams::default()
+        };
+        renderer.draw_sprite(&sprite, &params);
+
+        let gray: Vec<u8> = row(&renderer, 0).iter().map(|c| c.red).collect();
+        assert_eq!(gray, [0, 64, 191, 255]);
+    }
+
+    #[test]
+    fn indexed_palette_swap() {
+        let mut indexed = IndexedTexture::new(2, 1);
+        indexed.set_index(1, 0, 1);
+        let palette = Palette::from_colors(&[Color::RED, Color::BLUE]);
+        let mut sprite = IndexedSprite::new(indexed, palette);
+
+        let mut renderer = SoftwareRenderer::new(4, 1);
+        renderer.draw_indexed_sprite(&sprite, &DrawParams::default());
+
+        sprite.set_palette(sprite.palette().swapped(Color::RED, Color::GREEN));
+        renderer.draw_indexed_sprite(&sprite, &DrawParams::at(2.0, 0.0));
+
+        assert_eq!(
+            row(&renderer, 0),
+            [Color::RED, Color::BLUE, Color::GREEN, Color::BLUE]
+        );
+    }
+
+    #[test]
+    fn scene_snapshot() {
+        let mut checker = Texture::new(4, 4);
+        for y in 0..4 {
+            for x in 0..4 {
+                let color = if (x + y) % 2 == 0 {
+                    Color::WHITE
+                } else {
+                    Color::MAGENTA
+                };
+                checker.set_pixel(x, y, color);
+            }
+        }
+        let checker = Sprite::from_texture(checker);
+
+        let mut renderer = SoftwareRenderer::new(32, 24);
+        renderer.clear(Color::from_rgb(32, 32, 48));
+        renderer.draw_sprite(
+            &checker,
+            &DrawParams {
+                position: (2.0, 2.0),
+                scale: (3.0, 3.0),
+                ..DrawParams::default()
+            },
+        );
+        renderer.draw_sprite(
+            &quad_sprite(),
+            &DrawParams {
+                position: (22.0, 8.0),
+                origin: (1.0, 1.0),
+                scale: (5.0, 5.0),
+                rotation: 0.5,
+                filter: Filter::Bilinear,
+                ..DrawParams::default()
+            },
+        );
+        renderer.set_clip(Some(Rect::new(0, 16, 20, 6)));
+        renderer.draw_sprite(
+            &checker,
+            &DrawParams {
+                position: (4.0, 12.0),
+                scale: (4.0, 4.0),
+                tint: Color::from_rgba(0, 255, 255, 160),
+                ..DrawParams::default()
+            },
+        );
+
+        assert_snapshot("software_renderer_scene", renderer.target());
+    }
+}
@@ -1,6 +1,10 @@
 #![allow(dead_code)]
 
-use super::texture::Texture;
+use super::{
+    indexed_texture::IndexedTexture,
+    palette::{Palette, MAX_COLORS},
+    texture::Texture,
+};
 
 pub struct Sprite {
     texture: Texture,
@@ -8,7 +12,13 @@ pub struct Sprite {
 }
 
 impl Sprite {
+    /// Creates a `Sprite` and uploads the texture to the GPU.
+    /// The upload is skipped when no OpenGL context is loaded, e.g. when running headless.
     pub fn from_texture(texture: Texture) -> Sprite {
+        if !gl::GenTextures::is_loaded() {
+            return Sprite { texture, id: 0 };
+        }
+
         unsafe {
             let mut id = 0;
             gl::GenTextures(1, &mut id);
@@ -32,7 +42,12 @@ impl Sprite {
         }
     }
 
+    /// Uploads the texture again after it was changed
     pub fn refresh(&self) {
+        if self.id == 0 {
+            return;
+        }
+
         unsafe {
             gl::BindTexture(gl::TEXTURE_2D, self.id);
             gl::TexSubImage2D(
@@ -49,9 +64,134 @@ impl Sprite {
         }
     }
 
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    pub fn texture_mut(&mut self) -> &mut Texture {
+        &mut self.texture
+    }
+
     pub fn bind(&self) {
         unsafe {
             gl::BindTexture(gl::TEXTURE_2D, self.id);
         }
     }
 }
+
+/// Indexed image on the GPU that is colored through a palette lookup texture.
+/// Swapping the palette only re-uploads the 256 palette colors.
+pub struct IndexedSprite {
+    indexed: IndexedTexture,
+    palette: Palette,
+    index_id: u32,
+    palette_id: u32,
+}
+
+impl IndexedSprite {
+    /// Creates an `IndexedSprite` and uploads the indices and palette to the GPU.
+    /// The upload is skipped when no OpenGL context is loaded.
+    pub fn new(indexed: IndexedTexture, palette: Palette) -> IndexedSprite {
+        let mut sprite = IndexedSprite {
+            indexed,
+            palette,
+            index_id: 0,
+            palette_id: 0,
+        };
+
+        if !gl::GenTextures::is_loaded() {
+            return sprite;
+        }
+
+        unsafe {
+            gl::GenTextures(1, &mut sprite.index_id);
+            gl::GenTextures(1, &mut sprite.palette_id);
+
+            // Rows of single byte indices are not 4 byte aligned
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl::BindTexture(gl::TEXTURE_2D, sprite.index_id);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::R8 as i32,
+                sprite.indexed.width() as i32,
+                sprite.indexed.height() as i32,
+                0,
+                gl::RED,
+                gl::UNSIGNED_BYTE,
+                sprite.indexed.indices().as_ptr() as *const _,
+            );
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+
+            // Indices must never be interpolated
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+
+            gl::BindTexture(gl::TEXTURE_2D, sprite.palette_id);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+        }
+
+        sprite.upload_palette();
+        sprite
+    }
+
+    /// Replaces the palette, the indices stay on the GPU
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+        self.upload_palette();
+    }
+
+    pub fn palette(&self) -> &Palette {
+        &self.palette
+    }
+
+    pub fn indexed(&self) -> &IndexedTexture {
+        &self.indexed
+    }
+
+    /// Binds the indices to texture unit 0 and the palette to texture unit 1
+    pub fn bind(&self) {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, self.index_id);
+            gl::ActiveTexture(gl::TEXTURE1);
+            gl::BindTexture(gl::TEXTURE_2D, self.palette_id);
+            gl::ActiveTexture(gl::TEXTURE0);
+        }
+    }
+
+    fn upload_palette(&self) {
+        if self.palette_id == 0 {
+            return;
+        }
+
+        let colors = self.palette.lookup_colors();
+
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.palette_id);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::RGBA as i32,
+                MAX_COLORS as i32,
+                1,
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                colors.as_ptr() as *const _,
+            );
+        }
+    }
+}
+
+impl Drop for IndexedSprite {
+    fn drop(&mut self) {
+        if self.index_id != 0 {
+            unsafe {
+                gl::DeleteTextures(1, &self.index_id);
+                gl::DeleteTextures(1, &self.palette_id);
+            }
+        }
+    }
+}
@@ -0,0 +1,348 @@
+#![allow(dead_code)]
+
+use super::{color::Color, renderer::Rect, sprite::Sprite};
+
+/// Placement of a single character inside a font's atlas pages.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Glyph {
+    /// Atlas page the glyph is stored on
+    pub page: usize,
+    /// Region of the page holding the glyph image
+    pub source: Rect,
+    /// Offset from the pen position at the top of the line to the top left of the image
+    pub offset: (f32, f32),
+    /// Distance the pen moves after the glyph
+    pub advance: f32,
+}
+
+/// Source of glyphs for text layout and drawing.
+pub trait Font {
+    /// Returns the distance between two lines of text
+    fn line_height(&self) -> f32;
+
+    fn glyph(&self, c: char) -> Option<Glyph>;
+
+    /// Returns the extra spacing between two characters, usually negative
+    fn kerning(&self, left: char, right: char) -> f32;
+
+    /// Returns an atlas page as a sprite
+    fn page(&self, page: usize) -> Option<&Sprite>;
+
+    /// Returns the spread in pixels when the pages hold signed distance fields in their alpha
+    fn distance_field(&self) -> Option<f32> {
+        None
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// How text is laid out and drawn.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TextStyle {
+    pub color: Color,
+    pub scale: f32,
+    pub align: Align,
+    /// Wraps lines at spaces to fit, and aligns inside this width instead of around the position
+    pub max_width: Option<f32>,
+    /// Copy of the text drawn behind it
+    pub shadow: Option<TextShadow>,
+    /// Border around the glyphs, only drawn for distance field fonts
+    pub outline: Option<TextOutline>,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TextShadow {
+    /// Offset from the text in pixels
+    pub offset: (f32, f32),
+    pub color: Color,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TextOutline {
+    /// Width in pixels of the target
+    pub width: f32,
+    pub color: Color,
+}
+
+/// Glyph with its final position, ready to be drawn.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PositionedGlyph {
+    pub c: char,
+    pub glyph: Glyph,
+    /// Top left of the glyph image in pixels
+    pub position: (f32, f32),
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl TextStyle {
+    pub fn new(color: Color) -> TextStyle {
+        TextStyle {
+            color,
+            ..TextStyle::default()
+        }
+    }
+}
+
+/// Lays text out with its first line's top at `position`
+pub fn layout_text(
+    font: &dyn Font,
+    text: &str,
+    position: (f32, f32),
+    style: &TextStyle,
+) -> Vec<PositionedGlyph> {
+    let mut glyphs = Vec::new();
+    let line_height = font.line_height() * style.scale;
+
+    for (index, line) in wrap_lines(font, text, style).iter().enumerate() {
+        let width = line_width(font, line) * style.scale;
+        let x = match (style.align, style.max_width) {
+            (Align::Left, _) => position.0,
+            (Align::Center, Some(max)) => position.0 + (max - width) / 2.0,
+            (Align::Right, Some(max)) => position.0 + max - width,
+            (Align::Center, None) => position.0 - width / 2.0,
+            (Align::Right, None) => position.0 - width,
+        };
+        let y = position.1 + index as f32 * line_height;
+
+        let mut pen = 0.0;
+        let mut last = None;
+        for c in line.chars() {
+            let glyph = match font.glyph(c) {
+                Some(glyph) => glyph,
+                None => continue,
+            };
+
+            if let Some(last) = last {
+                pen += font.kerning(last, c);
+            }
+
+            glyphs.push(PositionedGlyph {
+                c,
+                glyph,
+                position: (
+                    x + (pen + glyph.offset.0) * style.scale,
+                    y + glyph.offset.1 * style.scale,
+                ),
+            });
+
+            pen += glyph.advance;
+            last = Some(c);
+        }
+    }
+
+    glyphs
+}
+
+/// Returns the width of the widest line and the height of all lines
+pub fn measure_text(font: &dyn Font, text: &str, style: &TextStyle) -> (f32, f32) {
+    let lines = wrap_lines(font, text, style);
+    let width = lines
+        .iter()
+        .map(|line| line_width(font, line))
+        .fold(0.0, f32::max);
+
+    (
+        width * style.scale,
+        lines.len() as f32 * font.line_height() * style.scale,
+    )
+}
+
+/// Returns the unscaled advance of a line including kerning
+fn line_width(font: &dyn Font, line: &str) -> f32 {
+    let mut width = 0.0;
+    let mut last = None;
+    for c in line.chars() {
+        if let Some(glyph) = font.glyph(c) {
+            if let Some(last) = last {
+                width += font.kerning(last, c);
+            }
+            width += glyph.advance;
+            last = Some(c);
+        }
+    }
+    width
+}
+
+/// Splits text at newlines and, with a maximum width, at the last space that fits.
+/// Words wider than the maximum are split between characters.
+fn wrap_lines(font: &dyn Font, text: &str, style: &TextStyle) -> Vec<String> {
+    let max = match style.max_width {
+        Some(max) => max / style.scale,
+        None => return text.split('\n').map(String::from).collect(),
+    };
+
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+
+        for word in paragraph.split(' ') {
+            let candidate = if line.is_empty() {
+                word.to_string()
+            } else {
+                format!("{} {}", line, word)
+            };
+
+            if line_width(font, &candidate) <= max {
+                line = candidate;
+                continue;
+            }
+
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+
+            for c in word.chars() {
+                line.push(c);
+                if line.chars().count() > 1 && line_width(font, &line) > max {
+                    line.pop();
+                    lines.push(std::mem::take(&mut line));
+                    line.push(c);
+                }
+            }
+        }
+
+        lines.push(line);
+    }
+
+    lines
+}
+
+// ====================================
+// Other Implementations
+// ====================================
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        TextStyle {
+            color: Color::WHITE,
+            scale: 1.0,
+            align: Align::Left,
+            max_width: None,
+            shadow: None,
+            outline: None,
+        }
+    }
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod text_tests {
+
+    use super::*;
+    use crate::graphics::{
+        bitmap_font::BitmapFont, renderer::Renderer, software_renderer::SoftwareRenderer,
+        texture::Texture,
+    };
+
+    /// Grid font with 2x3 cells where every glyph advances 2 pixels
+    fn grid_font() -> BitmapFont {
+        let mut texture = Texture::new(8, 3);
+        for y in 0..3 {
+            texture.set_pixel(0, y, Color::WHITE);
+            texture.set_pixel(2, y, Color::WHITE);
+            texture.set_pixel(3, y, Color::WHITE);
+        }
+        BitmapFont::from_grid(texture, 2, 3, "il ")
+    }
+
+    fn positions(glyphs: &[PositionedGlyph]) -> Vec<(char, f32, f32)> {
+        glyphs
+            .iter()
+            .map(|g| (g.c, g.position.0, g.position.1))
+            .collect()
+    }
+
+    #[test]
+    fn measure_and_align() {
+        let font = grid_font();
+        let style = TextStyle::default();
+        assert_eq!(measure_text(&font, "iil\nl", &style), (6.0, 6.0));
+
+        let scaled = TextStyle {
+            scale: 2.0,
+            ..style
+        };
+        assert_eq!(measure_text(&font, "il", &scaled), (8.0, 6.0));
+
+        let centered = TextStyle {
+            align: Align::Center,
+            ..style
+        };
+        let glyphs = layout_text(&font, "il\nl", (10.0, 0.0), &centered);
+        assert_eq!(
+            positions(&glyphs),
+            [('i', 8.0, 0.0), ('l', 10.0, 0.0), ('l', 9.0, 3.0)]
+        );
+
+        let right = TextStyle {
+            align: Align::Right,
+            max_width: Some(10.0),
+            ..style
+        };
+        let glyphs = layout_text(&font, "il", (0.0, 0.0), &right);
+        assert_eq!(positions(&glyphs), [('i', 6.0, 0.0), ('l', 8.0, 0.0)]);
+    }
+
+    #[test]
+    fn kerning_from_bmfont() {
+        let font = BitmapFont::parse(
+            b"common lineHeight=10 base=8
+char id=65 x=0 y=0 width=6 height=8 xoffset=0 yoffset=0 xadvance=7
+char id=86 x=6 y=0 width=6 height=8 xoffset=0 yoffset=0 xadvance=6
+kerning first=65 second=86 amount=-2",
+        )
+        .unwrap();
+
+        let style = TextStyle::default();
+        assert_eq!(measure_text(&font, "AV", &style).0, 11.0);
+        assert_eq!(measure_text(&font, "VA", &style).0, 13.0);
+        let glyphs = layout_text(&font, "AV", (0.0, 0.0), &style);
+        assert_eq!(glyphs[1].position, (5.0, 0.0));
+    }
+
+    #[test]
+    fn wrap_within_width() {
+        let font = grid_font();
+        let style = TextStyle {
+            max_width: Some(6.0),
+            ..TextStyle::default()
+        };
+
+        assert_eq!(
+            wrap_lines(&font, "il li lil iiiiii", &style),
+            ["il", "li", "lil", "iii", "iii"]
+        );
+        assert_eq!(measure_text(&font, "il li", &style), (4.0, 6.0));
+    }
+
+    #[test]
+    fn draw_text_with_color() {
+        let font = grid_font();
+        let mut renderer = SoftwareRenderer::new(6, 3);
+        renderer.draw_text(&font, "l i", (0.0, 0.0), &TextStyle::new(Color::RED));
+
+        let row: Vec<Color> = (0..6).map(|x| renderer.target().get_pixel(x, 1)).collect();
+        assert_eq!(
+            row,
+            [
+                Color::RED,
+                Color::RED,
+                Color::default(),
+                Color::default(),
+                Color::RED,
+                Color::default()
+            ]
+        );
+    }
+}
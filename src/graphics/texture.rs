@@ -1,8 +1,9 @@
 #![allow(dead_code)]
 
-use super::color::Color;
+use super::{blend::BlendMode, color::Color, color_f::ColorF};
 use image::{GenericImageView, ImageError};
 
+#[derive(Clone)]
 pub struct Texture {
     width: usize,
     height: usize,
@@ -19,14 +20,23 @@ impl Texture {
         }
     }
 
+    /// Creates a `Texture` of width and height filled with a single color
+    pub fn from_color(width: usize, height: usize, color: Color) -> Texture {
+        Texture {
+            width,
+            height,
+            pixels: vec![color; width * height],
+        }
+    }
+
     /// Reads a image from file and creates a `Texture`
     pub fn from_image(path: &str) -> Result<Texture, ImageError> {
         let img = image::open(path)?;
         let mut texture = Texture::new(img.width() as usize, img.height() as usize);
-        let buffer = img.as_rgba8().unwrap();
+        let buffer = img.to_rgba8();
 
-        for y in 0..texture.width {
-            for x in 0..texture.height {
+        for y in 0..texture.height {
+            for x in 0..texture.width {
                 let pixel = buffer.get_pixel(x as u32, y as u32);
                 texture.pixels[y * texture.width + x] = Color {
                     red: pixel.0[0],
@@ -40,6 +50,22 @@ impl Texture {
         return Ok(texture);
     }
 
+    /// Writes the `Texture` to an image file, the format is chosen from the extension
+    pub fn save(&self, path: &str) -> Result<(), ImageError> {
+        let mut buffer = Vec::with_capacity(self.pixels.len() * 4);
+        for pixel in self.pixels.iter() {
+            buffer.extend_from_slice(&[pixel.red, pixel.green, pixel.blue, pixel.alpha]);
+        }
+
+        image::save_buffer(
+            path,
+            &buffer,
+            self.width as u32,
+            self.height as u32,
+            image::ColorType::Rgba8,
+        )
+    }
+
     /// Returns a pixel at the specified x and y coordinates
     pub fn get_pixel(&self, x: usize, y: usize) -> Color {
         if x < self.width && y < self.height {
@@ -56,6 +82,19 @@ impl Texture {
         }
     }
 
+    /// Blends a color onto the pixel at the specified x and y coordinates
+    pub fn blend_pixel(&mut self, x: usize, y: usize, color: ColorF, mode: BlendMode) {
+        if x < self.width && y < self.height {
+            let dst = &mut self.pixels[y * self.width + x];
+            *dst = mode.blend(color, ColorF::from(*dst)).into();
+        }
+    }
+
+    /// Returns the pixels row by row, starting at the top left
+    pub fn pixels(&self) -> &[Color] {
+        &self.pixels
+    }
+
     /// Returns a pointer to the underlining pixel data
     pub unsafe fn as_ptr(&self) -> *const Color {
         self.pixels.as_ptr()
@@ -0,0 +1,1546 @@
+#![allow(dead_code)]
+
+use std::{collections::HashMap, path::Path, str::FromStr};
+
+use super::{
+    color::Color,
+    json::Json,
+    texture::Texture,
+    tilemap::{Orientation, Tile, TileFrame, Tilemap, Tileset},
+    xml::Element,
+};
+use image::ImageError;
+
+/// Global tile id flag of tiles mirrored left to right
+pub const FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+/// Global tile id flag of tiles mirrored top to bottom
+pub const FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+/// Global tile id flag of tiles with x and y swapped
+pub const FLIPPED_DIAGONALLY: u32 = 0x2000_0000;
+/// All flag bits of a global tile id, the last one is only used by hexagonal maps
+const FLAGS: u32 = 0xf000_0000;
+
+/// Most cells in a map, bigger sizes come from corrupt files
+const MAX_CELLS: usize = 1 << 24;
+
+/// Map made in the Tiled editor, loaded from a `.tmx` or `.tmj` file.
+/// Tile layers hold global tile ids: 0 is an empty cell, other ids point into the tileset with
+/// the largest `first_gid` not above them, and the highest bits are the `FLIPPED_*` flags.
+#[derive(Clone, PartialEq, Debug)]
+pub struct TiledMap {
+    pub orientation: Orientation,
+    /// Size in cells
+    pub width: usize,
+    pub height: usize,
+    /// Size of a cell in pixels
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub background: Option<Color>,
+    pub properties: Properties,
+    pub tilesets: Vec<TiledTileset>,
+    /// Layers from bottom to top, groups are flattened into the layers they contain
+    pub layers: Vec<TiledLayer>,
+}
+
+/// Tileset of a Tiled map, either embedded in the map or from a `.tsx` or `.tsj` file.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct TiledTileset {
+    /// Global tile id of the first tile
+    pub first_gid: u32,
+    /// File of an external tileset as named in the map, `TiledMap::load` reads it
+    pub source: Option<String>,
+    pub name: String,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub margin: u32,
+    pub spacing: u32,
+    pub columns: u32,
+    pub tile_count: u32,
+    /// Image with all tiles, `None` for tilesets made of separate images
+    pub image: Option<String>,
+    /// Color of the image that is made transparent
+    pub transparent: Option<Color>,
+    pub properties: Properties,
+    /// Extra data of single tiles by tile index
+    pub tiles: HashMap<u32, TiledTile>,
+}
+
+/// Class, properties and animation of a tile in a tileset.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct TiledTile {
+    pub class: String,
+    pub properties: Properties,
+    /// Frames refer to tiles of the same tileset
+    pub animation: Vec<TileFrame>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct TiledLayer {
+    pub name: String,
+    pub visible: bool,
+    pub opacity: f32,
+    /// Offset in pixels, including the offsets of the groups the layer is in
+    pub offset: (f32, f32),
+    pub properties: Properties,
+    pub content: LayerContent,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum LayerContent {
+    /// Global tile ids row by row
+    Tiles {
+        width: usize,
+        height: usize,
+        gids: Vec<u32>,
+    },
+    Objects(Vec<TiledObject>),
+    /// Single image, repeated along the axes that are set
+    Image {
+        image: String,
+        repeat_x: bool,
+        repeat_y: bool,
+    },
+}
+
+/// Shape or tile placed freely in an object layer, positions are in pixels.
+#[derive(Clone, PartialEq, Debug)]
+pub struct TiledObject {
+    pub id: u32,
+    pub name: String,
+    pub class: String,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    /// Clockwise rotation in degrees
+    pub rotation: f32,
+    pub visible: bool,
+    /// Global tile id of tile objects
+    pub gid: Option<u32>,
+    pub shape: ObjectShape,
+    pub properties: Properties,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum ObjectShape {
+    Rectangle,
+    Ellipse,
+    Point,
+    /// Points relative to the object position
+    Polygon(Vec<(f32, f32)>),
+    Polyline(Vec<(f32, f32)>),
+    Text(String),
+}
+
+/// Value of a custom property.
+#[derive(Clone, PartialEq, Debug)]
+pub enum PropertyValue {
+    String(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Color(Color),
+    File(String),
+    /// Id of an object in the map
+    Object(u32),
+    /// Members of a custom class
+    Class(Properties),
+}
+
+pub type Properties = HashMap<String, PropertyValue>;
+
+#[derive(Debug)]
+pub enum TiledError {
+    Io(std::io::Error),
+    Image(ImageError),
+    /// The XML or JSON could not be parsed
+    Parse(String),
+    /// A value is missing or has the wrong type
+    InvalidFormat(String),
+    /// The map uses a feature that is not supported, like infinite or hexagonal maps
+    Unsupported(String),
+    /// Compressed layer data could not be inflated
+    Decompress,
+}
+
+/// Offset, opacity and visibility a group passes on to its layers.
+#[derive(Clone, Copy)]
+struct Group {
+    offset: (f32, f32),
+    opacity: f32,
+    visible: bool,
+}
+
+const ROOT_GROUP: Group = Group {
+    offset: (0.0, 0.0),
+    opacity: 1.0,
+    visible: true,
+};
+
+// ====================================
+// Constructors
+// ====================================
+
+impl TiledMap {
+    /// Loads a `.tmx` or `.tmj` map and the external tilesets it uses.
+    /// Image paths are made relative to the working directory.
+    pub fn load(path: &str) -> Result<TiledMap, TiledError> {
+        let text = std::fs::read_to_string(path).map_err(TiledError::Io)?;
+        let mut map = if is_json(path) {
+            TiledMap::parse_tmj(&text)?
+        } else {
+            TiledMap::parse_tmx(&text)?
+        };
+
+        let directory = Path::new(path).parent().unwrap_or_else(|| Path::new(""));
+        for tileset in map.tilesets.iter_mut() {
+            match tileset.source.clone() {
+                Some(source) => {
+                    let file = directory.join(&source).to_string_lossy().into_owned();
+                    *tileset = TiledTileset::load(&file, tileset.first_gid)?;
+                    tileset.source = Some(source);
+                }
+                None => tileset.resolve_image(directory),
+            }
+        }
+
+        for layer in map.layers.iter_mut() {
+            if let LayerContent::Image { image, .. } = &mut layer.content {
+                if !image.is_empty() {
+                    *image = directory.join(&image).to_string_lossy().into_owned();
+                }
+            }
+        }
+
+        Ok(map)
+    }
+
+    /// Parses a TMX map, external tilesets are left for `load` to read
+    pub fn parse_tmx(text: &str) -> Result<TiledMap, TiledError> {
+        let root = Element::parse(text).map_err(TiledError::Parse)?;
+        if root.name != "map" {
+            return Err(TiledError::InvalidFormat("expected a map".to_string()));
+        }
+        if attribute(&root, "infinite", 0u8)? != 0 {
+            return Err(TiledError::Unsupported("infinite maps".to_string()));
+        }
+
+        let mut map = TiledMap {
+            orientation: orientation(root.attribute("orientation").unwrap_or("orthogonal"))?,
+            width: required(&root, "width")?,
+            height: required(&root, "height")?,
+            tile_width: required(&root, "tilewidth")?,
+            tile_height: required(&root, "tileheight")?,
+            background: root.attribute("backgroundcolor").and_then(parse_color),
+            properties: xml_properties(&root)?,
+            tilesets: Vec::new(),
+            layers: Vec::new(),
+        };
+        cell_count(map.width, map.height)?;
+
+        for tileset in root.children_named("tileset") {
+            let first_gid = attribute(tileset, "firstgid", 1)?;
+            map.tilesets.push(match tileset.attribute("source") {
+                Some(source) => TiledTileset::external(source, first_gid),
+                None => xml_tileset(tileset, first_gid)?,
+            });
+        }
+
+        xml_layers(&root, ROOT_GROUP, &mut map.layers)?;
+        check_layer_sizes(&map)?;
+        Ok(map)
+    }
+
+    /// Parses a TMJ map, external tilesets are left for `load` to read
+    pub fn parse_tmj(text: &str) -> Result<TiledMap, TiledError> {
+        let root = Json::parse(text).map_err(TiledError::Parse)?;
+        if root.get("infinite").and_then(Json::as_bool) == Some(true) {
+            return Err(TiledError::Unsupported("infinite maps".to_string()));
+        }
+
+        let mut map = TiledMap {
+            orientation: orientation(string(&root, "orientation").unwrap_or("orthogonal"))?,
+            width: required_number(&root, "width")? as usize,
+            height: required_number(&root, "height")? as usize,
+            tile_width: required_number(&root, "tilewidth")? as u32,
+            tile_height: required_number(&root, "tileheight")? as u32,
+            background: string(&root, "backgroundcolor").and_then(parse_color),
+            properties: json_properties(&root)?,
+            tilesets: Vec::new(),
+            layers: Vec::new(),
+        };
+        cell_count(map.width, map.height)?;
+
+        for tileset in array(&root, "tilesets") {
+            let first_gid = number(tileset, "firstgid", 1.0)? as u32;
+            map.tilesets.push(match string(tileset, "source") {
+                Some(source) => TiledTileset::external(source, first_gid),
+                None => json_tileset(tileset, first_gid)?,
+            });
+        }
+
+        json_layers(array(&root, "layers"), ROOT_GROUP, &mut map.layers)?;
+        check_layer_sizes(&map)?;
+        Ok(map)
+    }
+}
+
+impl TiledTileset {
+    /// Loads a `.tsx` or `.tsj` tileset, the image path is made relative to the working directory
+    pub fn load(path: &str, first_gid: u32) -> Result<TiledTileset, TiledError> {
+        let text = std::fs::read_to_string(path).map_err(TiledError::Io)?;
+        let mut tileset = if is_json(path) {
+            TiledTileset::parse_tsj(&text, first_gid)?
+        } else {
+            TiledTileset::parse_tsx(&text, first_gid)?
+        };
+
+        tileset.resolve_image(Path::new(path).parent().unwrap_or_else(|| Path::new("")));
+        Ok(tileset)
+    }
+
+    pub fn parse_tsx(text: &str, first_gid: u32) -> Result<TiledTileset, TiledError> {
+        let root = Element::parse(text).map_err(TiledError::Parse)?;
+        if root.name != "tileset" {
+            return Err(TiledError::InvalidFormat("expected a tileset".to_string()));
+        }
+        xml_tileset(&root, first_gid)
+    }
+
+    pub fn parse_tsj(text: &str, first_gid: u32) -> Result<TiledTileset, TiledError> {
+        let root = Json::parse(text).map_err(TiledError::Parse)?;
+        json_tileset(&root, first_gid)
+    }
+
+    /// Creates a placeholder for a tileset in another file
+    fn external(source: &str, first_gid: u32) -> TiledTileset {
+        TiledTileset {
+            first_gid,
+            source: Some(source.to_string()),
+            ..TiledTileset::default()
+        }
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl TiledMap {
+    /// Returns the tile of a global tile id, `None` for empty cells and unknown tiles
+    pub fn tile(&self, gid: u32) -> Option<Tile> {
+        let id = gid & !FLAGS;
+        if id == 0 {
+            return None;
+        }
+
+        let tileset = self
+            .tilesets
+            .iter()
+            .enumerate()
+            .filter(|(_, tileset)| tileset.first_gid <= id)
+            .max_by_key(|(_, tileset)| tileset.first_gid)
+            .map(|(index, _)| index)?;
+
+        Some(Tile {
+            tileset,
+            index: id - self.tilesets[tileset].first_gid,
+            flip_x: gid & FLIPPED_HORIZONTALLY != 0,
+            flip_y: gid & FLIPPED_VERTICALLY != 0,
+            flip_diagonal: gid & FLIPPED_DIAGONALLY != 0,
+        })
+    }
+
+    /// Loads the tileset images and builds a `Tilemap` from the tile layers
+    pub fn to_tilemap(&self) -> Result<Tilemap, TiledError> {
+        let textures = self
+            .tilesets
+            .iter()
+            .map(TiledTileset::load_texture)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(self.build_tilemap(textures))
+    }
+
+    /// Builds a `Tilemap` from the tile layers with one texture for every tileset.
+    /// Object and image layers are left out, they are read from `layers` directly.
+    pub fn build_tilemap(&self, textures: Vec<Texture>) -> Tilemap {
+        let mut tilemap = Tilemap::new(self.width, self.height, self.tile_width, self.tile_height);
+        tilemap.set_orientation(self.orientation);
+
+        for (tileset, texture) in self.tilesets.iter().zip(textures) {
+            let mut sliced = Tileset::with_spacing(
+                texture,
+                tileset.tile_width,
+                tileset.tile_height,
+                tileset.margin,
+                tileset.spacing,
+            );
+            for (&index, tile) in tileset.tiles.iter() {
+                sliced.set_animation(index, tile.animation.clone());
+            }
+            tilemap.add_tileset(sliced);
+        }
+
+        for layer in self.layers.iter() {
+            if let LayerContent::Tiles { width, gids, .. } = &layer.content {
+                let index = tilemap.add_layer(&layer.name);
+                let target = tilemap.layer_mut(index).unwrap();
+                target.visible = layer.visible;
+                target.opacity = layer.opacity;
+                target.offset = layer.offset;
+
+                for (cell, &gid) in gids.iter().enumerate() {
+                    target.set(cell % width, cell / width, self.tile(gid));
+                }
+            }
+        }
+
+        tilemap
+    }
+
+    /// Returns the first layer with a name
+    pub fn layer_named(&self, name: &str) -> Option<&TiledLayer> {
+        self.layers.iter().find(|layer| layer.name == name)
+    }
+}
+
+impl TiledTileset {
+    /// Loads the tileset image and makes the transparent color clear
+    pub fn load_texture(&self) -> Result<Texture, TiledError> {
+        let image = self.image.as_ref().ok_or_else(|| {
+            TiledError::Unsupported(format!("tileset '{}' without an image", self.name))
+        })?;
+        let mut texture = Texture::from_image(image).map_err(TiledError::Image)?;
+
+        if let Some(key) = self.transparent {
+            for y in 0..texture.height() {
+                for x in 0..texture.width() {
+                    let pixel = texture.get_pixel(x, y);
+                    if (pixel.red, pixel.green, pixel.blue) == (key.red, key.green, key.blue) {
+                        texture.set_pixel(x, y, Color::CLEAR);
+                    }
+                }
+            }
+        }
+
+        Ok(texture)
+    }
+
+    fn resolve_image(&mut self, directory: &Path) {
+        if let Some(image) = &mut self.image {
+            *image = directory.join(&image).to_string_lossy().into_owned();
+        }
+    }
+}
+
+fn is_json(path: &str) -> bool {
+    let extension = Path::new(path)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    matches!(
+        extension.as_deref(),
+        Some("tmj") | Some("tsj") | Some("json")
+    )
+}
+
+fn orientation(name: &str) -> Result<Orientation, TiledError> {
+    match name {
+        "orthogonal" => Ok(Orientation::Orthogonal),
+        "isometric" => Ok(Orientation::Isometric),
+        _ => Err(TiledError::Unsupported(format!("{} maps", name))),
+    }
+}
+
+/// Parses a Tiled color, `#aarrggbb` or `#rrggbb` with an optional `#`
+fn parse_color(text: &str) -> Option<Color> {
+    let digits = text.strip_prefix('#').unwrap_or(text);
+    let value = u32::from_str_radix(digits, 16).ok()?;
+    let [a, r, g, b] = value.to_be_bytes();
+    match digits.len() {
+        6 => Some(Color::from_rgb(r, g, b)),
+        8 => Some(Color::from_rgba(r, g, b, a)),
+        _ => None,
+    }
+}
+
+/// Decodes the tile data of a layer with a number of cells into global tile ids
+fn decode_gids(
+    text: &str,
+    encoding: &str,
+    compression: &str,
+    cells: usize,
+) -> Result<Vec<u32>, TiledError> {
+    match encoding {
+        "csv" => text
+            .split(',')
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|_| TiledError::InvalidFormat(format!("tile id '{}'", value)))
+            })
+            .collect(),
+        "base64" => {
+            let data = decode_base64(text)?;
+            // Compressed data never inflates past what the layer can hold
+            let limit = cells * 4;
+            let data = match compression {
+                "" => data,
+                "zlib" => miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(&data, limit)
+                    .map_err(|_| TiledError::Decompress)?,
+                "gzip" => gunzip(&data, limit)?,
+                _ => {
+                    return Err(TiledError::Unsupported(format!(
+                        "{} compression",
+                        compression
+                    )))
+                }
+            };
+
+            if data.len() % 4 != 0 {
+                return Err(TiledError::InvalidFormat("tile data length".to_string()));
+            }
+            Ok(data
+                .chunks_exact(4)
+                .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                .collect())
+        }
+        _ => Err(TiledError::Unsupported(format!("{} encoding", encoding))),
+    }
+}
+
+/// Decodes standard base64, whitespace is skipped
+fn decode_base64(text: &str) -> Result<Vec<u8>, TiledError> {
+    let mut data = Vec::with_capacity(text.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+
+    for c in text.bytes().filter(|c| !c.is_ascii_whitespace()) {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            _ => return Err(TiledError::InvalidFormat("base64 data".to_string())),
+        };
+
+        buffer = (buffer << 6 | value as u32) & 0xff_ffff;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            data.push((buffer >> bits) as u8);
+        }
+    }
+
+    Ok(data)
+}
+
+/// Inflates gzip data up to a size limit, the checksum is not verified
+fn gunzip(data: &[u8], limit: usize) -> Result<Vec<u8>, TiledError> {
+    if data.len() < 18 || data[0..3] != [0x1f, 0x8b, 8] {
+        return Err(TiledError::Decompress);
+    }
+
+    let flags = data[3];
+    let mut at = 10;
+    if flags & 0x04 != 0 {
+        let extra = u16::from_le_bytes([data[10], data[11]]) as usize;
+        at += 2 + extra;
+    }
+    // Zero terminated file name and comment
+    for flag in [0x08, 0x10] {
+        if flags & flag != 0 {
+            let end = data
+                .get(at..)
+                .and_then(|rest| rest.iter().position(|&byte| byte == 0))
+                .ok_or(TiledError::Decompress)?;
+            at += end + 1;
+        }
+    }
+    if flags & 0x02 != 0 {
+        at += 2;
+    }
+
+    let deflated = data.get(at..).ok_or(TiledError::Decompress)?;
+    miniz_oxide::inflate::decompress_to_vec_with_limit(deflated, limit)
+        .map_err(|_| TiledError::Decompress)
+}
+
+fn invalid(name: &str) -> TiledError {
+    TiledError::InvalidFormat(format!("'{}'", name))
+}
+
+// TMX and TSX
+
+/// Parses an attribute, a missing attribute gives the default
+fn attribute<T: FromStr>(element: &Element, name: &str, default: T) -> Result<T, TiledError> {
+    match element.attribute(name) {
+        Some(value) => value.trim().parse().map_err(|_| invalid(name)),
+        None => Ok(default),
+    }
+}
+
+fn required<T: FromStr>(element: &Element, name: &str) -> Result<T, TiledError> {
+    let value = element.attribute(name).ok_or_else(|| invalid(name))?;
+    value.trim().parse().map_err(|_| invalid(name))
+}
+
+fn text_attribute(element: &Element, name: &str) -> String {
+    element.attribute(name).unwrap_or("").to_string()
+}
+
+fn xml_tileset(element: &Element, first_gid: u32) -> Result<TiledTileset, TiledError> {
+    let image = element.child("image");
+    let mut tileset = TiledTileset {
+        first_gid,
+        source: None,
+        name: text_attribute(element, "name"),
+        tile_width: required(element, "tilewidth")?,
+        tile_height: required(element, "tileheight")?,
+        margin: attribute(element, "margin", 0)?,
+        spacing: attribute(element, "spacing", 0)?,
+        columns: attribute(element, "columns", 0)?,
+        tile_count: attribute(element, "tilecount", 0)?,
+        image: image
+            .and_then(|image| image.attribute("source"))
+            .map(String::from),
+        transparent: image
+            .and_then(|image| image.attribute("trans"))
+            .and_then(parse_color),
+        properties: xml_properties(element)?,
+        tiles: HashMap::new(),
+    };
+
+    for tile in element.children_named("tile") {
+        let mut animation = Vec::new();
+        if let Some(frames) = tile.child("animation") {
+            for frame in frames.children_named("frame") {
+                animation.push(TileFrame {
+                    tile: required(frame, "tileid")?,
+                    duration: required::<f32>(frame, "duration")? / 1000.0,
+                });
+            }
+        }
+
+        let class = tile.attribute("class").or_else(|| tile.attribute("type"));
+        tileset.tiles.insert(
+            required(tile, "id")?,
+            TiledTile {
+                class: class.unwrap_or("").to_string(),
+                properties: xml_properties(tile)?,
+                animation,
+            },
+        );
+    }
+
+    Ok(tileset)
+}
+
+fn xml_layers(
+    parent: &Element,
+    group: Group,
+    layers: &mut Vec<TiledLayer>,
+) -> Result<(), TiledError> {
+    for element in parent.children.iter() {
+        if !matches!(
+            element.name.as_str(),
+            "layer" | "objectgroup" | "imagelayer" | "group"
+        ) {
+            continue;
+        }
+
+        let inner = Group {
+            offset: (
+                group.offset.0 + attribute(element, "offsetx", 0.0)?,
+                group.offset.1 + attribute(element, "offsety", 0.0)?,
+            ),
+            opacity: group.opacity * attribute(element, "opacity", 1.0)?,
+            visible: group.visible && attribute(element, "visible", 1u8)? != 0,
+        };
+
+        let content = match element.name.as_str() {
+            "group" => {
+                xml_layers(element, inner, layers)?;
+                continue;
+            }
+            "layer" => {
+                let width = required(element, "width")?;
+                let height = required(element, "height")?;
+                let cells = cell_count(width, height)?;
+                let data = element.child("data").ok_or_else(|| invalid("data"))?;
+                if data.child("chunk").is_some() {
+                    return Err(TiledError::Unsupported("infinite maps".to_string()));
+                }
+
+                let gids = match data.attribute("encoding") {
+                    Some(encoding) => decode_gids(
+                        &data.text,
+                        encoding,
+                        data.attribute("compression").unwrap_or(""),
+                        cells,
+                    )?,
+                    None => data
+                        .children_named("tile")
+                        .map(|tile| attribute(tile, "gid", 0))
+                        .collect::<Result<_, _>>()?,
+                };
+                tile_content(width, height, gids)?
+            }
+            "objectgroup" => LayerContent::Objects(
+                element
+                    .children_named("object")
+                    .map(xml_object)
+                    .collect::<Result<_, _>>()?,
+            ),
+            _ => LayerContent::Image {
+                image: element
+                    .child("image")
+                    .map(|image| text_attribute(image, "source"))
+                    .unwrap_or_default(),
+                repeat_x: attribute(element, "repeatx", 0u8)? != 0,
+                repeat_y: attribute(element, "repeaty", 0u8)? != 0,
+            },
+        };
+
+        layers.push(TiledLayer {
+            name: text_attribute(element, "name"),
+            visible: inner.visible,
+            opacity: inner.opacity,
+            offset: inner.offset,
+            properties: xml_properties(element)?,
+            content,
+        });
+    }
+
+    Ok(())
+}
+
+fn xml_object(element: &Element) -> Result<TiledObject, TiledError> {
+    let points = |element: &Element| -> Result<Vec<(f32, f32)>, TiledError> {
+        element
+            .attribute("points")
+            .unwrap_or("")
+            .split_whitespace()
+            .map(|point| {
+                let (x, y) = point.split_once(',').ok_or_else(|| invalid("points"))?;
+                let x = x.parse().map_err(|_| invalid("points"))?;
+                let y = y.parse().map_err(|_| invalid("points"))?;
+                Ok((x, y))
+            })
+            .collect()
+    };
+
+    let shape = if element.child("ellipse").is_some() {
+        ObjectShape::Ellipse
+    } else if element.child("point").is_some() {
+        ObjectShape::Point
+    } else if let Some(polygon) = element.child("polygon") {
+        ObjectShape::Polygon(points(polygon)?)
+    } else if let Some(polyline) = element.child("polyline") {
+        ObjectShape::Polyline(points(polyline)?)
+    } else if let Some(text) = element.child("text") {
+        ObjectShape::Text(text.text.clone())
+    } else {
+        ObjectShape::Rectangle
+    };
+
+    let class = element
+        .attribute("class")
+        .or_else(|| element.attribute("type"));
+
+    Ok(TiledObject {
+        id: attribute(element, "id", 0)?,
+        name: text_attribute(element, "name"),
+        class: class.unwrap_or("").to_string(),
+        x: attribute(element, "x", 0.0)?,
+        y: attribute(element, "y", 0.0)?,
+        width: attribute(element, "width", 0.0)?,
+        height: attribute(element, "height", 0.0)?,
+        rotation: attribute(element, "rotation", 0.0)?,
+        visible: attribute(element, "visible", 1u8)? != 0,
+        gid: element
+            .attribute("gid")
+            .map(|gid| gid.parse().map_err(|_| invalid("gid")))
+            .transpose()?,
+        shape,
+        properties: xml_properties(element)?,
+    })
+}
+
+/// Reads the properties child of an element
+fn xml_properties(element: &Element) -> Result<Properties, TiledError> {
+    let mut properties = Properties::new();
+    let list = match element.child("properties") {
+        Some(list) => list,
+        None => return Ok(properties),
+    };
+
+    for property in list.children_named("property") {
+        let name = text_attribute(property, "name");
+        // Multiline strings are stored as text instead of an attribute
+        let text = property.attribute("value").unwrap_or(&property.text);
+        let bad = || invalid(&name);
+
+        let value = match property.attribute("type").unwrap_or("string") {
+            "int" => PropertyValue::Int(text.trim().parse().map_err(|_| bad())?),
+            "float" => PropertyValue::Float(text.trim().parse().map_err(|_| bad())?),
+            "bool" => PropertyValue::Bool(text.trim() == "true"),
+            "color" => PropertyValue::Color(parse_color(text).unwrap_or(Color::CLEAR)),
+            "file" => PropertyValue::File(text.to_string()),
+            "object" => PropertyValue::Object(text.trim().parse().map_err(|_| bad())?),
+            "class" => PropertyValue::Class(xml_properties(property)?),
+            _ => PropertyValue::String(text.to_string()),
+        };
+        properties.insert(name, value);
+    }
+
+    Ok(properties)
+}
+
+// TMJ and TSJ
+
+fn string<'a>(json: &'a Json, key: &str) -> Option<&'a str> {
+    json.get(key).and_then(Json::as_str)
+}
+
+fn text(json: &Json, key: &str) -> String {
+    string(json, key).unwrap_or("").to_string()
+}
+
+fn array<'a>(json: &'a Json, key: &str) -> &'a [Json] {
+    json.get(key).and_then(Json::as_array).unwrap_or(&[])
+}
+
+/// Reads a number, a missing key gives the default
+fn number(json: &Json, key: &str, default: f64) -> Result<f64, TiledError> {
+    match json.get(key) {
+        Some(value) => value.as_f64().ok_or_else(|| invalid(key)),
+        None => Ok(default),
+    }
+}
+
+fn required_number(json: &Json, key: &str) -> Result<f64, TiledError> {
+    json.get(key)
+        .and_then(Json::as_f64)
+        .ok_or_else(|| invalid(key))
+}
+
+fn boolean(json: &Json, key: &str, default: bool) -> bool {
+    json.get(key).and_then(Json::as_bool).unwrap_or(default)
+}
+
+fn json_tileset(json: &Json, first_gid: u32) -> Result<TiledTileset, TiledError> {
+    let mut tileset = TiledTileset {
+        first_gid,
+        source: None,
+        name: text(json, "name"),
+        tile_width: required_number(json, "tilewidth")? as u32,
+        tile_height: required_number(json, "tileheight")? as u32,
+        margin: number(json, "margin", 0.0)? as u32,
+        spacing: number(json, "spacing", 0.0)? as u32,
+        columns: number(json, "columns", 0.0)? as u32,
+        tile_count: number(json, "tilecount", 0.0)? as u32,
+        image: string(json, "image").map(String::from),
+        transparent: string(json, "transparentcolor").and_then(parse_color),
+        properties: json_properties(json)?,
+        tiles: HashMap::new(),
+    };
+
+    for tile in array(json, "tiles") {
+        let mut animation = Vec::new();
+        for frame in array(tile, "animation") {
+            animation.push(TileFrame {
+                tile: required_number(frame, "tileid")? as u32,
+                duration: required_number(frame, "duration")? as f32 / 1000.0,
+            });
+        }
+
+        let class = string(tile, "class").or_else(|| string(tile, "type"));
+        tileset.tiles.insert(
+            required_number(tile, "id")? as u32,
+            TiledTile {
+                class: class.unwrap_or("").to_string(),
+                properties: json_properties(tile)?,
+                animation,
+            },
+        );
+    }
+
+    Ok(tileset)
+}
+
+fn json_layers(
+    list: &[Json],
+    group: Group,
+    layers: &mut Vec<TiledLayer>,
+) -> Result<(), TiledError> {
+    for json in list.iter() {
+        let inner = Group {
+            offset: (
+                group.offset.0 + number(json, "offsetx", 0.0)? as f32,
+                group.offset.1 + number(json, "offsety", 0.0)? as f32,
+            ),
+            opacity: group.opacity * number(json, "opacity", 1.0)? as f32,
+            visible: group.visible && boolean(json, "visible", true),
+        };
+
+        let content = match string(json, "type").unwrap_or("") {
+            "group" => {
+                json_layers(array(json, "layers"), inner, layers)?;
+                continue;
+            }
+            "tilelayer" => {
+                if json.get("chunks").is_some() {
+                    return Err(TiledError::Unsupported("infinite maps".to_string()));
+                }
+
+                let width = required_number(json, "width")? as usize;
+                let height = required_number(json, "height")? as usize;
+                let cells = cell_count(width, height)?;
+                let gids = match json.get("data") {
+                    Some(Json::String(data)) => decode_gids(
+                        data,
+                        string(json, "encoding").unwrap_or("base64"),
+                        string(json, "compression").unwrap_or(""),
+                        cells,
+                    )?,
+                    Some(Json::Array(data)) => data
+                        .iter()
+                        .map(|gid| {
+                            gid.as_f64()
+                                .map(|gid| gid as u32)
+                                .ok_or_else(|| invalid("data"))
+                        })
+                        .collect::<Result<_, _>>()?,
+                    _ => return Err(invalid("data")),
+                };
+                tile_content(width, height, gids)?
+            }
+            "objectgroup" => LayerContent::Objects(
+                array(json, "objects")
+                    .iter()
+                    .map(json_object)
+                    .collect::<Result<_, _>>()?,
+            ),
+            "imagelayer" => LayerContent::Image {
+                image: text(json, "image"),
+                repeat_x: boolean(json, "repeatx", false),
+                repeat_y: boolean(json, "repeaty", false),
+            },
+            _ => return Err(invalid("type")),
+        };
+
+        layers.push(TiledLayer {
+            name: text(json, "name"),
+            visible: inner.visible,
+            opacity: inner.opacity,
+            offset: inner.offset,
+            properties: json_properties(json)?,
+            content,
+        });
+    }
+
+    Ok(())
+}
+
+fn json_object(json: &Json) -> Result<TiledObject, TiledError> {
+    let points = |key: &str| -> Result<Vec<(f32, f32)>, TiledError> {
+        array(json, key)
+            .iter()
+            .map(|point| {
+                Ok((
+                    required_number(point, "x")? as f32,
+                    required_number(point, "y")? as f32,
+                ))
+            })
+            .collect()
+    };
+
+    let shape = if boolean(json, "ellipse", false) {
+        ObjectShape::Ellipse
+    } else if boolean(json, "point", false) {
+        ObjectShape::Point
+    } else if json.get("polygon").is_some() {
+        ObjectShape::Polygon(points("polygon")?)
+    } else if json.get("polyline").is_some() {
+        ObjectShape::Polyline(points("polyline")?)
+    } else if let Some(text) = json.get("text") {
+        ObjectShape::Text(string(text, "text").unwrap_or("").to_string())
+    } else {
+        ObjectShape::Rectangle
+    };
+
+    let class = string(json, "class").or_else(|| string(json, "type"));
+
+    Ok(TiledObject {
+        id: number(json, "id", 0.0)? as u32,
+        name: text(json, "name"),
+        class: class.unwrap_or("").to_string(),
+        x: number(json, "x", 0.0)? as f32,
+        y: number(json, "y", 0.0)? as f32,
+        width: number(json, "width", 0.0)? as f32,
+        height: number(json, "height", 0.0)? as f32,
+        rotation: number(json, "rotation", 0.0)? as f32,
+        visible: boolean(json, "visible", true),
+        gid: json.get("gid").and_then(Json::as_f64).map(|gid| gid as u32),
+        shape,
+        properties: json_properties(json)?,
+    })
+}
+
+/// Reads the properties array of an object
+fn json_properties(json: &Json) -> Result<Properties, TiledError> {
+    let mut properties = Properties::new();
+    for property in array(json, "properties") {
+        let name = text(property, "name");
+        let value = property.get("value").unwrap_or(&Json::Null);
+        let bad = || invalid(&name);
+
+        let value = match string(property, "type").unwrap_or("string") {
+            "int" => PropertyValue::Int(value.as_i64().ok_or_else(bad)?),
+            "float" => PropertyValue::Float(value.as_f64().ok_or_else(bad)?),
+            "bool" => PropertyValue::Bool(value.as_bool().ok_or_else(bad)?),
+            "color" => {
+                PropertyValue::Color(value.as_str().and_then(parse_color).unwrap_or(Color::CLEAR))
+            }
+            "file" => PropertyValue::File(value.as_str().ok_or_else(bad)?.to_string()),
+            "object" => PropertyValue::Object(value.as_i64().ok_or_else(bad)? as u32),
+            "class" => json_class(value),
+            _ => PropertyValue::String(value.as_str().ok_or_else(bad)?.to_string()),
+        };
+        properties.insert(name, value);
+    }
+
+    Ok(properties)
+}
+
+/// Converts the members of a class value, which are stored without their types
+fn json_class(value: &Json) -> PropertyValue {
+    let mut members = Properties::new();
+    for (name, member) in value.as_object().unwrap_or(&[]) {
+        let member = match member {
+            Json::Bool(value) => PropertyValue::Bool(*value),
+            Json::Number(value) if value.fract() == 0.0 => PropertyValue::Int(*value as i64),
+            Json::Number(value) => PropertyValue::Float(*value),
+            Json::String(value) => PropertyValue::String(value.clone()),
+            Json::Object(_) => json_class(member),
+            _ => continue,
+        };
+        members.insert(name.clone(), member);
+    }
+    PropertyValue::Class(members)
+}
+
+/// Returns the number of cells of a map or layer, sizes over `MAX_CELLS` are rejected
+fn cell_count(width: usize, height: usize) -> Result<usize, TiledError> {
+    width
+        .checked_mul(height)
+        .filter(|&cells| cells <= MAX_CELLS)
+        .ok_or_else(|| TiledError::InvalidFormat(format!("{}x{} cells", width, height)))
+}
+
+/// Checks that the tile layers are the size of the map, `build_tilemap` gives every layer
+/// the map's cells
+fn check_layer_sizes(map: &TiledMap) -> Result<(), TiledError> {
+    for layer in map.layers.iter() {
+        if let LayerContent::Tiles { width, height, .. } = layer.content {
+            if (width, height) != (map.width, map.height) {
+                return Err(TiledError::InvalidFormat(format!(
+                    "{}x{} layer in a {}x{} map",
+                    width, height, map.width, map.height
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the tile data fills the layer
+fn tile_content(width: usize, height: usize, gids: Vec<u32>) -> Result<LayerContent, TiledError> {
+    if gids.len() != cell_count(width, height)? {
+        return Err(TiledError::InvalidFormat(format!(
+            "{} tiles in a {}x{} layer",
+            gids.len(),
+            width,
+            height
+        )));
+    }
+
+    Ok(LayerContent::Tiles {
+        width,
+        height,
+        gids,
+    })
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod tiled_tests {
+
+    use super::*;
+
+    const TMX_MAP: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="3" height="2" tilewidth="16" tileheight="16" infinite="0" backgroundcolor="#80ff0000">
+ <properties>
+  <property name="title" value="Caves"/>
+  <property name="gravity" type="float" value="9.5"/>
+  <property name="lives" type="int" value="3"/>
+  <property name="dark" type="bool" value="true"/>
+  <property name="tint" type="color" value="#ff00ff00"/>
+  <property name="intro">Line one
+Line two</property>
+  <property name="spawn" type="class" propertytype="Spawn">
+   <properties>
+    <property name="count" type="int" value="2"/>
+   </properties>
+  </property>
+ </properties>
+ <tileset firstgid="1" name="terrain" tilewidth="16" tileheight="16" spacing="1" margin="1" tilecount="4" columns="2">
+  <image source="terrain.png" trans="ff00ff" width="35" height="35"/>
+  <tile id="1" type="water">
+   <properties>
+    <property name="wet" type="bool" value="true"/>
+   </properties>
+   <animation>
+    <frame tileid="1" duration="200"/>
+    <frame tileid="3" duration="100"/>
+   </animation>
+  </tile>
+ </tileset>
+ <tileset firstgid="5" source="items.tsx"/>
+ <layer id="1" name="ground" width="3" height="2">
+  <data encoding="csv">
+1,2,0,
+2147483652,5,1610612737
+</data>
+ </layer>
+ <group id="2" name="decor" offsetx="4" opacity="0.5">
+  <objectgroup id="3" name="things" offsety="2" visible="0">
+   <object id="1" name="door" type="exit" x="8" y="16" width="16" height="32">
+    <properties>
+     <property name="to" value="level2"/>
+    </properties>
+   </object>
+   <object id="2" x="1" y="2">
+    <point/>
+   </object>
+   <object id="3" x="0" y="0" rotation="45">
+    <polygon points="0,0 10,0 5,-8.5"/>
+   </object>
+   <object id="4" gid="2147483653" x="32" y="32" width="16" height="16"/>
+  </objectgroup>
+  <imagelayer id="4" name="sky" repeatx="1">
+   <image source="sky.png" width="64" height="32"/>
+  </imagelayer>
+ </group>
+</map>
+"##;
+
+    const TSX_TILESET: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" name="items" tilewidth="16" tileheight="32" tilecount="2" columns="2">
+ <image source="../art/items.png" width="32" height="32"/>
+</tileset>
+"#;
+
+    fn encode_base64(data: &[u8]) -> String {
+        let alphabet = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut text = String::new();
+        for group in data.chunks(3) {
+            let bytes = [
+                group[0],
+                *group.get(1).unwrap_or(&0),
+                *group.get(2).unwrap_or(&0),
+            ];
+            let value = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+            for i in 0..4 {
+                if i <= group.len() {
+                    text.push(alphabet[(value >> (18 - 6 * i) & 63) as usize] as char);
+                } else {
+                    text.push('=');
+                }
+            }
+        }
+        text
+    }
+
+    fn gid_bytes(gids: &[u32]) -> Vec<u8> {
+        gids.iter().flat_map(|gid| gid.to_le_bytes()).collect()
+    }
+
+    /// Wraps raw deflate data in a gzip header with a file name and an unchecked trailer
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut file = vec![0x1f, 0x8b, 8, 0x08, 0, 0, 0, 0, 0, 3];
+        file.extend_from_slice(b"layer.bin\0");
+        file.extend(miniz_oxide::deflate::compress_to_vec(data, 6));
+        file.extend_from_slice(&[0; 8]);
+        file
+    }
+
+    #[test]
+    fn parse_tmx_map() {
+        let map = TiledMap::parse_tmx(TMX_MAP).unwrap();
+        assert_eq!(map.orientation, Orientation::Orthogonal);
+        assert_eq!((map.width, map.height, map.tile_width), (3, 2, 16));
+        assert_eq!(map.background, Some(Color::from_rgba(255, 0, 0, 128)));
+
+        let property = |name: &str| map.properties.get(name).cloned();
+        assert_eq!(
+            property("title"),
+            Some(PropertyValue::String("Caves".to_string()))
+        );
+        assert_eq!(property("gravity"), Some(PropertyValue::Float(9.5)));
+        assert_eq!(property("lives"), Some(PropertyValue::Int(3)));
+        assert_eq!(property("dark"), Some(PropertyValue::Bool(true)));
+        assert_eq!(property("tint"), Some(PropertyValue::Color(Color::GREEN)));
+        assert_eq!(
+            property("intro"),
+            Some(PropertyValue::String("Line one\nLine two".to_string()))
+        );
+        let mut spawn = Properties::new();
+        spawn.insert("count".to_string(), PropertyValue::Int(2));
+        assert_eq!(property("spawn"), Some(PropertyValue::Class(spawn)));
+
+        let terrain = &map.tilesets[0];
+        assert_eq!(
+            (terrain.margin, terrain.spacing, terrain.columns),
+            (1, 1, 2)
+        );
+        assert_eq!(terrain.image.as_deref(), Some("terrain.png"));
+        assert_eq!(terrain.transparent, Some(Color::MAGENTA));
+        let water = &terrain.tiles[&1];
+        assert_eq!(water.class, "water");
+        assert_eq!(water.properties["wet"], PropertyValue::Bool(true));
+        assert_eq!(
+            water.animation,
+            [
+                TileFrame {
+                    tile: 1,
+                    duration: 0.2
+                },
+                TileFrame {
+                    tile: 3,
+                    duration: 0.1
+                }
+            ]
+        );
+        assert_eq!(map.tilesets[1].first_gid, 5);
+        assert_eq!(map.tilesets[1].source.as_deref(), Some("items.tsx"));
+
+        assert_eq!(map.layers.len(), 3);
+        assert_eq!(
+            map.layers[0].content,
+            LayerContent::Tiles {
+                width: 3,
+                height: 2,
+                gids: vec![1, 2, 0, 0x8000_0004, 5, 0x6000_0001],
+            }
+        );
+
+        // Groups pass their offset, opacity and visibility on
+        let things = map.layer_named("things").unwrap();
+        assert_eq!(things.offset, (4.0, 2.0));
+        assert_eq!(things.opacity, 0.5);
+        assert!(!things.visible);
+        let objects = match &things.content {
+            LayerContent::Objects(objects) => objects,
+            _ => panic!("expected objects"),
+        };
+        assert_eq!(objects.len(), 4);
+        assert_eq!(
+            (objects[0].name.as_str(), objects[0].class.as_str()),
+            ("door", "exit")
+        );
+        assert_eq!((objects[0].width, objects[0].height), (16.0, 32.0));
+        assert_eq!(
+            objects[0].properties["to"],
+            PropertyValue::String("level2".to_string())
+        );
+        assert_eq!(objects[1].shape, ObjectShape::Point);
+        assert_eq!(objects[2].rotation, 45.0);
+        assert_eq!(
+            objects[2].shape,
+            ObjectShape::Polygon(vec![(0.0, 0.0), (10.0, 0.0), (5.0, -8.5)])
+        );
+        assert_eq!(objects[3].shape, ObjectShape::Rectangle);
+        assert_eq!(
+            map.tile(objects[3].gid.unwrap()),
+            Some(Tile::new(1, 0).flipped_x())
+        );
+
+        let sky = map.layer_named("sky").unwrap();
+        assert_eq!(sky.offset, (4.0, 0.0));
+        assert!(sky.visible);
+        assert_eq!(
+            sky.content,
+            LayerContent::Image {
+                image: "sky.png".to_string(),
+                repeat_x: true,
+                repeat_y: false,
+            }
+        );
+    }
+
+    #[test]
+    fn build_tilemap() {
+        let mut map = TiledMap::parse_tmx(TMX_MAP).unwrap();
+        map.tilesets[1] = TiledTileset::parse_tsx(TSX_TILESET, 5).unwrap();
+        assert_eq!(map.tilesets[1].tile_height, 32);
+        assert_eq!(map.tilesets[1].image.as_deref(), Some("../art/items.png"));
+
+        let tilemap = map.build_tilemap(vec![Texture::new(35, 35), Texture::new(32, 32)]);
+        assert_eq!(tilemap.tilesets().len(), 2);
+        assert_eq!(tilemap.tileset(0).unwrap().count(), 4);
+        assert!(tilemap.tileset(0).unwrap().is_animated(1));
+        assert_eq!(tilemap.tileset(1).unwrap().tile_height(), 32);
+
+        // Only tile layers become layers of the tilemap
+        assert_eq!(tilemap.layers().len(), 1);
+        let ground = tilemap.layer_named("ground").unwrap();
+        assert_eq!(ground.get(0, 0), Some(Tile::new(0, 0)));
+        assert_eq!(ground.get(2, 0), None);
+        assert_eq!(ground.get(0, 1), Some(Tile::new(0, 3).flipped_x()));
+        assert_eq!(ground.get(1, 1), Some(Tile::new(1, 0)));
+        let rotated = ground.get(2, 1).unwrap();
+        assert!(rotated.flip_y && rotated.flip_diagonal && !rotated.flip_x);
+    }
+
+    #[test]
+    fn decode_layer_data() {
+        let gids = [1, 0, 0x8000_0002, 70000];
+        let bytes = gid_bytes(&gids);
+
+        assert_eq!(
+            decode_gids(" 1,0,\n2147483650,70000\n", "csv", "", 4).unwrap(),
+            gids
+        );
+        assert_eq!(
+            decode_gids(&format!("\n  {}\n", encode_base64(&bytes)), "base64", "", 4).unwrap(),
+            gids
+        );
+
+        let zlib = miniz_oxide::deflate::compress_to_vec_zlib(&bytes, 6);
+        assert_eq!(
+            decode_gids(&encode_base64(&zlib), "base64", "zlib", 4).unwrap(),
+            gids
+        );
+        assert_eq!(
+            decode_gids(&encode_base64(&gzip(&bytes)), "base64", "gzip", 4).unwrap(),
+            gids
+        );
+
+        assert!(matches!(
+            decode_gids(&encode_base64(&bytes), "base64", "zstd", 4),
+            Err(TiledError::Unsupported(_))
+        ));
+        assert!(matches!(
+            decode_gids("AAAA", "base64", "zlib", 4),
+            Err(TiledError::Decompress)
+        ));
+        assert!(decode_gids("1,x", "csv", "", 4).is_err());
+
+        // Compressed data may not inflate past the cells of the layer
+        assert!(matches!(
+            decode_gids(&encode_base64(&zlib), "base64", "zlib", 3),
+            Err(TiledError::Decompress)
+        ));
+        assert!(matches!(
+            decode_gids(&encode_base64(&gzip(&bytes)), "base64", "gzip", 3),
+            Err(TiledError::Decompress)
+        ));
+    }
+
+    #[test]
+    fn parse_tmj_map() {
+        let data = encode_base64(&gzip(&gid_bytes(&[3, 0, 1, 0x4000_0002])));
+        let text = format!(
+            r##"{{
+  "type": "map", "orientation": "isometric", "infinite": false,
+  "width": 2, "height": 2, "tilewidth": 32, "tileheight": 16,
+  "properties": [
+    {{"name": "music", "type": "file", "value": "cave.ogg"}},
+    {{"name": "boss", "type": "object", "value": 7}},
+    {{"name": "light", "type": "class", "propertytype": "Light",
+      "value": {{"radius": 2.5, "on": true, "steps": 4, "name": "torch"}}}}
+  ],
+  "tilesets": [
+    {{"firstgid": 1, "name": "iso", "tilewidth": 32, "tileheight": 32,
+      "tilecount": 4, "columns": 4, "image": "iso.png", "transparentcolor": "#00ff00",
+      "tiles": [{{"id": 2, "class": "lava",
+                  "animation": [{{"tileid": 2, "duration": 50}}, {{"tileid": 3, "duration": 50}}]}}]}},
+    {{"firstgid": 5, "source": "extra.tsj"}}
+  ],
+  "layers": [
+    {{"type": "tilelayer", "name": "floor", "width": 2, "height": 2,
+      "encoding": "base64", "compression": "gzip", "data": "{}"}},
+    {{"type": "group", "name": "upper", "offsetx": 8, "visible": false, "layers": [
+      {{"type": "tilelayer", "name": "walls", "width": 2, "height": 2, "opacity": 0.25,
+        "data": [0, 5, 0, 0]}},
+      {{"type": "objectgroup", "name": "zones", "objects": [
+        {{"id": 1, "type": "trigger", "x": 4, "y": 8, "width": 10, "height": 6, "ellipse": true,
+          "properties": [{{"name": "once", "type": "bool", "value": false}}]}},
+        {{"id": 2, "x": 0, "y": 0, "polyline": [{{"x": 0, "y": 0}}, {{"x": 3, "y": 4}}]}},
+        {{"id": 3, "x": 1, "y": 1, "text": {{"text": "Hello", "wrap": true}}}}
+      ]}}
+    ]}},
+    {{"type": "imagelayer", "name": "clouds", "image": "clouds.png", "repeaty": true}}
+  ]
+}}"##,
+            data
+        );
+
+        let map = TiledMap::parse_tmj(&text).unwrap();
+        assert_eq!(map.orientation, Orientation::Isometric);
+        assert_eq!(
+            map.properties["music"],
+            PropertyValue::File("cave.ogg".to_string())
+        );
+        assert_eq!(map.properties["boss"], PropertyValue::Object(7));
+        let light = match &map.properties["light"] {
+            PropertyValue::Class(members) => members,
+            _ => panic!("expected a class"),
+        };
+        assert_eq!(light["radius"], PropertyValue::Float(2.5));
+        assert_eq!(light["on"], PropertyValue::Bool(true));
+        assert_eq!(light["steps"], PropertyValue::Int(4));
+        assert_eq!(light["name"], PropertyValue::String("torch".to_string()));
+
+        let iso = &map.tilesets[0];
+        assert_eq!(iso.transparent, Some(Color::GREEN));
+        assert_eq!(iso.tiles[&2].class, "lava");
+        assert_eq!(iso.tiles[&2].animation.len(), 2);
+        assert_eq!(map.tilesets[1].source.as_deref(), Some("extra.tsj"));
+
+        assert_eq!(
+            map.layers[0].content,
+            LayerContent::Tiles {
+                width: 2,
+                height: 2,
+                gids: vec![3, 0, 1, 0x4000_0002],
+            }
+        );
+        let walls = map.layer_named("walls").unwrap();
+        assert_eq!(
+            (walls.offset, walls.opacity, walls.visible),
+            ((8.0, 0.0), 0.25, false)
+        );
+
+        let objects = match &map.layer_named("zones").unwrap().content {
+            LayerContent::Objects(objects) => objects.clone(),
+            _ => panic!("expected objects"),
+        };
+        assert_eq!(objects[0].shape, ObjectShape::Ellipse);
+        assert_eq!(objects[0].class, "trigger");
+        assert_eq!(objects[0].properties["once"], PropertyValue::Bool(false));
+        assert_eq!(
+            objects[1].shape,
+            ObjectShape::Polyline(vec![(0.0, 0.0), (3.0, 4.0)])
+        );
+        assert_eq!(objects[2].shape, ObjectShape::Text("Hello".to_string()));
+        assert!(matches!(
+            map.layer_named("clouds").unwrap().content,
+            LayerContent::Image {
+                repeat_y: true,
+                repeat_x: false,
+                ..
+            }
+        ));
+
+        let tilemap = map.build_tilemap(vec![Texture::new(128, 32), Texture::new(32, 32)]);
+        assert_eq!(tilemap.orientation(), Orientation::Isometric);
+        assert_eq!(
+            tilemap.layer(0).unwrap().get(1, 1),
+            Some(Tile::new(0, 1).flipped_y())
+        );
+    }
+
+    #[test]
+    fn reject_unsupported_maps() {
+        let hexagonal =
+            r#"<map orientation="hexagonal" width="1" height="1" tilewidth="8" tileheight="8"/>"#;
+        assert!(matches!(
+            TiledMap::parse_tmx(hexagonal),
+            Err(TiledError::Unsupported(_))
+        ));
+        let infinite =
+            r#"{"orientation": "orthogonal", "infinite": true, "width": 1, "height": 1}"#;
+        assert!(matches!(
+            TiledMap::parse_tmj(infinite),
+            Err(TiledError::Unsupported(_))
+        ));
+
+        let short = r#"<map width="2" height="2" tilewidth="8" tileheight="8">
+            <layer width="2" height="2"><data encoding="csv">1,2,3</data></layer></map>"#;
+        assert!(matches!(
+            TiledMap::parse_tmx(short),
+            Err(TiledError::InvalidFormat(_))
+        ));
+        assert!(matches!(
+            TiledMap::parse_tmx("<map"),
+            Err(TiledError::Parse(_))
+        ));
+
+        let huge = format!(
+            r#"<map width="{0}" height="{0}" tilewidth="8" tileheight="8"/>"#,
+            usize::MAX
+        );
+        assert!(matches!(
+            TiledMap::parse_tmx(&huge),
+            Err(TiledError::InvalidFormat(_))
+        ));
+        let huge_layer = r#"{"width": 1, "height": 1, "tilewidth": 8, "tileheight": 8,
+            "layers": [{"type": "tilelayer", "width": 65536, "height": 65536, "data": ""}]}"#;
+        assert!(matches!(
+            TiledMap::parse_tmj(huge_layer),
+            Err(TiledError::InvalidFormat(_))
+        ));
+        let small_layer = r#"<map width="2" height="2" tilewidth="8" tileheight="8">
+            <layer width="1" height="1"><data encoding="csv">1</data></layer></map>"#;
+        assert!(matches!(
+            TiledMap::parse_tmx(small_layer),
+            Err(TiledError::InvalidFormat(_))
+        ));
+    }
+
+    #[test]
+    fn load_external_tilesets() {
+        let directory = std::env::temp_dir().join(format!("walrust_tiled_{}", std::process::id()));
+        std::fs::create_dir_all(directory.join("sets")).unwrap();
+
+        let map = r#"<map width="1" height="1" tilewidth="16" tileheight="16">
+            <tileset firstgid="1" source="sets/items.tsx"/>
+            <imagelayer name="back"><image source="back.png"/></imagelayer>
+            <layer name="main" width="1" height="1"><data><tile gid="2"/></data></layer>
+        </map>"#;
+        std::fs::write(directory.join("level.tmx"), map).unwrap();
+        std::fs::write(directory.join("sets/items.tsx"), TSX_TILESET).unwrap();
+
+        let path = directory.join("level.tmx");
+        let loaded = TiledMap::load(&path.to_string_lossy());
+        std::fs::remove_dir_all(&directory).unwrap();
+        let map = loaded.unwrap();
+
+        let tileset = &map.tilesets[0];
+        assert_eq!(tileset.name, "items");
+        assert_eq!(tileset.source.as_deref(), Some("sets/items.tsx"));
+        let image = directory.join("sets").join("../art/items.png");
+        assert_eq!(tileset.image, Some(image.to_string_lossy().into_owned()));
+
+        let back = &map.layer_named("back").unwrap().content;
+        let image = directory.join("back.png").to_string_lossy().into_owned();
+        assert!(matches!(back, LayerContent::Image { image: path, .. } if *path == image));
+        assert_eq!(
+            map.layer_named("main").unwrap().content,
+            LayerContent::Tiles {
+                width: 1,
+                height: 1,
+                gids: vec![2],
+            }
+        );
+    }
+}
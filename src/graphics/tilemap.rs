@@ -0,0 +1,974 @@
+#![allow(dead_code)]
+
+use std::{collections::HashMap, f32::consts::FRAC_PI_2, ops::Range};
+
+use super::{camera::Camera, renderer::Rect, sprite::Sprite, texture::Texture};
+
+/// Width and height of a chunk in tiles
+pub const CHUNK_SIZE: usize = 16;
+
+/// Floats per vertex of a chunk mesh, the position followed by the texture coordinates
+pub(crate) const VERTEX_SIZE: usize = 4;
+
+/// How the cells of a `Tilemap` are laid out on screen.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Orientation {
+    /// Cells are rectangles in rows and columns
+    #[default]
+    Orthogonal,
+    /// Cells are diamonds, x goes down to the right and y down to the left
+    Isometric,
+}
+
+/// Tile shown during part of a tile animation.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TileFrame {
+    pub tile: u32,
+    /// Time the frame is shown in seconds
+    pub duration: f32,
+}
+
+/// Texture sliced into a grid of equally sized tiles, numbered row by row.
+pub struct Tileset {
+    sprite: Sprite,
+    tile_width: u32,
+    tile_height: u32,
+    /// Pixels around the tiles at the edge of the texture
+    margin: u32,
+    /// Pixels between neighbouring tiles
+    spacing: u32,
+    columns: u32,
+    count: u32,
+    animations: HashMap<u32, Vec<TileFrame>>,
+}
+
+/// Tile placed in a layer.
+/// The flags work like in Tiled: the diagonal flip swaps x and y and happens before the others.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Tile {
+    /// Index into the tilesets of the `Tilemap`
+    pub tileset: usize,
+    /// Index of the tile in its tileset
+    pub index: u32,
+    pub flip_x: bool,
+    pub flip_y: bool,
+    pub flip_diagonal: bool,
+}
+
+/// Grid of tiles the size of the map.
+/// The grid is split into chunks of `CHUNK_SIZE` tiles that each keep a mesh for the GPU.
+pub struct TileLayer {
+    pub name: String,
+    pub visible: bool,
+    /// From 0 to 1, multiplied with the alpha of the tiles
+    pub opacity: f32,
+    /// Moves the layer by pixels when drawn
+    pub offset: (f32, f32),
+    width: usize,
+    height: usize,
+    tiles: Vec<Option<Tile>>,
+    chunk_columns: usize,
+    chunks: Vec<Chunk>,
+}
+
+/// Meshes of one chunk, one per tileset used in it.
+pub(crate) struct Chunk {
+    pub meshes: Vec<ChunkMesh>,
+    /// Tiles changed since the meshes were built
+    dirty: bool,
+    /// Animated tiles in the chunk as tileset and tile index
+    animated: Vec<(usize, u32)>,
+}
+
+/// Triangles for all tiles of a chunk that use one tileset.
+pub(crate) struct ChunkMesh {
+    pub tileset: usize,
+    /// Two triangles per tile in world pixels, `VERTEX_SIZE` floats per vertex
+    pub vertices: Vec<f32>,
+    /// Vertex buffer on the GPU, 0 when no OpenGL context is loaded
+    pub vbo: u32,
+}
+
+/// Map made of tile layers drawn on top of each other on a grid.
+/// Change tiles through `TileLayer::set` and call `update` once per frame, which advances
+/// animated tiles and rebuilds the meshes of the chunks that changed.
+pub struct Tilemap {
+    orientation: Orientation,
+    width: usize,
+    height: usize,
+    tile_width: u32,
+    tile_height: u32,
+    tilesets: Vec<Tileset>,
+    layers: Vec<TileLayer>,
+    /// Seconds passed, drives the tile animations
+    time: f32,
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl Tileset {
+    /// Slices a texture into tiles of width and height and uploads it to the GPU
+    pub fn new(texture: Texture, tile_width: u32, tile_height: u32) -> Tileset {
+        Tileset::with_spacing(texture, tile_width, tile_height, 0, 0)
+    }
+
+    /// Slices a texture into tiles with a margin around them and spacing between them
+    pub fn with_spacing(
+        texture: Texture,
+        tile_width: u32,
+        tile_height: u32,
+        margin: u32,
+        spacing: u32,
+    ) -> Tileset {
+        // The first tile takes its size and every other one its size and the spacing.
+        // Margin and spacing come from files, so out of range values must not overflow.
+        let fit = |size: usize, tile: u32| {
+            let rest = (size as u32)
+                .checked_sub(margin.saturating_mul(2))
+                .and_then(|inside| inside.checked_sub(tile));
+            match rest {
+                Some(rest) => rest / tile.saturating_add(spacing).max(1) + 1,
+                None => 0,
+            }
+        };
+        let columns = fit(texture.width(), tile_width);
+        let rows = fit(texture.height(), tile_height);
+
+        Tileset {
+            sprite: Sprite::from_texture(texture),
+            tile_width,
+            tile_height,
+            margin,
+            spacing,
+            columns,
+            count: columns.saturating_mul(rows),
+            animations: HashMap::new(),
+        }
+    }
+}
+
+impl Tile {
+    pub const fn new(tileset: usize, index: u32) -> Tile {
+        Tile {
+            tileset,
+            index,
+            flip_x: false,
+            flip_y: false,
+            flip_diagonal: false,
+        }
+    }
+
+    /// Creates the tile that shows a 2x2 matrix applied to the unflipped tile.
+    /// The matrix maps x to `m[0] * x + m[1] * y` and y to `m[2] * x + m[3] * y`.
+    fn from_matrix(tileset: usize, index: u32, m: [f32; 4]) -> Tile {
+        let flip_diagonal = m[0] == 0.0;
+        let (x, y) = if flip_diagonal {
+            (m[1], m[2])
+        } else {
+            (m[0], m[3])
+        };
+
+        Tile {
+            tileset,
+            index,
+            flip_x: x < 0.0,
+            flip_y: y < 0.0,
+            flip_diagonal,
+        }
+    }
+}
+
+impl TileLayer {
+    /// Creates an empty layer of width and height tiles
+    pub fn new(name: &str, width: usize, height: usize) -> TileLayer {
+        let chunk_columns = width.div_ceil(CHUNK_SIZE);
+        let chunk_rows = height.div_ceil(CHUNK_SIZE);
+
+        TileLayer {
+            name: name.to_string(),
+            visible: true,
+            opacity: 1.0,
+            offset: (0.0, 0.0),
+            width,
+            height,
+            tiles: vec![None; width * height],
+            chunk_columns,
+            chunks: (0..chunk_columns * chunk_rows)
+                .map(|_| Chunk {
+                    meshes: Vec::new(),
+                    dirty: true,
+                    animated: Vec::new(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Tilemap {
+    /// Creates a map of width and height tiles with the size of a grid cell in pixels
+    pub fn new(width: usize, height: usize, tile_width: u32, tile_height: u32) -> Tilemap {
+        Tilemap {
+            orientation: Orientation::Orthogonal,
+            width,
+            height,
+            tile_width,
+            tile_height,
+            tilesets: Vec::new(),
+            layers: Vec::new(),
+            time: 0.0,
+        }
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl Tileset {
+    /// Returns the region of a tile in the texture, `None` if there is no such tile
+    pub fn tile_rect(&self, tile: u32) -> Option<Rect> {
+        if tile >= self.count {
+            return None;
+        }
+
+        let (column, row) = (tile % self.columns, tile / self.columns);
+        let offset = |index: u32, size: u32| {
+            let step = size.saturating_add(self.spacing);
+            self.margin.saturating_add(index.saturating_mul(step)) as i32
+        };
+        Some(Rect::new(
+            offset(column, self.tile_width),
+            offset(row, self.tile_height),
+            self.tile_width,
+            self.tile_height,
+        ))
+    }
+
+    /// Animates a tile by cycling through frames, an empty list removes the animation
+    pub fn set_animation(&mut self, tile: u32, frames: Vec<TileFrame>) {
+        if frames.is_empty() {
+            self.animations.remove(&tile);
+        } else {
+            self.animations.insert(tile, frames);
+        }
+    }
+
+    pub fn animation(&self, tile: u32) -> Option<&[TileFrame]> {
+        self.animations.get(&tile).map(Vec::as_slice)
+    }
+
+    pub fn is_animated(&self, tile: u32) -> bool {
+        self.animations.contains_key(&tile)
+    }
+
+    /// Returns the tile shown in place of a tile at a time in seconds
+    pub fn frame_at(&self, tile: u32, time: f32) -> u32 {
+        let frames = match self.animations.get(&tile) {
+            Some(frames) => frames,
+            None => return tile,
+        };
+
+        let total: f32 = frames.iter().map(|frame| frame.duration).sum();
+        if total <= 0.0 {
+            return frames[0].tile;
+        }
+
+        let mut elapsed = time.rem_euclid(total);
+        for frame in frames.iter() {
+            if elapsed < frame.duration {
+                return frame.tile;
+            }
+            elapsed -= frame.duration;
+        }
+        frames[frames.len() - 1].tile
+    }
+
+    pub fn sprite(&self) -> &Sprite {
+        &self.sprite
+    }
+
+    pub fn tile_width(&self) -> u32 {
+        self.tile_width
+    }
+
+    pub fn tile_height(&self) -> u32 {
+        self.tile_height
+    }
+
+    pub fn columns(&self) -> u32 {
+        self.columns
+    }
+
+    /// Returns the number of tiles
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+}
+
+impl Tile {
+    /// Returns the tile mirrored left to right
+    pub fn flipped_x(self) -> Tile {
+        let m = self.matrix();
+        Tile::from_matrix(self.tileset, self.index, [-m[0], -m[1], m[2], m[3]])
+    }
+
+    /// Returns the tile mirrored top to bottom
+    pub fn flipped_y(self) -> Tile {
+        let m = self.matrix();
+        Tile::from_matrix(self.tileset, self.index, [m[0], m[1], -m[2], -m[3]])
+    }
+
+    /// Returns the tile turned a quarter clockwise
+    pub fn rotated_cw(self) -> Tile {
+        let m = self.matrix();
+        Tile::from_matrix(self.tileset, self.index, [-m[2], -m[3], m[0], m[1]])
+    }
+
+    /// Returns the tile turned a quarter counterclockwise
+    pub fn rotated_ccw(self) -> Tile {
+        let m = self.matrix();
+        Tile::from_matrix(self.tileset, self.index, [m[2], m[3], -m[0], -m[1]])
+    }
+
+    /// Returns the matrix that maps the unflipped tile to the shown one, around its center
+    fn matrix(&self) -> [f32; 4] {
+        let x = if self.flip_x { -1.0 } else { 1.0 };
+        let y = if self.flip_y { -1.0 } else { 1.0 };
+        if self.flip_diagonal {
+            [0.0, x, y, 0.0]
+        } else {
+            [x, 0.0, 0.0, y]
+        }
+    }
+
+    /// Returns the point of the unflipped tile shown at a point of the drawn tile.
+    /// Both points are relative to the tile center and range from -0.5 to 0.5.
+    pub(crate) fn source_point(&self, point: (f32, f32)) -> (f32, f32) {
+        // The matrix only swaps and negates, so its inverse is its transpose
+        let m = self.matrix();
+        (
+            m[0] * point.0 + m[2] * point.1,
+            m[1] * point.0 + m[3] * point.1,
+        )
+    }
+
+    /// Returns the `DrawParams` rotation and scale that flip the tile around its center
+    pub(crate) fn rotation_and_scale(&self) -> (f32, (f32, f32)) {
+        let m = self.matrix();
+        if self.flip_diagonal {
+            (FRAC_PI_2, (m[2], -m[1]))
+        } else {
+            (0.0, (m[0], m[3]))
+        }
+    }
+}
+
+impl TileLayer {
+    pub fn get(&self, x: usize, y: usize) -> Option<Tile> {
+        if x < self.width && y < self.height {
+            self.tiles[y * self.width + x]
+        } else {
+            None
+        }
+    }
+
+    /// Places or removes a tile, the mesh of its chunk is rebuilt on the next update
+    pub fn set(&mut self, x: usize, y: usize, tile: Option<Tile>) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+
+        let cell = &mut self.tiles[y * self.width + x];
+        if *cell != tile {
+            *cell = tile;
+            let chunk = (y / CHUNK_SIZE) * self.chunk_columns + x / CHUNK_SIZE;
+            self.chunks[chunk].dirty = true;
+        }
+    }
+
+    /// Removes every tile
+    pub fn clear(&mut self) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                self.set(x, y, None);
+            }
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the chunk at a chunk column and row
+    pub(crate) fn chunk(&self, column: usize, row: usize) -> Option<&Chunk> {
+        if column < self.chunk_columns {
+            self.chunks.get(row * self.chunk_columns + column)
+        } else {
+            None
+        }
+    }
+
+    fn mark_dirty(&mut self) {
+        for chunk in self.chunks.iter_mut() {
+            chunk.dirty = true;
+        }
+    }
+}
+
+impl Tilemap {
+    /// Adds a tileset and returns the index tiles refer to it by
+    pub fn add_tileset(&mut self, tileset: Tileset) -> usize {
+        self.tilesets.push(tileset);
+        self.tilesets.len() - 1
+    }
+
+    pub fn tileset(&self, index: usize) -> Option<&Tileset> {
+        self.tilesets.get(index)
+    }
+
+    /// Returns a tileset for changing its animations, every chunk is rebuilt on the next update
+    pub fn tileset_mut(&mut self, index: usize) -> Option<&mut Tileset> {
+        for layer in self.layers.iter_mut() {
+            layer.mark_dirty();
+        }
+        self.tilesets.get_mut(index)
+    }
+
+    pub fn tilesets(&self) -> &[Tileset] {
+        &self.tilesets
+    }
+
+    /// Adds an empty layer on top of the others and returns its index
+    pub fn add_layer(&mut self, name: &str) -> usize {
+        self.layers
+            .push(TileLayer::new(name, self.width, self.height));
+        self.layers.len() - 1
+    }
+
+    pub fn layer(&self, index: usize) -> Option<&TileLayer> {
+        self.layers.get(index)
+    }
+
+    pub fn layer_mut(&mut self, index: usize) -> Option<&mut TileLayer> {
+        self.layers.get_mut(index)
+    }
+
+    /// Returns the first layer with a name
+    pub fn layer_named(&self, name: &str) -> Option<&TileLayer> {
+        self.layers.iter().find(|layer| layer.name == name)
+    }
+
+    /// Returns the layers from bottom to top
+    pub fn layers(&self) -> &[TileLayer] {
+        &self.layers
+    }
+
+    pub fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    /// Changes how cells are laid out, every chunk is rebuilt on the next update
+    pub fn set_orientation(&mut self, orientation: Orientation) {
+        self.orientation = orientation;
+        for layer in self.layers.iter_mut() {
+            layer.mark_dirty();
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn tile_width(&self) -> u32 {
+        self.tile_width
+    }
+
+    pub fn tile_height(&self) -> u32 {
+        self.tile_height
+    }
+
+    /// Returns the seconds passed in all updates
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    /// Advances the tile animations and rebuilds the chunks that changed
+    pub fn update(&mut self, delta_time: f32) {
+        let previous = self.time;
+        let time = self.time + delta_time;
+        self.time = time;
+
+        let tilesets = &self.tilesets;
+        for layer in self.layers.iter_mut() {
+            for chunk in layer.chunks.iter_mut().filter(|chunk| !chunk.dirty) {
+                chunk.dirty = chunk.animated.iter().any(|&(tileset, tile)| {
+                    let tileset = &tilesets[tileset];
+                    tileset.frame_at(tile, previous) != tileset.frame_at(tile, time)
+                });
+            }
+        }
+
+        self.rebuild();
+    }
+
+    /// Builds the meshes of the chunks that changed and returns how many were built
+    pub fn rebuild(&mut self) -> usize {
+        // Taken out so the chunks can be built from the rest of the map
+        let mut layers = std::mem::take(&mut self.layers);
+        let mut rebuilt = 0;
+        for layer in layers.iter_mut() {
+            for index in 0..layer.chunks.len() {
+                if !layer.chunks[index].dirty {
+                    continue;
+                }
+
+                let column = index % layer.chunk_columns;
+                let row = index / layer.chunk_columns;
+                let chunk = self.build_chunk(layer, column, row);
+                layer.chunks[index] = chunk;
+                rebuilt += 1;
+            }
+        }
+
+        self.layers = layers;
+        rebuilt
+    }
+
+    /// Returns the top left corner of a tile at a cell in world pixels, without the layer offset.
+    /// Tiles larger than the grid are aligned to the bottom left of the cell like in Tiled.
+    pub fn tile_position(&self, x: usize, y: usize, tile: &Tile) -> (f32, f32) {
+        let height = self
+            .tileset(tile.tileset)
+            .map_or(self.tile_height, Tileset::tile_height) as f32;
+        let (x, y) = (x as f32, y as f32);
+        let (tile_width, tile_height) = (self.tile_width as f32, self.tile_height as f32);
+
+        match self.orientation {
+            Orientation::Orthogonal => (x * tile_width, (y + 1.0) * tile_height - height),
+            Orientation::Isometric => {
+                // The left corner of the bottom left cell is at the left edge of the map
+                let rows = self.height as f32;
+                (
+                    (x - y + rows - 1.0) * tile_width / 2.0,
+                    (x + y + 2.0) * tile_height / 2.0 - height,
+                )
+            }
+        }
+    }
+
+    /// Returns the columns and rows of the cells of a layer that a camera can see.
+    /// For isometric maps this is the range of cells around the visible diamond.
+    pub fn visible_cells(
+        &self,
+        layer: &TileLayer,
+        camera: &Camera,
+    ) -> (Range<usize>, Range<usize>) {
+        // Tiles larger than the grid reach into the cells above and to the right
+        let overhang_x = self
+            .tilesets
+            .iter()
+            .map(|tileset| tileset.tile_width.saturating_sub(self.tile_width))
+            .max()
+            .unwrap_or(0) as f32;
+        let overhang_y = self
+            .tilesets
+            .iter()
+            .map(|tileset| tileset.tile_height.saturating_sub(self.tile_height))
+            .max()
+            .unwrap_or(0) as f32;
+
+        let area = camera.visible_area();
+        let left = area.x as f32 - layer.offset.0 - overhang_x;
+        let top = area.y as f32 - layer.offset.1;
+        let right = area.x as f32 - layer.offset.0 + area.width as f32;
+        let bottom = top + area.height as f32 + overhang_y;
+
+        let (tile_width, tile_height) = (self.tile_width as f32, self.tile_height as f32);
+        let column = |x: f32| x.clamp(0.0, layer.width as f32) as usize;
+        let row = |y: f32| y.clamp(0.0, layer.height as f32) as usize;
+
+        match self.orientation {
+            Orientation::Orthogonal => (
+                column((left / tile_width).floor())..column((right / tile_width).ceil()),
+                row((top / tile_height).floor())..row((bottom / tile_height).ceil()),
+            ),
+            Orientation::Isometric => {
+                // Cell coordinates of the corners of the area, inverse of `tile_position`
+                let origin = self.height as f32 * tile_width / 2.0;
+                let cell = |x: f32, y: f32| {
+                    (
+                        y / tile_height + (x - origin) / tile_width,
+                        y / tile_height - (x - origin) / tile_width,
+                    )
+                };
+                let corners = [
+                    cell(left, top),
+                    cell(right, top),
+                    cell(left, bottom),
+                    cell(right, bottom),
+                ];
+
+                let min = |values: [f32; 4]| values.iter().fold(f32::INFINITY, |a, &b| a.min(b));
+                let max =
+                    |values: [f32; 4]| values.iter().fold(f32::NEG_INFINITY, |a, &b| a.max(b));
+                let xs = corners.map(|corner| corner.0);
+                let ys = corners.map(|corner| corner.1);
+
+                (
+                    column(min(xs).floor())..column(max(xs).floor() + 1.0),
+                    row(min(ys).floor())..row(max(ys).floor() + 1.0),
+                )
+            }
+        }
+    }
+
+    /// Builds the meshes of a chunk from the tiles of a layer
+    fn build_chunk(&self, layer: &TileLayer, column: usize, row: usize) -> Chunk {
+        let mut meshes: Vec<ChunkMesh> = Vec::new();
+        let mut animated = Vec::new();
+
+        let columns = column * CHUNK_SIZE..((column + 1) * CHUNK_SIZE).min(layer.width);
+        let rows = row * CHUNK_SIZE..((row + 1) * CHUNK_SIZE).min(layer.height);
+        for y in rows {
+            for x in columns.clone() {
+                let tile = match layer.get(x, y) {
+                    Some(tile) => tile,
+                    None => continue,
+                };
+                let tileset = match self.tileset(tile.tileset) {
+                    Some(tileset) => tileset,
+                    None => continue,
+                };
+
+                if tileset.is_animated(tile.index)
+                    && !animated.contains(&(tile.tileset, tile.index))
+                {
+                    animated.push((tile.tileset, tile.index));
+                }
+
+                let shown = tileset.frame_at(tile.index, self.time);
+                let source = match tileset.tile_rect(shown) {
+                    Some(source) => source,
+                    None => continue,
+                };
+
+                let mesh = match meshes.iter().position(|mesh| mesh.tileset == tile.tileset) {
+                    Some(index) => &mut meshes[index],
+                    None => {
+                        meshes.push(ChunkMesh {
+                            tileset: tile.tileset,
+                            vertices: Vec::new(),
+                            vbo: 0,
+                        });
+                        meshes.last_mut().unwrap()
+                    }
+                };
+
+                let texture = tileset.sprite.texture();
+                let (left, top) = self.tile_position(x, y, &tile);
+                let (width, height) = (source.width as f32, source.height as f32);
+
+                // Two triangles, the corners as offsets from the tile center
+                let corners = [
+                    (-0.5, -0.5),
+                    (0.5, -0.5),
+                    (-0.5, 0.5),
+                    (0.5, -0.5),
+                    (0.5, 0.5),
+                    (-0.5, 0.5),
+                ];
+                for &corner in corners.iter() {
+                    let (u, v) = tile.source_point(corner);
+                    mesh.vertices.extend_from_slice(&[
+                        left + (corner.0 + 0.5) * width,
+                        top + (corner.1 + 0.5) * height,
+                        (source.x as f32 + (u + 0.5) * width) / texture.width() as f32,
+                        (source.y as f32 + (v + 0.5) * height) / texture.height() as f32,
+                    ]);
+                }
+            }
+        }
+
+        for mesh in meshes.iter_mut() {
+            mesh.upload();
+        }
+
+        Chunk {
+            meshes,
+            dirty: false,
+            animated,
+        }
+    }
+}
+
+impl Chunk {
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+}
+
+impl ChunkMesh {
+    /// Returns the number of vertices
+    pub fn len(&self) -> usize {
+        self.vertices.len() / VERTEX_SIZE
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+
+    /// Copies the vertices to a new vertex buffer, skipped when no OpenGL context is loaded
+    fn upload(&mut self) {
+        if !gl::GenBuffers::is_loaded() {
+            return;
+        }
+
+        unsafe {
+            gl::GenBuffers(1, &mut self.vbo);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                (self.vertices.len() * std::mem::size_of::<f32>()) as isize,
+                self.vertices.as_ptr() as *const _,
+                gl::STATIC_DRAW,
+            );
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+        }
+    }
+}
+
+// ====================================
+// Other Implementations
+// ====================================
+
+impl Drop for ChunkMesh {
+    fn drop(&mut self) {
+        if self.vbo != 0 {
+            unsafe {
+                gl::DeleteBuffers(1, &self.vbo);
+            }
+        }
+    }
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod tilemap_tests {
+
+    use super::*;
+    use crate::graphics::{color::Color, renderer::Renderer, software_renderer::SoftwareRenderer};
+
+    /// Tileset of four 2x2 tiles in a row, the first with a different color in each corner
+    fn tileset() -> Tileset {
+        let mut texture = Texture::from_color(8, 2, Color::BLACK);
+        texture.set_pixel(0, 0, Color::RED);
+        texture.set_pixel(1, 0, Color::GREEN);
+        texture.set_pixel(0, 1, Color::BLUE);
+        texture.set_pixel(1, 1, Color::WHITE);
+        for (tile, color) in [(1, Color::YELLOW), (2, Color::CYAN), (3, Color::MAGENTA)] {
+            for y in 0..2 {
+                for x in 0..2 {
+                    texture.set_pixel(tile * 2 + x, y, color);
+                }
+            }
+        }
+        Tileset::new(texture, 2, 2)
+    }
+
+    fn corners(renderer: &SoftwareRenderer, x: usize) -> [Color; 4] {
+        let target = renderer.target();
+        [
+            target.get_pixel(x, 0),
+            target.get_pixel(x + 1, 0),
+            target.get_pixel(x, 1),
+            target.get_pixel(x + 1, 1),
+        ]
+    }
+
+    #[test]
+    fn slice_tileset() {
+        let tileset = Tileset::with_spacing(Texture::new(37, 29), 16, 8, 2, 1);
+        assert_eq!((tileset.columns(), tileset.count()), (2, 4));
+        assert_eq!(tileset.tile_rect(3), Some(Rect::new(19, 11, 16, 8)));
+        assert_eq!(tileset.tile_rect(4), None);
+
+        // A margin past the texture leaves no tiles, spacing past it only the first
+        let tileset = Tileset::with_spacing(Texture::new(37, 29), 16, 8, u32::MAX, u32::MAX);
+        assert_eq!(tileset.count(), 0);
+        let tileset = Tileset::with_spacing(Texture::new(37, 29), 16, 8, 0, u32::MAX);
+        assert_eq!(tileset.count(), 1);
+        assert_eq!(tileset.tile_rect(0), Some(Rect::new(0, 0, 16, 8)));
+    }
+
+    #[test]
+    fn flip_flags() {
+        let tile = Tile::new(0, 0);
+        let cw = tile.rotated_cw();
+        assert!(cw.flip_diagonal && cw.flip_x && !cw.flip_y);
+        assert_eq!(cw.rotated_cw(), tile.flipped_x().flipped_y());
+        assert_eq!(cw.rotated_ccw(), tile);
+        assert_eq!(tile.rotated_ccw().rotated_ccw().rotated_ccw(), cw);
+        assert_eq!(tile.flipped_x().flipped_x(), tile);
+
+        // The top left corner of the source ends up at the top right
+        assert_eq!(cw.source_point((0.5, -0.5)), (-0.5, -0.5));
+    }
+
+    #[test]
+    fn draw_flipped_tiles() {
+        let mut map = Tilemap::new(4, 1, 2, 2);
+        map.add_tileset(tileset());
+        let layer = map.add_layer("ground");
+
+        let tile = Tile::new(0, 0);
+        let layer = map.layer_mut(layer).unwrap();
+        layer.set(0, 0, Some(tile));
+        layer.set(1, 0, Some(tile.flipped_x()));
+        layer.set(2, 0, Some(tile.rotated_cw()));
+        layer.set(3, 0, Some(tile.flipped_x().rotated_ccw()));
+
+        let mut renderer = SoftwareRenderer::new(8, 2);
+        renderer.clear(Color::CLEAR);
+        renderer.draw_tilemap(&map, &Camera::new(8.0, 2.0));
+
+        let (r, g, b, w) = (Color::RED, Color::GREEN, Color::BLUE, Color::WHITE);
+        assert_eq!(corners(&renderer, 0), [r, g, b, w]);
+        assert_eq!(corners(&renderer, 2), [g, r, w, b]);
+        assert_eq!(corners(&renderer, 4), [b, r, w, g]);
+        assert_eq!(corners(&renderer, 6), [r, b, g, w]);
+    }
+
+    #[test]
+    fn rebuild_changed_chunks() {
+        let mut map = Tilemap::new(40, 20, 2, 2);
+        map.add_tileset(tileset());
+        map.add_layer("ground");
+        map.add_layer("details");
+
+        // Three chunk columns and two chunk rows per layer
+        assert_eq!(map.rebuild(), 12);
+        assert_eq!(map.rebuild(), 0);
+
+        let layer = map.layer_mut(0).unwrap();
+        layer.set(17, 3, Some(Tile::new(0, 1)));
+        layer.set(18, 4, Some(Tile::new(0, 2)));
+        layer.set(39, 19, Some(Tile::new(0, 1)));
+        // Nothing changes when the same tile is placed again
+        map.update(0.0);
+        map.layer_mut(0).unwrap().set(39, 19, Some(Tile::new(0, 1)));
+        assert_eq!(map.rebuild(), 0);
+
+        let chunk = map.layer(0).unwrap().chunk(1, 0).unwrap();
+        assert_eq!(chunk.meshes.len(), 1);
+        assert_eq!(chunk.meshes[0].len(), 12);
+
+        // The second tile is the first vertex of its second triangle
+        let vertices = &chunk.meshes[0].vertices;
+        assert_eq!(vertices[24..28], [36.0, 8.0, 0.5, 0.0]);
+    }
+
+    #[test]
+    fn animated_tiles() {
+        let mut map = Tilemap::new(40, 1, 2, 2);
+        map.add_tileset(tileset());
+        map.tileset_mut(0).unwrap().set_animation(
+            1,
+            vec![
+                TileFrame {
+                    tile: 1,
+                    duration: 0.5,
+                },
+                TileFrame {
+                    tile: 3,
+                    duration: 0.25,
+                },
+            ],
+        );
+        map.add_layer("water");
+        map.layer_mut(0).unwrap().set(20, 0, Some(Tile::new(0, 1)));
+        map.update(0.0);
+
+        let tileset = map.tileset(0).unwrap();
+        assert_eq!(tileset.frame_at(1, 0.6), 3);
+        assert_eq!(tileset.frame_at(1, 0.8), 1);
+        assert_eq!(tileset.frame_at(2, 0.6), 2);
+
+        // Only the chunk with the animated tile is rebuilt and only when its frame changes
+        let u = |map: &Tilemap| map.layer(0).unwrap().chunk(1, 0).unwrap().meshes[0].vertices[2];
+        map.update(0.25);
+        assert_eq!(map.rebuild(), 0);
+        assert_eq!(u(&map), 0.25);
+
+        map.update(0.3);
+        assert_eq!(u(&map), 0.75);
+        assert!(!map.layer(0).unwrap().chunk(0, 0).unwrap().is_dirty());
+
+        let mut renderer = SoftwareRenderer::new(2, 2);
+        let mut camera = Camera::new(2.0, 2.0);
+        camera.position = (41.0, 1.0);
+        renderer.draw_tilemap(&map, &camera);
+        assert_eq!(renderer.target().get_pixel(0, 0), Color::MAGENTA);
+    }
+
+    #[test]
+    fn cull_to_camera() {
+        let mut map = Tilemap::new(64, 64, 8, 8);
+        map.add_tileset(tileset());
+        map.add_layer("ground");
+        let layer = map.layer(0).unwrap();
+
+        let mut camera = Camera::new(32.0, 32.0);
+        camera.position = (100.0, 100.0);
+        assert_eq!(map.visible_cells(layer, &camera), (10..15, 10..15));
+
+        camera.position = (0.0, 500.0);
+        assert_eq!(map.visible_cells(layer, &camera), (0..2, 60..64));
+
+        // Tiles taller than the grid are seen from the rows below
+        let tall = Tileset::new(Texture::new(8, 16), 8, 16);
+        map.add_tileset(tall);
+        let layer = map.layer(0).unwrap();
+        camera.position = (100.0, 100.0);
+        assert_eq!(map.visible_cells(layer, &camera), (10..15, 10..16));
+    }
+
+    #[test]
+    fn isometric_layout() {
+        let mut map = Tilemap::new(4, 3, 32, 16);
+        map.add_tileset(Tileset::new(Texture::new(32, 32), 32, 32));
+        map.set_orientation(Orientation::Isometric);
+        map.add_layer("ground");
+
+        // Tall tiles stand on the bottom corner of their diamond
+        let tile = Tile::new(0, 0);
+        assert_eq!(map.tile_position(0, 2, &tile), (0.0, 0.0));
+        assert_eq!(map.tile_position(0, 0, &tile), (32.0, -16.0));
+        assert_eq!(map.tile_position(3, 0, &tile), (80.0, 8.0));
+
+        // The whole map is 112x56 pixels
+        let layer = map.layer(0).unwrap();
+        let mut camera = Camera::new(112.0, 56.0);
+        assert_eq!(map.visible_cells(layer, &camera), (0..4, 0..3));
+
+        // Only the cells around the middle of the map
+        camera.viewport = (8.0, 4.0);
+        camera.position = (56.0, 24.0);
+        assert_eq!(map.visible_cells(layer, &camera), (1..4, 1..3));
+        camera.viewport = (4.0, 2.0);
+        camera.position = (48.0, 21.0);
+        assert_eq!(map.visible_cells(layer, &camera), (1..3, 1..3));
+    }
+}
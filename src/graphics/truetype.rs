@@ -0,0 +1,1075 @@
+#![allow(dead_code)]
+
+use std::convert::TryInto;
+
+use super::{
+    bitmap_font::FontError,
+    rasterizer::{Bitmap, Rasterizer},
+};
+
+/// Deepest nesting of composite glyphs that is followed
+const MAX_COMPONENT_DEPTH: usize = 8;
+
+/// Most points and components in one glyph, repeated components could otherwise multiply
+/// at every level of nesting
+const MAX_OUTLINE_SIZE: usize = 65536;
+
+/// Largest glyph bitmap in pixels per side, bigger glyphs come from corrupt bounds or sizes
+pub const MAX_GLYPH_SIZE: usize = 4096;
+
+/// Parsed TrueType or OpenType font with TrueType outlines.
+/// OpenType fonts with CFF outlines are not supported.
+pub struct TrueTypeFont {
+    data: Vec<u8>,
+    units_per_em: u16,
+    glyph_count: u16,
+    long_loca: bool,
+    ascent: i16,
+    descent: i16,
+    line_gap: i16,
+    metric_count: u16,
+    /// Offset of the unicode cmap subtable
+    cmap: Option<usize>,
+    loca: usize,
+    glyf: usize,
+    hmtx: usize,
+    kern: Option<usize>,
+    gpos: Option<usize>,
+}
+
+/// Piece of a glyph outline in font units, y points up.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Segment {
+    Line((f32, f32), (f32, f32)),
+    Quad((f32, f32), (f32, f32), (f32, f32)),
+}
+
+/// Rasterized glyph image.
+#[derive(Clone, PartialEq, Debug)]
+pub struct GlyphBitmap {
+    pub bitmap: Bitmap,
+    /// Offset from the pen position on the baseline to the top left of the bitmap, y points down
+    pub offset: (f32, f32),
+}
+
+/// Point of a simple glyph before it is turned into segments
+#[derive(Clone, Copy)]
+struct Point {
+    x: f32,
+    y: f32,
+    on_curve: bool,
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl TrueTypeFont {
+    /// Loads a `.ttf`, `.otf` or the first font of a `.ttc` file
+    pub fn load(path: &str) -> Result<TrueTypeFont, FontError> {
+        let data = std::fs::read(path).map_err(FontError::Io)?;
+        TrueTypeFont::parse(data)
+    }
+
+    pub fn parse(data: Vec<u8>) -> Result<TrueTypeFont, FontError> {
+        let mut start = 0;
+        if data.starts_with(b"ttcf") {
+            start = u32_at(&data, 12).ok_or(FontError::InvalidFormat)? as usize;
+        }
+
+        match data.get(start..start + 4) {
+            Some(b"OTTO") => return Err(FontError::UnsupportedOutlines),
+            Some([0, 1, 0, 0]) | Some(b"true") => (),
+            _ => return Err(FontError::InvalidFormat),
+        }
+
+        let table_count = u16_at(&data, start + 4).ok_or(FontError::InvalidFormat)?;
+        let find = |tag: &[u8]| {
+            (0..table_count as usize)
+                .map(|i| start + 12 + i * 16)
+                .find(|&record| data.get(record..record + 4) == Some(tag))
+                .and_then(|record| u32_at(&data, record + 8))
+                .map(|offset| offset as usize)
+        };
+        let require = |tag: &[u8]| {
+            find(tag).ok_or(if tag == b"glyf" && find(b"CFF ").is_some() {
+                FontError::UnsupportedOutlines
+            } else {
+                FontError::InvalidFormat
+            })
+        };
+
+        let head = require(b"head")?;
+        let maxp = require(b"maxp")?;
+        let hhea = require(b"hhea")?;
+        let loca = require(b"loca")?;
+        let glyf = require(b"glyf")?;
+        let hmtx = require(b"hmtx")?;
+
+        let cmap = find(b"cmap").and_then(|cmap| unicode_subtable(&data, cmap));
+        let kern = find(b"kern");
+        let gpos = find(b"GPOS");
+
+        let read = || -> Option<TrueTypeFont> {
+            Some(TrueTypeFont {
+                units_per_em: u16_at(&data, head + 18)?.max(1),
+                long_loca: i16_at(&data, head + 50)? != 0,
+                glyph_count: u16_at(&data, maxp + 4)?,
+                ascent: i16_at(&data, hhea + 4)?,
+                descent: i16_at(&data, hhea + 6)?,
+                line_gap: i16_at(&data, hhea + 8)?,
+                metric_count: u16_at(&data, hhea + 34)?,
+                cmap,
+                loca,
+                glyf,
+                hmtx,
+                kern,
+                gpos,
+                data: Vec::new(),
+            })
+        };
+
+        let mut font = read().ok_or(FontError::InvalidFormat)?;
+        font.data = data;
+        Ok(font)
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl TrueTypeFont {
+    pub fn units_per_em(&self) -> u16 {
+        self.units_per_em
+    }
+
+    pub fn glyph_count(&self) -> u16 {
+        self.glyph_count
+    }
+
+    /// Returns the distance from the baseline to the top of the line in font units
+    pub fn ascent(&self) -> i16 {
+        self.ascent
+    }
+
+    /// Returns the distance from the baseline to the bottom of the line in font units, negative
+    pub fn descent(&self) -> i16 {
+        self.descent
+    }
+
+    pub fn line_gap(&self) -> i16 {
+        self.line_gap
+    }
+
+    /// Returns the factor from font units to pixels for a font size in pixels
+    pub fn scale(&self, size: f32) -> f32 {
+        size / self.units_per_em as f32
+    }
+
+    /// Returns the glyph for a character, `None` if the font does not have one
+    pub fn glyph_index(&self, c: char) -> Option<u16> {
+        let table = self.cmap?;
+        let c = c as u32;
+
+        let glyph = match u16_at(&self.data, table)? {
+            4 => self.format_4_index(table, c)?,
+            12 => self.format_12_index(table, c)?,
+            _ => 0,
+        };
+
+        if glyph != 0 && glyph < self.glyph_count {
+            Some(glyph)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the horizontal advance of a glyph in font units
+    pub fn advance(&self, glyph: u16) -> u16 {
+        let index = glyph.min(self.metric_count.saturating_sub(1));
+        u16_at(&self.data, self.hmtx + index as usize * 4).unwrap_or(0)
+    }
+
+    /// Returns the adjustment between two glyphs in font units, from GPOS or the kern table
+    pub fn kerning(&self, left: u16, right: u16) -> i16 {
+        if let Some(gpos) = self.gpos {
+            if let Some(value) = gpos_kerning(&self.data, gpos, left, right) {
+                return value;
+            }
+        }
+
+        self.kern
+            .and_then(|kern| kern_table(&self.data, kern, left, right))
+            .unwrap_or(0)
+    }
+
+    /// Returns the outline of a glyph, empty for glyphs like space and for broken glyphs
+    pub fn outline(&self, glyph: u16) -> Vec<Segment> {
+        let mut segments = Vec::new();
+        let mut budget = MAX_OUTLINE_SIZE;
+        let transform = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+        match self.append_outline(glyph, transform, 0, &mut budget, &mut segments) {
+            Some(()) => segments,
+            None => Vec::new(),
+        }
+    }
+
+    /// Rasterizes a glyph at a font size in pixels with empty pixels around it.
+    /// Returns `None` for glyphs without an outline or over `MAX_GLYPH_SIZE` pixels per side.
+    pub fn rasterize(&self, glyph: u16, size: f32, padding: usize) -> Option<GlyphBitmap> {
+        let segments = self.outline(glyph);
+        if segments.is_empty() {
+            return None;
+        }
+
+        // Control points bound the curves, so their box contains the whole outline
+        let scale = self.scale(size);
+        let mut min = (f32::INFINITY, f32::INFINITY);
+        let mut max = (f32::NEG_INFINITY, f32::NEG_INFINITY);
+        for point in segments.iter().flat_map(points) {
+            min = (min.0.min(point.0), min.1.min(point.1));
+            max = (max.0.max(point.0), max.1.max(point.1));
+        }
+
+        let padding = padding as f32;
+        let left = (min.0 * scale).floor() - padding;
+        let top = (-max.1 * scale).floor() - padding;
+        let right = (max.0 * scale).ceil() + padding;
+        let bottom = (-min.1 * scale).ceil() + padding;
+
+        // Also rejects the NaN of an empty or infinite outline
+        let limit = MAX_GLYPH_SIZE as f32;
+        if !(right - left <= limit && bottom - top <= limit) {
+            return None;
+        }
+
+        let width = (right - left) as usize;
+        let height = (bottom - top) as usize;
+        let to_pixels = |(x, y): (f32, f32)| (x * scale - left, -y * scale - top);
+
+        let mut rasterizer = Rasterizer::new(width, height);
+        for segment in segments.iter() {
+            match *segment {
+                Segment::Line(p0, p1) => rasterizer.line(to_pixels(p0), to_pixels(p1)),
+                Segment::Quad(p0, control, p2) => {
+                    rasterizer.quad(to_pixels(p0), to_pixels(control), to_pixels(p2))
+                }
+            }
+        }
+
+        Some(GlyphBitmap {
+            bitmap: rasterizer.finish(),
+            offset: (left, top),
+        })
+    }
+
+    /// Returns the range of a glyph inside the glyf table, empty for glyphs without outline
+    fn glyph_range(&self, glyph: u16) -> Option<(usize, usize)> {
+        if glyph >= self.glyph_count {
+            return None;
+        }
+
+        let index = glyph as usize;
+        let (start, end) = if self.long_loca {
+            (
+                u32_at(&self.data, self.loca + index * 4)? as usize,
+                u32_at(&self.data, self.loca + index * 4 + 4)? as usize,
+            )
+        } else {
+            (
+                u16_at(&self.data, self.loca + index * 2)? as usize * 2,
+                u16_at(&self.data, self.loca + index * 2 + 2)? as usize * 2,
+            )
+        };
+
+        if start < end {
+            Some((self.glyf + start, self.glyf + end))
+        } else {
+            None
+        }
+    }
+
+    /// Adds the outline of a glyph transformed by `[a, b, c, d, dx, dy]`.
+    /// Returns `None` for broken glyphs and when the points and components exceed the budget.
+    fn append_outline(
+        &self,
+        glyph: u16,
+        transform: [f32; 6],
+        depth: usize,
+        budget: &mut usize,
+        segments: &mut Vec<Segment>,
+    ) -> Option<()> {
+        let (start, _) = match self.glyph_range(glyph) {
+            Some(range) => range,
+            None => return Some(()),
+        };
+        let contours = i16_at(&self.data, start)?;
+
+        let [a, b, c, d, dx, dy] = transform;
+        let apply = |(x, y): (f32, f32)| (a * x + c * y + dx, b * x + d * y + dy);
+
+        if contours >= 0 {
+            for contour in self.simple_contours(start, contours as usize)? {
+                *budget = budget.checked_sub(contour.len())?;
+                for segment in contour_segments(&contour) {
+                    segments.push(match segment {
+                        Segment::Line(p0, p1) => Segment::Line(apply(p0), apply(p1)),
+                        Segment::Quad(p0, control, p2) => {
+                            Segment::Quad(apply(p0), apply(control), apply(p2))
+                        }
+                    });
+                }
+            }
+            return Some(());
+        }
+
+        if depth >= MAX_COMPONENT_DEPTH {
+            return None;
+        }
+
+        let data = &self.data;
+        let mut at = start + 10;
+        loop {
+            let flags = u16_at(data, at)?;
+            let component = u16_at(data, at + 2)?;
+            at += 4;
+            if component == glyph {
+                return None;
+            }
+            *budget = budget.checked_sub(1)?;
+
+            let (arg1, arg2) = if flags & 0x0001 != 0 {
+                at += 4;
+                (i16_at(data, at - 4)? as f32, i16_at(data, at - 2)? as f32)
+            } else {
+                at += 2;
+                (
+                    *data.get(at - 2)? as i8 as f32,
+                    *data.get(at - 1)? as i8 as f32,
+                )
+            };
+            // Matching points instead of offsets is rare and placed without an offset
+            let (offset_x, offset_y) = if flags & 0x0002 != 0 {
+                (arg1, arg2)
+            } else {
+                (0.0, 0.0)
+            };
+
+            let f2dot14 = |at: usize| i16_at(data, at).map(|v| v as f32 / 16384.0);
+            let (ca, cb, cc, cd) = if flags & 0x0008 != 0 {
+                at += 2;
+                let scale = f2dot14(at - 2)?;
+                (scale, 0.0, 0.0, scale)
+            } else if flags & 0x0040 != 0 {
+                at += 4;
+                (f2dot14(at - 4)?, 0.0, 0.0, f2dot14(at - 2)?)
+            } else if flags & 0x0080 != 0 {
+                at += 8;
+                (
+                    f2dot14(at - 8)?,
+                    f2dot14(at - 6)?,
+                    f2dot14(at - 4)?,
+                    f2dot14(at - 2)?,
+                )
+            } else {
+                (1.0, 0.0, 0.0, 1.0)
+            };
+
+            // Component transform first, then the parent transform
+            let (ox, oy) = apply((offset_x, offset_y));
+            let combined = [
+                a * ca + c * cb,
+                b * ca + d * cb,
+                a * cc + c * cd,
+                b * cc + d * cd,
+                ox,
+                oy,
+            ];
+            self.append_outline(component, combined, depth + 1, budget, segments)?;
+
+            if flags & 0x0020 == 0 {
+                return Some(());
+            }
+        }
+    }
+
+    /// Reads the points of a simple glyph, split into contours
+    fn simple_contours(&self, start: usize, contours: usize) -> Option<Vec<Vec<Point>>> {
+        let data = &self.data;
+        let ends = (0..contours)
+            .map(|i| u16_at(data, start + 10 + i * 2).map(|end| end as usize))
+            .collect::<Option<Vec<usize>>>()?;
+        let point_count = match ends.last() {
+            Some(&last) => last + 1,
+            None => return Some(Vec::new()),
+        };
+
+        let instructions = u16_at(data, start + 10 + contours * 2)? as usize;
+        let mut at = start + 12 + contours * 2 + instructions;
+
+        let mut flags = Vec::with_capacity(point_count);
+        while flags.len() < point_count {
+            let flag = *data.get(at)?;
+            at += 1;
+            flags.push(flag);
+            if flag & 0x08 != 0 {
+                let repeat = *data.get(at)?;
+                at += 1;
+                for _ in 0..repeat {
+                    flags.push(flag);
+                }
+            }
+        }
+        flags.truncate(point_count);
+
+        // Coordinates are deltas, short ones are a byte with the sign in the flags
+        let mut read_coordinates = |short: u8, same_or_positive: u8| -> Option<Vec<f32>> {
+            let mut value = 0i32;
+            let mut values = Vec::with_capacity(point_count);
+            for flag in flags.iter() {
+                if flag & short != 0 {
+                    let delta = *data.get(at)? as i32;
+                    at += 1;
+                    value += if flag & same_or_positive != 0 {
+                        delta
+                    } else {
+                        -delta
+                    };
+                } else if flag & same_or_positive == 0 {
+                    value += i16_at(data, at)? as i32;
+                    at += 2;
+                }
+                values.push(value as f32);
+            }
+            Some(values)
+        };
+        let xs = read_coordinates(0x02, 0x10)?;
+        let ys = read_coordinates(0x04, 0x20)?;
+
+        let mut result = Vec::with_capacity(contours);
+        let mut first = 0;
+        for &end in ends.iter() {
+            if end < first || end >= point_count {
+                return None;
+            }
+            result.push(
+                (first..=end)
+                    .map(|i| Point {
+                        x: xs[i],
+                        y: ys[i],
+                        on_curve: flags[i] & 0x01 != 0,
+                    })
+                    .collect(),
+            );
+            first = end + 1;
+        }
+
+        Some(result)
+    }
+
+    fn format_4_index(&self, table: usize, c: u32) -> Option<u16> {
+        if c > 0xFFFF {
+            return None;
+        }
+
+        let data = &self.data;
+        let segments = u16_at(data, table + 6)? as usize / 2;
+        let ends = table + 14;
+        let starts = ends + segments * 2 + 2;
+        let deltas = starts + segments * 2;
+        let range_offsets = deltas + segments * 2;
+
+        // First segment that ends at or after the character
+        let (mut low, mut high) = (0, segments);
+        while low < high {
+            let middle = (low + high) / 2;
+            if (u16_at(data, ends + middle * 2)? as u32) < c {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+        if low == segments {
+            return None;
+        }
+
+        let start = u16_at(data, starts + low * 2)? as u32;
+        if c < start {
+            return None;
+        }
+
+        let delta = u16_at(data, deltas + low * 2)?;
+        let range_offset_at = range_offsets + low * 2;
+        let range_offset = u16_at(data, range_offset_at)? as usize;
+        if range_offset == 0 {
+            return Some((c as u16).wrapping_add(delta));
+        }
+
+        let glyph = u16_at(
+            data,
+            range_offset_at + range_offset + (c - start) as usize * 2,
+        )?;
+        if glyph == 0 {
+            Some(0)
+        } else {
+            Some(glyph.wrapping_add(delta))
+        }
+    }
+
+    fn format_12_index(&self, table: usize, c: u32) -> Option<u16> {
+        let data = &self.data;
+        let groups = u32_at(data, table + 12)? as usize;
+
+        let (mut low, mut high) = (0, groups);
+        while low < high {
+            let middle = (low + high) / 2;
+            let group = table + 16 + middle * 12;
+            let start = u32_at(data, group)?;
+            let end = u32_at(data, group + 4)?;
+            if c < start {
+                high = middle;
+            } else if c > end {
+                low = middle + 1;
+            } else {
+                let first = u32_at(data, group + 8)?;
+                return Some((first + c - start) as u16);
+            }
+        }
+
+        None
+    }
+}
+
+/// Finds the cmap subtable with the widest unicode coverage that can be read
+fn unicode_subtable(data: &[u8], cmap: usize) -> Option<usize> {
+    let count = u16_at(data, cmap + 2)? as usize;
+
+    let mut best = None;
+    let mut best_rank = 0;
+    for i in 0..count {
+        let record = cmap + 4 + i * 8;
+        let platform = u16_at(data, record)?;
+        let encoding = u16_at(data, record + 2)?;
+        let table = cmap + u32_at(data, record + 4)? as usize;
+
+        let unicode = platform == 0 || (platform == 3 && [0, 1, 10].contains(&encoding));
+        let rank = match (unicode, u16_at(data, table)?) {
+            (true, 12) => 2,
+            (true, 4) => 1,
+            _ => 0,
+        };
+        if rank > best_rank {
+            best = Some(table);
+            best_rank = rank;
+        }
+    }
+
+    best
+}
+
+/// Turns the points of a contour into segments.
+/// Two off curve points in a row have an implied on curve point halfway between them.
+fn contour_segments(points: &[Point]) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    if points.len() < 2 {
+        return segments;
+    }
+
+    let middle = |a: (f32, f32), b: (f32, f32)| ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0);
+    let position = |point: &Point| (point.x, point.y);
+
+    // Start on a point that is on the curve, or between the first and last point
+    let first_on = points.iter().position(|point| point.on_curve);
+    let (start, order): (_, Vec<&Point>) = match first_on {
+        Some(index) => (
+            position(&points[index]),
+            points[index + 1..]
+                .iter()
+                .chain(points[..=index].iter())
+                .collect(),
+        ),
+        None => (
+            middle(position(&points[points.len() - 1]), position(&points[0])),
+            points.iter().collect(),
+        ),
+    };
+
+    let mut current = start;
+    let mut control: Option<(f32, f32)> = None;
+    for point in order {
+        let here = position(point);
+        match (point.on_curve, control) {
+            (true, Some(c)) => {
+                segments.push(Segment::Quad(current, c, here));
+                current = here;
+                control = None;
+            }
+            (true, None) => {
+                segments.push(Segment::Line(current, here));
+                current = here;
+            }
+            (false, Some(c)) => {
+                let implied = middle(c, here);
+                segments.push(Segment::Quad(current, c, implied));
+                current = implied;
+                control = Some(here);
+            }
+            (false, None) => control = Some(here),
+        }
+    }
+
+    match control {
+        Some(c) => segments.push(Segment::Quad(current, c, start)),
+        None if current != start => segments.push(Segment::Line(current, start)),
+        None => (),
+    }
+
+    segments.retain(|segment| !matches!(segment, Segment::Line(p0, p1) if p0 == p1));
+    segments
+}
+
+fn points(segment: &Segment) -> Vec<(f32, f32)> {
+    match *segment {
+        Segment::Line(p0, p1) => vec![p0, p1],
+        Segment::Quad(p0, control, p2) => vec![p0, control, p2],
+    }
+}
+
+/// Pair adjustment from the GPOS lookups, only the horizontal advance of the first glyph is used
+fn gpos_kerning(data: &[u8], gpos: usize, left: u16, right: u16) -> Option<i16> {
+    let lookups = gpos + u16_at(data, gpos + 8)? as usize;
+    let count = u16_at(data, lookups)? as usize;
+
+    let mut total = None;
+    for i in 0..count {
+        let lookup = lookups + u16_at(data, lookups + 2 + i * 2)? as usize;
+        let kind = u16_at(data, lookup)?;
+        let subtables = u16_at(data, lookup + 4)? as usize;
+
+        for j in 0..subtables {
+            let mut subtable = lookup + u16_at(data, lookup + 6 + j * 2)? as usize;
+            let mut kind = kind;
+
+            // Extension lookups point at the real subtable with a 32 bit offset
+            if kind == 9 {
+                kind = u16_at(data, subtable + 2)?;
+                subtable += u32_at(data, subtable + 4)? as usize;
+            }
+            if kind != 2 {
+                continue;
+            }
+
+            if let Some(value) = pair_adjustment(data, subtable, left, right) {
+                total = Some(total.unwrap_or(0) + value);
+                break;
+            }
+        }
+    }
+
+    total
+}
+
+fn pair_adjustment(data: &[u8], subtable: usize, left: u16, right: u16) -> Option<i16> {
+    let format = u16_at(data, subtable)?;
+    let coverage = subtable + u16_at(data, subtable + 2)? as usize;
+    let first_format = u16_at(data, subtable + 4)?;
+    let second_format = u16_at(data, subtable + 6)?;
+    let coverage_index = coverage_index(data, coverage, left)?;
+
+    // Value records hold one 16 bit field per set format bit, x advance is bit 2
+    let record_size = |format: u16| (format.count_ones() * 2) as usize;
+    let advance = |record: usize| {
+        if first_format & 0x0004 != 0 {
+            i16_at(data, record + record_size(first_format & 0x0003))
+        } else {
+            Some(0)
+        }
+    };
+
+    match format {
+        1 => {
+            let set_count = u16_at(data, subtable + 8)? as usize;
+            if coverage_index >= set_count {
+                return None;
+            }
+            let set = subtable + u16_at(data, subtable + 10 + coverage_index * 2)? as usize;
+            let pairs = u16_at(data, set)? as usize;
+            let size = 2 + record_size(first_format) + record_size(second_format);
+
+            let (mut low, mut high) = (0, pairs);
+            while low < high {
+                let middle = (low + high) / 2;
+                let record = set + 2 + middle * size;
+                let second = u16_at(data, record)?;
+                if second < right {
+                    low = middle + 1;
+                } else if second > right {
+                    high = middle;
+                } else {
+                    return advance(record + 2);
+                }
+            }
+            None
+        }
+        2 => {
+            let first_classes = subtable + u16_at(data, subtable + 8)? as usize;
+            let second_classes = subtable + u16_at(data, subtable + 10)? as usize;
+            let first_count = u16_at(data, subtable + 12)? as usize;
+            let second_count = u16_at(data, subtable + 14)? as usize;
+
+            let first = class_of(data, first_classes, left)? as usize;
+            let second = class_of(data, second_classes, right)? as usize;
+            if first >= first_count || second >= second_count {
+                return None;
+            }
+
+            let size = record_size(first_format) + record_size(second_format);
+            advance(subtable + 16 + (first * second_count + second) * size)
+        }
+        _ => None,
+    }
+}
+
+fn coverage_index(data: &[u8], coverage: usize, glyph: u16) -> Option<usize> {
+    let count = u16_at(data, coverage + 2)? as usize;
+    match u16_at(data, coverage)? {
+        1 => (0..count).find(|&i| u16_at(data, coverage + 4 + i * 2) == Some(glyph)),
+        2 => (0..count).find_map(|i| {
+            let range = coverage + 4 + i * 6;
+            let start = u16_at(data, range)?;
+            let end = u16_at(data, range + 2)?;
+            if (start..=end).contains(&glyph) {
+                Some(u16_at(data, range + 4)? as usize + (glyph - start) as usize)
+            } else {
+                None
+            }
+        }),
+        _ => None,
+    }
+}
+
+/// Returns the class of a glyph, glyphs that are not listed are class 0
+fn class_of(data: &[u8], table: usize, glyph: u16) -> Option<u16> {
+    match u16_at(data, table)? {
+        1 => {
+            let start = u16_at(data, table + 2)?;
+            let count = u16_at(data, table + 4)?;
+            if glyph >= start && glyph - start < count {
+                u16_at(data, table + 6 + (glyph - start) as usize * 2)
+            } else {
+                Some(0)
+            }
+        }
+        2 => {
+            let count = u16_at(data, table + 2)? as usize;
+            for i in 0..count {
+                let range = table + 4 + i * 6;
+                if (u16_at(data, range)?..=u16_at(data, range + 2)?).contains(&glyph) {
+                    return u16_at(data, range + 4);
+                }
+            }
+            Some(0)
+        }
+        _ => Some(0),
+    }
+}
+
+/// Kerning pairs from the horizontal format 0 subtables of the old kern table
+fn kern_table(data: &[u8], kern: usize, left: u16, right: u16) -> Option<i16> {
+    if u16_at(data, kern)? != 0 {
+        return None;
+    }
+
+    let count = u16_at(data, kern + 2)? as usize;
+    let key = (left as u32) << 16 | right as u32;
+    let mut total = None;
+    let mut subtable = kern + 4;
+    for _ in 0..count {
+        let length = u16_at(data, subtable + 2)? as usize;
+        let coverage = u16_at(data, subtable + 4)?;
+
+        // Format 0, horizontal and not cross stream
+        if coverage >> 8 == 0 && coverage & 0x0001 != 0 && coverage & 0x0004 == 0 {
+            let pairs = u16_at(data, subtable + 6)? as usize;
+            let (mut low, mut high) = (0, pairs);
+            while low < high {
+                let middle = (low + high) / 2;
+                let pair = subtable + 14 + middle * 6;
+                let found = u32_at(data, pair)?;
+                if found < key {
+                    low = middle + 1;
+                } else if found > key {
+                    high = middle;
+                } else {
+                    total = Some(total.unwrap_or(0) + i16_at(data, pair + 4)?);
+                    break;
+                }
+            }
+        }
+
+        subtable += length;
+    }
+
+    total
+}
+
+fn u16_at(data: &[u8], at: usize) -> Option<u16> {
+    let bytes = data.get(at..at + 2)?;
+    Some(u16::from_be_bytes(bytes.try_into().unwrap()))
+}
+
+fn i16_at(data: &[u8], at: usize) -> Option<i16> {
+    u16_at(data, at).map(|v| v as i16)
+}
+
+fn u32_at(data: &[u8], at: usize) -> Option<u32> {
+    let bytes = data.get(at..at + 4)?;
+    Some(u32::from_be_bytes(bytes.try_into().unwrap()))
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+pub(crate) mod truetype_tests {
+
+    use super::*;
+
+    fn words(values: &[i32]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|&v| (v as u16).to_be_bytes().to_vec())
+            .collect()
+    }
+
+    /// Builds a small font with 1000 units per em.
+    /// Glyphs are space, a 300x700 bar for 'I', the bar moved right by 200 as a composite for
+    /// 'J' and a rounded shape from off curve points only for `round`, which must sort after 'J'.
+    pub(crate) fn test_font(round: char) -> Vec<u8> {
+        let round = round as i32;
+
+        let bar = [
+            words(&[1, 100, 0, 400, 700, 3, 0]),
+            vec![0x01 | 0x08, 3],
+            words(&[100, 0, 300, 0, 0, 700, 0, -700]),
+        ]
+        .concat();
+        let composite = words(&[-1, 300, 0, 600, 700, 0x0001 | 0x0002, 2, 200, 0]);
+        let rounded = [
+            words(&[1, 50, 100, 550, 600, 3, 0]),
+            vec![0x08, 3],
+            words(&[50, 500, 0, -500, 100, 0, 500, 0]),
+        ]
+        .concat();
+
+        let glyf = [bar.clone(), composite.clone(), rounded.clone()].concat();
+        let loca = [0, 0, 0, bar.len(), bar.len() + composite.len(), glyf.len()];
+        let loca: Vec<u8> = loca
+            .iter()
+            .flat_map(|&offset| (offset as u32).to_be_bytes().to_vec())
+            .collect();
+
+        let tables: Vec<(&[u8], Vec<u8>)> = vec![
+            (
+                b"GPOS",
+                words(&[
+                    1, 0, 0, 0, 10, 1, 4, 2, 0, 1, 8, 1, 12, 4, 0, 1, 18, 1, 1, 4, 1, 2, -50,
+                ]),
+            ),
+            (
+                b"cmap",
+                [
+                    words(&[0, 1, 3, 1, 0, 12, 4, 48, 0, 8, 0, 0, 0]),
+                    words(&[32, 74, round, 0xFFFF, 0, 32, 73, round, 0xFFFF]),
+                    words(&[1 - 32, 2 - 73, 4 - round, 1, 0, 0, 0, 0]),
+                ]
+                .concat(),
+            ),
+            (b"glyf", glyf),
+            (
+                b"head",
+                words(&[
+                    1, 0, 1, 0, 0, 0, 0x5F0F, 0x3CF5, 0, 1000, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                    0, 0, 0, 1, 0,
+                ]),
+            ),
+            (
+                b"hhea",
+                words(&[1, 0, 800, -200, 0, 600, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 5]),
+            ),
+            (
+                b"hmtx",
+                words(&[500, 0, 250, 0, 500, 100, 700, 300, 600, 50]),
+            ),
+            (b"kern", words(&[0, 1, 0, 20, 1, 1, 6, 0, 0, 2, 4, -30])),
+            (b"loca", loca),
+            (b"maxp", words(&[0, 0x5000, 5])),
+        ];
+
+        let mut font = words(&[1, 0, tables.len() as i32, 0, 0, 0]);
+        let mut offset = 12 + tables.len() * 16;
+        let mut contents = Vec::new();
+        for (tag, table) in tables.iter() {
+            font.extend_from_slice(tag);
+            font.extend(words(&[0, 0]));
+            font.extend((offset as u32).to_be_bytes().iter());
+            font.extend((table.len() as u32).to_be_bytes().iter());
+
+            let mut table = table.clone();
+            while table.len() % 4 != 0 {
+                table.push(0);
+            }
+            offset += table.len();
+            contents.extend(table);
+        }
+        font.extend(contents);
+        font
+    }
+
+    #[test]
+    fn parse_metrics_and_cmap() {
+        let font = TrueTypeFont::parse(test_font('O')).unwrap();
+        assert_eq!(font.units_per_em(), 1000);
+        assert_eq!(
+            (font.ascent(), font.descent(), font.line_gap()),
+            (800, -200, 0)
+        );
+        assert_eq!(font.glyph_count(), 5);
+
+        assert_eq!(font.glyph_index(' '), Some(1));
+        assert_eq!(font.glyph_index('I'), Some(2));
+        assert_eq!(font.glyph_index('J'), Some(3));
+        assert_eq!(font.glyph_index('O'), Some(4));
+        assert_eq!(font.glyph_index('K'), None);
+        assert_eq!(font.glyph_index('\u{1F600}'), None);
+
+        assert_eq!(font.advance(1), 250);
+        assert_eq!(font.advance(4), 600);
+    }
+
+    #[test]
+    fn outlines() {
+        let font = TrueTypeFont::parse(test_font('O')).unwrap();
+        assert!(font.outline(1).is_empty());
+
+        let bar = font.outline(2);
+        assert_eq!(
+            bar,
+            [
+                Segment::Line((100.0, 0.0), (100.0, 700.0)),
+                Segment::Line((100.0, 700.0), (400.0, 700.0)),
+                Segment::Line((400.0, 700.0), (400.0, 0.0)),
+                Segment::Line((400.0, 0.0), (100.0, 0.0)),
+            ]
+        );
+
+        // The composite is the bar moved right
+        let moved: Vec<Segment> = bar
+            .iter()
+            .map(|segment| match *segment {
+                Segment::Line(p0, p1) => Segment::Line((p0.0 + 200.0, p0.1), (p1.0 + 200.0, p1.1)),
+                other => other,
+            })
+            .collect();
+        assert_eq!(font.outline(3), moved);
+
+        // Only off curve points, so every segment is a curve between implied points
+        let rounded = font.outline(4);
+        assert_eq!(rounded.len(), 4);
+        assert_eq!(
+            rounded[0],
+            Segment::Quad((50.0, 350.0), (50.0, 100.0), (300.0, 100.0))
+        );
+    }
+
+    #[test]
+    fn reject_component_loops() {
+        // Replaces glyphs 3 and 4 with composites of their components, placed at the origin
+        fn set_composites(font: &mut TrueTypeFont, first: &[u16], second: &[u16]) {
+            let composite = |components: &[u16]| {
+                let mut glyph = words(&[-1, 0, 0, 0, 0]);
+                for (i, &component) in components.iter().enumerate() {
+                    let more = if i + 1 < components.len() { 0x0020 } else { 0 };
+                    glyph.extend(words(&[0x0001 | 0x0002 | more, component as i32, 0, 0]));
+                }
+                glyph
+            };
+            let start = font.data.len() - font.glyf;
+            font.data.extend(composite(first));
+            let middle = font.data.len() - font.glyf;
+            font.data.extend(composite(second));
+            let end = font.data.len() - font.glyf;
+            for (i, offset) in [start, middle, end].iter().enumerate() {
+                let at = font.loca + (3 + i) * 4;
+                font.data[at..at + 4].copy_from_slice(&(*offset as u32).to_be_bytes());
+            }
+        }
+
+        let mut font = TrueTypeFont::parse(test_font('O')).unwrap();
+
+        // Repeating a component is fine while it stays small
+        set_composites(&mut font, &[2; 16], &[2]);
+        assert_eq!(font.outline(3).len(), 64);
+
+        // 64 copies of 256 bars are over the budget
+        set_composites(&mut font, &[4; 64], &[2; 256]);
+        assert_eq!(font.outline(4).len(), 1024);
+        assert!(font.outline(3).is_empty());
+        assert!(font.rasterize(3, 10.0, 0).is_none());
+
+        // Loops end at the nesting limit instead of repeating the outline at every level
+        set_composites(&mut font, &[4; 16], &[3; 16]);
+        assert!(font.outline(3).is_empty());
+
+        set_composites(&mut font, &[2], &[2, 4]);
+        assert!(font.outline(4).is_empty());
+        assert_eq!(font.outline(3).len(), 4);
+    }
+
+    #[test]
+    fn rasterize_glyph() {
+        let font = TrueTypeFont::parse(test_font('O')).unwrap();
+        assert!(font.rasterize(1, 10.0, 0).is_none());
+
+        let glyph = font.rasterize(2, 10.0, 0).unwrap();
+        assert_eq!(glyph.offset, (1.0, -7.0));
+        assert_eq!((glyph.bitmap.width, glyph.bitmap.height), (3, 7));
+        assert!(glyph.bitmap.data.iter().all(|&v| v == 1.0));
+
+        let padded = font.rasterize(2, 10.0, 2).unwrap();
+        assert_eq!(padded.offset, (-1.0, -9.0));
+        assert_eq!((padded.bitmap.width, padded.bitmap.height), (7, 11));
+        assert_eq!(padded.bitmap.get(1, 1), 0.0);
+        assert_eq!(padded.bitmap.get(3, 5), 1.0);
+
+        // The bar would be 3000 by 7000 pixels
+        assert!(font.rasterize(2, 10000.0, 0).is_none());
+        assert!(font.rasterize(2, f32::INFINITY, 0).is_none());
+    }
+
+    #[test]
+    fn kerning_pairs() {
+        let font = TrueTypeFont::parse(test_font('O')).unwrap();
+        // From GPOS
+        assert_eq!(font.kerning(4, 2), -50);
+        // Not in GPOS, from the kern table
+        assert_eq!(font.kerning(2, 4), -30);
+        assert_eq!(font.kerning(2, 3), 0);
+    }
+
+    #[test]
+    fn reject_unsupported() {
+        let mut cff = test_font('O');
+        cff[..4].copy_from_slice(b"OTTO");
+        assert!(matches!(
+            TrueTypeFont::parse(cff),
+            Err(FontError::UnsupportedOutlines)
+        ));
+        assert!(matches!(
+            TrueTypeFont::parse(b"not a font".to_vec()),
+            Err(FontError::InvalidFormat)
+        ));
+    }
+}
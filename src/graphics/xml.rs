@@ -0,0 +1,283 @@
+#![allow(dead_code)]
+
+/// Element of a parsed XML document with its attributes, children and text.
+/// Only what data files need is supported: no namespaces, DTDs or custom entities.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub(crate) struct Element {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Element>,
+    /// Text directly inside the element with entities and CDATA sections decoded
+    pub text: String,
+}
+
+/// Deepest nesting of elements, guards the stack against hostile files
+const MAX_DEPTH: usize = 256;
+
+/// Cursor over the document text.
+struct Parser<'a> {
+    text: &'a str,
+    at: usize,
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl Element {
+    /// Parses a document and returns its root element
+    pub fn parse(text: &str) -> Result<Element, String> {
+        let mut parser = Parser { text, at: 0 };
+        parser.skip_misc()?;
+        let root = parser.element(0)?;
+        parser.skip_misc()?;
+
+        if parser.at < text.len() {
+            return Err(parser.error("content after the root element"));
+        }
+        Ok(root)
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl Element {
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the first child with a name
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    /// Returns the children with a name
+    pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> + 'a {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.text[self.at..]
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("{} at byte {}", message, self.at)
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.at += rest.len() - rest.trim_start().len();
+    }
+
+    /// Moves past the next occurrence of a pattern
+    fn skip_past(&mut self, pattern: &str) -> Result<&'a str, String> {
+        let rest = self.rest();
+        match rest.find(pattern) {
+            Some(end) => {
+                self.at += end + pattern.len();
+                Ok(&rest[..end])
+            }
+            None => Err(self.error(&format!("missing '{}'", pattern))),
+        }
+    }
+
+    fn expect(&mut self, pattern: &str) -> Result<(), String> {
+        if self.rest().starts_with(pattern) {
+            self.at += pattern.len();
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", pattern)))
+        }
+    }
+
+    /// Skips whitespace, comments, processing instructions and the doctype
+    fn skip_misc(&mut self) -> Result<(), String> {
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if rest.starts_with("<!DOCTYPE") {
+                self.skip_past(">")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn name(&mut self) -> Result<&'a str, String> {
+        let rest = self.rest();
+        let end = rest
+            .find(|c: char| c.is_whitespace() || c == '/' || c == '>' || c == '=')
+            .unwrap_or(rest.len());
+        if end == 0 {
+            return Err(self.error("expected a name"));
+        }
+
+        self.at += end;
+        Ok(&rest[..end])
+    }
+
+    fn element(&mut self, depth: usize) -> Result<Element, String> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("nested too deeply"));
+        }
+
+        self.expect("<")?;
+        let mut element = Element {
+            name: self.name()?.to_string(),
+            ..Element::default()
+        };
+
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("/>") {
+                self.at += 2;
+                return Ok(element);
+            }
+            if self.rest().starts_with('>') {
+                self.at += 1;
+                break;
+            }
+
+            let key = self.name()?.to_string();
+            self.skip_whitespace();
+            self.expect("=")?;
+            self.skip_whitespace();
+            let quote = match self.rest().chars().next() {
+                Some(quote @ '"') | Some(quote @ '\'') => quote,
+                _ => return Err(self.error("expected a quoted value")),
+            };
+            self.at += 1;
+            let value = self.skip_past(&quote.to_string())?;
+            element.attributes.push((key, decode_entities(value)?));
+        }
+
+        loop {
+            let rest = self.rest();
+            if rest.starts_with("</") {
+                self.at += 2;
+                let name = self.name()?;
+                if name != element.name {
+                    return Err(self.error(&format!("expected '</{}>'", element.name)));
+                }
+                self.skip_whitespace();
+                self.expect(">")?;
+                return Ok(element);
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if rest.starts_with("<![CDATA[") {
+                self.at += "<![CDATA[".len();
+                let data = self.skip_past("]]>")?;
+                element.text.push_str(data);
+            } else if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if rest.starts_with('<') {
+                element.children.push(self.element(depth + 1)?);
+            } else if rest.is_empty() {
+                return Err(self.error(&format!("missing '</{}>'", element.name)));
+            } else {
+                let end = rest.find('<').unwrap_or(rest.len());
+                element.text.push_str(&decode_entities(&rest[..end])?);
+                self.at += end;
+            }
+        }
+    }
+}
+
+/// Replaces the predefined entities and character references
+fn decode_entities(text: &str) -> Result<String, String> {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find(';')
+            .ok_or_else(|| format!("unterminated entity in '{}'", text))?;
+        let entity = &rest[start + 1..start + end];
+
+        let c = match entity {
+            "lt" => '<',
+            "gt" => '>',
+            "amp" => '&',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => {
+                let code = if let Some(hex) = entity.strip_prefix("#x") {
+                    u32::from_str_radix(hex, 16).ok()
+                } else if let Some(decimal) = entity.strip_prefix('#') {
+                    decimal.parse().ok()
+                } else {
+                    None
+                };
+                code.and_then(std::char::from_u32)
+                    .ok_or_else(|| format!("unknown entity '&{};'", entity))?
+            }
+        };
+
+        decoded.push(c);
+        rest = &rest[start + end + 1..];
+    }
+
+    decoded.push_str(rest);
+    Ok(decoded)
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod xml_tests {
+
+    use super::*;
+
+    #[test]
+    fn parse_document() {
+        let text = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <!-- Level one -->
+            <map width='2' name="a &amp; b">
+              <layer id="1"/>
+              <data>1,2&#44;3<![CDATA[<raw>]]></data>
+              <layer id="2"></layer >
+            </map>
+        "#;
+        let root = Element::parse(text).unwrap();
+
+        assert_eq!(root.name, "map");
+        assert_eq!(root.attribute("width"), Some("2"));
+        assert_eq!(root.attribute("name"), Some("a & b"));
+        assert_eq!(root.attribute("height"), None);
+        assert_eq!(root.children.len(), 3);
+
+        let ids: Vec<&str> = root
+            .children_named("layer")
+            .filter_map(|layer| layer.attribute("id"))
+            .collect();
+        assert_eq!(ids, ["1", "2"]);
+        assert_eq!(root.child("data").unwrap().text, "1,2,3<raw>");
+    }
+
+    #[test]
+    fn reject_malformed() {
+        assert!(Element::parse("<a><b></a>").is_err());
+        assert!(Element::parse("<a>").is_err());
+        assert!(Element::parse("<a x=1/>").is_err());
+        assert!(Element::parse("<a>&nope;</a>").is_err());
+        assert!(Element::parse("<a/><b/>").is_err());
+
+        let nested = "<a>".repeat(100_000) + &"</a>".repeat(100_000);
+        assert!(Element::parse(&nested).is_err());
+        let shallow = "<a>".repeat(MAX_DEPTH) + &"</a>".repeat(MAX_DEPTH);
+        assert!(Element::parse(&shallow).is_ok());
+    }
+}
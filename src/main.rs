@@ -1,19 +1,30 @@
 mod context;
+mod ecs;
 mod graphics;
 mod math;
+mod physics;
+mod scene;
+mod spatial;
 
+use context::gamepad::GamepadButton;
+use context::input_map::{Input, InputMap};
 use context::keyboard::Key;
 use context::window::{EventReciever, Window, WindowArgs, WindowError};
 use std::ffi::CStr;
 
 struct Application {
     fps_timer: f32,
+    input: InputMap,
 }
 
 impl EventReciever for Application {
     fn init(&mut self, _: &mut Window) {
         println!("[App]: Initialized");
 
+        self.input.bind("pause", Input::Key(Key::Escape));
+        self.input
+            .bind("pause", Input::GamepadButton(GamepadButton::ButtonStart));
+
         // Prints the current OpenGL version
         unsafe {
             let version_str = gl::GetString(gl::VERSION);
@@ -29,9 +40,9 @@ impl EventReciever for Application {
             self.fps_timer = 0.0;
         }
 
-        let keyboard = window.keyboard();
-        if keyboard.get_key_down(Key::Escape) {
-            println!("[App]: Escape pressed!");
+        self.input.update(window);
+        if self.input.just_pressed("pause") {
+            println!("[App]: Pause pressed!");
         }
     }
 
@@ -58,7 +69,10 @@ fn main() {
             }
         },
         Ok(mut window) => {
-            let mut app = Application { fps_timer: 0.0 };
+            let mut app = Application {
+                fps_timer: 0.0,
+                input: InputMap::new(),
+            };
             window.show(&mut app);
         }
     }
@@ -0,0 +1,308 @@
+#![allow(dead_code)]
+
+use super::{
+    geometry::{sweep_from_hit, Geometry, Hit, Intersection, Sweep, EPSILON},
+    ray::Ray,
+    vector2::Vector2,
+};
+
+/// Axis-aligned bounding box, from its lowest corner to its highest one.
+/// Boxes that only touch on an edge count as overlapping.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vector2,
+    pub max: Vector2,
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl Aabb {
+    pub fn new(min: Vector2, max: Vector2) -> Aabb {
+        Aabb { min, max }
+    }
+
+    pub fn from_center(center: Vector2, half_extents: Vector2) -> Aabb {
+        Aabb {
+            min: center - half_extents,
+            max: center + half_extents,
+        }
+    }
+
+    /// Returns the smallest box holding all the points, `None` without points
+    pub fn from_points(points: &[Vector2]) -> Option<Aabb> {
+        let first = *points.first()?;
+        Some(
+            points
+                .iter()
+                .fold(Aabb::new(first, first), |bounds, &point| {
+                    bounds.merged(&Aabb::new(point, point))
+                }),
+        )
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl Aabb {
+    pub fn center(&self) -> Vector2 {
+        (self.min + self.max) * 0.5f64
+    }
+
+    pub fn half_extents(&self) -> Vector2 {
+        (self.max - self.min) * 0.5f64
+    }
+
+    pub fn size(&self) -> Vector2 {
+        self.max - self.min
+    }
+
+    pub fn area(&self) -> f64 {
+        let size = self.size();
+        size.x * size.y
+    }
+
+    pub fn perimeter(&self) -> f64 {
+        let size = self.size();
+        2f64 * (size.x + size.y)
+    }
+
+    pub fn overlaps(&self, other: &Aabb) -> bool {
+        self.min.x <= other.max.x
+            && other.min.x <= self.max.x
+            && self.min.y <= other.max.y
+            && other.min.y <= self.max.y
+    }
+
+    /// Returns true when the other box is entirely inside this one
+    pub fn contains_aabb(&self, other: &Aabb) -> bool {
+        self.min.x <= other.min.x
+            && self.min.y <= other.min.y
+            && other.max.x <= self.max.x
+            && other.max.y <= self.max.y
+    }
+
+    /// Returns the box shared by both, `None` when they do not overlap
+    pub fn overlap(&self, other: &Aabb) -> Option<Aabb> {
+        if !self.overlaps(other) {
+            return None;
+        }
+        Some(Aabb::new(
+            Vector2::new(self.min.x.max(other.min.x), self.min.y.max(other.min.y)),
+            Vector2::new(self.max.x.min(other.max.x), self.max.y.min(other.max.y)),
+        ))
+    }
+
+    /// Pushes along the axis of least overlap
+    pub fn intersection(&self, other: &Aabb) -> Option<Intersection> {
+        let shared = self.overlap(other)?;
+        let size = shared.size();
+        let offset = other.center() - self.center();
+
+        let normal = if size.x < size.y {
+            Vector2::new(if offset.x < 0f64 { -1f64 } else { 1f64 }, 0f64)
+        } else {
+            Vector2::new(0f64, if offset.y < 0f64 { -1f64 } else { 1f64 })
+        };
+
+        // Measured to the far side of this box, which also holds when one box contains the other
+        let depth = if normal.x != 0f64 {
+            if normal.x > 0f64 {
+                self.max.x - other.min.x
+            } else {
+                other.max.x - self.min.x
+            }
+        } else if normal.y > 0f64 {
+            self.max.y - other.min.y
+        } else {
+            other.max.y - self.min.y
+        };
+
+        Some(Intersection {
+            point: shared.center(),
+            normal,
+            depth,
+        })
+    }
+
+    /// Returns the smallest box holding both
+    pub fn merged(&self, other: &Aabb) -> Aabb {
+        Aabb::new(
+            Vector2::new(self.min.x.min(other.min.x), self.min.y.min(other.min.y)),
+            Vector2::new(self.max.x.max(other.max.x), self.max.y.max(other.max.y)),
+        )
+    }
+
+    /// Returns the box grown by a margin on every side
+    pub fn expanded(&self, margin: f64) -> Aabb {
+        let margin = Vector2::new(margin, margin);
+        Aabb::new(self.min - margin, self.max + margin)
+    }
+
+    pub fn translated(&self, offset: Vector2) -> Aabb {
+        Aabb::new(self.min + offset, self.max + offset)
+    }
+
+    /// Finds when this box, moved by `velocity`, first touches another box
+    pub fn sweep(&self, velocity: Vector2, other: &Aabb) -> Option<Sweep> {
+        if self.overlaps(other) {
+            let intersection = self.intersection(other)?;
+            return Some(Sweep {
+                time: 0f64,
+                point: intersection.point,
+                normal: intersection.normal * -1f64,
+            });
+        }
+
+        let length = velocity.magnitude();
+        if length <= EPSILON {
+            return None;
+        }
+
+        // The center of this box against the other one grown by its size
+        let half = self.half_extents();
+        let grown = Aabb::new(other.min - half, other.max + half);
+        let sweep = sweep_from_hit(grown.raycast(&Ray::new(self.center(), velocity)), length)?;
+        let center = self.center() + velocity * sweep.time;
+        Some(Sweep {
+            point: other.closest_point(center),
+            ..sweep
+        })
+    }
+}
+
+impl Geometry for Aabb {
+    fn contains(&self, point: Vector2) -> bool {
+        point.x >= self.min.x
+            && point.x <= self.max.x
+            && point.y >= self.min.y
+            && point.y <= self.max.y
+    }
+
+    fn closest_point(&self, point: Vector2) -> Vector2 {
+        Vector2::new(
+            point.x.clamp(self.min.x, self.max.x),
+            point.y.clamp(self.min.y, self.max.y),
+        )
+    }
+
+    /// Slab test, the ray enters the box once it is between both pairs of sides
+    fn raycast(&self, ray: &Ray) -> Option<Hit> {
+        let origin = [ray.origin.x, ray.origin.y];
+        let direction = [ray.direction.x, ray.direction.y];
+        let (min, max) = ([self.min.x, self.min.y], [self.max.x, self.max.y]);
+
+        let mut enter = f64::NEG_INFINITY;
+        let mut exit = f64::INFINITY;
+        let mut normal = Vector2::new_empty();
+        for axis in 0..2 {
+            if direction[axis].abs() <= EPSILON {
+                if origin[axis] < min[axis] || origin[axis] > max[axis] {
+                    return None;
+                }
+                continue;
+            }
+
+            let near = (min[axis] - origin[axis]) / direction[axis];
+            let far = (max[axis] - origin[axis]) / direction[axis];
+            let (near, far, side) = if near < far {
+                (near, far, -1f64)
+            } else {
+                (far, near, 1f64)
+            };
+            if near > enter {
+                enter = near;
+                normal = if axis == 0 {
+                    Vector2::new(side, 0f64)
+                } else {
+                    Vector2::new(0f64, side)
+                };
+            }
+            exit = exit.min(far);
+            if enter > exit {
+                return None;
+            }
+        }
+
+        if exit < 0f64 {
+            return None;
+        }
+        if enter < 0f64 {
+            return Some(Hit {
+                distance: 0f64,
+                point: ray.origin,
+                normal: ray.direction * -1f64,
+            });
+        }
+        Some(Hit {
+            distance: enter,
+            point: ray.at(enter),
+            normal,
+        })
+    }
+
+    fn bounds(&self) -> Aabb {
+        *self
+    }
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod aabb_tests {
+
+    use super::*;
+
+    #[test]
+    fn overlap_and_intersection() {
+        let a = Aabb::new(Vector2::new(0f64, 0f64), Vector2::new(4f64, 2f64));
+        let b = Aabb::new(Vector2::new(3f64, 0.5f64), Vector2::new(6f64, 5f64));
+        assert_eq!(
+            a.overlap(&b),
+            Some(Aabb::new(
+                Vector2::new(3f64, 0.5f64),
+                Vector2::new(4f64, 2f64)
+            ))
+        );
+
+        let intersection = a.intersection(&b).unwrap();
+        assert_eq!(intersection.normal, Vector2::new(1f64, 0f64));
+        assert_eq!(intersection.depth, 1f64);
+
+        let touching = a.translated(Vector2::new(4f64, 0f64));
+        assert!(a.overlaps(&touching));
+        assert!(!a.overlaps(&touching.translated(Vector2::new(0.1f64, 0f64))));
+        assert_eq!(
+            Aabb::from_points(&[Vector2::new(1f64, 5f64), Vector2::new(-2f64, 3f64)]),
+            Some(Aabb::new(
+                Vector2::new(-2f64, 3f64),
+                Vector2::new(1f64, 5f64)
+            ))
+        );
+    }
+
+    #[test]
+    fn raycast_and_sweep() {
+        let bounds = Aabb::new(Vector2::new(2f64, -1f64), Vector2::new(4f64, 1f64));
+        let hit = bounds
+            .raycast(&Ray::new(Vector2::new_empty(), Vector2::new(1f64, 0f64)))
+            .unwrap();
+        assert_eq!(hit.distance, 2f64);
+        assert_eq!(hit.normal, Vector2::new(-1f64, 0f64));
+        assert!(bounds
+            .raycast(&Ray::new(Vector2::new_empty(), Vector2::new(-1f64, 0f64)))
+            .is_none());
+
+        let mover = Aabb::from_center(Vector2::new(0f64, 3f64), Vector2::new(0.5f64, 0.5f64));
+        let sweep = mover.sweep(Vector2::new(8f64, -6f64), &bounds).unwrap();
+        // The bottom of the box reaches the top of the other a quarter of the way
+        assert!((sweep.time - 0.25f64).abs() < 1e-12);
+        assert_eq!(sweep.normal, Vector2::new(0f64, 1f64));
+        assert!(mover.sweep(Vector2::new(1f64, 0f64), &bounds).is_none());
+    }
+}
@@ -0,0 +1,273 @@
+#![allow(dead_code)]
+
+use super::{
+    aabb::Aabb,
+    geometry::{perpendicular, sweep_from_hit, Geometry, Hit, Intersection, Sweep, EPSILON},
+    ray::Ray,
+    segment::LineSegment,
+    vector2::Vector2,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Circle {
+    pub center: Vector2,
+    pub radius: f64,
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl Circle {
+    pub fn new(center: Vector2, radius: f64) -> Circle {
+        Circle { center, radius }
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl Circle {
+    pub fn area(&self) -> f64 {
+        std::f64::consts::PI * self.radius * self.radius
+    }
+
+    pub fn overlaps(&self, other: &Circle) -> bool {
+        let reach = self.radius + other.radius;
+        (other.center - self.center).dot(other.center - self.center) <= reach * reach
+    }
+
+    pub fn overlaps_aabb(&self, bounds: &Aabb) -> bool {
+        let offset = bounds.closest_point(self.center) - self.center;
+        offset.dot(offset) <= self.radius * self.radius
+    }
+
+    pub fn intersection(&self, other: &Circle) -> Option<Intersection> {
+        if !self.overlaps(other) {
+            return None;
+        }
+
+        // Circles with the same center push apart along an arbitrary axis
+        let offset = other.center - self.center;
+        let distance = offset.magnitude();
+        let normal = if distance > EPSILON {
+            offset / distance
+        } else {
+            Vector2::new(0f64, 1f64)
+        };
+        let depth = self.radius + other.radius - distance;
+        Some(Intersection {
+            point: self.center + normal * (self.radius - depth / 2f64),
+            normal,
+            depth,
+        })
+    }
+
+    /// Pushes the box out of the circle
+    pub fn intersection_aabb(&self, bounds: &Aabb) -> Option<Intersection> {
+        if !bounds.contains(self.center) {
+            let closest = bounds.closest_point(self.center);
+            let offset = closest - self.center;
+            let distance = offset.magnitude();
+            if distance > self.radius {
+                return None;
+            }
+            return Some(Intersection {
+                point: closest,
+                normal: offset / distance,
+                depth: self.radius - distance,
+            });
+        }
+
+        // With the center inside, the circle leaves through the nearest side and the box goes the other way
+        let sides = [
+            (self.center.x - bounds.min.x, Vector2::new(1f64, 0f64)),
+            (bounds.max.x - self.center.x, Vector2::new(-1f64, 0f64)),
+            (self.center.y - bounds.min.y, Vector2::new(0f64, 1f64)),
+            (bounds.max.y - self.center.y, Vector2::new(0f64, -1f64)),
+        ];
+        let (distance, normal) = sides
+            .iter()
+            .copied()
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .unwrap();
+        Some(Intersection {
+            point: self.center,
+            normal,
+            depth: distance + self.radius,
+        })
+    }
+
+    /// Finds when this circle, moved by `velocity`, first touches another circle
+    pub fn sweep(&self, velocity: Vector2, other: &Circle) -> Option<Sweep> {
+        if let Some(intersection) = self.intersection(other) {
+            return Some(Sweep {
+                time: 0f64,
+                point: intersection.point,
+                normal: intersection.normal * -1f64,
+            });
+        }
+
+        let length = velocity.magnitude();
+        if length <= EPSILON {
+            return None;
+        }
+
+        // The center of this circle against the other one grown by this radius
+        let grown = Circle::new(other.center, other.radius + self.radius);
+        let sweep = sweep_from_hit(grown.raycast(&Ray::new(self.center, velocity)), length)?;
+        Some(Sweep {
+            point: other.center + sweep.normal * other.radius,
+            ..sweep
+        })
+    }
+
+    /// Finds when this circle, moved by `velocity`, first touches a segment, like a ball against a wall
+    pub fn sweep_segment(&self, velocity: Vector2, segment: &LineSegment) -> Option<Sweep> {
+        let closest = segment.closest_point(self.center);
+        if closest.distance(self.center) <= self.radius {
+            let offset = self.center - closest;
+            let normal = if offset.magnitude() > EPSILON {
+                offset.normalized()
+            } else if velocity.magnitude() > EPSILON {
+                velocity.normalized() * -1f64
+            } else {
+                Vector2::new(0f64, 1f64)
+            };
+            return Some(Sweep {
+                time: 0f64,
+                point: closest,
+                normal,
+            });
+        }
+
+        let length = velocity.magnitude();
+        if length <= EPSILON {
+            return None;
+        }
+
+        // The center against the segment grown into a capsule: two end circles and two shifted sides
+        let ray = Ray::new(self.center, velocity);
+        let mut hits = vec![
+            Circle::new(segment.start, self.radius).raycast(&ray),
+            Circle::new(segment.end, self.radius).raycast(&ray),
+        ];
+        let along = segment.end - segment.start;
+        if along.magnitude() > EPSILON {
+            let side = perpendicular(along.normalized()) * self.radius;
+            for offset in [side, side * -1f64] {
+                let shifted = LineSegment::new(segment.start + offset, segment.end + offset);
+                hits.push(shifted.raycast(&ray));
+            }
+        }
+        let hit = hits
+            .into_iter()
+            .flatten()
+            .min_by(|a, b| a.distance.total_cmp(&b.distance));
+
+        let sweep = sweep_from_hit(hit, length)?;
+        let center = self.center + velocity * sweep.time;
+        let point = segment.closest_point(center);
+        Some(Sweep {
+            point,
+            normal: (center - point).normalized(),
+            ..sweep
+        })
+    }
+}
+
+impl Geometry for Circle {
+    fn contains(&self, point: Vector2) -> bool {
+        (point - self.center).dot(point - self.center) <= self.radius * self.radius
+    }
+
+    fn closest_point(&self, point: Vector2) -> Vector2 {
+        let offset = point - self.center;
+        let distance = offset.magnitude();
+        if distance <= self.radius {
+            point
+        } else {
+            self.center + offset * (self.radius / distance)
+        }
+    }
+
+    fn raycast(&self, ray: &Ray) -> Option<Hit> {
+        // Solves |origin + t * direction - center| = radius for the smallest t
+        let offset = ray.origin - self.center;
+        let b = offset.dot(ray.direction);
+        let c = offset.dot(offset) - self.radius * self.radius;
+        if c <= 0f64 {
+            return Some(Hit {
+                distance: 0f64,
+                point: ray.origin,
+                normal: ray.direction * -1f64,
+            });
+        }
+        if b > 0f64 {
+            return None;
+        }
+
+        let discriminant = b * b - c;
+        if discriminant < 0f64 {
+            return None;
+        }
+        let distance = -b - discriminant.sqrt();
+        let point = ray.at(distance);
+        Some(Hit {
+            distance,
+            point,
+            normal: (point - self.center) / self.radius,
+        })
+    }
+
+    fn bounds(&self) -> Aabb {
+        Aabb::from_center(self.center, Vector2::new(self.radius, self.radius))
+    }
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod circle_tests {
+
+    use super::*;
+
+    #[test]
+    fn intersections() {
+        let a = Circle::new(Vector2::new_empty(), 2f64);
+        let b = Circle::new(Vector2::new(3f64, 0f64), 2f64);
+        let intersection = a.intersection(&b).unwrap();
+        assert_eq!(intersection.normal, Vector2::new(1f64, 0f64));
+        assert_eq!(intersection.depth, 1f64);
+        assert_eq!(intersection.point, Vector2::new(1.5f64, 0f64));
+
+        let ground = Aabb::new(Vector2::new(-5f64, -5f64), Vector2::new(5f64, -1.5f64));
+        let intersection = a.intersection_aabb(&ground).unwrap();
+        assert_eq!(intersection.normal, Vector2::new(0f64, -1f64));
+        assert_eq!(intersection.depth, 0.5f64);
+        assert!(!a.overlaps_aabb(&ground.translated(Vector2::new(0f64, -1f64))));
+    }
+
+    #[test]
+    fn sweeps() {
+        let ball = Circle::new(Vector2::new(0f64, 5f64), 1f64);
+        let wall = LineSegment::new(Vector2::new(-5f64, 0f64), Vector2::new(5f64, 0f64));
+        let sweep = ball
+            .sweep_segment(Vector2::new(0f64, -8f64), &wall)
+            .unwrap();
+        assert!((sweep.time - 0.5f64).abs() < 1e-12);
+        assert!(sweep.point.distance(Vector2::new(0f64, 0f64)) < 1e-12);
+        assert!(sweep.normal.distance(Vector2::new(0f64, 1f64)) < 1e-12);
+        assert!(ball
+            .sweep_segment(Vector2::new(0f64, -3f64), &wall)
+            .is_none());
+
+        let target = Circle::new(Vector2::new(10f64, 5f64), 2f64);
+        let sweep = ball.sweep(Vector2::new(14f64, 0f64), &target).unwrap();
+        assert!((sweep.time - 0.5f64).abs() < 1e-12);
+        assert!(sweep.point.distance(Vector2::new(8f64, 5f64)) < 1e-12);
+    }
+}
@@ -0,0 +1,347 @@
+#![allow(dead_code)]
+
+use super::{aabb::Aabb, ray::Ray, vector2::Vector2};
+
+/// Tolerance for parallel directions and points lying on edges
+pub const EPSILON: f64 = 1e-9;
+
+/// Where a ray first enters a shape.
+/// A ray starting inside the shape hits at distance 0, with the normal facing back along the ray.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hit {
+    /// Distance along the ray, which has a unit direction
+    pub distance: f64,
+    pub point: Vector2,
+    /// Unit normal of the surface at the point
+    pub normal: Vector2,
+}
+
+/// How far two overlapping shapes reach into each other.
+/// Moving the second shape by `normal * depth` separates them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Intersection {
+    /// Point in the middle of the overlap
+    pub point: Vector2,
+    /// Unit direction from the first shape towards the second
+    pub normal: Vector2,
+    pub depth: f64,
+}
+
+/// When a moving shape first touches another one.
+/// Shapes that already overlap touch at time 0.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sweep {
+    /// Fraction of the motion done before touching, from 0 to 1
+    pub time: f64,
+    /// Point of contact on the surface of the other shape
+    pub point: Vector2,
+    /// Unit normal of the other shape at the point, facing the moving shape
+    pub normal: Vector2,
+}
+
+/// Queries shared by the geometry primitives.
+pub trait Geometry {
+    /// Returns true for points inside the shape or on its boundary
+    fn contains(&self, point: Vector2) -> bool;
+
+    /// Returns the point of the shape nearest to a point, the point itself when it is inside
+    fn closest_point(&self, point: Vector2) -> Vector2;
+
+    fn raycast(&self, ray: &Ray) -> Option<Hit>;
+
+    fn bounds(&self) -> Aabb;
+
+    /// Returns the distance from a point to the shape, 0 inside
+    fn distance(&self, point: Vector2) -> f64 {
+        self.closest_point(point).distance(point)
+    }
+}
+
+/// Returns the 2D cross product, positive when `b` is counter-clockwise from `a`
+pub fn cross(a: Vector2, b: Vector2) -> f64 {
+    a.x * b.y - a.y * b.x
+}
+
+/// Returns the vector turned a quarter clockwise, the outward normal of a counter-clockwise edge
+pub fn perpendicular(v: Vector2) -> Vector2 {
+    Vector2::new(v.y, -v.x)
+}
+
+/// Turns a ray hit into a sweep hit for a motion of the given length
+pub(crate) fn sweep_from_hit(hit: Option<Hit>, length: f64) -> Option<Sweep> {
+    let hit = hit?;
+    if hit.distance > length {
+        return None;
+    }
+    Some(Sweep {
+        time: if length > 0f64 {
+            hit.distance / length
+        } else {
+            0f64
+        },
+        point: hit.point,
+        normal: hit.normal,
+    })
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod geometry_tests {
+
+    use super::super::{circle::Circle, polygon::Polygon, rect::Rect, segment::LineSegment};
+    use super::*;
+
+    /// Seeded generator so failures can be replayed
+    struct Random(u64);
+
+    impl Random {
+        fn next(&mut self) -> f64 {
+            self.0 = self
+                .0
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (self.0 >> 11) as f64 / (1u64 << 53) as f64
+        }
+
+        fn range(&mut self, min: f64, max: f64) -> f64 {
+            min + (max - min) * self.next()
+        }
+
+        fn point(&mut self, extent: f64) -> Vector2 {
+            Vector2::new(self.range(-extent, extent), self.range(-extent, extent))
+        }
+
+        fn direction(&mut self) -> Vector2 {
+            let angle = self.range(0f64, std::f64::consts::TAU);
+            Vector2::new(angle.cos(), angle.sin())
+        }
+
+        fn aabb(&mut self) -> Aabb {
+            let center = self.point(5f64);
+            let half = Vector2::new(self.range(0.2f64, 3f64), self.range(0.2f64, 3f64));
+            Aabb::from_center(center, half)
+        }
+
+        fn circle(&mut self) -> Circle {
+            Circle::new(self.point(5f64), self.range(0.2f64, 3f64))
+        }
+
+        fn polygon(&mut self) -> Polygon {
+            let center = self.point(5f64);
+            let sides = 3 + (self.next() * 6f64) as usize;
+            let radius = self.range(0.5f64, 3f64);
+            Polygon::regular(center, radius, sides).rotated(self.range(0f64, 1f64), center)
+        }
+
+        fn shapes(&mut self) -> Vec<Box<dyn Geometry>> {
+            let corner = self.point(5f64);
+            vec![
+                Box::new(self.aabb()),
+                Box::new(Rect::new(
+                    corner.x,
+                    corner.y,
+                    self.range(0.2f64, 4f64),
+                    self.range(0.2f64, 4f64),
+                )),
+                Box::new(self.circle()),
+                Box::new(self.polygon()),
+            ]
+        }
+    }
+
+    const CASES: usize = 500;
+
+    #[test]
+    fn closest_point_properties() {
+        let mut random = Random(1);
+        for _ in 0..CASES {
+            for shape in random.shapes() {
+                let point = random.point(10f64);
+                let closest = shape.closest_point(point);
+                assert!(shape.distance(closest) < EPSILON, "{} outside", closest);
+                if shape.contains(point) {
+                    assert!(closest.distance(point) < EPSILON);
+                }
+
+                // No point of the shape is nearer than the closest one
+                let bounds = shape.bounds();
+                for _ in 0..20 {
+                    let inside = Vector2::new(
+                        random.range(bounds.min.x, bounds.max.x),
+                        random.range(bounds.min.y, bounds.max.y),
+                    );
+                    if shape.contains(inside) {
+                        assert!(inside.distance(point) >= closest.distance(point) - EPSILON);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn raycast_properties() {
+        let mut random = Random(2);
+        for _ in 0..CASES {
+            for shape in random.shapes() {
+                let ray = Ray::new(random.point(10f64), random.direction());
+                match shape.raycast(&ray) {
+                    Some(hit) => {
+                        assert!(hit.distance >= 0f64);
+                        assert!(ray.at(hit.distance).distance(hit.point) < 1e-6);
+                        assert!((hit.normal.magnitude() - 1f64).abs() < 1e-6);
+                        assert!(shape.distance(hit.point) < 1e-6);
+                        if hit.distance > 0f64 {
+                            // The ray enters through the surface, so it was outside just before
+                            assert!(hit.normal.dot(ray.direction) <= 1e-6);
+                            assert!(!shape.contains(ray.at(hit.distance - 1e-3)));
+                        } else {
+                            assert!(shape.contains(ray.origin));
+                        }
+                    }
+                    None => {
+                        for step in 0..50 {
+                            assert!(!shape.contains(ray.at(step as f64 * 0.5f64)));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn overlap_properties() {
+        let mut random = Random(3);
+        for _ in 0..CASES {
+            let (a, b) = (random.aabb(), random.aabb());
+            assert_eq!(a.overlaps(&b), b.overlaps(&a));
+            assert_eq!(a.overlaps(&b), a.intersection(&b).is_some());
+            if let Some(intersection) = a.intersection(&b) {
+                let moved = b.translated(intersection.normal * (intersection.depth + 1e-6));
+                assert!(!a.overlaps(&moved));
+            }
+
+            let (c, d) = (random.circle(), random.circle());
+            assert_eq!(c.overlaps(&d), d.overlaps(&c));
+            if let Some(intersection) = c.intersection(&d) {
+                let mut moved = d;
+                moved.center += intersection.normal * (intersection.depth + 1e-6);
+                assert!(!c.overlaps(&moved));
+            }
+
+            assert_eq!(c.overlaps_aabb(&a), a.distance(c.center) <= c.radius);
+            if let Some(intersection) = c.intersection_aabb(&a) {
+                let moved = a.translated(intersection.normal * (intersection.depth + 1e-6));
+                assert!(!c.overlaps_aabb(&moved));
+            }
+
+            let (p, q) = (random.polygon(), random.polygon());
+            assert_eq!(p.overlaps(&q), q.overlaps(&p));
+            match p.intersection(&q) {
+                Some(intersection) => {
+                    let moved = q.translated(intersection.normal * (intersection.depth + 1e-6));
+                    assert!(!p.overlaps(&moved));
+                }
+                None => {
+                    // Separated shapes share no vertex
+                    assert!(!q.vertices().iter().any(|&vertex| p.contains(vertex)));
+                }
+            }
+            assert_eq!(p.overlaps_circle(&c), p.distance(c.center) <= c.radius);
+        }
+    }
+
+    #[test]
+    fn sweep_properties() {
+        let mut random = Random(4);
+        for _ in 0..CASES {
+            let velocity = random.point(8f64);
+
+            let (a, b) = (random.aabb(), random.aabb());
+            match a.sweep(velocity, &b) {
+                Some(sweep) => {
+                    assert!((0f64..=1f64).contains(&sweep.time));
+                    let touching = a.translated(velocity * sweep.time);
+                    assert!(touching.expanded(1e-9).overlaps(&b));
+                    if sweep.time > 0f64 {
+                        assert!(!a.translated(velocity * (sweep.time - 1e-6)).overlaps(&b));
+                    }
+                }
+                None => {
+                    for step in 0..=20 {
+                        assert!(!a.translated(velocity * (step as f64 / 20f64)).overlaps(&b));
+                    }
+                }
+            }
+
+            let (c, d) = (random.circle(), random.circle());
+            let moved = |time: f64| Circle::new(c.center + velocity * time, c.radius);
+            match c.sweep(velocity, &d) {
+                Some(sweep) => {
+                    if sweep.time > 0f64 {
+                        // Touching at the time of contact, overlapping right after
+                        let contact = moved(sweep.time).intersection(&d);
+                        assert!(contact.is_none_or(|contact| contact.depth < 1e-6));
+                        assert!(moved(sweep.time + 1e-9).overlaps(&d));
+                        assert!((d.center.distance(sweep.point) - d.radius).abs() < 1e-6);
+                    } else {
+                        assert!(c.overlaps(&d));
+                    }
+                }
+                None => {
+                    for step in 0..=20 {
+                        assert!(!moved(step as f64 / 20f64).overlaps(&d));
+                    }
+                }
+            }
+
+            let wall = LineSegment::new(random.point(5f64), random.point(5f64));
+            match c.sweep_segment(velocity, &wall) {
+                Some(sweep) => {
+                    let center = c.center + velocity * sweep.time;
+                    assert!(wall.distance(center) <= c.radius + 1e-6);
+                    if sweep.time > 0f64 {
+                        assert!((wall.distance(center) - c.radius).abs() < 1e-6);
+                        assert!(wall.closest_point(center).distance(sweep.point) < 1e-6);
+                    }
+                }
+                None => {
+                    for step in 0..=20 {
+                        assert!(
+                            wall.distance(c.center + velocity * (step as f64 / 20f64)) > c.radius
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn segment_properties() {
+        let mut random = Random(5);
+        for _ in 0..CASES {
+            let a = LineSegment::new(random.point(5f64), random.point(5f64));
+            let b = LineSegment::new(random.point(5f64), random.point(5f64));
+            assert_eq!(a.intersection(&b).is_some(), b.intersection(&a).is_some());
+            if let Some(point) = a.intersection(&b) {
+                assert!(a.distance(point) < 1e-6 && b.distance(point) < 1e-6);
+            }
+
+            let point = random.point(10f64);
+            let closest = a.closest_point(point);
+            assert!(a.distance(closest) < 1e-9);
+            for step in 0..=10 {
+                let along = a.start + (a.end - a.start) * (step as f64 / 10f64);
+                assert!(along.distance(point) >= closest.distance(point) - EPSILON);
+            }
+
+            let ray = Ray::new(random.point(10f64), random.direction());
+            if let Some(hit) = a.raycast(&ray) {
+                assert!(a.distance(hit.point) < 1e-6);
+                assert!(hit.normal.dot(ray.direction) <= 0f64);
+            }
+        }
+    }
+}
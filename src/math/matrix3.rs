@@ -0,0 +1,224 @@
+#![allow(dead_code)]
+
+use super::vector2::Vector2;
+
+/// 3x3 matrix stored by rows, used for 2D affine transforms.
+/// Points are column vectors `[x, y, 1]`, so `a * b` applies `b` first.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Matrix3 {
+    pub m: [[f64; 3]; 3],
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl Matrix3 {
+    pub fn new(m: [[f64; 3]; 3]) -> Matrix3 {
+        Matrix3 { m }
+    }
+
+    pub fn identity() -> Matrix3 {
+        Matrix3 {
+            m: [[1f64, 0f64, 0f64], [0f64, 1f64, 0f64], [0f64, 0f64, 1f64]],
+        }
+    }
+
+    pub fn translation(offset: Vector2) -> Matrix3 {
+        Matrix3 {
+            m: [
+                [1f64, 0f64, offset.x],
+                [0f64, 1f64, offset.y],
+                [0f64, 0f64, 1f64],
+            ],
+        }
+    }
+
+    /// Rotation in radians, positive angles turn x towards y
+    pub fn rotation(angle: f64) -> Matrix3 {
+        let (sin, cos) = angle.sin_cos();
+        Matrix3 {
+            m: [[cos, -sin, 0f64], [sin, cos, 0f64], [0f64, 0f64, 1f64]],
+        }
+    }
+
+    pub fn scale(scale: Vector2) -> Matrix3 {
+        Matrix3 {
+            m: [
+                [scale.x, 0f64, 0f64],
+                [0f64, scale.y, 0f64],
+                [0f64, 0f64, 1f64],
+            ],
+        }
+    }
+}
+
+impl Default for Matrix3 {
+    fn default() -> Matrix3 {
+        Matrix3::identity()
+    }
+}
+
+// ====================================
+// Overloading Operators
+// ====================================
+
+impl std::ops::Mul<Matrix3> for Matrix3 {
+    type Output = Matrix3;
+
+    fn mul(self, _rhs: Self) -> Self::Output {
+        let mut m = [[0f64; 3]; 3];
+        for (row, values) in m.iter_mut().enumerate() {
+            for (column, value) in values.iter_mut().enumerate() {
+                *value = (0..3).map(|i| self.m[row][i] * _rhs.m[i][column]).sum();
+            }
+        }
+        Matrix3 { m }
+    }
+}
+
+impl std::ops::MulAssign<Matrix3> for Matrix3 {
+    fn mul_assign(&mut self, _rhs: Self) {
+        *self = *self * _rhs;
+    }
+}
+
+// ====================================
+// Operations
+// ====================================
+
+impl Matrix3 {
+    /// Transforms a position, translation included
+    pub fn transform_point(&self, point: Vector2) -> Vector2 {
+        Vector2 {
+            x: self.m[0][0] * point.x + self.m[0][1] * point.y + self.m[0][2],
+            y: self.m[1][0] * point.x + self.m[1][1] * point.y + self.m[1][2],
+        }
+    }
+
+    /// Transforms a direction, translation ignored
+    pub fn transform_vector(&self, vector: Vector2) -> Vector2 {
+        Vector2 {
+            x: self.m[0][0] * vector.x + self.m[0][1] * vector.y,
+            y: self.m[1][0] * vector.x + self.m[1][1] * vector.y,
+        }
+    }
+
+    /// Returns the translation part of an affine transform
+    pub fn get_translation(&self) -> Vector2 {
+        Vector2 {
+            x: self.m[0][2],
+            y: self.m[1][2],
+        }
+    }
+
+    pub fn transposed(&self) -> Matrix3 {
+        let mut m = [[0f64; 3]; 3];
+        for (row, values) in m.iter_mut().enumerate() {
+            for (column, value) in values.iter_mut().enumerate() {
+                *value = self.m[column][row];
+            }
+        }
+        Matrix3 { m }
+    }
+
+    pub fn determinant(&self) -> f64 {
+        let m = &self.m;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    /// Returns `None` for matrices that flatten space, such as a scale of 0
+    pub fn inverse(&self) -> Option<Matrix3> {
+        let determinant = self.determinant();
+        if determinant == 0f64 || !determinant.is_finite() {
+            return None;
+        }
+
+        // Transposed matrix of cofactors divided by the determinant
+        let m = &self.m;
+        let cofactor = |row: usize, column: usize| {
+            let (r0, r1) = ((row + 1) % 3, (row + 2) % 3);
+            let (c0, c1) = ((column + 1) % 3, (column + 2) % 3);
+            m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]
+        };
+        let mut inverse = [[0f64; 3]; 3];
+        for (row, values) in inverse.iter_mut().enumerate() {
+            for (column, value) in values.iter_mut().enumerate() {
+                *value = cofactor(column, row) / determinant;
+            }
+        }
+        Some(Matrix3 { m: inverse })
+    }
+
+    /// Returns true if every element is within `epsilon` of the other matrix
+    pub fn approx_eq(&self, _rhs: &Matrix3, epsilon: f64) -> bool {
+        self.m
+            .iter()
+            .flatten()
+            .zip(_rhs.m.iter().flatten())
+            .all(|(a, b)| (a - b).abs() <= epsilon)
+    }
+}
+
+// ====================================
+// Other Implementations
+// ====================================
+
+impl std::fmt::Display for Matrix3 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let m = &self.m;
+        f.write_fmt(format_args!(
+            "[{}, {}, {}; {}, {}, {}; {}, {}, {}]",
+            m[0][0], m[0][1], m[0][2], m[1][0], m[1][1], m[1][2], m[2][0], m[2][1], m[2][2]
+        ))
+    }
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod matrix3_tests {
+
+    use super::*;
+
+    #[test]
+    fn simple_transform_point() {
+        let transform = Matrix3::translation(Vector2::new(10f64, 0f64))
+            * Matrix3::scale(Vector2::new(2f64, 3f64));
+        let result = transform.transform_point(Vector2::new(1f64, 1f64));
+        assert_eq!(result, Vector2::new(12f64, 3f64));
+        let result = transform.transform_vector(Vector2::new(1f64, 1f64));
+        assert_eq!(result, Vector2::new(2f64, 3f64));
+    }
+
+    #[test]
+    fn simple_rotation() {
+        let result = Matrix3::rotation(std::f64::consts::FRAC_PI_2)
+            .transform_point(Vector2::new(1f64, 0f64));
+        assert!((result - Vector2::new(0f64, 1f64)).magnitude() < 1e-12);
+    }
+
+    #[test]
+    fn simple_inverse() {
+        let transform = Matrix3::translation(Vector2::new(3f64, -4f64))
+            * Matrix3::rotation(0.5f64)
+            * Matrix3::scale(Vector2::new(2f64, 0.5f64));
+        let inverse = transform.inverse().unwrap();
+        assert!((transform * inverse).approx_eq(&Matrix3::identity(), 1e-12));
+        assert!((inverse * transform).approx_eq(&Matrix3::identity(), 1e-12));
+        assert_eq!(Matrix3::scale(Vector2::new(0f64, 1f64)).inverse(), None);
+    }
+
+    #[test]
+    fn simple_transpose_determinant() {
+        let matrix = Matrix3::new([[1f64, 2f64, 3f64], [0f64, 1f64, 4f64], [5f64, 6f64, 0f64]]);
+        assert_eq!(matrix.determinant(), 1f64);
+        assert_eq!(matrix.transposed().m[0], [1f64, 0f64, 5f64]);
+        assert_eq!(matrix.transposed().transposed(), matrix);
+        assert_eq!(matrix * Matrix3::identity(), matrix);
+    }
+}
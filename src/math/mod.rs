@@ -1 +1,10 @@
-pub mod vector2;
\ No newline at end of file
+pub mod aabb;
+pub mod circle;
+pub mod geometry;
+pub mod matrix3;
+pub mod polygon;
+pub mod ray;
+pub mod rect;
+pub mod segment;
+pub mod transform2d;
+pub mod vector2;
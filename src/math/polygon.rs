@@ -0,0 +1,329 @@
+#![allow(dead_code)]
+
+use super::{
+    aabb::Aabb,
+    circle::Circle,
+    geometry::{cross, perpendicular, Geometry, Hit, Intersection, EPSILON},
+    ray::Ray,
+    segment::LineSegment,
+    vector2::Vector2,
+};
+
+/// Convex polygon with its vertices in counter-clockwise order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polygon {
+    vertices: Vec<Vector2>,
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl Polygon {
+    /// Takes the vertices in either winding order.
+    /// Returns `None` for fewer than three vertices, no area or a concave outline.
+    pub fn new(vertices: &[Vector2]) -> Option<Polygon> {
+        let mut vertices = vertices.to_vec();
+        vertices.dedup();
+        if vertices.len() > 1 && vertices.first() == vertices.last() {
+            vertices.pop();
+        }
+        if vertices.len() < 3 {
+            return None;
+        }
+
+        let mut polygon = Polygon { vertices };
+        let area = polygon.signed_area();
+        if area.abs() <= EPSILON {
+            return None;
+        }
+        if area < 0f64 {
+            polygon.vertices[1..].reverse();
+        }
+
+        // Every corner turns left in a convex counter-clockwise outline
+        let count = polygon.vertices.len();
+        let convex = (0..count).all(|i| {
+            let (a, b, c) = (
+                polygon.vertices[i],
+                polygon.vertices[(i + 1) % count],
+                polygon.vertices[(i + 2) % count],
+            );
+            cross(b - a, c - b) >= -EPSILON
+        });
+        if convex {
+            Some(polygon)
+        } else {
+            None
+        }
+    }
+
+    /// Polygon with equal sides whose first vertex points along x
+    pub fn regular(center: Vector2, radius: f64, sides: usize) -> Polygon {
+        let step = std::f64::consts::TAU / sides.max(3) as f64;
+        let vertices = (0..sides.max(3))
+            .map(|i| {
+                let (sin, cos) = (step * i as f64).sin_cos();
+                center + Vector2::new(cos, sin) * radius
+            })
+            .collect();
+        Polygon { vertices }
+    }
+
+    pub fn from_aabb(bounds: &Aabb) -> Polygon {
+        Polygon {
+            vertices: vec![
+                bounds.min,
+                Vector2::new(bounds.max.x, bounds.min.y),
+                bounds.max,
+                Vector2::new(bounds.min.x, bounds.max.y),
+            ],
+        }
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl Polygon {
+    pub fn vertices(&self) -> &[Vector2] {
+        &self.vertices
+    }
+
+    pub fn edges(&self) -> impl Iterator<Item = LineSegment> + '_ {
+        let count = self.vertices.len();
+        (0..count).map(move |i| LineSegment::new(self.vertices[i], self.vertices[(i + 1) % count]))
+    }
+
+    pub fn area(&self) -> f64 {
+        self.signed_area()
+    }
+
+    pub fn centroid(&self) -> Vector2 {
+        let mut centroid = Vector2::new_empty();
+        let origin = self.vertices[0];
+        for i in 1..self.vertices.len() - 1 {
+            let (a, b) = (self.vertices[i] - origin, self.vertices[i + 1] - origin);
+            centroid += (a + b) * (cross(a, b) / 6f64);
+        }
+        origin + centroid / self.signed_area()
+    }
+
+    pub fn translated(&self, offset: Vector2) -> Polygon {
+        Polygon {
+            vertices: self
+                .vertices
+                .iter()
+                .map(|&vertex| vertex + offset)
+                .collect(),
+        }
+    }
+
+    /// Returns the polygon turned counter-clockwise by an angle in radians around a point
+    pub fn rotated(&self, angle: f64, around: Vector2) -> Polygon {
+        let (sin, cos) = angle.sin_cos();
+        Polygon {
+            vertices: self
+                .vertices
+                .iter()
+                .map(|&vertex| {
+                    let offset = vertex - around;
+                    around
+                        + Vector2::new(
+                            offset.x * cos - offset.y * sin,
+                            offset.x * sin + offset.y * cos,
+                        )
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the vertex furthest along a direction
+    pub fn support(&self, direction: Vector2) -> Vector2 {
+        self.vertices
+            .iter()
+            .copied()
+            .max_by(|a, b| a.dot(direction).total_cmp(&b.dot(direction)))
+            .unwrap()
+    }
+
+    pub fn overlaps(&self, other: &Polygon) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    /// Separating axis test over the edge normals of both polygons, pushing along the shallowest one
+    pub fn intersection(&self, other: &Polygon) -> Option<Intersection> {
+        let mut best: Option<(f64, Vector2)> = None;
+        for (polygon, sign) in [(self, 1f64), (other, -1f64)] {
+            for edge in polygon.edges() {
+                let axis = perpendicular(edge.direction()) * sign;
+
+                // Overlap along the axis going from this polygon towards the other
+                let depth = self.support(axis).dot(axis) - other.support(axis * -1f64).dot(axis);
+                if depth < 0f64 {
+                    return None;
+                }
+                if best.is_none_or(|(least, _)| depth < least) {
+                    best = Some((depth, axis));
+                }
+            }
+        }
+
+        let (depth, normal) = best?;
+        let deepest = other.support(normal * -1f64);
+        Some(Intersection {
+            point: deepest + normal * (depth / 2f64),
+            normal,
+            depth,
+        })
+    }
+
+    pub fn overlaps_circle(&self, circle: &Circle) -> bool {
+        self.distance(circle.center) <= circle.radius
+    }
+
+    fn signed_area(&self) -> f64 {
+        let count = self.vertices.len();
+        (0..count)
+            .map(|i| cross(self.vertices[i], self.vertices[(i + 1) % count]))
+            .sum::<f64>()
+            / 2f64
+    }
+}
+
+impl Geometry for Polygon {
+    fn contains(&self, point: Vector2) -> bool {
+        self.edges()
+            .all(|edge| cross(edge.end - edge.start, point - edge.start) >= -EPSILON)
+    }
+
+    fn closest_point(&self, point: Vector2) -> Vector2 {
+        if self.contains(point) {
+            return point;
+        }
+        self.edges()
+            .map(|edge| edge.closest_point(point))
+            .min_by(|a, b| a.distance(point).total_cmp(&b.distance(point)))
+            .unwrap()
+    }
+
+    /// Clips the ray against each edge, it enters through the last edge it crosses inwards
+    fn raycast(&self, ray: &Ray) -> Option<Hit> {
+        let mut enter = f64::NEG_INFINITY;
+        let mut exit = f64::INFINITY;
+        let mut normal = Vector2::new_empty();
+        for edge in self.edges() {
+            let outward = perpendicular(edge.direction());
+            let facing = outward.dot(ray.direction);
+            let gap = outward.dot(edge.start - ray.origin);
+            if facing.abs() <= EPSILON {
+                // Parallel to the edge, on its outer side the ray never enters
+                if gap < 0f64 {
+                    return None;
+                }
+                continue;
+            }
+
+            let distance = gap / facing;
+            if facing < 0f64 {
+                if distance > enter {
+                    enter = distance;
+                    normal = outward;
+                }
+            } else {
+                exit = exit.min(distance);
+            }
+            if enter > exit {
+                return None;
+            }
+        }
+
+        if exit < 0f64 {
+            return None;
+        }
+        if enter < 0f64 {
+            return Some(Hit {
+                distance: 0f64,
+                point: ray.origin,
+                normal: ray.direction * -1f64,
+            });
+        }
+        Some(Hit {
+            distance: enter,
+            point: ray.at(enter),
+            normal,
+        })
+    }
+
+    fn bounds(&self) -> Aabb {
+        Aabb::from_points(&self.vertices).unwrap()
+    }
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod polygon_tests {
+
+    use super::*;
+
+    #[test]
+    fn winding_and_convexity() {
+        let clockwise = [
+            Vector2::new(0f64, 0f64),
+            Vector2::new(0f64, 2f64),
+            Vector2::new(2f64, 2f64),
+            Vector2::new(2f64, 0f64),
+        ];
+        let square = Polygon::new(&clockwise).unwrap();
+        assert_eq!(square.vertices()[1], Vector2::new(2f64, 0f64));
+        assert_eq!(square.area(), 4f64);
+        assert_eq!(square.centroid(), Vector2::new(1f64, 1f64));
+
+        let dart = [
+            Vector2::new(0f64, 0f64),
+            Vector2::new(2f64, 1f64),
+            Vector2::new(4f64, 0f64),
+            Vector2::new(2f64, 3f64),
+        ];
+        assert_eq!(Polygon::new(&dart), None);
+        assert_eq!(Polygon::new(&clockwise[..2]), None);
+    }
+
+    #[test]
+    fn queries() {
+        let triangle = Polygon::new(&[
+            Vector2::new(0f64, 0f64),
+            Vector2::new(4f64, 0f64),
+            Vector2::new(0f64, 4f64),
+        ])
+        .unwrap();
+        assert!(triangle.contains(Vector2::new(1f64, 1f64)));
+        assert!(!triangle.contains(Vector2::new(3f64, 3f64)));
+        assert_eq!(
+            triangle.closest_point(Vector2::new(3f64, 3f64)),
+            Vector2::new(2f64, 2f64)
+        );
+
+        let hit = triangle
+            .raycast(&Ray::new(
+                Vector2::new(1f64, -3f64),
+                Vector2::new(0f64, 1f64),
+            ))
+            .unwrap();
+        assert_eq!(hit.distance, 3f64);
+        assert_eq!(hit.normal, Vector2::new(0f64, -1f64));
+
+        let square = Polygon::from_aabb(&Aabb::new(
+            Vector2::new(3.5f64, -1f64),
+            Vector2::new(5.5f64, 1f64),
+        ));
+        let intersection = triangle.intersection(&square).unwrap();
+        assert_eq!(intersection.normal, Vector2::new(1f64, 0f64));
+        assert_eq!(intersection.depth, 0.5f64);
+        assert!(!triangle.overlaps(&square.translated(Vector2::new(1f64, 0f64))));
+    }
+}
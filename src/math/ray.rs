@@ -0,0 +1,100 @@
+#![allow(dead_code)]
+
+use super::{
+    geometry::{Geometry, Hit},
+    vector2::Vector2,
+};
+
+/// Half line from an origin, its direction always has a length of 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray {
+    pub origin: Vector2,
+    pub direction: Vector2,
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl Ray {
+    /// Normalizes the direction, which must not be zero
+    pub fn new(origin: Vector2, direction: Vector2) -> Ray {
+        Ray {
+            origin,
+            direction: direction.normalized(),
+        }
+    }
+
+    /// Ray from one point through another
+    pub fn between(from: Vector2, to: Vector2) -> Ray {
+        Ray::new(from, to - from)
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl Ray {
+    /// Returns the point at a distance along the ray
+    pub fn at(&self, distance: f64) -> Vector2 {
+        self.origin + self.direction * distance
+    }
+
+    /// Returns the point of the ray nearest to a point
+    pub fn closest_point(&self, point: Vector2) -> Vector2 {
+        self.at((point - self.origin).dot(self.direction).max(0f64))
+    }
+
+    /// Returns where the ray first enters a shape
+    pub fn cast(&self, shape: &dyn Geometry) -> Option<Hit> {
+        shape.raycast(self)
+    }
+
+    /// Returns the nearest hit among shapes with the index of the shape, ignoring hits past `max_distance`
+    pub fn cast_all<'a, I>(&self, shapes: I, max_distance: f64) -> Option<(usize, Hit)>
+    where
+        I: IntoIterator<Item = &'a dyn Geometry>,
+    {
+        shapes
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, shape)| shape.raycast(self).map(|hit| (index, hit)))
+            .filter(|(_, hit)| hit.distance <= max_distance)
+            .min_by(|a, b| a.1.distance.total_cmp(&b.1.distance))
+    }
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod ray_tests {
+
+    use super::super::{aabb::Aabb, circle::Circle};
+    use super::*;
+
+    #[test]
+    fn nearest_hit() {
+        let ray = Ray::between(Vector2::new_empty(), Vector2::new(0f64, 10f64));
+        assert_eq!(ray.direction, Vector2::new(0f64, 1f64));
+        assert_eq!(
+            ray.closest_point(Vector2::new(3f64, 4f64)),
+            Vector2::new(0f64, 4f64)
+        );
+        assert_eq!(
+            ray.closest_point(Vector2::new(3f64, -4f64)),
+            Vector2::new_empty()
+        );
+
+        let far = Circle::new(Vector2::new(0f64, 8f64), 1f64);
+        let near = Aabb::new(Vector2::new(-1f64, 3f64), Vector2::new(1f64, 4f64));
+        let shapes: [&dyn Geometry; 2] = [&far, &near];
+        let (index, hit) = ray.cast_all(shapes, 100f64).unwrap();
+        assert_eq!(index, 1);
+        assert_eq!(hit.point, Vector2::new(0f64, 3f64));
+        assert!(ray.cast_all(shapes, 2f64).is_none());
+        assert_eq!(ray.cast(&far).unwrap().distance, 7f64);
+    }
+}
@@ -0,0 +1,159 @@
+#![allow(dead_code)]
+
+use super::{
+    aabb::Aabb,
+    geometry::{Geometry, Hit},
+    ray::Ray,
+    vector2::Vector2,
+};
+
+/// Rectangle from a corner and a size, the usual form for hitboxes and screen areas.
+/// `x` and `y` are the corner with the lowest coordinates, which is the top left when y points down.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl Rect {
+    pub fn new(x: f64, y: f64, width: f64, height: f64) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn from_center(center: Vector2, size: Vector2) -> Rect {
+        Rect::new(
+            center.x - size.x / 2f64,
+            center.y - size.y / 2f64,
+            size.x,
+            size.y,
+        )
+    }
+
+    pub fn from_aabb(bounds: &Aabb) -> Rect {
+        let size = bounds.size();
+        Rect::new(bounds.min.x, bounds.min.y, size.x, size.y)
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl Rect {
+    pub fn position(&self) -> Vector2 {
+        Vector2::new(self.x, self.y)
+    }
+
+    pub fn size(&self) -> Vector2 {
+        Vector2::new(self.width, self.height)
+    }
+
+    pub fn center(&self) -> Vector2 {
+        Vector2::new(self.x + self.width / 2f64, self.y + self.height / 2f64)
+    }
+
+    pub fn area(&self) -> f64 {
+        self.width * self.height
+    }
+
+    pub fn aabb(&self) -> Aabb {
+        Aabb::new(self.position(), self.position() + self.size())
+    }
+
+    /// Returns true when the rectangles share more than an edge
+    pub fn overlaps(&self, other: &Rect) -> bool {
+        self.x < other.x + other.width
+            && other.x < self.x + self.width
+            && self.y < other.y + other.height
+            && other.y < self.y + self.height
+    }
+
+    /// Returns the area shared by both, `None` when they do not overlap
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        if !self.overlaps(other) {
+            return None;
+        }
+        self.aabb()
+            .overlap(&other.aabb())
+            .map(|shared| Rect::from_aabb(&shared))
+    }
+
+    /// Returns the smallest rectangle holding both
+    pub fn union(&self, other: &Rect) -> Rect {
+        Rect::from_aabb(&self.aabb().merged(&other.aabb()))
+    }
+
+    pub fn contains_rect(&self, other: &Rect) -> bool {
+        self.aabb().contains_aabb(&other.aabb())
+    }
+
+    pub fn translated(&self, offset: Vector2) -> Rect {
+        Rect::new(
+            self.x + offset.x,
+            self.y + offset.y,
+            self.width,
+            self.height,
+        )
+    }
+
+    /// Returns the rectangle grown by a margin on every side, negative margins shrink it
+    pub fn expanded(&self, margin: f64) -> Rect {
+        Rect::from_aabb(&self.aabb().expanded(margin))
+    }
+}
+
+impl Geometry for Rect {
+    fn contains(&self, point: Vector2) -> bool {
+        self.aabb().contains(point)
+    }
+
+    fn closest_point(&self, point: Vector2) -> Vector2 {
+        self.aabb().closest_point(point)
+    }
+
+    fn raycast(&self, ray: &Ray) -> Option<Hit> {
+        self.aabb().raycast(ray)
+    }
+
+    fn bounds(&self) -> Aabb {
+        self.aabb()
+    }
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod rect_tests {
+
+    use super::*;
+
+    #[test]
+    fn intersection_and_union() {
+        let a = Rect::new(0f64, 0f64, 4f64, 3f64);
+        let b = Rect::new(2f64, 1f64, 4f64, 4f64);
+        assert_eq!(a.intersection(&b), Some(Rect::new(2f64, 1f64, 2f64, 2f64)));
+        assert_eq!(a.union(&b), Rect::new(0f64, 0f64, 6f64, 5f64));
+        assert_eq!(a.center(), Vector2::new(2f64, 1.5f64));
+
+        // Rectangles side by side only share an edge
+        let beside = Rect::new(4f64, 0f64, 1f64, 1f64);
+        assert!(!a.overlaps(&beside));
+        assert_eq!(a.intersection(&beside), None);
+        assert!(a.contains(Vector2::new(4f64, 3f64)));
+        assert!(a.union(&b).contains_rect(&a));
+        assert_eq!(a.expanded(1f64), Rect::new(-1f64, -1f64, 6f64, 5f64));
+    }
+}
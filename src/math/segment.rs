@@ -0,0 +1,188 @@
+#![allow(dead_code)]
+
+use super::{
+    aabb::Aabb,
+    circle::Circle,
+    geometry::{cross, perpendicular, Geometry, Hit, EPSILON},
+    ray::Ray,
+    vector2::Vector2,
+};
+
+/// Straight line between two points, like a wall or a line of sight.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineSegment {
+    pub start: Vector2,
+    pub end: Vector2,
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl LineSegment {
+    pub fn new(start: Vector2, end: Vector2) -> LineSegment {
+        LineSegment { start, end }
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl LineSegment {
+    pub fn length(&self) -> f64 {
+        self.start.distance(self.end)
+    }
+
+    pub fn midpoint(&self) -> Vector2 {
+        (self.start + self.end) * 0.5f64
+    }
+
+    /// Returns the unit direction from start to end
+    pub fn direction(&self) -> Vector2 {
+        (self.end - self.start).normalized()
+    }
+
+    /// Returns the point where two segments cross, `None` for parallel segments
+    pub fn intersection(&self, other: &LineSegment) -> Option<Vector2> {
+        let (along, other_along) = (self.end - self.start, other.end - other.start);
+        let denominator = cross(along, other_along);
+        if denominator.abs() <= EPSILON {
+            return None;
+        }
+
+        // Fractions along each segment where the lines meet
+        let offset = other.start - self.start;
+        let t = cross(offset, other_along) / denominator;
+        let u = cross(offset, along) / denominator;
+        if (0f64..=1f64).contains(&t) && (0f64..=1f64).contains(&u) {
+            Some(self.start + along * t)
+        } else {
+            None
+        }
+    }
+
+    pub fn overlaps_circle(&self, circle: &Circle) -> bool {
+        self.distance(circle.center) <= circle.radius
+    }
+
+    pub fn overlaps_aabb(&self, bounds: &Aabb) -> bool {
+        if bounds.contains(self.start) {
+            return true;
+        }
+        let length = self.length();
+        length > EPSILON
+            && bounds
+                .raycast(&Ray::new(self.start, self.end - self.start))
+                .is_some_and(|hit| hit.distance <= length)
+    }
+}
+
+impl Geometry for LineSegment {
+    /// Points within `EPSILON` of the segment count as on it
+    fn contains(&self, point: Vector2) -> bool {
+        self.distance(point) <= EPSILON
+    }
+
+    fn closest_point(&self, point: Vector2) -> Vector2 {
+        let along = self.end - self.start;
+        let length_squared = along.dot(along);
+        if length_squared <= EPSILON * EPSILON {
+            return self.start;
+        }
+        let t = ((point - self.start).dot(along) / length_squared).clamp(0f64, 1f64);
+        self.start + along * t
+    }
+
+    /// Hits either side, the normal faces the ray
+    fn raycast(&self, ray: &Ray) -> Option<Hit> {
+        let along = self.end - self.start;
+        let denominator = cross(ray.direction, along);
+        if denominator.abs() <= EPSILON {
+            return None;
+        }
+
+        let offset = self.start - ray.origin;
+        let distance = cross(offset, along) / denominator;
+        let t = cross(offset, ray.direction) / denominator;
+        if distance < 0f64 || !(0f64..=1f64).contains(&t) {
+            return None;
+        }
+
+        let normal = perpendicular(along.normalized());
+        Some(Hit {
+            distance,
+            point: ray.at(distance),
+            normal: if normal.dot(ray.direction) > 0f64 {
+                normal * -1f64
+            } else {
+                normal
+            },
+        })
+    }
+
+    fn bounds(&self) -> Aabb {
+        Aabb::from_points(&[self.start, self.end]).unwrap()
+    }
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod segment_tests {
+
+    use super::*;
+
+    #[test]
+    fn crossing_and_closest() {
+        let a = LineSegment::new(Vector2::new(0f64, 0f64), Vector2::new(4f64, 4f64));
+        let b = LineSegment::new(Vector2::new(0f64, 4f64), Vector2::new(4f64, 0f64));
+        assert_eq!(a.intersection(&b), Some(Vector2::new(2f64, 2f64)));
+        let parallel = LineSegment::new(Vector2::new(1f64, 0f64), Vector2::new(5f64, 4f64));
+        assert_eq!(a.intersection(&parallel), None);
+
+        assert_eq!(
+            a.closest_point(Vector2::new(4f64, 0f64)),
+            Vector2::new(2f64, 2f64)
+        );
+        assert_eq!(
+            a.closest_point(Vector2::new(-3f64, -1f64)),
+            Vector2::new_empty()
+        );
+        assert!(a.overlaps_aabb(&Aabb::new(
+            Vector2::new(3f64, 3.5f64),
+            Vector2::new(5f64, 5f64)
+        )));
+        assert!(!a.overlaps_aabb(&Aabb::new(
+            Vector2::new(3f64, 0f64),
+            Vector2::new(5f64, 1f64)
+        )));
+    }
+
+    #[test]
+    fn raycast_from_both_sides() {
+        let wall = LineSegment::new(Vector2::new(2f64, -1f64), Vector2::new(2f64, 1f64));
+        let hit = wall
+            .raycast(&Ray::new(Vector2::new_empty(), Vector2::new(1f64, 0f64)))
+            .unwrap();
+        assert_eq!(hit.distance, 2f64);
+        assert_eq!(hit.normal, Vector2::new(-1f64, 0f64));
+
+        let hit = wall
+            .raycast(&Ray::new(
+                Vector2::new(5f64, 0.5f64),
+                Vector2::new(-1f64, 0f64),
+            ))
+            .unwrap();
+        assert_eq!(hit.point, Vector2::new(2f64, 0.5f64));
+        assert_eq!(hit.normal, Vector2::new(1f64, 0f64));
+        assert!(wall
+            .raycast(&Ray::new(
+                Vector2::new(0f64, 2f64),
+                Vector2::new(1f64, 0f64)
+            ))
+            .is_none());
+    }
+}
@@ -0,0 +1,150 @@
+#![allow(dead_code)]
+
+use super::{matrix3::Matrix3, vector2::Vector2};
+
+/// Position, rotation and scale of an object in 2D.
+/// The object is scaled and rotated around `origin`, which is then placed at `position`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform2D {
+    pub position: Vector2,
+    /// Rotation in radians
+    pub rotation: f64,
+    pub scale: Vector2,
+    /// Pivot in the object's own space
+    pub origin: Vector2,
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl Transform2D {
+    pub fn identity() -> Transform2D {
+        Transform2D {
+            position: Vector2::new_empty(),
+            rotation: 0f64,
+            scale: Vector2::new(1f64, 1f64),
+            origin: Vector2::new_empty(),
+        }
+    }
+
+    pub fn new(position: Vector2, rotation: f64, scale: Vector2) -> Transform2D {
+        Transform2D {
+            position,
+            rotation,
+            scale,
+            origin: Vector2::new_empty(),
+        }
+    }
+
+    pub fn from_position(position: Vector2) -> Transform2D {
+        Transform2D {
+            position,
+            ..Transform2D::identity()
+        }
+    }
+
+    /// Splits an affine matrix into a transform around `origin`.
+    /// Shearing, which nested non-uniform scales can produce, cannot be represented and is lost.
+    pub fn from_matrix(matrix: &Matrix3, origin: Vector2) -> Transform2D {
+        // The matrix without the origin offset is translation * rotation * scale
+        let placed = *matrix * Matrix3::translation(origin);
+        let m = &placed.m;
+        let rotation = m[1][0].atan2(m[0][0]);
+        let scale_x = (m[0][0] * m[0][0] + m[1][0] * m[1][0]).sqrt();
+
+        // The second column projected on the rotated y axis keeps the sign of a mirrored scale
+        let (sin, cos) = rotation.sin_cos();
+        let scale_y = -sin * m[0][1] + cos * m[1][1];
+
+        Transform2D {
+            position: placed.get_translation(),
+            rotation,
+            scale: Vector2::new(scale_x, scale_y),
+            origin,
+        }
+    }
+}
+
+impl Default for Transform2D {
+    fn default() -> Transform2D {
+        Transform2D::identity()
+    }
+}
+
+// ====================================
+// Operations
+// ====================================
+
+impl Transform2D {
+    /// Returns the matrix from the object's space to its parent's space
+    pub fn matrix(&self) -> Matrix3 {
+        Matrix3::translation(self.position)
+            * Matrix3::rotation(self.rotation)
+            * Matrix3::scale(self.scale)
+            * Matrix3::translation(self.origin * -1f64)
+    }
+
+    pub fn transform_point(&self, point: Vector2) -> Vector2 {
+        self.matrix().transform_point(point)
+    }
+
+    pub fn translate(&mut self, offset: Vector2) {
+        self.position += offset;
+    }
+
+    pub fn rotate(&mut self, angle: f64) {
+        self.rotation += angle;
+    }
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod transform2d_tests {
+
+    use super::*;
+
+    fn close(a: Vector2, b: Vector2) -> bool {
+        (a - b).magnitude() < 1e-9
+    }
+
+    #[test]
+    fn simple_matrix() {
+        let mut transform = Transform2D::new(
+            Vector2::new(10f64, 5f64),
+            std::f64::consts::FRAC_PI_2,
+            Vector2::new(2f64, 2f64),
+        );
+        transform.origin = Vector2::new(1f64, 0f64);
+
+        // The origin lands on the position
+        assert!(close(
+            transform.transform_point(Vector2::new(1f64, 0f64)),
+            Vector2::new(10f64, 5f64)
+        ));
+        assert!(close(
+            transform.transform_point(Vector2::new(2f64, 0f64)),
+            Vector2::new(10f64, 7f64)
+        ));
+        assert_eq!(Transform2D::default().matrix(), Matrix3::identity());
+    }
+
+    #[test]
+    fn simple_from_matrix() {
+        let mut transform = Transform2D::new(
+            Vector2::new(-3f64, 4f64),
+            2.5f64,
+            Vector2::new(0.5f64, -3f64),
+        );
+        transform.origin = Vector2::new(8f64, 8f64);
+
+        let result = Transform2D::from_matrix(&transform.matrix(), transform.origin);
+        assert!(result.matrix().approx_eq(&transform.matrix(), 1e-9));
+        assert!(close(result.position, transform.position));
+        assert!((result.rotation - transform.rotation).abs() < 1e-9);
+        assert!(close(result.scale, transform.scale));
+    }
+}
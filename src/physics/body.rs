@@ -0,0 +1,254 @@
+#![allow(dead_code)]
+
+use crate::math::{aabb::Aabb, vector2::Vector2};
+
+use super::shape::{cross, cross_scalar, Core, Shape};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyType {
+    /// Never moves, like the ground
+    Static,
+    /// Moves with its velocity but ignores forces and collisions, like a moving platform
+    Kinematic,
+    /// Moved by forces and collisions
+    Dynamic,
+}
+
+/// Surface properties, combined between two bodies when they touch.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Material {
+    pub density: f64,
+    /// 0 slides freely
+    pub friction: f64,
+    /// 0 stops on impact, 1 bounces back at the same speed
+    pub restitution: f64,
+}
+
+/// Handle to a body in a `World`, stale once the body is removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BodyHandle {
+    pub(crate) index: u32,
+    pub(crate) generation: u32,
+}
+
+/// Rigid body, its position is the center of mass of its shape.
+#[derive(Debug, Clone)]
+pub struct Body {
+    pub(crate) body_type: BodyType,
+    pub position: Vector2,
+    /// Rotation in radians
+    pub rotation: f64,
+    pub velocity: Vector2,
+    pub angular_velocity: f64,
+    pub gravity_scale: f64,
+    /// Fraction of the velocity lost per second
+    pub linear_damping: f64,
+    pub angular_damping: f64,
+    /// Prevents rotation, like characters that should stay upright
+    pub(crate) fixed_rotation: bool,
+    pub(crate) shape: Shape,
+    pub(crate) material: Material,
+    pub(crate) inverse_mass: f64,
+    pub(crate) inverse_inertia: f64,
+    pub(crate) force: Vector2,
+    pub(crate) torque: f64,
+    pub(crate) sleeping: bool,
+    /// Time spent nearly still, the body falls asleep past `World::time_to_sleep`
+    pub(crate) sleep_time: f64,
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl Material {
+    pub fn new(density: f64, friction: f64, restitution: f64) -> Material {
+        Material {
+            density,
+            friction,
+            restitution,
+        }
+    }
+}
+
+impl Default for Material {
+    fn default() -> Material {
+        Material::new(1f64, 0.4f64, 0f64)
+    }
+}
+
+impl Body {
+    pub fn new(body_type: BodyType, shape: Shape, position: Vector2) -> Body {
+        let mut body = Body {
+            body_type,
+            position,
+            rotation: 0f64,
+            velocity: Vector2::new_empty(),
+            angular_velocity: 0f64,
+            gravity_scale: 1f64,
+            linear_damping: 0f64,
+            angular_damping: 0f64,
+            fixed_rotation: false,
+            shape,
+            material: Material::default(),
+            inverse_mass: 0f64,
+            inverse_inertia: 0f64,
+            force: Vector2::new_empty(),
+            torque: 0f64,
+            sleeping: false,
+            sleep_time: 0f64,
+        };
+        body.update_mass();
+        body
+    }
+
+    pub fn new_static(shape: Shape, position: Vector2) -> Body {
+        Body::new(BodyType::Static, shape, position)
+    }
+
+    pub fn new_kinematic(shape: Shape, position: Vector2) -> Body {
+        Body::new(BodyType::Kinematic, shape, position)
+    }
+
+    pub fn new_dynamic(shape: Shape, position: Vector2) -> Body {
+        Body::new(BodyType::Dynamic, shape, position)
+    }
+
+    pub fn with_material(mut self, material: Material) -> Body {
+        self.set_material(material);
+        self
+    }
+
+    pub fn with_velocity(mut self, velocity: Vector2) -> Body {
+        self.velocity = velocity;
+        self
+    }
+
+    pub fn with_rotation(mut self, rotation: f64) -> Body {
+        self.rotation = rotation;
+        self
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl Body {
+    pub fn body_type(&self) -> BodyType {
+        self.body_type
+    }
+
+    pub fn set_body_type(&mut self, body_type: BodyType) {
+        self.body_type = body_type;
+        if body_type == BodyType::Static {
+            self.velocity = Vector2::new_empty();
+            self.angular_velocity = 0f64;
+        }
+        self.update_mass();
+        self.wake();
+    }
+
+    pub fn shape(&self) -> &Shape {
+        &self.shape
+    }
+
+    pub fn set_shape(&mut self, shape: Shape) {
+        self.shape = shape;
+        self.update_mass();
+    }
+
+    pub fn material(&self) -> Material {
+        self.material
+    }
+
+    pub fn set_material(&mut self, material: Material) {
+        self.material = material;
+        self.update_mass();
+    }
+
+    /// Returns the mass, 0 for bodies that are not dynamic
+    pub fn mass(&self) -> f64 {
+        if self.inverse_mass > 0f64 {
+            1f64 / self.inverse_mass
+        } else {
+            0f64
+        }
+    }
+
+    pub fn is_sleeping(&self) -> bool {
+        self.sleeping
+    }
+
+    pub fn is_dynamic(&self) -> bool {
+        self.body_type == BodyType::Dynamic
+    }
+
+    pub fn wake(&mut self) {
+        self.sleeping = false;
+        self.sleep_time = 0f64;
+    }
+
+    /// Applies a force at the center until the next step
+    pub fn apply_force(&mut self, force: Vector2) {
+        self.force += force;
+        self.wake();
+    }
+
+    /// Applies a force at a point in the world until the next step
+    pub fn apply_force_at(&mut self, force: Vector2, point: Vector2) {
+        self.force += force;
+        self.torque += cross(point - self.position, force);
+        self.wake();
+    }
+
+    pub fn apply_torque(&mut self, torque: f64) {
+        self.torque += torque;
+        self.wake();
+    }
+
+    /// Changes the velocity at once, like a jump or a hit
+    pub fn apply_impulse(&mut self, impulse: Vector2, point: Vector2) {
+        self.velocity += impulse * self.inverse_mass;
+        self.angular_velocity += self.inverse_inertia * cross(point - self.position, impulse);
+        self.wake();
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        self.shape.bounds(self.position, self.rotation)
+    }
+
+    /// Returns the velocity of a point attached to the body
+    pub fn velocity_at(&self, point: Vector2) -> Vector2 {
+        self.velocity + cross_scalar(self.angular_velocity, point - self.position)
+    }
+
+    pub(crate) fn core(&self) -> Core {
+        self.shape.core(self.position, self.rotation)
+    }
+
+    pub fn has_fixed_rotation(&self) -> bool {
+        self.fixed_rotation
+    }
+
+    pub fn set_fixed_rotation(&mut self, fixed: bool) {
+        self.fixed_rotation = fixed;
+        self.update_mass();
+    }
+
+    fn update_mass(&mut self) {
+        self.inverse_mass = 0f64;
+        self.inverse_inertia = 0f64;
+        if self.body_type != BodyType::Dynamic {
+            return;
+        }
+
+        let (mass, inertia) = self.shape.mass(self.material.density);
+        if mass > 0f64 {
+            self.inverse_mass = 1f64 / mass;
+        }
+        if inertia > 0f64 && !self.fixed_rotation && !self.shape.is_axis_aligned() {
+            self.inverse_inertia = 1f64 / inertia;
+        }
+    }
+}
@@ -0,0 +1,129 @@
+#![allow(dead_code)]
+
+use crate::math::aabb::Aabb;
+
+/// Sort and sweep along the x axis: finds the pairs of boxes that overlap
+/// without testing every pair. Boxes keep their order between steps,
+/// so the insertion sort that restores it is close to linear.
+#[derive(Debug, Default)]
+pub struct Broadphase {
+    /// Indices into the boxes of the last call, sorted by their left edge
+    order: Vec<usize>,
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl Broadphase {
+    pub fn new() -> Broadphase {
+        Broadphase::default()
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl Broadphase {
+    /// Returns the overlapping pairs `(i, j)` with `i < j`, sorted, so results are deterministic.
+    /// `None` boxes take no part, like removed bodies.
+    pub fn pairs(&mut self, boxes: &[Option<Aabb>]) -> Vec<(usize, usize)> {
+        if self.order.len() > boxes.len() {
+            self.order.retain(|&index| index < boxes.len());
+        } else {
+            self.order.extend(self.order.len()..boxes.len());
+        }
+
+        let key = |index: usize| boxes[index].map_or(f64::MAX, |bounds| bounds.min.x);
+        for i in 1..self.order.len() {
+            let mut j = i;
+            while j > 0
+                && (key(self.order[j - 1]), self.order[j - 1]) > (key(self.order[j]), self.order[j])
+            {
+                self.order.swap(j - 1, j);
+                j -= 1;
+            }
+        }
+
+        let mut pairs = Vec::new();
+        for (position, &i) in self.order.iter().enumerate() {
+            let a = match boxes[i] {
+                Some(a) => a,
+                None => break,
+            };
+            for &j in self.order[position + 1..].iter() {
+                let b = match boxes[j] {
+                    Some(b) => b,
+                    None => break,
+                };
+                if b.min.x > a.max.x {
+                    break;
+                }
+                if a.overlaps(&b) {
+                    pairs.push((i.min(j), i.max(j)));
+                }
+            }
+        }
+
+        pairs.sort_unstable();
+        pairs
+    }
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod broadphase_tests {
+
+    use super::*;
+    use crate::math::vector2::Vector2;
+
+    fn square(x: f64, y: f64) -> Option<Aabb> {
+        Some(Aabb::new(
+            Vector2::new(x, y),
+            Vector2::new(x + 1f64, y + 1f64),
+        ))
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let mut seed = 7u32;
+        let mut random = move || {
+            seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+            (seed >> 8) as f64 / (1u32 << 24) as f64 * 20f64
+        };
+
+        let mut broadphase = Broadphase::new();
+        for _ in 0..5 {
+            let boxes: Vec<Option<Aabb>> = (0..60)
+                .map(|index| {
+                    if index % 7 == 3 {
+                        None
+                    } else {
+                        square(random(), random())
+                    }
+                })
+                .collect();
+
+            let mut expected = Vec::new();
+            for i in 0..boxes.len() {
+                for j in i + 1..boxes.len() {
+                    if let (Some(a), Some(b)) = (boxes[i], boxes[j]) {
+                        if a.overlaps(&b) {
+                            expected.push((i, j));
+                        }
+                    }
+                }
+            }
+            assert_eq!(broadphase.pairs(&boxes), expected);
+        }
+
+        assert_eq!(
+            broadphase.pairs(&[square(0f64, 0f64), square(0.5f64, 0.5f64)]),
+            [(0, 1)]
+        );
+    }
+}
@@ -0,0 +1,538 @@
+#![allow(dead_code)]
+
+use crate::math::vector2::Vector2;
+
+use super::shape::{cross, right_normal, Core};
+
+/// Overlaps smaller than this are allowed, so resting contacts persist between steps
+pub const LINEAR_SLOP: f64 = 0.005;
+
+const GJK_ITERATIONS: usize = 32;
+
+/// Point where two shapes touch.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContactPoint {
+    /// Point in the world, halfway between the two surfaces
+    pub position: Vector2,
+    /// How deep the shapes overlap along the normal
+    pub depth: f64,
+}
+
+/// Contact between two shapes, with one or two points.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Manifold {
+    /// Direction from the first shape to the second
+    pub normal: Vector2,
+    pub points: Vec<ContactPoint>,
+}
+
+/// Result of a GJK distance query between two cores.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Distance {
+    pub distance: f64,
+    /// Closest points on each core
+    pub point_a: Vector2,
+    pub point_b: Vector2,
+}
+
+/// Point of the Minkowski difference with the points of the cores it came from.
+#[derive(Debug, Clone, Copy)]
+struct SimplexVertex {
+    a: Vector2,
+    b: Vector2,
+    /// `a - b`
+    w: Vector2,
+    /// Barycentric weight of the closest point
+    u: f64,
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+/// Finds the contact between two shapes, `None` if they do not touch
+pub(crate) fn collide(a: &Core, b: &Core) -> Option<Manifold> {
+    if a.is_polygon() && b.is_polygon() && a.radius == 0f64 && b.radius == 0f64 {
+        return collide_polygons(a, b);
+    }
+
+    let radius = a.radius + b.radius;
+    let closest = distance(a, b);
+    if closest.distance > radius {
+        return None;
+    }
+    if closest.distance <= f64::EPSILON.sqrt() {
+        return collide_deep(a, b);
+    }
+
+    let normal = (closest.point_b - closest.point_a) / closest.distance;
+    let mut manifold = Manifold {
+        normal,
+        points: vec![surface_contact(&closest, normal, a.radius, b.radius)],
+    };
+
+    // A capsule lying on a face touches at both ends, one point would let it rock
+    for (segment, other, flip) in [(a, b, 1f64), (b, a, -1f64)] {
+        if segment.points.len() != 2 {
+            continue;
+        }
+
+        let mut ends = Vec::new();
+        for &end in segment.points.iter() {
+            let point = Core {
+                points: vec![end],
+                radius: segment.radius,
+            };
+            let closest = distance(&point, other);
+            let direction = (closest.point_b - closest.point_a) / closest.distance * flip;
+            if closest.distance > f64::EPSILON.sqrt()
+                && closest.distance <= radius + LINEAR_SLOP
+                && direction.dot(normal) > 0.999f64
+            {
+                let (radius_a, radius_b) = if flip > 0f64 {
+                    (segment.radius, other.radius)
+                } else {
+                    (other.radius, segment.radius)
+                };
+                let (point_a, point_b) = if flip > 0f64 {
+                    (closest.point_a, closest.point_b)
+                } else {
+                    (closest.point_b, closest.point_a)
+                };
+                let along = Distance {
+                    distance: closest.distance,
+                    point_a,
+                    point_b,
+                };
+                ends.push(surface_contact(&along, normal, radius_a, radius_b));
+            }
+        }
+        if ends.len() == 2 {
+            manifold.points = ends;
+        }
+    }
+
+    Some(manifold)
+}
+
+/// Contact halfway between the surfaces grown from the closest points of the cores
+fn surface_contact(
+    closest: &Distance,
+    normal: Vector2,
+    radius_a: f64,
+    radius_b: f64,
+) -> ContactPoint {
+    let surface_a = closest.point_a + normal * radius_a;
+    let surface_b = closest.point_b - normal * radius_b;
+    ContactPoint {
+        position: (surface_a + surface_b) * 0.5f64,
+        depth: radius_a + radius_b - closest.distance,
+    }
+}
+
+/// Returns the separation between two cores along an axis, negative when they overlap
+fn separation(a: &Core, b: &Core, axis: Vector2) -> f64 {
+    b.support(axis * -1f64).dot(axis) - a.support(axis).dot(axis)
+}
+
+/// Handles cores that overlap with SAT, picking the axis of least penetration
+fn collide_deep(a: &Core, b: &Core) -> Option<Manifold> {
+    let mut axes = Vec::new();
+    for core in [a, b] {
+        let count = core.points.len();
+        if count < 2 {
+            continue;
+        }
+        for i in 0..count {
+            let edge = core.points[(i + 1) % count] - core.points[i];
+            if edge.dot(edge) > f64::EPSILON {
+                let normal = right_normal(edge).normalized();
+                axes.push(normal);
+                axes.push(normal * -1f64);
+            }
+            if count == 2 {
+                break;
+            }
+        }
+    }
+
+    // Concentric circles push apart along an arbitrary axis
+    if axes.is_empty() {
+        axes.push(Vector2::new(0f64, 1f64));
+    }
+
+    let mut best = axes[0];
+    let mut best_separation = f64::MIN;
+    for &axis in axes.iter() {
+        let separation = separation(a, b, axis);
+        if separation > best_separation {
+            best_separation = separation;
+            best = axis;
+        }
+    }
+
+    let depth = a.radius + b.radius - best_separation;
+    if depth < 0f64 {
+        return None;
+    }
+
+    // The deepest point of the second core, moved to halfway between the surfaces
+    let deepest = b.support(best * -1f64) - best * b.radius;
+    Some(Manifold {
+        normal: best,
+        points: vec![ContactPoint {
+            position: deepest + best * (depth / 2f64),
+            depth,
+        }],
+    })
+}
+
+/// Returns the edge of a polygon whose normal separates it the most from another polygon
+fn max_separation(a: &Core, b: &Core) -> (usize, f64) {
+    let count = a.points.len();
+    let mut best = (0, f64::MIN);
+    for i in 0..count {
+        let normal = right_normal(a.points[(i + 1) % count] - a.points[i]).normalized();
+        let separation = b.support(normal * -1f64).dot(normal) - a.points[i].dot(normal);
+        if separation > best.1 {
+            best = (i, separation);
+        }
+    }
+    best
+}
+
+/// SAT between two polygons, then clips the incident edge against the reference edge
+fn collide_polygons(a: &Core, b: &Core) -> Option<Manifold> {
+    let (edge_a, separation_a) = max_separation(a, b);
+    if separation_a > 0f64 {
+        return None;
+    }
+    let (edge_b, separation_b) = max_separation(b, a);
+    if separation_b > 0f64 {
+        return None;
+    }
+
+    // Prefer the first polygon's edge unless the other is clearly better, for stable contacts
+    let (reference, incident, edge, flip) = if separation_b > separation_a + 0.1f64 * LINEAR_SLOP {
+        (b, a, edge_b, true)
+    } else {
+        (a, b, edge_a, false)
+    };
+
+    let count = reference.points.len();
+    let v1 = reference.points[edge];
+    let v2 = reference.points[(edge + 1) % count];
+    let tangent = (v2 - v1).normalized();
+    let normal = right_normal(tangent);
+
+    // The incident edge faces the reference normal the most
+    let incident_count = incident.points.len();
+    let mut incident_edge = 0;
+    let mut min_dot = f64::MAX;
+    for i in 0..incident_count {
+        let edge_normal =
+            right_normal(incident.points[(i + 1) % incident_count] - incident.points[i])
+                .normalized();
+        let dot = edge_normal.dot(normal);
+        if dot < min_dot {
+            min_dot = dot;
+            incident_edge = i;
+        }
+    }
+    let mut segment = [
+        incident.points[incident_edge],
+        incident.points[(incident_edge + 1) % incident_count],
+    ];
+
+    // Clips to the slab between the side planes of the reference edge
+    for (direction, offset) in [
+        (tangent * -1f64, -tangent.dot(v1)),
+        (tangent, tangent.dot(v2)),
+    ] {
+        segment = clip_segment(segment, direction, offset)?;
+    }
+
+    let reference_offset = normal.dot(v1);
+    let mut points = Vec::new();
+    for point in segment {
+        let separation = normal.dot(point) - reference_offset;
+        if separation <= 0f64 {
+            points.push(ContactPoint {
+                position: point - normal * (separation / 2f64),
+                depth: -separation,
+            });
+        }
+    }
+    if points.is_empty() {
+        return None;
+    }
+
+    Some(Manifold {
+        normal: if flip { normal * -1f64 } else { normal },
+        points,
+    })
+}
+
+/// Keeps the part of a segment where `dot(direction, point) <= offset`
+fn clip_segment(segment: [Vector2; 2], direction: Vector2, offset: f64) -> Option<[Vector2; 2]> {
+    let distance_0 = direction.dot(segment[0]) - offset;
+    let distance_1 = direction.dot(segment[1]) - offset;
+
+    if distance_0 > 0f64 && distance_1 > 0f64 {
+        return None;
+    }
+    if distance_0 <= 0f64 && distance_1 <= 0f64 {
+        return Some(segment);
+    }
+
+    let t = distance_0 / (distance_0 - distance_1);
+    let crossing = segment[0] + (segment[1] - segment[0]) * t;
+    if distance_0 > 0f64 {
+        Some([crossing, segment[1]])
+    } else {
+        Some([segment[0], crossing])
+    }
+}
+
+/// Returns the distance between the closest points of two cores, ignoring their radius.
+/// Overlapping cores have a distance of 0.
+pub(crate) fn distance(a: &Core, b: &Core) -> Distance {
+    let vertex = |direction: Vector2| {
+        let point_a = a.support(direction);
+        let point_b = b.support(direction * -1f64);
+        SimplexVertex {
+            a: point_a,
+            b: point_b,
+            w: point_a - point_b,
+            u: 1f64,
+        }
+    };
+
+    let mut simplex = vec![vertex(b.points[0] - a.points[0])];
+    for _ in 0..GJK_ITERATIONS {
+        solve_simplex(&mut simplex);
+        if simplex.len() == 3 {
+            break;
+        }
+
+        let closest = closest_point(&simplex);
+        if closest.dot(closest) < f64::EPSILON * f64::EPSILON {
+            break;
+        }
+
+        // Searches towards the origin, stops when the new point gets no closer
+        let next = vertex(closest * -1f64);
+        let progress = closest.dot(closest) - next.w.dot(closest);
+        let duplicate = simplex
+            .iter()
+            .any(|vertex| vertex.a == next.a && vertex.b == next.b);
+        if duplicate || progress <= 1e-10f64 * closest.dot(closest) {
+            break;
+        }
+        simplex.push(next);
+    }
+    solve_simplex(&mut simplex);
+
+    let mut point_a = Vector2::new_empty();
+    let mut point_b = Vector2::new_empty();
+    for vertex in simplex.iter() {
+        point_a += vertex.a * vertex.u;
+        point_b += vertex.b * vertex.u;
+    }
+
+    let distance = if simplex.len() == 3 {
+        0f64
+    } else {
+        (point_a - point_b).magnitude()
+    };
+    Distance {
+        distance,
+        point_a,
+        point_b,
+    }
+}
+
+fn closest_point(simplex: &[SimplexVertex]) -> Vector2 {
+    let mut closest = Vector2::new_empty();
+    for vertex in simplex.iter() {
+        closest += vertex.w * vertex.u;
+    }
+    closest
+}
+
+/// Reduces the simplex to the vertices closest to the origin and sets their weights.
+/// Three vertices remain only when the origin is inside the triangle.
+fn solve_simplex(simplex: &mut Vec<SimplexVertex>) {
+    match simplex.len() {
+        1 => simplex[0].u = 1f64,
+        2 => {
+            let (w1, w2) = (simplex[0].w, simplex[1].w);
+            let edge = w2 - w1;
+            let d2 = -w1.dot(edge);
+            let d1 = w2.dot(edge);
+            if d2 <= 0f64 {
+                simplex.truncate(1);
+                simplex[0].u = 1f64;
+            } else if d1 <= 0f64 {
+                simplex.remove(0);
+                simplex[0].u = 1f64;
+            } else {
+                simplex[0].u = d1 / (d1 + d2);
+                simplex[1].u = d2 / (d1 + d2);
+            }
+        }
+        _ => {
+            let (w1, w2, w3) = (simplex[0].w, simplex[1].w, simplex[2].w);
+            let (e12, e13, e23) = (w2 - w1, w3 - w1, w3 - w2);
+            let (d12_1, d12_2) = (w2.dot(e12), -w1.dot(e12));
+            let (d13_1, d13_2) = (w3.dot(e13), -w1.dot(e13));
+            let (d23_1, d23_2) = (w3.dot(e23), -w2.dot(e23));
+
+            let area = cross(e12, e13);
+            let d123_1 = area * cross(w2, w3);
+            let d123_2 = area * cross(w3, w1);
+            let d123_3 = area * cross(w1, w2);
+
+            let keep = |simplex: &mut Vec<SimplexVertex>, kept: &[(usize, f64)]| {
+                let vertices: Vec<SimplexVertex> = kept
+                    .iter()
+                    .map(|&(index, u)| SimplexVertex {
+                        u,
+                        ..simplex[index]
+                    })
+                    .collect();
+                *simplex = vertices;
+            };
+
+            if d12_2 <= 0f64 && d13_2 <= 0f64 {
+                keep(simplex, &[(0, 1f64)]);
+            } else if d12_1 > 0f64 && d12_2 > 0f64 && d123_3 <= 0f64 {
+                let sum = d12_1 + d12_2;
+                keep(simplex, &[(0, d12_1 / sum), (1, d12_2 / sum)]);
+            } else if d13_1 > 0f64 && d13_2 > 0f64 && d123_2 <= 0f64 {
+                let sum = d13_1 + d13_2;
+                keep(simplex, &[(0, d13_1 / sum), (2, d13_2 / sum)]);
+            } else if d12_1 <= 0f64 && d23_2 <= 0f64 {
+                keep(simplex, &[(1, 1f64)]);
+            } else if d13_1 <= 0f64 && d23_1 <= 0f64 {
+                keep(simplex, &[(2, 1f64)]);
+            } else if d23_1 > 0f64 && d23_2 > 0f64 && d123_1 <= 0f64 {
+                let sum = d23_1 + d23_2;
+                keep(simplex, &[(1, d23_1 / sum), (2, d23_2 / sum)]);
+            } else {
+                let sum = d123_1 + d123_2 + d123_3;
+                keep(
+                    simplex,
+                    &[(0, d123_1 / sum), (1, d123_2 / sum), (2, d123_3 / sum)],
+                );
+            }
+        }
+    }
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod collision_tests {
+
+    use super::super::shape::Shape;
+    use super::*;
+
+    fn core(shape: &Shape, x: f64, y: f64, rotation: f64) -> Core {
+        shape.core(Vector2::new(x, y), rotation)
+    }
+
+    fn close(a: Vector2, b: Vector2) -> bool {
+        (a - b).magnitude() < 1e-9
+    }
+
+    #[test]
+    fn gjk_distance() {
+        let square = Shape::rectangle(1f64, 1f64);
+        let a = core(&square, 0f64, 0f64, 0f64);
+        let b = core(&square, 5f64, 0.5f64, 0f64);
+        let result = distance(&a, &b);
+        assert!((result.distance - 3f64).abs() < 1e-9);
+        assert!((result.point_a.x - 1f64).abs() < 1e-9);
+        assert!((result.point_b.x - 4f64).abs() < 1e-9);
+
+        // Corner to face once turned an eighth, the face sits 1 from the center
+        let b = core(&square, 4f64, 4f64, std::f64::consts::FRAC_PI_4);
+        let result = distance(&a, &b);
+        let face = Vector2::new(4f64, 4f64) - Vector2::new(1f64, 1f64).normalized();
+        assert!(close(result.point_a, Vector2::new(1f64, 1f64)));
+        assert!(close(result.point_b, face));
+        assert!((result.distance - (face - Vector2::new(1f64, 1f64)).magnitude()).abs() < 1e-9);
+
+        let overlapping = core(&square, 1f64, 1f64, 0.3f64);
+        assert_eq!(distance(&a, &overlapping).distance, 0f64);
+
+        let segment = core(&Shape::capsule(2f64, 0.5f64), 3f64, 0f64, 0f64);
+        let point = core(&Shape::circle(1f64), 0f64, 1f64, 0f64);
+        let result = distance(&point, &segment);
+        assert!((result.distance - 3f64).abs() < 1e-9);
+        assert!(close(result.point_b, Vector2::new(3f64, 1f64)));
+    }
+
+    #[test]
+    fn circles() {
+        let circle = Shape::circle(1f64);
+        let a = core(&circle, 0f64, 0f64, 0f64);
+        let manifold = collide(&a, &core(&circle, 1.5f64, 0f64, 0f64)).unwrap();
+        assert!(close(manifold.normal, Vector2::new(1f64, 0f64)));
+        assert_eq!(manifold.points.len(), 1);
+        assert!((manifold.points[0].depth - 0.5f64).abs() < 1e-9);
+        assert!(close(
+            manifold.points[0].position,
+            Vector2::new(0.75f64, 0f64)
+        ));
+
+        assert_eq!(collide(&a, &core(&circle, 2.5f64, 0f64, 0f64)), None);
+        let same = collide(&a, &a).unwrap();
+        assert!((same.points[0].depth - 2f64).abs() < 1e-9);
+    }
+
+    #[test]
+    fn box_on_box() {
+        let ground = core(&Shape::aabb(5f64, 1f64), 0f64, 0f64, 0f64);
+        let crate_box = core(&Shape::rectangle(1f64, 1f64), 0.5f64, 1.9f64, 0f64);
+        let manifold = collide(&ground, &crate_box).unwrap();
+
+        assert!(close(manifold.normal, Vector2::new(0f64, 1f64)));
+        assert_eq!(manifold.points.len(), 2);
+        for point in manifold.points.iter() {
+            assert!((point.depth - 0.1f64).abs() < 1e-9);
+            assert!((point.position.y - 0.95f64).abs() < 1e-9);
+        }
+
+        // The normal still points from the first shape to the second when the second is the reference
+        let manifold = collide(&crate_box, &ground).unwrap();
+        assert!(close(manifold.normal, Vector2::new(0f64, -1f64)));
+
+        let apart = core(&Shape::rectangle(1f64, 1f64), 0f64, 2.1f64, 0f64);
+        assert_eq!(collide(&ground, &apart), None);
+    }
+
+    #[test]
+    fn capsule_on_box() {
+        let ground = core(&Shape::aabb(5f64, 1f64), 0f64, 0f64, 0f64);
+        let lying = core(
+            &Shape::capsule(1f64, 0.5f64),
+            0f64,
+            1.45f64,
+            std::f64::consts::FRAC_PI_2,
+        );
+        let manifold = collide(&ground, &lying).unwrap();
+        assert!(close(manifold.normal, Vector2::new(0f64, 1f64)));
+        assert_eq!(manifold.points.len(), 2);
+        assert!((manifold.points[0].depth - 0.05f64).abs() < 1e-9);
+
+        // A circle sunk into a box is pushed out of the nearest face
+        let sunk = core(&Shape::circle(0.5f64), 4.8f64, 0f64, 0f64);
+        let manifold = collide(&ground, &sunk).unwrap();
+        assert!(close(manifold.normal, Vector2::new(1f64, 0f64)));
+        assert!((manifold.points[0].depth - 0.7f64).abs() < 1e-9);
+    }
+}
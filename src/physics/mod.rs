@@ -0,0 +1,6 @@
+pub mod body;
+pub mod broadphase;
+pub mod collision;
+pub mod shape;
+pub mod solver;
+pub mod world;
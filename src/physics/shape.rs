@@ -0,0 +1,370 @@
+#![allow(dead_code)]
+
+use crate::math::{aabb::Aabb, vector2::Vector2};
+
+/// Collision shape, centered on the position of its body.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    Circle {
+        radius: f64,
+    },
+    /// Box that stays aligned with the axes, its body never rotates
+    Aabb {
+        half_extents: Vector2,
+    },
+    /// Box that rotates with its body
+    Box {
+        half_extents: Vector2,
+    },
+    /// Segment along the local y axis swept by a circle, a common shape for characters
+    Capsule {
+        half_length: f64,
+        radius: f64,
+    },
+    /// Convex polygon in counter-clockwise order with its centroid at the origin
+    Polygon {
+        vertices: Vec<Vector2>,
+    },
+}
+
+/// Shape in world space split into a convex core and a radius around it.
+/// Circles and capsules are a point and a segment grown by their radius.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Core {
+    pub points: Vec<Vector2>,
+    pub radius: f64,
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl Shape {
+    pub fn circle(radius: f64) -> Shape {
+        Shape::Circle { radius }
+    }
+
+    pub fn aabb(half_width: f64, half_height: f64) -> Shape {
+        Shape::Aabb {
+            half_extents: Vector2::new(half_width, half_height),
+        }
+    }
+
+    pub fn rectangle(half_width: f64, half_height: f64) -> Shape {
+        Shape::Box {
+            half_extents: Vector2::new(half_width, half_height),
+        }
+    }
+
+    pub fn capsule(half_length: f64, radius: f64) -> Shape {
+        Shape::Capsule {
+            half_length,
+            radius,
+        }
+    }
+
+    /// Builds the convex hull of the points, moved so its centroid is at the origin.
+    /// Returns `None` when the points do not enclose an area.
+    pub fn polygon(points: &[Vector2]) -> Option<Shape> {
+        let hull = convex_hull(points);
+        if hull.len() < 3 {
+            return None;
+        }
+
+        let (area, centroid, _) = polygon_mass(&hull);
+        if area <= f64::EPSILON {
+            return None;
+        }
+        Some(Shape::Polygon {
+            vertices: hull.into_iter().map(|point| point - centroid).collect(),
+        })
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl Shape {
+    /// Returns the mass and the rotational inertia around the center for a density
+    pub fn mass(&self, density: f64) -> (f64, f64) {
+        match self {
+            Shape::Circle { radius } => {
+                let mass = density * std::f64::consts::PI * radius * radius;
+                (mass, mass * radius * radius / 2f64)
+            }
+            Shape::Aabb { half_extents } | Shape::Box { half_extents } => {
+                let (width, height) = (half_extents.x * 2f64, half_extents.y * 2f64);
+                let mass = density * width * height;
+                (mass, mass * (width * width + height * height) / 12f64)
+            }
+            Shape::Capsule {
+                half_length,
+                radius,
+            } => {
+                let (length, radius) = (half_length * 2f64, *radius);
+                let rectangle = density * length * radius * 2f64;
+                let caps = density * std::f64::consts::PI * radius * radius;
+
+                // Each half disc sits at 4r/3pi from its flat side, past the end of the segment
+                let offset = 4f64 * radius / (3f64 * std::f64::consts::PI);
+                let inertia = rectangle * (length * length + 4f64 * radius * radius) / 12f64
+                    + caps
+                        * (radius * radius / 2f64
+                            + half_length * half_length
+                            + 2f64 * half_length * offset);
+                (rectangle + caps, inertia)
+            }
+            Shape::Polygon { vertices } => {
+                let (area, _, inertia) = polygon_mass(vertices);
+                (density * area, density * inertia)
+            }
+        }
+    }
+
+    /// Returns true for shapes that ignore the rotation of their body
+    pub fn is_axis_aligned(&self) -> bool {
+        matches!(self, Shape::Aabb { .. })
+    }
+
+    /// Places the shape in the world
+    pub(crate) fn core(&self, position: Vector2, rotation: f64) -> Core {
+        let (sin, cos) = rotation.sin_cos();
+        let place = |point: Vector2| position + rotate(point, sin, cos);
+
+        match self {
+            Shape::Circle { radius } => Core {
+                points: vec![position],
+                radius: *radius,
+            },
+            Shape::Aabb { half_extents } => Core {
+                points: box_vertices(*half_extents)
+                    .iter()
+                    .map(|&point| position + point)
+                    .collect(),
+                radius: 0f64,
+            },
+            Shape::Box { half_extents } => Core {
+                points: box_vertices(*half_extents)
+                    .iter()
+                    .map(|&point| place(point))
+                    .collect(),
+                radius: 0f64,
+            },
+            Shape::Capsule {
+                half_length,
+                radius,
+            } => Core {
+                points: vec![
+                    place(Vector2::new(0f64, -half_length)),
+                    place(Vector2::new(0f64, *half_length)),
+                ],
+                radius: *radius,
+            },
+            Shape::Polygon { vertices } => Core {
+                points: vertices.iter().map(|&point| place(point)).collect(),
+                radius: 0f64,
+            },
+        }
+    }
+
+    pub fn bounds(&self, position: Vector2, rotation: f64) -> Aabb {
+        self.core(position, rotation).bounds()
+    }
+}
+
+impl Core {
+    /// Returns the point of the core furthest along a direction
+    pub fn support(&self, direction: Vector2) -> Vector2 {
+        let mut best = self.points[0];
+        for &point in self.points.iter().skip(1) {
+            if point.dot(direction) > best.dot(direction) {
+                best = point;
+            }
+        }
+        best
+    }
+
+    pub fn is_polygon(&self) -> bool {
+        self.points.len() >= 3
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        let mut min = self.points[0];
+        let mut max = self.points[0];
+        for point in self.points.iter() {
+            min = Vector2::new(min.x.min(point.x), min.y.min(point.y));
+            max = Vector2::new(max.x.max(point.x), max.y.max(point.y));
+        }
+
+        let grow = Vector2::new(self.radius, self.radius);
+        Aabb::new(min - grow, max + grow)
+    }
+}
+
+/// Returns the 2D cross product, the z of the 3D one
+pub(crate) fn cross(a: Vector2, b: Vector2) -> f64 {
+    a.x * b.y - a.y * b.x
+}
+
+/// Returns the cross product of a scalar rotation speed with a vector
+pub(crate) fn cross_scalar(s: f64, v: Vector2) -> Vector2 {
+    Vector2::new(-s * v.y, s * v.x)
+}
+
+/// Returns the vector turned a quarter clockwise, the outward normal of a counter-clockwise edge
+pub(crate) fn right_normal(v: Vector2) -> Vector2 {
+    Vector2::new(v.y, -v.x)
+}
+
+pub(crate) fn rotate(v: Vector2, sin: f64, cos: f64) -> Vector2 {
+    Vector2::new(v.x * cos - v.y * sin, v.x * sin + v.y * cos)
+}
+
+fn box_vertices(half: Vector2) -> [Vector2; 4] {
+    [
+        Vector2::new(-half.x, -half.y),
+        Vector2::new(half.x, -half.y),
+        Vector2::new(half.x, half.y),
+        Vector2::new(-half.x, half.y),
+    ]
+}
+
+/// Returns the counter-clockwise convex hull, without collinear points
+fn convex_hull(points: &[Vector2]) -> Vec<Vector2> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    sorted.dedup();
+    if sorted.len() < 3 {
+        return sorted;
+    }
+
+    // Monotone chain: lower hull then upper hull
+    let mut hull: Vec<Vector2> = Vec::new();
+    for pass in 0..2 {
+        let start = hull.len();
+        let ordered: Box<dyn Iterator<Item = &Vector2>> = if pass == 0 {
+            Box::new(sorted.iter())
+        } else {
+            Box::new(sorted.iter().rev())
+        };
+        for &point in ordered {
+            while hull.len() >= start + 2 {
+                let (a, b) = (hull[hull.len() - 2], hull[hull.len() - 1]);
+                if cross(b - a, point - a) > 0f64 {
+                    break;
+                }
+                hull.pop();
+            }
+            hull.push(point);
+        }
+        hull.pop();
+    }
+    hull
+}
+
+/// Returns the area, centroid and inertia around the centroid for a unit density
+fn polygon_mass(vertices: &[Vector2]) -> (f64, Vector2, f64) {
+    let mut area = 0f64;
+    let mut centroid = Vector2::new_empty();
+    let mut inertia = 0f64;
+
+    // Triangles fanned from the first vertex
+    let origin = vertices[0];
+    for i in 1..vertices.len().saturating_sub(1) {
+        let (a, b) = (vertices[i] - origin, vertices[i + 1] - origin);
+        let triangle = cross(a, b) / 2f64;
+        area += triangle;
+        centroid += (a + b) * (triangle / 3f64);
+        inertia += triangle * (a.dot(a) + a.dot(b) + b.dot(b)) / 6f64;
+    }
+
+    if area <= f64::EPSILON {
+        return (0f64, origin, 0f64);
+    }
+    let offset = centroid / area;
+    (area, origin + offset, inertia - area * offset.dot(offset))
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod shape_tests {
+
+    use super::*;
+    use crate::math::geometry::Geometry;
+
+    #[test]
+    fn polygon_hull() {
+        let points = [
+            Vector2::new(0f64, 0f64),
+            Vector2::new(4f64, 0f64),
+            Vector2::new(2f64, 1f64),
+            Vector2::new(4f64, 2f64),
+            Vector2::new(0f64, 2f64),
+            Vector2::new(2f64, 0f64),
+        ];
+        let vertices = match Shape::polygon(&points).unwrap() {
+            Shape::Polygon { vertices } => vertices,
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            vertices,
+            [
+                Vector2::new(-2f64, -1f64),
+                Vector2::new(2f64, -1f64),
+                Vector2::new(2f64, 1f64),
+                Vector2::new(-2f64, 1f64)
+            ]
+        );
+
+        let line = [
+            Vector2::new(0f64, 0f64),
+            Vector2::new(1f64, 1f64),
+            Vector2::new(2f64, 2f64),
+        ];
+        assert_eq!(Shape::polygon(&line), None);
+    }
+
+    #[test]
+    fn mass_matches_between_shapes() {
+        let square = Shape::polygon(&[
+            Vector2::new(0f64, 0f64),
+            Vector2::new(2f64, 0f64),
+            Vector2::new(2f64, 2f64),
+            Vector2::new(0f64, 2f64),
+        ])
+        .unwrap();
+        let (mass, inertia) = square.mass(3f64);
+        let (box_mass, box_inertia) = Shape::rectangle(1f64, 1f64).mass(3f64);
+        assert!((mass - box_mass).abs() < 1e-12);
+        assert!((inertia - box_inertia).abs() < 1e-12);
+
+        // A capsule without length is a circle
+        let (mass, inertia) = Shape::capsule(0f64, 2f64).mass(1f64);
+        let (circle_mass, circle_inertia) = Shape::circle(2f64).mass(1f64);
+        assert!((mass - circle_mass).abs() < 1e-12);
+        assert!((inertia - circle_inertia).abs() < 1e-12);
+    }
+
+    #[test]
+    fn bounds() {
+        let bounds = Shape::capsule(1f64, 0.5f64)
+            .bounds(Vector2::new(10f64, 0f64), std::f64::consts::FRAC_PI_2);
+        assert!((bounds.min - Vector2::new(8.5f64, -0.5f64)).magnitude() < 1e-12);
+        assert!((bounds.max - Vector2::new(11.5f64, 0.5f64)).magnitude() < 1e-12);
+
+        let aligned = Shape::aabb(1f64, 2f64).bounds(Vector2::new_empty(), 1f64);
+        assert_eq!(
+            aligned,
+            Aabb::new(Vector2::new(-1f64, -2f64), Vector2::new(1f64, 2f64))
+        );
+        assert!(aligned.overlaps(&Aabb::new(
+            Vector2::new(1f64, 2f64),
+            Vector2::new(3f64, 3f64)
+        )));
+        assert!(aligned.contains(Vector2::new(0.5f64, -2f64)));
+    }
+}
@@ -0,0 +1,224 @@
+#![allow(dead_code)]
+
+use crate::math::vector2::Vector2;
+
+use super::{
+    body::{Body, BodyHandle},
+    collision::{Manifold, LINEAR_SLOP},
+    shape::{cross, cross_scalar},
+};
+
+/// Approach speeds below this do not bounce, so resting bodies settle
+pub const RESTITUTION_THRESHOLD: f64 = 1f64;
+
+/// Fraction of the overlap removed per position iteration
+const BAUMGARTE: f64 = 0.2;
+
+/// Largest correction per position iteration, avoids overshooting deep overlaps
+const MAX_CORRECTION: f64 = 0.2;
+
+/// Contact points closer than this between two steps are taken as the same point
+const WARM_START_DISTANCE: f64 = 0.1;
+
+/// Touching pair of bodies with what the solver needs to push them apart.
+#[derive(Debug, Clone)]
+pub struct Contact {
+    pub a: BodyHandle,
+    pub b: BodyHandle,
+    pub manifold: Manifold,
+    pub friction: f64,
+    pub restitution: f64,
+    points: Vec<PointState>,
+    /// Positions of the bodies when the contact was prepared
+    origin_a: Vector2,
+    origin_b: Vector2,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct PointState {
+    /// Offsets of the contact from each center of mass
+    r_a: Vector2,
+    r_b: Vector2,
+    normal_mass: f64,
+    tangent_mass: f64,
+    /// Speed the bodies must separate at after the impact
+    bounce: f64,
+    normal_impulse: f64,
+    tangent_impulse: f64,
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl Contact {
+    pub fn new(
+        a: BodyHandle,
+        b: BodyHandle,
+        manifold: Manifold,
+        body_a: &Body,
+        body_b: &Body,
+    ) -> Contact {
+        let (material_a, material_b) = (body_a.material, body_b.material);
+        Contact {
+            a,
+            b,
+            manifold,
+            friction: (material_a.friction * material_b.friction).sqrt(),
+            restitution: material_a.restitution.max(material_b.restitution),
+            points: Vec::new(),
+            origin_a: body_a.position,
+            origin_b: body_b.position,
+        }
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl Contact {
+    /// Returns the total impulse along the normal applied in the last step
+    pub fn normal_impulse(&self) -> f64 {
+        self.points.iter().map(|point| point.normal_impulse).sum()
+    }
+
+    /// Starts from the impulses of the same contact in the last step, matching points by position.
+    /// Resting contacts then need few iterations to hold bodies up, which keeps stacks steady.
+    pub(crate) fn warm_start(&mut self, previous: &Contact) {
+        self.points = self
+            .manifold
+            .points
+            .iter()
+            .map(|point| {
+                let mut state = PointState::default();
+                let nearest = previous
+                    .manifold
+                    .points
+                    .iter()
+                    .zip(previous.points.iter())
+                    .map(|(old, old_state)| {
+                        ((old.position - point.position).magnitude(), old_state)
+                    })
+                    .min_by(|a, b| a.0.total_cmp(&b.0));
+                if let Some((distance, old_state)) = nearest {
+                    if distance < WARM_START_DISTANCE {
+                        state.normal_impulse = old_state.normal_impulse;
+                        state.tangent_impulse = old_state.tangent_impulse;
+                    }
+                }
+                state
+            })
+            .collect();
+    }
+
+    /// Computes the effective masses and bounce speeds, then applies the impulses kept by `warm_start`
+    pub(crate) fn prepare(&mut self, a: &mut Body, b: &mut Body) {
+        let normal = self.manifold.normal;
+        let tangent = cross_scalar(-1f64, normal);
+        self.origin_a = a.position;
+        self.origin_b = b.position;
+        self.points
+            .resize(self.manifold.points.len(), PointState::default());
+
+        for (point, state) in self.manifold.points.iter().zip(self.points.iter_mut()) {
+            let r_a = point.position - a.position;
+            let r_b = point.position - b.position;
+            let mass = |axis: Vector2| {
+                let (cross_a, cross_b) = (cross(r_a, axis), cross(r_b, axis));
+                let k = a.inverse_mass
+                    + b.inverse_mass
+                    + a.inverse_inertia * cross_a * cross_a
+                    + b.inverse_inertia * cross_b * cross_b;
+                if k > 0f64 {
+                    1f64 / k
+                } else {
+                    0f64
+                }
+            };
+
+            let approach =
+                (b.velocity_at(point.position) - a.velocity_at(point.position)).dot(normal);
+            state.bounce = if approach < -RESTITUTION_THRESHOLD {
+                -self.restitution * approach
+            } else {
+                0f64
+            };
+            state.r_a = r_a;
+            state.r_b = r_b;
+            state.normal_mass = mass(normal);
+            state.tangent_mass = mass(tangent);
+
+            let impulse = normal * state.normal_impulse + tangent * state.tangent_impulse;
+            apply(a, b, impulse, r_a, r_b);
+        }
+    }
+
+    /// Applies impulses so the bodies stop approaching, with friction along the surface
+    pub(crate) fn solve_velocity(&mut self, a: &mut Body, b: &mut Body) {
+        let normal = self.manifold.normal;
+        let tangent = cross_scalar(-1f64, normal);
+
+        for point in self.points.iter_mut() {
+            let (r_a, r_b) = (point.r_a, point.r_b);
+            let relative = |a: &Body, b: &Body| {
+                b.velocity + cross_scalar(b.angular_velocity, r_b)
+                    - a.velocity
+                    - cross_scalar(a.angular_velocity, r_a)
+            };
+
+            // Friction is bounded by the normal impulse, the Coulomb cone
+            let speed = relative(a, b).dot(tangent);
+            let limit = self.friction * point.normal_impulse;
+            let total = (point.tangent_impulse - speed * point.tangent_mass).clamp(-limit, limit);
+            let impulse = total - point.tangent_impulse;
+            point.tangent_impulse = total;
+            apply(a, b, tangent * impulse, r_a, r_b);
+
+            // The accumulated normal impulse may only push
+            let speed = relative(a, b).dot(normal);
+            let total =
+                (point.normal_impulse - (speed - point.bounce) * point.normal_mass).max(0f64);
+            let impulse = total - point.normal_impulse;
+            point.normal_impulse = total;
+            apply(a, b, normal * impulse, r_a, r_b);
+        }
+    }
+
+    /// Moves the bodies apart to remove the overlap left after integration, returns the largest one
+    pub(crate) fn solve_position(&self, a: &mut Body, b: &mut Body) -> f64 {
+        let normal = self.manifold.normal;
+        let mut deepest = 0f64;
+
+        for (index, point) in self.manifold.points.iter().enumerate() {
+            let state = match self.points.get(index) {
+                Some(state) => *state,
+                None => continue,
+            };
+
+            // The bodies moved since the contact was found, rotation is ignored
+            let moved = (b.position - self.origin_b) - (a.position - self.origin_a);
+            let depth = point.depth - moved.dot(normal);
+            deepest = deepest.max(depth);
+
+            let correction = (BAUMGARTE * (depth - LINEAR_SLOP)).clamp(0f64, MAX_CORRECTION);
+            if correction <= 0f64 || state.normal_mass <= 0f64 {
+                continue;
+            }
+
+            let push = normal * (correction * state.normal_mass);
+            a.position -= push * a.inverse_mass;
+            a.rotation -= a.inverse_inertia * cross(state.r_a, push);
+            b.position += push * b.inverse_mass;
+            b.rotation += b.inverse_inertia * cross(state.r_b, push);
+        }
+        deepest
+    }
+}
+
+fn apply(a: &mut Body, b: &mut Body, impulse: Vector2, r_a: Vector2, r_b: Vector2) {
+    a.velocity -= impulse * a.inverse_mass;
+    a.angular_velocity -= a.inverse_inertia * cross(r_a, impulse);
+    b.velocity += impulse * b.inverse_mass;
+    b.angular_velocity += b.inverse_inertia * cross(r_b, impulse);
+}
@@ -0,0 +1,654 @@
+#![allow(dead_code)]
+
+use crate::math::vector2::Vector2;
+
+use super::{
+    body::{Body, BodyHandle, BodyType},
+    broadphase::Broadphase,
+    collision::{self, LINEAR_SLOP},
+    solver::Contact,
+};
+
+/// Bodies slower than this, in units per second, count as still
+const SLEEP_LINEAR_SPEED: f64 = 0.05;
+
+/// Bodies turning slower than this, in radians per second, count as still
+const SLEEP_ANGULAR_SPEED: f64 = 0.05;
+
+#[derive(Debug)]
+struct Slot {
+    generation: u32,
+    body: Option<Body>,
+}
+
+/// Simulates rigid bodies in fixed steps, so the same inputs always give
+/// the same results whatever the frame rate.
+#[derive(Debug)]
+pub struct World {
+    pub gravity: Vector2,
+    /// Duration of a step in seconds
+    pub timestep: f64,
+    pub velocity_iterations: usize,
+    pub position_iterations: usize,
+    /// Most steps `update` runs per call, so a slow frame cannot snowball
+    pub max_steps: usize,
+    pub allow_sleep: bool,
+    /// Time in seconds a group of touching bodies must stay still to fall asleep
+    pub time_to_sleep: f64,
+    slots: Vec<Slot>,
+    free: Vec<u32>,
+    broadphase: Broadphase,
+    contacts: Vec<Contact>,
+    /// Time left over by `update` for the next steps
+    accumulator: f64,
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl World {
+    pub fn new(gravity: Vector2) -> World {
+        World {
+            gravity,
+            timestep: 1f64 / 60f64,
+            velocity_iterations: 8,
+            position_iterations: 3,
+            max_steps: 8,
+            allow_sleep: true,
+            time_to_sleep: 0.5f64,
+            slots: Vec::new(),
+            free: Vec::new(),
+            broadphase: Broadphase::new(),
+            contacts: Vec::new(),
+            accumulator: 0f64,
+        }
+    }
+}
+
+impl Default for World {
+    fn default() -> World {
+        World::new(Vector2::new(0f64, -9.81f64))
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl World {
+    pub fn add_body(&mut self, body: Body) -> BodyHandle {
+        match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index as usize];
+                slot.body = Some(body);
+                BodyHandle {
+                    index,
+                    generation: slot.generation,
+                }
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    body: Some(body),
+                });
+                BodyHandle {
+                    index: self.slots.len() as u32 - 1,
+                    generation: 0,
+                }
+            }
+        }
+    }
+
+    /// Removes a body and wakes the bodies that were touching it
+    pub fn remove_body(&mut self, handle: BodyHandle) -> Option<Body> {
+        self.body(handle)?;
+        let slot = &mut self.slots[handle.index as usize];
+        let body = slot.body.take();
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(handle.index);
+
+        let touching: Vec<BodyHandle> = self
+            .contacts
+            .iter()
+            .filter(|contact| contact.a == handle || contact.b == handle)
+            .map(|contact| {
+                if contact.a == handle {
+                    contact.b
+                } else {
+                    contact.a
+                }
+            })
+            .collect();
+        self.contacts
+            .retain(|contact| contact.a != handle && contact.b != handle);
+        for other in touching {
+            if let Some(other) = self.body_mut(other) {
+                other.wake();
+            }
+        }
+        body
+    }
+
+    pub fn contains(&self, handle: BodyHandle) -> bool {
+        self.body(handle).is_some()
+    }
+
+    pub fn body(&self, handle: BodyHandle) -> Option<&Body> {
+        let slot = self.slots.get(handle.index as usize)?;
+        if slot.generation == handle.generation {
+            slot.body.as_ref()
+        } else {
+            None
+        }
+    }
+
+    /// Returns the body to change it, which wakes it up
+    pub fn body_mut(&mut self, handle: BodyHandle) -> Option<&mut Body> {
+        let slot = self.slots.get_mut(handle.index as usize)?;
+        if slot.generation != handle.generation {
+            return None;
+        }
+        let body = slot.body.as_mut()?;
+        body.wake();
+        Some(body)
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len() - self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn bodies(&self) -> impl Iterator<Item = (BodyHandle, &Body)> {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            let handle = BodyHandle {
+                index: index as u32,
+                generation: slot.generation,
+            };
+            slot.body.as_ref().map(|body| (handle, body))
+        })
+    }
+
+    /// Returns the contacts found in the last step
+    pub fn contacts(&self) -> &[Contact] {
+        &self.contacts
+    }
+
+    /// Returns true when the two bodies touched in the last step
+    pub fn in_contact(&self, a: BodyHandle, b: BodyHandle) -> bool {
+        self.contacts
+            .iter()
+            .any(|contact| (contact.a == a && contact.b == b) || (contact.a == b && contact.b == a))
+    }
+
+    /// Returns how far the simulation is between the last step and the next one, to interpolate drawing
+    pub fn alpha(&self) -> f64 {
+        self.accumulator / self.timestep
+    }
+
+    /// Runs as many fixed steps as fit in the elapsed time and returns how many ran
+    pub fn update(&mut self, elapsed: f64) -> usize {
+        self.accumulator += elapsed;
+        let mut steps = 0;
+        while self.accumulator >= self.timestep {
+            if steps == self.max_steps {
+                self.accumulator = 0f64;
+                break;
+            }
+            self.step();
+            self.accumulator -= self.timestep;
+            steps += 1;
+        }
+        steps
+    }
+
+    /// Advances the simulation by one timestep
+    pub fn step(&mut self) {
+        let dt = self.timestep;
+        let gravity = self.gravity;
+
+        for body in self.awake_bodies() {
+            if body.body_type == BodyType::Dynamic {
+                let acceleration = gravity * body.gravity_scale + body.force * body.inverse_mass;
+                body.velocity += acceleration * dt;
+                body.angular_velocity += body.torque * body.inverse_inertia * dt;
+                body.velocity *= 1f64 / (1f64 + dt * body.linear_damping);
+                body.angular_velocity *= 1f64 / (1f64 + dt * body.angular_damping);
+            }
+        }
+
+        let previous = std::mem::take(&mut self.contacts);
+        self.find_contacts();
+
+        // Both lists are sorted by body pair, so one pass matches them up
+        let mut contacts = std::mem::take(&mut self.contacts);
+        let mut old = previous.iter().peekable();
+        for contact in contacts.iter_mut() {
+            while old
+                .peek()
+                .is_some_and(|old| (old.a, old.b) < (contact.a, contact.b))
+            {
+                old.next();
+            }
+            if let Some(old) = old
+                .peek()
+                .filter(|old| (old.a, old.b) == (contact.a, contact.b))
+            {
+                contact.warm_start(old);
+            }
+        }
+        for contact in contacts.iter_mut() {
+            let (a, b) = self.pair_mut(contact.a, contact.b);
+            contact.prepare(a, b);
+        }
+        for _ in 0..self.velocity_iterations {
+            for contact in contacts.iter_mut() {
+                let (a, b) = self.pair_mut(contact.a, contact.b);
+                contact.solve_velocity(a, b);
+            }
+        }
+
+        for body in self.awake_bodies() {
+            if body.body_type != BodyType::Static {
+                body.position += body.velocity * dt;
+                body.rotation += body.angular_velocity * dt;
+            }
+        }
+
+        for _ in 0..self.position_iterations {
+            let mut deepest = 0f64;
+            for contact in contacts.iter() {
+                let (a, b) = self.pair_mut(contact.a, contact.b);
+                deepest = deepest.max(contact.solve_position(a, b));
+            }
+            if deepest < LINEAR_SLOP * 3f64 {
+                break;
+            }
+        }
+        self.contacts = contacts;
+
+        for body in self.slots.iter_mut().filter_map(|slot| slot.body.as_mut()) {
+            body.force = Vector2::new_empty();
+            body.torque = 0f64;
+        }
+
+        if self.allow_sleep {
+            self.update_sleep();
+        }
+    }
+
+    fn awake_bodies(&mut self) -> impl Iterator<Item = &mut Body> {
+        self.slots
+            .iter_mut()
+            .filter_map(|slot| slot.body.as_mut())
+            .filter(|body| !body.sleeping)
+    }
+
+    fn handle(&self, index: usize) -> BodyHandle {
+        BodyHandle {
+            index: index as u32,
+            generation: self.slots[index].generation,
+        }
+    }
+
+    fn pair_mut(&mut self, a: BodyHandle, b: BodyHandle) -> (&mut Body, &mut Body) {
+        let (a, b) = (a.index as usize, b.index as usize);
+        let (low, high) = self.slots.split_at_mut(b);
+        (
+            low[a].body.as_mut().unwrap(),
+            high[0].body.as_mut().unwrap(),
+        )
+    }
+
+    /// Finds the touching pairs with at least one dynamic body, waking sleeping bodies that are hit
+    fn find_contacts(&mut self) {
+        let boxes: Vec<_> = self
+            .slots
+            .iter()
+            .map(|slot| slot.body.as_ref().map(|body| body.bounds()))
+            .collect();
+        let pairs = self.broadphase.pairs(&boxes);
+
+        self.contacts.clear();
+        for (i, j) in pairs {
+            let (a, b) = match (&self.slots[i].body, &self.slots[j].body) {
+                (Some(a), Some(b)) => (a, b),
+                _ => continue,
+            };
+            if !a.is_dynamic() && !b.is_dynamic() {
+                continue;
+            }
+            if is_resting(a) && is_resting(b) {
+                continue;
+            }
+            if let Some(manifold) = collision::collide(&a.core(), &b.core()) {
+                let contact = Contact::new(self.handle(i), self.handle(j), manifold, a, b);
+                self.contacts.push(contact);
+            }
+        }
+
+        // A moving body wakes a sleeping one it hits, contacts with still bodies are dropped
+        for index in 0..self.contacts.len() {
+            let (a, b) = (self.contacts[index].a, self.contacts[index].b);
+            let (a, b) = self.pair_mut(a, b);
+            if a.sleeping && is_moving(b) {
+                a.wake();
+            } else if b.sleeping && is_moving(a) {
+                b.wake();
+            }
+        }
+        let slots = &self.slots;
+        let asleep =
+            |handle: BodyHandle| slots[handle.index as usize].body.as_ref().unwrap().sleeping;
+        self.contacts
+            .retain(|contact| !asleep(contact.a) && !asleep(contact.b));
+    }
+
+    /// Puts groups of touching dynamic bodies to sleep once they all stayed still long enough
+    fn update_sleep(&mut self) {
+        let dt = self.timestep;
+        for body in self.awake_bodies() {
+            if body.body_type != BodyType::Dynamic {
+                continue;
+            }
+            if body.velocity.magnitude() < SLEEP_LINEAR_SPEED
+                && body.angular_velocity.abs() < SLEEP_ANGULAR_SPEED
+            {
+                body.sleep_time += dt;
+            } else {
+                body.sleep_time = 0f64;
+            }
+        }
+
+        // Islands of dynamic bodies linked by contacts, static bodies do not link them
+        let mut islands: Vec<usize> = (0..self.slots.len()).collect();
+        for contact in self.contacts.iter() {
+            let (a, b) = (contact.a.index as usize, contact.b.index as usize);
+            let dynamic = |index: usize| self.slots[index].body.as_ref().unwrap().is_dynamic();
+            if dynamic(a) && dynamic(b) {
+                let (root_a, root_b) = (find(&mut islands, a), find(&mut islands, b));
+                islands[root_a.max(root_b)] = root_a.min(root_b);
+            }
+        }
+
+        let mut least = vec![f64::MAX; self.slots.len()];
+        for index in 0..self.slots.len() {
+            if let Some(body) = &self.slots[index].body {
+                if body.is_dynamic() && !body.sleeping {
+                    let root = find(&mut islands, index);
+                    least[root] = least[root].min(body.sleep_time);
+                }
+            }
+        }
+
+        let time_to_sleep = self.time_to_sleep;
+        for index in 0..self.slots.len() {
+            let root = find(&mut islands, index);
+            if let Some(body) = &mut self.slots[index].body {
+                if body.is_dynamic() && !body.sleeping && least[root] >= time_to_sleep {
+                    body.sleeping = true;
+                    body.velocity = Vector2::new_empty();
+                    body.angular_velocity = 0f64;
+                }
+            }
+        }
+    }
+}
+
+/// Returns true for bodies that stay in place, static or asleep
+fn is_resting(body: &Body) -> bool {
+    body.body_type == BodyType::Static || body.sleeping
+}
+
+fn is_moving(body: &Body) -> bool {
+    match body.body_type {
+        BodyType::Static => false,
+        BodyType::Kinematic => {
+            body.velocity != Vector2::new_empty() || body.angular_velocity != 0f64
+        }
+        BodyType::Dynamic => !body.sleeping,
+    }
+}
+
+/// Returns the root of an island, flattening the path to it
+fn find(islands: &mut [usize], index: usize) -> usize {
+    let mut root = index;
+    while islands[root] != root {
+        root = islands[root];
+    }
+    let mut next = index;
+    while islands[next] != root {
+        let parent = islands[next];
+        islands[next] = root;
+        next = parent;
+    }
+    root
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod world_tests {
+
+    use super::*;
+    use crate::physics::{body::Material, shape::Shape};
+
+    fn ground(world: &mut World) -> BodyHandle {
+        world.add_body(Body::new_static(
+            Shape::rectangle(50f64, 0.5f64),
+            Vector2::new(0f64, -0.5f64),
+        ))
+    }
+
+    fn run(world: &mut World, seconds: f64) {
+        for _ in 0..(seconds / world.timestep).round() as usize {
+            world.step();
+        }
+    }
+
+    #[test]
+    fn box_comes_to_rest() {
+        let mut world = World::default();
+        let floor = ground(&mut world);
+        let crate_box = world.add_body(Body::new_dynamic(
+            Shape::rectangle(0.5f64, 0.5f64),
+            Vector2::new(0f64, 4f64),
+        ));
+        let ball = world.add_body(Body::new_dynamic(
+            Shape::circle(0.5f64),
+            Vector2::new(3f64, 2f64),
+        ));
+        let pill = world.add_body(Body::new_dynamic(
+            Shape::capsule(0.5f64, 0.25f64),
+            Vector2::new(-3f64, 2f64),
+        ));
+
+        run(&mut world, 4f64);
+        let resting = world.body(crate_box).unwrap();
+        assert!(
+            (resting.position.y - 0.5f64).abs() < 0.02f64,
+            "{}",
+            resting.position
+        );
+        assert!(resting.rotation.abs() < 1e-3);
+        assert!((world.body(ball).unwrap().position.y - 0.5f64).abs() < 0.02f64);
+        assert!((world.body(pill).unwrap().position.y - 0.75f64).abs() < 0.02f64);
+        assert!(world.body(crate_box).unwrap().is_sleeping());
+        assert!(!world.in_contact(floor, crate_box));
+
+        // Sleeping bodies keep no contacts until woken
+        world.body_mut(crate_box).unwrap();
+        world.step();
+        assert!(world.in_contact(floor, crate_box));
+        assert_eq!(
+            world.body(floor).unwrap().position,
+            Vector2::new(0f64, -0.5f64)
+        );
+    }
+
+    #[test]
+    fn stack_falls_asleep_and_wakes() {
+        let mut world = World::default();
+        ground(&mut world);
+        let boxes: Vec<BodyHandle> = (0..3)
+            .map(|level| {
+                let position = Vector2::new(0f64, 0.5f64 + level as f64 * 1.01f64);
+                world.add_body(Body::new_dynamic(
+                    Shape::rectangle(0.5f64, 0.5f64),
+                    position,
+                ))
+            })
+            .collect();
+
+        run(&mut world, 4f64);
+        assert!(boxes
+            .iter()
+            .all(|&handle| world.body(handle).unwrap().is_sleeping()));
+        let top = world.body(boxes[2]).unwrap().position;
+        assert!((top.y - 2.5f64).abs() < 0.05f64, "{}", top);
+
+        // A ball dropped on the stack wakes it through the contacts
+        world.add_body(Body::new_dynamic(
+            Shape::circle(0.3f64),
+            Vector2::new(0.2f64, 4f64),
+        ));
+        run(&mut world, 1f64);
+        assert!(
+            !world.body(boxes[2]).unwrap().is_sleeping()
+                || !world.body(boxes[1]).unwrap().is_sleeping()
+        );
+    }
+
+    #[test]
+    fn restitution_bounces() {
+        let mut world = World::default();
+        ground(&mut world);
+        let bouncy = Material::new(1f64, 0.4f64, 0.8f64);
+        let ball = world.add_body(
+            Body::new_dynamic(Shape::circle(0.5f64), Vector2::new(0f64, 5.5f64))
+                .with_material(bouncy),
+        );
+        let clay = world.add_body(Body::new_dynamic(
+            Shape::circle(0.5f64),
+            Vector2::new(5f64, 5.5f64),
+        ));
+
+        let mut highest = 0f64;
+        let mut landed = false;
+        for _ in 0..180 {
+            world.step();
+            let height = world.body(ball).unwrap().position.y;
+            if world.body(ball).unwrap().velocity.y > 0f64 {
+                landed = true;
+            }
+            if landed {
+                highest = highest.max(height);
+            }
+        }
+        // Falling 5 units and bouncing back at 0.8 times the speed climbs about 0.64 * 5
+        assert!(highest > 3f64 && highest < 4.6f64, "{}", highest);
+        assert!(world.body(clay).unwrap().position.y < 0.6f64);
+    }
+
+    #[test]
+    fn friction_stops_sliding() {
+        let mut world = World::default();
+        ground(&mut world);
+        let rough = world.add_body(
+            Body::new_dynamic(Shape::rectangle(0.5f64, 0.5f64), Vector2::new(0f64, 0.5f64))
+                .with_velocity(Vector2::new(5f64, 0f64)),
+        );
+        let icy = world.add_body(
+            Body::new_dynamic(Shape::aabb(0.5f64, 0.5f64), Vector2::new(-20f64, 0.5f64))
+                .with_velocity(Vector2::new(5f64, 0f64))
+                .with_material(Material::new(1f64, 0f64, 0f64)),
+        );
+
+        run(&mut world, 3f64);
+        // With a friction of 0.4 the box slows at 0.4g and travels v^2 / 2a
+        let distance = world.body(rough).unwrap().position.x;
+        assert!(
+            (distance - 25f64 / (2f64 * 0.4f64 * 9.81f64)).abs() < 0.5f64,
+            "{}",
+            distance
+        );
+        assert!(world.body(rough).unwrap().velocity.magnitude() < 1e-3);
+        assert!((world.body(icy).unwrap().velocity.x - 5f64).abs() < 1e-6);
+    }
+
+    #[test]
+    fn kinematic_platform_carries_bodies() {
+        let mut world = World::default();
+        let platform = world.add_body(
+            Body::new_kinematic(Shape::rectangle(2f64, 0.25f64), Vector2::new(0f64, 0f64))
+                .with_velocity(Vector2::new(0f64, 1f64)),
+        );
+        let rider = world.add_body(Body::new_dynamic(
+            Shape::rectangle(0.5f64, 0.5f64),
+            Vector2::new(0f64, 0.75f64),
+        ));
+        let wall = world.add_body(Body::new_static(
+            Shape::rectangle(0.5f64, 5f64),
+            Vector2::new(10f64, 0f64),
+        ));
+
+        run(&mut world, 2f64);
+        let platform = world.body(platform).unwrap();
+        assert!((platform.position.y - 2f64).abs() < 1e-9);
+        assert_eq!(platform.velocity, Vector2::new(0f64, 1f64));
+        assert!((world.body(rider).unwrap().position.y - 2.75f64).abs() < 0.05f64);
+        assert_eq!(
+            world.body(wall).unwrap().position,
+            Vector2::new(10f64, 0f64)
+        );
+    }
+
+    #[test]
+    fn steps_are_deterministic() {
+        let build = || {
+            let mut world = World::default();
+            ground(&mut world);
+            for index in 0..12 {
+                let position =
+                    Vector2::new((index % 4) as f64 * 0.9f64, 1f64 + index as f64 * 0.8f64);
+                let shape = match index % 3 {
+                    0 => Shape::circle(0.4f64),
+                    1 => Shape::rectangle(0.4f64, 0.3f64),
+                    _ => Shape::capsule(0.3f64, 0.2f64),
+                };
+                world.add_body(
+                    Body::new_dynamic(shape, position).with_rotation(index as f64 * 0.3f64),
+                );
+            }
+            world
+        };
+
+        let (mut first, mut second) = (build(), build());
+        assert_eq!(first.update(1.5f64), 8);
+        for _ in 0..120 {
+            first.update(1f64 / 60f64);
+        }
+        run(&mut second, 8f64 / 60f64 + 2f64);
+        for ((_, a), (_, b)) in first.bodies().zip(second.bodies()) {
+            assert_eq!(a.position.x.to_bits(), b.position.x.to_bits());
+            assert_eq!(a.position.y.to_bits(), b.position.y.to_bits());
+            assert_eq!(a.rotation.to_bits(), b.rotation.to_bits());
+        }
+    }
+
+    #[test]
+    fn handles_go_stale() {
+        let mut world = World::default();
+        let first = world.add_body(Body::new_dynamic(Shape::circle(1f64), Vector2::new_empty()));
+        assert!(world.remove_body(first).is_some());
+        let second = world.add_body(Body::new_dynamic(Shape::circle(1f64), Vector2::new_empty()));
+        assert!(world.body(first).is_none());
+        assert!(world.body(second).is_some());
+        assert_eq!(world.len(), 1);
+    }
+}
@@ -0,0 +1,555 @@
+#![allow(dead_code)]
+
+use crate::math::{matrix3::Matrix3, transform2d::Transform2D, vector2::Vector2};
+
+/// Handle to a node in a `SceneGraph`, stale once the node is removed.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct NodeId {
+    index: u32,
+    generation: u32,
+}
+
+/// Node with a transform relative to its parent and data such as a sprite.
+#[derive(Debug)]
+pub struct Node<T> {
+    pub name: String,
+    pub data: T,
+    /// Nodes are drawn after siblings with a lower z index, ties keep insertion order
+    pub z_index: i32,
+    /// Hidden nodes are skipped with their children when drawing
+    pub visible: bool,
+    local: Transform2D,
+    world: Matrix3,
+    /// When set, the world transforms of the node and all its children are outdated
+    dirty: bool,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+}
+
+#[derive(Debug)]
+struct Slot<T> {
+    generation: u32,
+    node: Option<Node<T>>,
+}
+
+/// Tree of nodes whose local transforms compose into world transforms,
+/// like a turret placed on a tank. World transforms are recomputed lazily:
+/// changing a local transform flags the node and its descendants.
+#[derive(Debug)]
+pub struct SceneGraph<T> {
+    slots: Vec<Slot<T>>,
+    free: Vec<u32>,
+    roots: Vec<NodeId>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SceneError {
+    /// The node was removed or belongs to another graph
+    MissingNode,
+    /// The new parent is the node itself or one of its descendants
+    Cycle,
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl<T> SceneGraph<T> {
+    pub fn new() -> SceneGraph<T> {
+        SceneGraph {
+            slots: Vec::new(),
+            free: Vec::new(),
+            roots: Vec::new(),
+        }
+    }
+}
+
+impl<T> Default for SceneGraph<T> {
+    fn default() -> SceneGraph<T> {
+        SceneGraph::new()
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl<T> Node<T> {
+    pub fn local(&self) -> &Transform2D {
+        &self.local
+    }
+
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+}
+
+impl<T> SceneGraph<T> {
+    /// Adds a node without a parent
+    pub fn add(&mut self, name: &str, local: Transform2D, data: T) -> NodeId {
+        let id = self.allocate(name, local, data, None);
+        self.roots.push(id);
+        id
+    }
+
+    /// Adds a node under a parent, `None` if the parent does not exist
+    pub fn add_child(
+        &mut self,
+        parent: NodeId,
+        name: &str,
+        local: Transform2D,
+        data: T,
+    ) -> Option<NodeId> {
+        self.get(parent)?;
+        let id = self.allocate(name, local, data, Some(parent));
+        self.node_mut(parent).unwrap().children.push(id);
+        Some(id)
+    }
+
+    /// Removes a node with all its descendants and returns its data
+    pub fn remove(&mut self, id: NodeId) -> Option<T> {
+        let parent = self.get(id)?.parent;
+        self.siblings_mut(parent).retain(|&sibling| sibling != id);
+
+        let mut removed = vec![id];
+        let mut data = None;
+        while let Some(next) = removed.pop() {
+            let slot = &mut self.slots[next.index as usize];
+            let node = slot.node.take().unwrap();
+            slot.generation = slot.generation.wrapping_add(1);
+            self.free.push(next.index);
+
+            removed.extend(node.children);
+            if next == id {
+                data = Some(node.data);
+            }
+        }
+        data
+    }
+
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.get(id).is_some()
+    }
+
+    pub fn get(&self, id: NodeId) -> Option<&Node<T>> {
+        let slot = self.slots.get(id.index as usize)?;
+        if slot.generation == id.generation {
+            slot.node.as_ref()
+        } else {
+            None
+        }
+    }
+
+    /// Returns a node to change its data, z index or visibility.
+    /// Use `local_mut` to move it so its world transform gets updated.
+    pub fn node_mut(&mut self, id: NodeId) -> Option<&mut Node<T>> {
+        let slot = self.slots.get_mut(id.index as usize)?;
+        if slot.generation == id.generation {
+            slot.node.as_mut()
+        } else {
+            None
+        }
+    }
+
+    pub fn data(&self, id: NodeId) -> Option<&T> {
+        self.get(id).map(|node| &node.data)
+    }
+
+    pub fn data_mut(&mut self, id: NodeId) -> Option<&mut T> {
+        self.node_mut(id).map(|node| &mut node.data)
+    }
+
+    pub fn roots(&self) -> &[NodeId] {
+        &self.roots
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len() - self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the first node with a name
+    pub fn find(&self, name: &str) -> Option<NodeId> {
+        self.slots.iter().enumerate().find_map(|(index, slot)| {
+            slot.node
+                .as_ref()
+                .filter(|node| node.name == name)
+                .map(|_| NodeId {
+                    index: index as u32,
+                    generation: slot.generation,
+                })
+        })
+    }
+
+    /// Returns the transform relative to the parent
+    pub fn local(&self, id: NodeId) -> Option<&Transform2D> {
+        self.get(id).map(|node| &node.local)
+    }
+
+    /// Returns the transform relative to the parent and flags the world transforms below
+    pub fn local_mut(&mut self, id: NodeId) -> Option<&mut Transform2D> {
+        self.get(id)?;
+        self.mark_dirty(id);
+        self.node_mut(id).map(|node| &mut node.local)
+    }
+
+    pub fn set_local(&mut self, id: NodeId, local: Transform2D) -> bool {
+        match self.local_mut(id) {
+            Some(transform) => {
+                *transform = local;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the transform from the node's space to the world, recomputing outdated parents
+    pub fn world_transform(&mut self, id: NodeId) -> Option<Matrix3> {
+        let node = self.get(id)?;
+        if !node.dirty {
+            return Some(node.world);
+        }
+
+        let parent = match node.parent {
+            Some(parent) => self.world_transform(parent)?,
+            None => Matrix3::identity(),
+        };
+        let node = self.node_mut(id).unwrap();
+        node.world = parent * node.local.matrix();
+        node.dirty = false;
+        Some(node.world)
+    }
+
+    /// Returns the world position of the node's origin
+    pub fn world_position(&mut self, id: NodeId) -> Option<Vector2> {
+        let origin = self.get(id)?.local.origin;
+        self.world_transform(id)
+            .map(|world| world.transform_point(origin))
+    }
+
+    /// Recomputes every outdated world transform and returns how many there were
+    pub fn update_transforms(&mut self) -> usize {
+        let mut updated = 0;
+        let mut stack: Vec<(NodeId, Matrix3, bool)> = self
+            .roots
+            .iter()
+            .rev()
+            .map(|&root| (root, Matrix3::identity(), false))
+            .collect();
+
+        while let Some((id, parent, parent_changed)) = stack.pop() {
+            let node = self.node_mut(id).unwrap();
+            let changed = parent_changed || node.dirty;
+            if changed {
+                node.world = parent * node.local.matrix();
+                node.dirty = false;
+                updated += 1;
+            }
+
+            let world = node.world;
+            stack.extend(
+                node.children
+                    .iter()
+                    .rev()
+                    .map(|&child| (child, world, changed)),
+            );
+        }
+        updated
+    }
+
+    /// Moves a node under another one, or to the top with `None`, keeping where it is in the world
+    pub fn set_parent(&mut self, id: NodeId, parent: Option<NodeId>) -> Result<(), SceneError> {
+        self.get(id).ok_or(SceneError::MissingNode)?;
+        if let Some(parent) = parent {
+            self.get(parent).ok_or(SceneError::MissingNode)?;
+            if self.is_ancestor(id, parent) {
+                return Err(SceneError::Cycle);
+            }
+        }
+
+        let world = self.world_transform(id).unwrap();
+        let parent_world = match parent {
+            Some(parent) => self.world_transform(parent).unwrap(),
+            None => Matrix3::identity(),
+        };
+        // A parent with a scale of 0 has no inverse, the node then keeps its local transform
+        let local = parent_world
+            .inverse()
+            .map(|inverse| inverse * world)
+            .map(|matrix| Transform2D::from_matrix(&matrix, self.get(id).unwrap().local.origin));
+
+        let old_parent = self.get(id).unwrap().parent;
+        self.siblings_mut(old_parent)
+            .retain(|&sibling| sibling != id);
+        self.siblings_mut(parent).push(id);
+
+        let node = self.node_mut(id).unwrap();
+        node.parent = parent;
+        if let Some(local) = local {
+            node.local = local;
+        }
+        self.mark_dirty(id);
+        Ok(())
+    }
+
+    /// Returns true if `ancestor` is `id` or one of its parents
+    pub fn is_ancestor(&self, ancestor: NodeId, id: NodeId) -> bool {
+        let mut current = Some(id);
+        while let Some(next) = current {
+            if next == ancestor {
+                return true;
+            }
+            current = self.get(next).and_then(|node| node.parent);
+        }
+        false
+    }
+
+    /// Returns the visible nodes in drawing order with their world transforms.
+    /// Parents come before their children and siblings are sorted by z index.
+    pub fn draw_order(&mut self) -> Vec<(NodeId, Matrix3)> {
+        self.update_transforms();
+
+        let mut order = Vec::new();
+        let mut stack = self.sorted(&self.roots);
+        stack.reverse();
+        while let Some(id) = stack.pop() {
+            let node = self.get(id).unwrap();
+            if !node.visible {
+                continue;
+            }
+
+            order.push((id, node.world));
+            let mut children = self.sorted(&node.children);
+            children.reverse();
+            stack.extend(children);
+        }
+        order
+    }
+
+    /// Calls `f` for every visible node in drawing order
+    pub fn visit<F: FnMut(NodeId, &Matrix3, &T)>(&mut self, mut f: F) {
+        for (id, world) in self.draw_order() {
+            f(id, &world, &self.get(id).unwrap().data);
+        }
+    }
+
+    fn allocate(
+        &mut self,
+        name: &str,
+        local: Transform2D,
+        data: T,
+        parent: Option<NodeId>,
+    ) -> NodeId {
+        let node = Node {
+            name: name.to_string(),
+            data,
+            z_index: 0,
+            visible: true,
+            local,
+            world: Matrix3::identity(),
+            dirty: true,
+            parent,
+            children: Vec::new(),
+        };
+
+        match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index as usize];
+                slot.node = Some(node);
+                NodeId {
+                    index,
+                    generation: slot.generation,
+                }
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    node: Some(node),
+                });
+                NodeId {
+                    index: self.slots.len() as u32 - 1,
+                    generation: 0,
+                }
+            }
+        }
+    }
+
+    fn siblings_mut(&mut self, parent: Option<NodeId>) -> &mut Vec<NodeId> {
+        match parent {
+            Some(parent) => &mut self.node_mut(parent).unwrap().children,
+            None => &mut self.roots,
+        }
+    }
+
+    /// Flags a node and its descendants, stopping at flagged ones since their children are too
+    fn mark_dirty(&mut self, id: NodeId) {
+        let mut stack = vec![id];
+        while let Some(next) = stack.pop() {
+            let node = self.node_mut(next).unwrap();
+            if node.dirty && next != id {
+                continue;
+            }
+            node.dirty = true;
+            stack.extend(node.children.iter().copied());
+        }
+    }
+
+    fn sorted(&self, ids: &[NodeId]) -> Vec<NodeId> {
+        let mut sorted = ids.to_vec();
+        sorted.sort_by_key(|&id| self.get(id).unwrap().z_index);
+        sorted
+    }
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod graph_tests {
+
+    use super::*;
+
+    fn close(a: Vector2, b: Vector2) -> bool {
+        (a - b).magnitude() < 1e-9
+    }
+
+    #[test]
+    fn compose_transforms() {
+        let mut scene = SceneGraph::new();
+        let tank = scene.add(
+            "tank",
+            Transform2D::from_position(Vector2::new(100f64, 50f64)),
+            (),
+        );
+        let turret = scene
+            .add_child(
+                tank,
+                "turret",
+                Transform2D::from_position(Vector2::new(0f64, -10f64)),
+                (),
+            )
+            .unwrap();
+        let barrel = scene
+            .add_child(
+                turret,
+                "barrel",
+                Transform2D::from_position(Vector2::new(20f64, 0f64)),
+                (),
+            )
+            .unwrap();
+
+        assert_eq!(scene.update_transforms(), 3);
+        assert_eq!(scene.update_transforms(), 0);
+        assert!(close(
+            scene.world_position(barrel).unwrap(),
+            Vector2::new(120f64, 40f64)
+        ));
+
+        // Turning the turret moves the barrel but not the tank
+        scene.local_mut(turret).unwrap().rotation = std::f64::consts::FRAC_PI_2;
+        assert!(close(
+            scene.world_position(barrel).unwrap(),
+            Vector2::new(100f64, 60f64)
+        ));
+        assert_eq!(scene.update_transforms(), 0);
+
+        scene.local_mut(tank).unwrap().scale = Vector2::new(2f64, 2f64);
+        assert_eq!(scene.update_transforms(), 3);
+        assert!(close(
+            scene.world_position(barrel).unwrap(),
+            Vector2::new(100f64, 70f64)
+        ));
+        assert!(close(
+            scene.world_position(turret).unwrap(),
+            Vector2::new(100f64, 30f64)
+        ));
+        assert_eq!(scene.find("barrel"), Some(barrel));
+    }
+
+    #[test]
+    fn reparent_keeps_world_position() {
+        let mut scene = SceneGraph::new();
+        let mut hand =
+            Transform2D::new(Vector2::new(40f64, 10f64), 0.7f64, Vector2::new(2f64, 2f64));
+        hand.origin = Vector2::new(3f64, 1f64);
+        let player = scene.add("player", hand, ());
+        let mut sword = Transform2D::from_position(Vector2::new(300f64, 200f64));
+        sword.rotation = 0.2f64;
+        let sword = scene.add("sword", sword, ());
+
+        let before = scene.world_transform(sword).unwrap();
+        scene.set_parent(sword, Some(player)).unwrap();
+        assert_eq!(scene.get(sword).unwrap().parent(), Some(player));
+        assert_eq!(scene.roots(), [player]);
+        assert!(scene
+            .world_transform(sword)
+            .unwrap()
+            .approx_eq(&before, 1e-9));
+        assert!((scene.local(sword).unwrap().scale.x - 0.5f64).abs() < 1e-9);
+
+        // The sword follows the hand once attached
+        scene
+            .local_mut(player)
+            .unwrap()
+            .translate(Vector2::new(5f64, 0f64));
+        assert!(close(
+            scene.world_position(sword).unwrap(),
+            Vector2::new(305f64, 200f64)
+        ));
+
+        scene.set_parent(sword, None).unwrap();
+        assert!(close(
+            scene.world_position(sword).unwrap(),
+            Vector2::new(305f64, 200f64)
+        ));
+        assert_eq!(
+            scene.set_parent(player, Some(player)),
+            Err(SceneError::Cycle)
+        );
+        scene.set_parent(sword, Some(player)).unwrap();
+        assert_eq!(
+            scene.set_parent(player, Some(sword)),
+            Err(SceneError::Cycle)
+        );
+    }
+
+    #[test]
+    fn draw_in_z_order() {
+        let mut scene = SceneGraph::new();
+        let identity = Transform2D::identity();
+        let back = scene.add("back", identity, 'b');
+        let world = scene.add("world", identity, 'w');
+        let hud = scene.add("hud", identity, 'h');
+        let shadow = scene.add_child(world, "shadow", identity, 's').unwrap();
+        let player = scene.add_child(world, "player", identity, 'p').unwrap();
+        scene.add_child(player, "hat", identity, 'a').unwrap();
+
+        scene.node_mut(back).unwrap().z_index = -1;
+        scene.node_mut(hud).unwrap().z_index = 10;
+        scene.node_mut(shadow).unwrap().z_index = -1;
+        scene.node_mut(world).unwrap().z_index = -1;
+
+        let mut drawn = String::new();
+        scene.visit(|_, _, data| drawn.push(*data));
+        assert_eq!(drawn, "bwspah");
+
+        scene.node_mut(player).unwrap().visible = false;
+        let drawn: Vec<NodeId> = scene.draw_order().into_iter().map(|(id, _)| id).collect();
+        assert_eq!(drawn, [back, world, shadow, hud]);
+
+        assert_eq!(scene.remove(world), Some('w'));
+        assert!(!scene.contains(player));
+        assert_eq!(scene.len(), 2);
+        let reused = scene.add("new", identity, 'n');
+        assert_ne!(reused, shadow);
+        assert_eq!(scene.roots(), [back, hud, reused]);
+    }
+}
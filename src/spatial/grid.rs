@@ -0,0 +1,347 @@
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+
+use crate::math::{
+    aabb::Aabb,
+    geometry::{Geometry, Hit},
+    ray::Ray,
+    vector2::Vector2,
+};
+
+use super::index::{nearer, Proxies, ProxyId, SpatialIndex};
+
+/// Range of cells covered by a box, inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Cells {
+    min: (i32, i32),
+    max: (i32, i32),
+}
+
+/// Uniform grid of square cells, each listing the objects whose boxes cover it.
+/// Only cells holding objects are stored, so the grid has no bounds.
+/// Works best when objects are about the size of a cell.
+#[derive(Debug, Clone)]
+pub struct SpatialHash<T> {
+    cell_size: f64,
+    cells: HashMap<(i32, i32), Vec<u32>>,
+    proxies: Proxies<T>,
+    /// Cells ever used, raycasts and nearest queries stop past them
+    extent: Option<Cells>,
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl<T> SpatialHash<T> {
+    pub fn new(cell_size: f64) -> SpatialHash<T> {
+        SpatialHash {
+            cell_size,
+            cells: HashMap::new(),
+            proxies: Proxies::new(),
+            extent: None,
+        }
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl<T> SpatialHash<T> {
+    pub fn cell_size(&self) -> f64 {
+        self.cell_size
+    }
+
+    /// Returns the number of cells holding objects
+    pub fn cell_count(&self) -> usize {
+        self.cells.len()
+    }
+
+    fn cell(&self, point: Vector2) -> (i32, i32) {
+        (
+            (point.x / self.cell_size).floor() as i32,
+            (point.y / self.cell_size).floor() as i32,
+        )
+    }
+
+    fn cells(&self, bounds: &Aabb) -> Cells {
+        Cells {
+            min: self.cell(bounds.min),
+            max: self.cell(bounds.max),
+        }
+    }
+
+    fn cell_bounds(&self, (x, y): (i32, i32)) -> Aabb {
+        let min = Vector2::new(x as f64, y as f64) * self.cell_size;
+        Aabb::new(min, min + Vector2::new(self.cell_size, self.cell_size))
+    }
+
+    fn add(&mut self, index: u32, cells: Cells) {
+        for x in cells.min.0..=cells.max.0 {
+            for y in cells.min.1..=cells.max.1 {
+                self.cells.entry((x, y)).or_default().push(index);
+            }
+        }
+        self.extent = Some(match self.extent {
+            Some(extent) => Cells {
+                min: (extent.min.0.min(cells.min.0), extent.min.1.min(cells.min.1)),
+                max: (extent.max.0.max(cells.max.0), extent.max.1.max(cells.max.1)),
+            },
+            None => cells,
+        });
+    }
+
+    fn take(&mut self, index: u32, cells: Cells) {
+        for x in cells.min.0..=cells.max.0 {
+            for y in cells.min.1..=cells.max.1 {
+                if let Some(list) = self.cells.get_mut(&(x, y)) {
+                    list.retain(|&other| other != index);
+                    if list.is_empty() {
+                        self.cells.remove(&(x, y));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<T> SpatialIndex<T> for SpatialHash<T> {
+    fn insert(&mut self, bounds: Aabb, data: T) -> ProxyId {
+        let id = self.proxies.insert(bounds, data, 0);
+        self.add(id.index(), self.cells(&bounds));
+        id
+    }
+
+    fn update(&mut self, id: ProxyId, bounds: Aabb) -> bool {
+        let old = match self.proxies.get_mut(id) {
+            Some(entry) => std::mem::replace(&mut entry.bounds, bounds),
+            None => return false,
+        };
+
+        // Small moves usually stay in the same cells
+        let (before, after) = (self.cells(&old), self.cells(&bounds));
+        if before != after {
+            self.take(id.index(), before);
+            self.add(id.index(), after);
+        }
+        true
+    }
+
+    fn remove(&mut self, id: ProxyId) -> Option<T> {
+        let entry = self.proxies.remove(id)?;
+        self.take(id.index(), self.cells(&entry.bounds));
+        Some(entry.data)
+    }
+
+    fn get(&self, id: ProxyId) -> Option<(&Aabb, &T)> {
+        self.proxies
+            .get(id)
+            .map(|entry| (&entry.bounds, &entry.data))
+    }
+
+    fn len(&self) -> usize {
+        self.proxies.len()
+    }
+
+    fn query(&self, region: &Aabb) -> Vec<ProxyId> {
+        let cells = self.cells(region);
+        let area = (cells.max.0 as i64 - cells.min.0 as i64 + 1)
+            * (cells.max.1 as i64 - cells.min.1 as i64 + 1);
+
+        // Regions covering more cells than are stored are cheaper to test object by object
+        let mut found: Vec<u32> = if area > self.cells.len() as i64 {
+            self.proxies
+                .iter()
+                .filter(|(_, entry)| entry.bounds.overlaps(region))
+                .map(|(index, _)| index)
+                .collect()
+        } else {
+            let mut found = Vec::new();
+            for x in cells.min.0..=cells.max.0 {
+                for y in cells.min.1..=cells.max.1 {
+                    if let Some(list) = self.cells.get(&(x, y)) {
+                        found.extend(
+                            list.iter()
+                                .copied()
+                                .filter(|&index| self.proxies.at(index).bounds.overlaps(region)),
+                        );
+                    }
+                }
+            }
+            found
+        };
+
+        // Objects covering several cells are listed once
+        found.sort_unstable();
+        found.dedup();
+        found
+            .into_iter()
+            .map(|index| self.proxies.id(index))
+            .collect()
+    }
+
+    /// Walks the cells along the ray in order, stopping once a hit is nearer than the next cell
+    fn raycast(&self, ray: &Ray, max_distance: f64) -> Option<(ProxyId, Hit)> {
+        let extent = self.extent?;
+        let world = Aabb::new(
+            self.cell_bounds(extent.min).min,
+            self.cell_bounds(extent.max).max,
+        );
+
+        // Starts where the ray enters the used cells
+        let start = world.raycast(ray)?.distance;
+        if start > max_distance {
+            return None;
+        }
+        let mut cell = self.cell(ray.at(start));
+        cell.0 = cell.0.clamp(extent.min.0, extent.max.0);
+        cell.1 = cell.1.clamp(extent.min.1, extent.max.1);
+
+        let step = |direction: f64| if direction > 0f64 { 1 } else { -1 };
+        let (step_x, step_y) = (step(ray.direction.x), step(ray.direction.y));
+        let bounds = self.cell_bounds(cell);
+        let crossing = |origin: f64, direction: f64, low: f64, high: f64| {
+            if direction.abs() <= f64::EPSILON {
+                f64::INFINITY
+            } else if direction > 0f64 {
+                (high - origin) / direction
+            } else {
+                (low - origin) / direction
+            }
+        };
+        let mut next_x = crossing(ray.origin.x, ray.direction.x, bounds.min.x, bounds.max.x);
+        let mut next_y = crossing(ray.origin.y, ray.direction.y, bounds.min.y, bounds.max.y);
+        let delta_x = (self.cell_size / ray.direction.x).abs();
+        let delta_y = (self.cell_size / ray.direction.y).abs();
+
+        let mut best: Option<(ProxyId, Hit)> = None;
+        loop {
+            if let Some(list) = self.cells.get(&cell) {
+                for &index in list.iter() {
+                    if let Some(hit) = self.proxies.at(index).bounds.raycast(ray) {
+                        if hit.distance <= max_distance {
+                            nearer(&mut best, self.proxies.id(index), hit);
+                        }
+                    }
+                }
+            }
+
+            let leave = next_x.min(next_y);
+            if best.is_some_and(|(_, hit)| hit.distance <= leave) || leave > max_distance {
+                return best;
+            }
+            if next_x < next_y {
+                cell.0 += step_x;
+                next_x += delta_x;
+            } else {
+                cell.1 += step_y;
+                next_y += delta_y;
+            }
+            if cell.0 < extent.min.0
+                || cell.0 > extent.max.0
+                || cell.1 < extent.min.1
+                || cell.1 > extent.max.1
+            {
+                return best;
+            }
+        }
+    }
+
+    /// Searches rings of cells around the point, growing until no nearer object can remain
+    fn nearest(&self, point: Vector2) -> Option<(ProxyId, f64)> {
+        let extent = self.extent?;
+        let center = self.cell(point);
+        let reach = [
+            center.0 - extent.min.0,
+            extent.max.0 - center.0,
+            center.1 - extent.min.1,
+            extent.max.1 - center.1,
+        ]
+        .iter()
+        .copied()
+        .max()
+        .unwrap()
+        .max(0);
+
+        let mut best: Option<(u32, f64)> = None;
+        for ring in 0..=reach {
+            // Every cell of the ring is at least this far from the point
+            if best.is_some_and(|(_, distance)| distance < (ring - 1) as f64 * self.cell_size) {
+                break;
+            }
+            for x in center.0 - ring..=center.0 + ring {
+                for y in center.1 - ring..=center.1 + ring {
+                    if (x - center.0).abs() != ring && (y - center.1).abs() != ring {
+                        continue;
+                    }
+                    for &index in self.cells.get(&(x, y)).into_iter().flatten() {
+                        let distance = self.proxies.at(index).bounds.distance(point);
+                        if best.is_none_or(|(_, nearest)| distance < nearest) {
+                            best = Some((index, distance));
+                        }
+                    }
+                }
+            }
+        }
+        best.map(|(index, distance)| (self.proxies.id(index), distance))
+    }
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod grid_tests {
+
+    use super::*;
+
+    #[test]
+    fn cells_follow_objects() {
+        let mut grid = SpatialHash::new(10f64);
+        let wide = grid.insert(
+            Aabb::new(Vector2::new(-5f64, 1f64), Vector2::new(25f64, 2f64)),
+            "wide",
+        );
+        let small = grid.insert(
+            Aabb::new(Vector2::new(1f64, 1f64), Vector2::new(2f64, 2f64)),
+            "small",
+        );
+        assert_eq!(grid.cell_count(), 4);
+
+        let found = grid.query(&Aabb::new(
+            Vector2::new(0f64, 0f64),
+            Vector2::new(5f64, 5f64),
+        ));
+        assert_eq!(found.len(), 2);
+        assert!(grid.update(
+            small,
+            Aabb::new(Vector2::new(41f64, 1f64), Vector2::new(42f64, 2f64))
+        ));
+        assert_eq!(grid.cell_count(), 5);
+        assert_eq!(grid.remove(wide), Some("wide"));
+        assert_eq!(grid.cell_count(), 1);
+        assert!(!grid.update(wide, Aabb::new(Vector2::new_empty(), Vector2::new_empty())));
+
+        let (id, hit) = grid
+            .raycast(
+                &Ray::new(Vector2::new(-100f64, 1.5f64), Vector2::new(1f64, 0f64)),
+                1000f64,
+            )
+            .unwrap();
+        assert_eq!(id, small);
+        assert_eq!(hit.distance, 141f64);
+        assert!(grid
+            .raycast(
+                &Ray::new(Vector2::new(-100f64, 1.5f64), Vector2::new(1f64, 0f64)),
+                100f64
+            )
+            .is_none());
+        assert_eq!(
+            grid.nearest(Vector2::new(0f64, 1.5f64)),
+            Some((small, 41f64))
+        );
+    }
+}
@@ -0,0 +1,329 @@
+#![allow(dead_code)]
+
+use crate::math::{aabb::Aabb, geometry::Hit, ray::Ray, vector2::Vector2};
+
+/// Handle to an object in a spatial index, stale once the object is removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ProxyId {
+    index: u32,
+    generation: u32,
+}
+
+/// Acceleration structure for finding objects by their bounding box.
+/// Queries test the boxes only, callers check exact shapes on the results.
+pub trait SpatialIndex<T> {
+    fn insert(&mut self, bounds: Aabb, data: T) -> ProxyId;
+
+    /// Moves an object, returns false when the handle is stale
+    fn update(&mut self, id: ProxyId, bounds: Aabb) -> bool;
+
+    fn remove(&mut self, id: ProxyId) -> Option<T>;
+
+    fn get(&self, id: ProxyId) -> Option<(&Aabb, &T)>;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the objects whose boxes overlap a region, in no particular order
+    fn query(&self, region: &Aabb) -> Vec<ProxyId>;
+
+    /// Returns the first box the ray enters within `max_distance`
+    fn raycast(&self, ray: &Ray, max_distance: f64) -> Option<(ProxyId, Hit)>;
+
+    /// Returns the object whose box is nearest to a point, with the distance, 0 inside the box
+    fn nearest(&self, point: Vector2) -> Option<(ProxyId, f64)>;
+}
+
+/// Object stored in a spatial index.
+#[derive(Debug, Clone)]
+pub(crate) struct Entry<T> {
+    pub bounds: Aabb,
+    pub data: T,
+    /// Where the structure keeps the object, like a tree node
+    pub node: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Slot<T> {
+    generation: u32,
+    entry: Option<Entry<T>>,
+}
+
+/// Generational storage behind the handles, shared by the structures.
+#[derive(Debug, Clone)]
+pub(crate) struct Proxies<T> {
+    slots: Vec<Slot<T>>,
+    free: Vec<u32>,
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl<T> Proxies<T> {
+    pub fn new() -> Proxies<T> {
+        Proxies {
+            slots: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl<T> Proxies<T> {
+    pub fn insert(&mut self, bounds: Aabb, data: T, node: usize) -> ProxyId {
+        let entry = Some(Entry { bounds, data, node });
+        match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index as usize];
+                slot.entry = entry;
+                ProxyId {
+                    index,
+                    generation: slot.generation,
+                }
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    entry,
+                });
+                ProxyId {
+                    index: self.slots.len() as u32 - 1,
+                    generation: 0,
+                }
+            }
+        }
+    }
+
+    pub fn remove(&mut self, id: ProxyId) -> Option<Entry<T>> {
+        self.get(id)?;
+        let slot = &mut self.slots[id.index as usize];
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(id.index);
+        slot.entry.take()
+    }
+
+    pub fn get(&self, id: ProxyId) -> Option<&Entry<T>> {
+        let slot = self.slots.get(id.index as usize)?;
+        if slot.generation == id.generation {
+            slot.entry.as_ref()
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, id: ProxyId) -> Option<&mut Entry<T>> {
+        let slot = self.slots.get_mut(id.index as usize)?;
+        if slot.generation == id.generation {
+            slot.entry.as_mut()
+        } else {
+            None
+        }
+    }
+
+    /// Returns the live entry at a raw index, as kept inside the structures
+    pub fn at(&self, index: u32) -> &Entry<T> {
+        self.slots[index as usize].entry.as_ref().unwrap()
+    }
+
+    pub fn at_mut(&mut self, index: u32) -> &mut Entry<T> {
+        self.slots[index as usize].entry.as_mut().unwrap()
+    }
+
+    pub fn id(&self, index: u32) -> ProxyId {
+        ProxyId {
+            index,
+            generation: self.slots[index as usize].generation,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len() - self.free.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &Entry<T>)> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(index, slot)| slot.entry.as_ref().map(|entry| (index as u32, entry)))
+    }
+}
+
+impl ProxyId {
+    pub(crate) fn index(&self) -> u32 {
+        self.index
+    }
+}
+
+/// Keeps the nearer of two ray hits
+pub(crate) fn nearer(best: &mut Option<(ProxyId, Hit)>, id: ProxyId, hit: Hit) {
+    if best.is_none_or(|(_, current)| hit.distance < current.distance) {
+        *best = Some((id, hit));
+    }
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod index_tests {
+
+    use super::super::{grid::SpatialHash, quadtree::LooseQuadtree, tree::AabbTree};
+    use super::*;
+    use crate::math::geometry::Geometry;
+    use std::time::Instant;
+
+    struct Random(u64);
+
+    impl Random {
+        fn next(&mut self) -> f64 {
+            self.0 = self
+                .0
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (self.0 >> 11) as f64 / (1u64 << 53) as f64
+        }
+
+        fn point(&mut self, extent: f64) -> Vector2 {
+            Vector2::new(self.next() * extent, self.next() * extent)
+        }
+
+        fn bounds(&mut self, extent: f64, largest: f64) -> Aabb {
+            let min = self.point(extent);
+            let size = Vector2::new(self.next() * largest, self.next() * largest);
+            Aabb::new(min, min + size)
+        }
+    }
+
+    fn indexes() -> Vec<(&'static str, Box<dyn SpatialIndex<usize>>)> {
+        let world = Aabb::new(Vector2::new_empty(), Vector2::new(1000f64, 1000f64));
+        vec![
+            ("grid", Box::new(SpatialHash::new(25f64))),
+            ("quadtree", Box::new(LooseQuadtree::new(world, 8))),
+            ("tree", Box::new(AabbTree::new(2f64))),
+        ]
+    }
+
+    /// Compares every structure against testing all the boxes
+    #[test]
+    fn matches_brute_force() {
+        for (name, mut index) in indexes() {
+            let mut random = Random(9);
+            let mut live: Vec<(ProxyId, Aabb, usize)> = Vec::new();
+            for step in 0..1500 {
+                match (random.next() * 10f64) as u32 {
+                    0 if !live.is_empty() => {
+                        let (id, _, data) =
+                            live.swap_remove((random.next() * live.len() as f64) as usize);
+                        assert_eq!(index.remove(id), Some(data), "{}", name);
+                        assert!(index.get(id).is_none());
+                    }
+                    1..=3 if !live.is_empty() => {
+                        let position = (random.next() * live.len() as f64) as usize;
+                        let bounds = live[position]
+                            .1
+                            .translated(random.point(40f64) - Vector2::new(20f64, 20f64));
+                        assert!(index.update(live[position].0, bounds));
+                        live[position].1 = bounds;
+                    }
+                    _ => {
+                        // Some boxes fall outside the quadtree bounds or cover many grid cells
+                        let bounds =
+                            random.bounds(1100f64, if step % 50 == 0 { 300f64 } else { 20f64 });
+                        live.push((index.insert(bounds, step), bounds, step));
+                    }
+                }
+            }
+            assert_eq!(index.len(), live.len(), "{}", name);
+
+            for _ in 0..100 {
+                let region = random.bounds(1000f64, 150f64);
+                let mut found = index.query(&region);
+                found.sort();
+                let mut expected: Vec<ProxyId> = live
+                    .iter()
+                    .filter(|(_, bounds, _)| bounds.overlaps(&region))
+                    .map(|(id, _, _)| *id)
+                    .collect();
+                expected.sort();
+                assert_eq!(found, expected, "{}", name);
+
+                let point = random.point(1000f64);
+                let nearest = live
+                    .iter()
+                    .map(|(_, bounds, _)| bounds.distance(point))
+                    .fold(f64::MAX, f64::min);
+                let (id, distance) = index.nearest(point).unwrap();
+                assert!((distance - nearest).abs() < 1e-9, "{}", name);
+                assert!((index.get(id).unwrap().0.distance(point) - nearest).abs() < 1e-9);
+
+                let ray = Ray::new(point, random.point(2f64) - Vector2::new(1f64, 1f64));
+                let first = live
+                    .iter()
+                    .filter_map(|(_, bounds, _)| bounds.raycast(&ray))
+                    .map(|hit| hit.distance)
+                    .filter(|&distance| distance <= 300f64)
+                    .fold(f64::MAX, f64::min);
+                match index.raycast(&ray, 300f64) {
+                    Some((_, hit)) => assert!((hit.distance - first).abs() < 1e-9, "{}", name),
+                    None => assert_eq!(first, f64::MAX, "{}", name),
+                }
+            }
+        }
+    }
+
+    /// Timings for choosing a structure, run with `cargo test --release -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn benchmark() {
+        const OBJECTS: usize = 20000;
+        for (name, mut index) in indexes() {
+            let mut random = Random(1);
+            let boxes: Vec<Aabb> = (0..OBJECTS).map(|_| random.bounds(1000f64, 8f64)).collect();
+
+            let start = Instant::now();
+            let ids: Vec<ProxyId> = boxes
+                .iter()
+                .enumerate()
+                .map(|(data, &bounds)| index.insert(bounds, data))
+                .collect();
+            let insert = start.elapsed();
+
+            let start = Instant::now();
+            for (id, bounds) in ids.iter().zip(boxes.iter()) {
+                index.update(*id, bounds.translated(Vector2::new(1f64, 0.5f64)));
+            }
+            let update = start.elapsed();
+
+            let start = Instant::now();
+            let mut found = 0;
+            for bounds in boxes.iter() {
+                found += index.query(&bounds.expanded(2f64)).len();
+            }
+            let query = start.elapsed();
+
+            let start = Instant::now();
+            for _ in 0..2000 {
+                let ray = Ray::new(
+                    random.point(1000f64),
+                    random.point(2f64) - Vector2::new(1f64, 1f64),
+                );
+                index.raycast(&ray, 200f64);
+                index.nearest(random.point(1000f64));
+            }
+            let search = start.elapsed();
+
+            println!(
+                "{:>8}: insert {:?}, update {:?}, query {:?} ({} found), 2000 raycasts and nearest {:?}",
+                name, insert, update, query, found, search
+            );
+        }
+    }
+}
@@ -0,0 +1,4 @@
+pub mod grid;
+pub mod index;
+pub mod quadtree;
+pub mod tree;
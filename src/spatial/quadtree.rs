@@ -0,0 +1,306 @@
+#![allow(dead_code)]
+
+use crate::math::{
+    aabb::Aabb,
+    geometry::{Geometry, Hit},
+    ray::Ray,
+    vector2::Vector2,
+};
+
+use super::index::{nearer, Proxies, ProxyId, SpatialIndex};
+
+#[derive(Debug, Clone)]
+struct QuadNode {
+    /// The quarter of the parent the node covers, objects are placed by their center
+    bounds: Aabb,
+    depth: u32,
+    items: Vec<u32>,
+    /// Index of the first of four children, created when an object first goes below the node
+    children: Option<usize>,
+}
+
+/// Quadtree whose nodes reach past their quarter by half its size on every side.
+/// An object belongs to the smallest node at least its size whose quarter holds its center,
+/// so objects never straddle nodes and moving one rarely changes its node.
+/// Objects centered outside the bounds stay in the root, which every query visits.
+#[derive(Debug, Clone)]
+pub struct LooseQuadtree<T> {
+    nodes: Vec<QuadNode>,
+    max_depth: u32,
+    proxies: Proxies<T>,
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl<T> LooseQuadtree<T> {
+    pub fn new(bounds: Aabb, max_depth: u32) -> LooseQuadtree<T> {
+        LooseQuadtree {
+            nodes: vec![QuadNode {
+                bounds,
+                depth: 0,
+                items: Vec::new(),
+                children: None,
+            }],
+            max_depth,
+            proxies: Proxies::new(),
+        }
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl<T> LooseQuadtree<T> {
+    pub fn bounds(&self) -> Aabb {
+        self.nodes[0].bounds
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns the area a node's objects can reach, the root reaches everywhere
+    fn loose_bounds(&self, node: usize) -> Option<Aabb> {
+        if node == 0 {
+            return None;
+        }
+        let bounds = self.nodes[node].bounds;
+        let half = bounds.half_extents();
+        Some(Aabb::new(bounds.min - half, bounds.max + half))
+    }
+
+    /// Finds the node for an object, creating the nodes on the way
+    fn place(&mut self, bounds: &Aabb) -> usize {
+        let center = bounds.center();
+        let size = bounds.size();
+        let largest = size.x.max(size.y);
+
+        let mut node = 0;
+        loop {
+            let current = &self.nodes[node];
+            let quarter = current.bounds.half_extents();
+            if current.depth >= self.max_depth
+                || largest > quarter.x.min(quarter.y)
+                || !current.bounds.contains(center)
+            {
+                return node;
+            }
+
+            let children = match current.children {
+                Some(children) => children,
+                None => self.split(node),
+            };
+            let middle = self.nodes[node].bounds.center();
+            let right = usize::from(center.x >= middle.x);
+            let top = usize::from(center.y >= middle.y);
+            node = children + right + top * 2;
+        }
+    }
+
+    fn split(&mut self, node: usize) -> usize {
+        let parent = &self.nodes[node];
+        let (min, half, depth) = (
+            parent.bounds.min,
+            parent.bounds.half_extents(),
+            parent.depth + 1,
+        );
+        let first = self.nodes.len();
+        for quarter in 0..4 {
+            let offset = Vector2::new((quarter % 2) as f64 * half.x, (quarter / 2) as f64 * half.y);
+            self.nodes.push(QuadNode {
+                bounds: Aabb::new(min + offset, min + offset + half),
+                depth,
+                items: Vec::new(),
+                children: None,
+            });
+        }
+        self.nodes[node].children = Some(first);
+        first
+    }
+
+    fn detach(&mut self, index: u32, node: usize) {
+        let items = &mut self.nodes[node].items;
+        if let Some(position) = items.iter().position(|&item| item == index) {
+            items.swap_remove(position);
+        }
+    }
+
+    fn children(&self, node: usize) -> impl Iterator<Item = usize> {
+        self.nodes[node]
+            .children
+            .into_iter()
+            .flat_map(|first| first..first + 4)
+    }
+}
+
+impl<T> SpatialIndex<T> for LooseQuadtree<T> {
+    fn insert(&mut self, bounds: Aabb, data: T) -> ProxyId {
+        let node = self.place(&bounds);
+        let id = self.proxies.insert(bounds, data, node);
+        self.nodes[node].items.push(id.index());
+        id
+    }
+
+    fn update(&mut self, id: ProxyId, bounds: Aabb) -> bool {
+        let old = match self.proxies.get(id) {
+            Some(entry) => entry.node,
+            None => return false,
+        };
+
+        let node = self.place(&bounds);
+        let entry = self.proxies.at_mut(id.index());
+        entry.bounds = bounds;
+        entry.node = node;
+        if node != old {
+            self.detach(id.index(), old);
+            self.nodes[node].items.push(id.index());
+        }
+        true
+    }
+
+    fn remove(&mut self, id: ProxyId) -> Option<T> {
+        let entry = self.proxies.remove(id)?;
+        self.detach(id.index(), entry.node);
+        Some(entry.data)
+    }
+
+    fn get(&self, id: ProxyId) -> Option<(&Aabb, &T)> {
+        self.proxies
+            .get(id)
+            .map(|entry| (&entry.bounds, &entry.data))
+    }
+
+    fn len(&self) -> usize {
+        self.proxies.len()
+    }
+
+    fn query(&self, region: &Aabb) -> Vec<ProxyId> {
+        let mut found = Vec::new();
+        let mut stack = vec![0];
+        while let Some(node) = stack.pop() {
+            if self
+                .loose_bounds(node)
+                .is_some_and(|loose| !loose.overlaps(region))
+            {
+                continue;
+            }
+            for &index in self.nodes[node].items.iter() {
+                if self.proxies.at(index).bounds.overlaps(region) {
+                    found.push(self.proxies.id(index));
+                }
+            }
+            stack.extend(self.children(node));
+        }
+        found
+    }
+
+    fn raycast(&self, ray: &Ray, max_distance: f64) -> Option<(ProxyId, Hit)> {
+        let mut best: Option<(ProxyId, Hit)> = None;
+        let mut stack = vec![0];
+        while let Some(node) = stack.pop() {
+            let limit = best.map_or(max_distance, |(_, hit)| hit.distance);
+            if let Some(loose) = self.loose_bounds(node) {
+                if loose.raycast(ray).is_none_or(|hit| hit.distance > limit) {
+                    continue;
+                }
+            }
+
+            for &index in self.nodes[node].items.iter() {
+                if let Some(hit) = self.proxies.at(index).bounds.raycast(ray) {
+                    if hit.distance <= max_distance {
+                        nearer(&mut best, self.proxies.id(index), hit);
+                    }
+                }
+            }
+            stack.extend(self.children(node));
+        }
+        best
+    }
+
+    fn nearest(&self, point: Vector2) -> Option<(ProxyId, f64)> {
+        let mut best: Option<(u32, f64)> = None;
+        let mut stack = vec![0];
+        while let Some(node) = stack.pop() {
+            if let (Some(loose), Some((_, nearest))) = (self.loose_bounds(node), best) {
+                if loose.distance(point) >= nearest {
+                    continue;
+                }
+            }
+
+            for &index in self.nodes[node].items.iter() {
+                let distance = self.proxies.at(index).bounds.distance(point);
+                if best.is_none_or(|(_, nearest)| distance < nearest) {
+                    best = Some((index, distance));
+                }
+            }
+
+            // The nearest child is searched first so the others are more often skipped
+            let mut children: Vec<usize> = self.children(node).collect();
+            children.sort_by(|&a, &b| {
+                let distance = |child: usize| self.nodes[child].bounds.distance(point);
+                distance(b).total_cmp(&distance(a))
+            });
+            stack.extend(children);
+        }
+        best.map(|(index, distance)| (self.proxies.id(index), distance))
+    }
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod quadtree_tests {
+
+    use super::*;
+
+    #[test]
+    fn objects_sink_to_their_size() {
+        let world = Aabb::new(Vector2::new_empty(), Vector2::new(64f64, 64f64));
+        let mut tree = LooseQuadtree::new(world, 4);
+
+        // Quarters are 32, 16, 8 then 4 units wide
+        let small = tree.insert(
+            Aabb::new(Vector2::new(1f64, 1f64), Vector2::new(2f64, 2f64)),
+            0,
+        );
+        assert_eq!(tree.proxies.get(small).unwrap().node, 13);
+        assert_eq!(tree.nodes[13].depth, 4);
+        let large = tree.insert(
+            Aabb::new(Vector2::new(10f64, 10f64), Vector2::new(30f64, 30f64)),
+            1,
+        );
+        assert_eq!(tree.nodes[tree.proxies.get(large).unwrap().node].depth, 1);
+        let outside = tree.insert(
+            Aabb::new(Vector2::new(-9f64, 0f64), Vector2::new(-8f64, 1f64)),
+            2,
+        );
+        assert_eq!(tree.proxies.get(outside).unwrap().node, 0);
+
+        // A small move stays in the loose bounds of the same node
+        let node_count = tree.node_count();
+        assert!(tree.update(
+            small,
+            Aabb::new(Vector2::new(1.5f64, 1.5f64), Vector2::new(2.5f64, 2.5f64))
+        ));
+        assert_eq!(tree.proxies.get(small).unwrap().node, 13);
+        assert_eq!(tree.node_count(), node_count);
+
+        let mut found = tree.query(&Aabb::new(
+            Vector2::new(-10f64, 0f64),
+            Vector2::new(3f64, 3f64),
+        ));
+        found.sort();
+        assert_eq!(found, [small, outside]);
+        assert_eq!(
+            tree.nearest(Vector2::new(-5f64, 0.5f64)),
+            Some((outside, 3f64))
+        );
+        assert_eq!(tree.remove(outside), Some(2));
+        assert!(tree.nodes[0].items.is_empty());
+    }
+}
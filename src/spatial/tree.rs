@@ -0,0 +1,404 @@
+#![allow(dead_code)]
+
+use crate::math::{
+    aabb::Aabb,
+    geometry::{Geometry, Hit},
+    ray::Ray,
+    vector2::Vector2,
+};
+
+use super::index::{nearer, Proxies, ProxyId, SpatialIndex};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    /// Holds the raw index of an object
+    Leaf(u32),
+    Branch(usize, usize),
+}
+
+#[derive(Debug, Clone)]
+struct TreeNode {
+    /// Covers the children, for leaves the object grown by the margin
+    bounds: Aabb,
+    parent: Option<usize>,
+    height: u32,
+    kind: Kind,
+}
+
+/// Balanced binary tree of boxes, each branch covering its two children.
+/// Leaves keep the object grown by a margin so small moves need no changes to the tree.
+/// Has no bounds and handles objects of any size, at the cost of more work per query.
+#[derive(Debug, Clone)]
+pub struct AabbTree<T> {
+    nodes: Vec<TreeNode>,
+    free: Vec<usize>,
+    root: Option<usize>,
+    margin: f64,
+    proxies: Proxies<T>,
+}
+
+// ====================================
+// Constructors
+// ====================================
+
+impl<T> AabbTree<T> {
+    pub fn new(margin: f64) -> AabbTree<T> {
+        AabbTree {
+            nodes: Vec::new(),
+            free: Vec::new(),
+            root: None,
+            margin,
+            proxies: Proxies::new(),
+        }
+    }
+}
+
+// ====================================
+// Implementation
+// ====================================
+
+impl<T> AabbTree<T> {
+    pub fn margin(&self) -> f64 {
+        self.margin
+    }
+
+    /// Returns the number of levels below the root, 0 for a single leaf or an empty tree
+    pub fn height(&self) -> u32 {
+        self.root.map_or(0, |root| self.nodes[root].height)
+    }
+
+    fn allocate(&mut self, node: TreeNode) -> usize {
+        match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = node;
+                index
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    fn replace_child(&mut self, parent: Option<usize>, old: usize, new: usize) {
+        match parent {
+            Some(parent) => {
+                if let Kind::Branch(left, right) = &mut self.nodes[parent].kind {
+                    if *left == old {
+                        *left = new;
+                    } else {
+                        *right = new;
+                    }
+                }
+            }
+            None => self.root = Some(new),
+        }
+        self.nodes[new].parent = parent;
+    }
+
+    /// Places a leaf next to the node whose box grows the least, then rebalances the path
+    fn insert_leaf(&mut self, leaf: usize) {
+        let root = match self.root {
+            Some(root) => root,
+            None => {
+                self.root = Some(leaf);
+                self.nodes[leaf].parent = None;
+                return;
+            }
+        };
+
+        let bounds = self.nodes[leaf].bounds;
+        let mut sibling = root;
+        while let Kind::Branch(left, right) = self.nodes[sibling].kind {
+            let perimeter = self.nodes[sibling].bounds.perimeter();
+            let combined = self.nodes[sibling].bounds.merged(&bounds).perimeter();
+
+            // Pairing here creates a branch, going lower grows this box anyway
+            let cost = 2f64 * combined;
+            let inherited = 2f64 * (combined - perimeter);
+            let descend = |child: usize| {
+                let node = &self.nodes[child];
+                let merged = node.bounds.merged(&bounds).perimeter();
+                match node.kind {
+                    Kind::Leaf(_) => merged + inherited,
+                    Kind::Branch(..) => merged - node.bounds.perimeter() + inherited,
+                }
+            };
+            let (left_cost, right_cost) = (descend(left), descend(right));
+            if cost < left_cost && cost < right_cost {
+                break;
+            }
+            sibling = if left_cost < right_cost { left } else { right };
+        }
+
+        let parent = self.nodes[sibling].parent;
+        let branch = self.allocate(TreeNode {
+            bounds: self.nodes[sibling].bounds.merged(&bounds),
+            parent,
+            height: self.nodes[sibling].height + 1,
+            kind: Kind::Branch(sibling, leaf),
+        });
+        self.replace_child(parent, sibling, branch);
+        self.nodes[sibling].parent = Some(branch);
+        self.nodes[leaf].parent = Some(branch);
+        self.refit(parent);
+    }
+
+    fn remove_leaf(&mut self, leaf: usize) {
+        let parent = match self.nodes[leaf].parent {
+            Some(parent) => parent,
+            None => {
+                self.root = None;
+                return;
+            }
+        };
+
+        let sibling = match self.nodes[parent].kind {
+            Kind::Branch(left, right) if left == leaf => right,
+            Kind::Branch(left, _) => left,
+            Kind::Leaf(_) => unreachable!(),
+        };
+        let grandparent = self.nodes[parent].parent;
+        self.replace_child(grandparent, parent, sibling);
+        self.free.push(parent);
+        self.nodes[leaf].parent = None;
+        self.refit(grandparent);
+    }
+
+    /// Recomputes the boxes and heights from a node up to the root, rotating where unbalanced
+    fn refit(&mut self, mut node: Option<usize>) {
+        while let Some(index) = node {
+            let index = self.balance(index);
+            self.fit(index);
+            node = self.nodes[index].parent;
+        }
+    }
+
+    fn fit(&mut self, node: usize) {
+        if let Kind::Branch(left, right) = self.nodes[node].kind {
+            let (left, right) = (&self.nodes[left], &self.nodes[right]);
+            let (bounds, height) = (
+                left.bounds.merged(&right.bounds),
+                left.height.max(right.height) + 1,
+            );
+            self.nodes[node].bounds = bounds;
+            self.nodes[node].height = height;
+        }
+    }
+
+    /// Lifts the taller child of a node whose children differ in height by more than one,
+    /// returns the node now in its place
+    fn balance(&mut self, node: usize) -> usize {
+        let (left, right) = match self.nodes[node].kind {
+            Kind::Branch(left, right) => (left, right),
+            Kind::Leaf(_) => return node,
+        };
+        let (left_height, right_height) = (self.nodes[left].height, self.nodes[right].height);
+        if right_height > left_height + 1 {
+            self.rotate(node, right, left)
+        } else if left_height > right_height + 1 {
+            self.rotate(node, left, right)
+        } else {
+            node
+        }
+    }
+
+    fn rotate(&mut self, node: usize, up: usize, other: usize) -> usize {
+        let (first, second) = match self.nodes[up].kind {
+            Kind::Branch(first, second) => (first, second),
+            Kind::Leaf(_) => return node,
+        };
+
+        // The lifted child takes the place of the node, which keeps the shorter grandchild
+        let parent = self.nodes[node].parent;
+        self.replace_child(parent, node, up);
+        let (kept, given) = if self.nodes[first].height > self.nodes[second].height {
+            (first, second)
+        } else {
+            (second, first)
+        };
+        self.nodes[up].kind = Kind::Branch(node, kept);
+        self.nodes[node].parent = Some(up);
+        self.nodes[node].kind = Kind::Branch(other, given);
+        self.nodes[given].parent = Some(node);
+        self.fit(node);
+        self.fit(up);
+        up
+    }
+}
+
+impl<T> SpatialIndex<T> for AabbTree<T> {
+    fn insert(&mut self, bounds: Aabb, data: T) -> ProxyId {
+        let id = self.proxies.insert(bounds, data, 0);
+        let leaf = self.allocate(TreeNode {
+            bounds: bounds.expanded(self.margin),
+            parent: None,
+            height: 0,
+            kind: Kind::Leaf(id.index()),
+        });
+        self.proxies.at_mut(id.index()).node = leaf;
+        self.insert_leaf(leaf);
+        id
+    }
+
+    fn update(&mut self, id: ProxyId, bounds: Aabb) -> bool {
+        let leaf = match self.proxies.get_mut(id) {
+            Some(entry) => {
+                entry.bounds = bounds;
+                entry.node
+            }
+            None => return false,
+        };
+
+        // Moves within the margin leave the tree as it is
+        if !self.nodes[leaf].bounds.contains_aabb(&bounds) {
+            self.remove_leaf(leaf);
+            self.nodes[leaf].bounds = bounds.expanded(self.margin);
+            self.insert_leaf(leaf);
+        }
+        true
+    }
+
+    fn remove(&mut self, id: ProxyId) -> Option<T> {
+        let entry = self.proxies.remove(id)?;
+        self.remove_leaf(entry.node);
+        self.free.push(entry.node);
+        Some(entry.data)
+    }
+
+    fn get(&self, id: ProxyId) -> Option<(&Aabb, &T)> {
+        self.proxies
+            .get(id)
+            .map(|entry| (&entry.bounds, &entry.data))
+    }
+
+    fn len(&self) -> usize {
+        self.proxies.len()
+    }
+
+    fn query(&self, region: &Aabb) -> Vec<ProxyId> {
+        let mut found = Vec::new();
+        let mut stack: Vec<usize> = self.root.into_iter().collect();
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            if !node.bounds.overlaps(region) {
+                continue;
+            }
+            match node.kind {
+                // The grown box may overlap when the object does not
+                Kind::Leaf(index) => {
+                    if self.proxies.at(index).bounds.overlaps(region) {
+                        found.push(self.proxies.id(index));
+                    }
+                }
+                Kind::Branch(left, right) => stack.extend([left, right].iter()),
+            }
+        }
+        found
+    }
+
+    fn raycast(&self, ray: &Ray, max_distance: f64) -> Option<(ProxyId, Hit)> {
+        let mut best: Option<(ProxyId, Hit)> = None;
+        let mut stack: Vec<usize> = self.root.into_iter().collect();
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            let limit = best.map_or(max_distance, |(_, hit)| hit.distance);
+            if node
+                .bounds
+                .raycast(ray)
+                .is_none_or(|hit| hit.distance > limit)
+            {
+                continue;
+            }
+            match node.kind {
+                Kind::Leaf(index) => {
+                    if let Some(hit) = self.proxies.at(index).bounds.raycast(ray) {
+                        if hit.distance <= max_distance {
+                            nearer(&mut best, self.proxies.id(index), hit);
+                        }
+                    }
+                }
+                Kind::Branch(left, right) => stack.extend([left, right].iter()),
+            }
+        }
+        best
+    }
+
+    fn nearest(&self, point: Vector2) -> Option<(ProxyId, f64)> {
+        let mut best: Option<(u32, f64)> = None;
+        let mut stack: Vec<usize> = self.root.into_iter().collect();
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            if best.is_some_and(|(_, nearest)| node.bounds.distance(point) >= nearest) {
+                continue;
+            }
+            match node.kind {
+                Kind::Leaf(index) => {
+                    let distance = self.proxies.at(index).bounds.distance(point);
+                    if best.is_none_or(|(_, nearest)| distance < nearest) {
+                        best = Some((index, distance));
+                    }
+                }
+                // The nearer child goes on top so the other is more often skipped
+                Kind::Branch(left, right) => {
+                    let distance = |child: usize| self.nodes[child].bounds.distance(point);
+                    if distance(left) < distance(right) {
+                        stack.extend([right, left].iter());
+                    } else {
+                        stack.extend([left, right].iter());
+                    }
+                }
+            }
+        }
+        best.map(|(index, distance)| (self.proxies.id(index), distance))
+    }
+}
+
+// ====================================
+// Unit Tests
+// ====================================
+
+#[cfg(test)]
+mod tree_tests {
+
+    use super::*;
+
+    #[test]
+    fn stays_balanced() {
+        let mut tree = AabbTree::new(0.5f64);
+        assert_eq!(tree.height(), 0);
+
+        // Boxes in a row would make a list of an unbalanced tree
+        let ids: Vec<ProxyId> = (0..64)
+            .map(|step| {
+                let min = Vector2::new(step as f64 * 2f64, 0f64);
+                tree.insert(Aabb::new(min, min + Vector2::new(1f64, 1f64)), step)
+            })
+            .collect();
+        assert!(tree.height() <= 8, "height {}", tree.height());
+        let nodes = tree.nodes.len();
+
+        // A move within the margin only changes the object box
+        let moved = Aabb::new(Vector2::new(0.25f64, 0f64), Vector2::new(1.25f64, 1f64));
+        assert!(tree.update(ids[0], moved));
+        assert_eq!(tree.nodes.len(), nodes);
+        assert_eq!(tree.get(ids[0]), Some((&moved, &0)));
+        assert!(tree
+            .query(&Aabb::new(
+                Vector2::new(-1f64, 0f64),
+                Vector2::new(0f64, 1f64)
+            ))
+            .is_empty());
+
+        for id in ids.iter().skip(1) {
+            tree.remove(*id);
+        }
+        assert_eq!(tree.height(), 0);
+        assert_eq!(tree.len(), 1);
+        assert_eq!(
+            tree.nearest(Vector2::new(0.25f64, 3f64)),
+            Some((ids[0], 2f64))
+        );
+        assert_eq!(tree.free.len() + 1, tree.nodes.len());
+    }
+}